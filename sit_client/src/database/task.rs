@@ -36,7 +36,7 @@ impl TaskManager {
             task_status: TaskStatus::Downloaded,
             task_result: None,
         };
-        Server::update_task(&task_update).unwrap();
+        Server::update_task(&task_update)?;
         Ok(())
     }
 
@@ -66,7 +66,9 @@ impl TaskManager {
             task_status: TaskStatus::Running,
             task_result: None,
         };
-        Server::update_task(&task_update).unwrap();
+        if let Err(e) = Server::update_task(&task_update) {
+            println!("Server::update_task {}", e);
+        }
     }
 
     pub fn task_update_failed(&self, task: &sit_lib::task::Task, task_result: Option<Value>) {
@@ -76,7 +78,9 @@ impl TaskManager {
             task_status: TaskStatus::Failed,
             task_result,
         };
-        Server::update_task(&task_update).unwrap();
+        if let Err(e) = Server::update_task(&task_update) {
+            println!("Server::update_task {}", e);
+        }
     }
 
     pub fn task_update_successful(&self, task: &sit_lib::task::Task, task_result: Option<Value>) {
@@ -86,6 +90,8 @@ impl TaskManager {
             task_status: TaskStatus::Successful,
             task_result,
         };
-        Server::update_task(&task_update).unwrap();
+        if let Err(e) = Server::update_task(&task_update) {
+            println!("Server::update_task {}", e);
+        }
     }
 }
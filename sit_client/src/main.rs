@@ -13,6 +13,7 @@ use clap::{arg, ArgAction, Command};
 use database::Database;
 use job_scheduler_ng::{Job, JobScheduler};
 use serde_json::json;
+use sit_lib::snapshot::{SectionStatus, Snapshot};
 use wmi::{COMLibrary, WMIConnection};
 
 use crate::config::Config;
@@ -29,6 +30,7 @@ mod hardware;
 mod licenses;
 mod server;
 mod service_mgmt;
+mod snapshot;
 mod software;
 mod system_status;
 mod win_core;
@@ -77,8 +79,12 @@ fn update_base_info() {
     let wmi_con = WMIConnection::new(com_con).unwrap();
     let os_info = OsInfo::get_os_info(&wmi_con);
     if let Ok(os_info) = os_info {
-        Server::register(&os_info.computer_name).unwrap();
-        Server::os(&os_info).unwrap();
+        if let Err(e) = Server::register(&os_info.computer_name) {
+            println!("Server::register {}", e);
+        }
+        let mut snapshot = Snapshot::new();
+        snapshot.os = Some(os_info);
+        upload_snapshot(snapshot);
     } else if let Err(e) = os_info {
         println!("OsInfo::get_os_info {}", e);
     }
@@ -87,37 +93,51 @@ fn update_base_info() {
 fn update_rich_info() {
     let com_con = COMLibrary::without_security().unwrap();
     let wmi_con = WMIConnection::new(com_con).unwrap();
-    let hardware_info = Hardware::get_hardware_info(&wmi_con);
-    if let Ok(hardware_info) = hardware_info {
-        Server::hardware(&hardware_info).unwrap();
-    } else if let Err(e) = hardware_info {
-        println!("Hardware::get_hardware_info {}", e);
+    let mut snapshot = Snapshot::new();
+    match Hardware::get_hardware_info(&wmi_con) {
+        Ok(hardware_info) => snapshot.hardware = Some(hardware_info),
+        Err(e) => println!("Hardware::get_hardware_info {}", e),
+    }
+    match OsInfo::get_user_profiles(&wmi_con) {
+        Ok(profiles) => snapshot.profiles = Some(profiles),
+        Err(e) => println!("OsInfo::get_user_profiles {}", e),
+    }
+    snapshot.software = Some(Software::get_software_list());
+    match SystemStatus::get_volume_status(&wmi_con) {
+        Ok(volumes) => snapshot.volumes = Some(volumes),
+        Err(e) => println!("SystemStatus::get_volume_status {}", e),
     }
-    let profiles = OsInfo::get_user_profiles(&wmi_con);
-    if let Ok(profiles) = profiles {
-        Server::profiles(&profiles).unwrap();
-    } else if let Err(e) = profiles {
-        println!("OsInfo::get_user_profiles {}", e);
+    match Licenses::collect_licenses() {
+        Ok(licenses) => snapshot.licenses = Some(licenses),
+        Err(e) => println!("Licenses::collect_licenses {}", e),
     }
-    let software_lib = Software::get_software_list();
-    Server::software(&software_lib).unwrap();
-    let volumes = SystemStatus::get_volume_status(&wmi_con);
-    if let Ok(volumes) = volumes {
-        Server::status_volumes(&volumes).unwrap();
-    } else if let Err(e) = volumes {
-        println!("SystemStatus::get_volume_status {}", e);
+    match Hardware::get_battery_status() {
+        Ok(battery_status) => snapshot.battery = Some(battery_status),
+        Err(e) => println!("Hardware::get_battery_status {}", e),
     }
-    let licenses = Licenses::collect_licenses();
-    if let Ok(licenses) = licenses {
-        Server::licenses(&licenses).unwrap();
-    } else if let Err(e) = licenses {
-        println!("Licenses::collect_licenses {}", e);
+    upload_snapshot(snapshot);
+}
+
+fn upload_snapshot(mut snapshot: Snapshot) {
+    if let Err(e) = snapshot::skip_unchanged(&mut snapshot) {
+        println!("snapshot::skip_unchanged {}", e);
     }
-    let battery_status = Hardware::get_battery_status();
-    if let Ok(battery_status) = battery_status {
-        Server::battery_status(&battery_status).unwrap();
-    } else if let Err(e) = battery_status {
-        println!("Hardware::get_battery_status {}", e);
+    if snapshot.is_empty() {
+        return;
+    }
+    match Server::snapshot(&snapshot) {
+        Ok(result) => {
+            for section in result.sections {
+                if section.status == SectionStatus::Failed {
+                    println!(
+                        "Server::snapshot {:?} {}",
+                        section.section,
+                        section.message.unwrap_or_default()
+                    );
+                }
+            }
+        }
+        Err(e) => println!("Server::snapshot {}", e),
     }
 }
 
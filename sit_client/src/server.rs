@@ -1,144 +1,125 @@
+use std::env;
 use std::path::Path;
-use anyhow::Result;
-use reqwest::blocking::Client;
-use sit_lib::hardware::{BatteryStatus, HardwareInfoV2};
-use sit_lib::licenses::LicenseBundle;
-use sit_lib::os::{UserProfiles, WinOsInfo};
+use anyhow::{anyhow, bail, Result};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use serde::Serialize;
+use sit_lib::error::{ApiError, ErrorKind};
 use sit_lib::server::Register;
-use sit_lib::software::SoftwareLibrary;
-use sit_lib::system_status::VolumeList;
+use sit_lib::snapshot::{Snapshot, SnapshotCheck, SnapshotCheckResult, SnapshotResult};
 use sit_lib::task::{Task, TaskBundle, TaskUpdate};
 
 use crate::Config;
 
+/// JSON bodies larger than this are sent compressed.
+const COMPRESS_ABOVE: usize = 1024;
+const ZSTD_LEVEL: i32 = 3;
+
 pub struct Server;
 
 impl Server {
     pub fn register(name: &str) -> Result<()> {
-        let request = Self::build_client()?
+        let response = Self::build_client()?
             .post(format!("{}/api/v1/register", Config::get_web_api()?))
             .json(&Register {
                 name: name.to_string(),
                 uuid: Config::get_uuid()?,
             })
-            .send();
-        if let Ok(request) = request {
-            if request.status().is_success() {
-                let register: Result<Register, _> = request.json();
-                Config::set_uuid(register?.uuid.unwrap())?;
-            } else {
-                println!("R:: {:#?}", request.status().as_u16());
-            }
+            .send()?;
+        let register: Register = Self::check(response)?.json()?;
+        match register.uuid {
+            Some(uuid) => Config::set_uuid(uuid),
+            None => bail!("Server did not assign a uuid"),
         }
-        Ok(())
     }
 
-    pub fn os(os_info: &WinOsInfo) -> Result<()> {
-        let _request = Self::build_client()?
-            .post(format!(
-                "{}/api/v1/os/{}",
-                Config::get_web_api()?,
-                Config::get_uuid()?.unwrap()
-            ))
-            .json(os_info)
-            .send();
-        Ok(())
+    /// Uploads the sections of `snapshot` in one request.
+    pub fn snapshot(snapshot: &Snapshot) -> Result<SnapshotResult> {
+        let response = Self::post_json("/api/v3/snapshot/{uuid}", snapshot)?;
+        Ok(response.json()?)
     }
 
-    pub fn hardware(hardware_info: &HardwareInfoV2) -> Result<()> {
-        let _request = Self::build_client()?
-            .post(format!(
-                "{}/api/v2/hardware/{}",
-                Config::get_web_api()?,
-                Config::get_uuid()?.unwrap()
-            ))
-            .json(hardware_info)
-            .send();
-        Ok(())
+    /// Asks which sections of a snapshot changed, by their hashes.
+    pub fn snapshot_check(check: &SnapshotCheck) -> Result<SnapshotCheckResult> {
+        let response = Self::post_json("/api/v3/snapshot/{uuid}/check", check)?;
+        Ok(response.json()?)
     }
 
-    pub fn software(software_lib: &SoftwareLibrary) -> Result<()> {
-        let _request = Self::build_client()?
-            .post(format!(
-                "{}/api/v1/software/{}",
-                Config::get_web_api()?,
-                Config::get_uuid()?.unwrap()
-            ))
-            .json(software_lib)
-            .send();
-        Ok(())
+    pub fn get_tasks() -> Result<Vec<Task>> {
+        let response = Self::send("/api/v1/tasks/{uuid}", |client, url| client.get(url))?;
+        let task_bundle: TaskBundle = response.json()?;
+        Ok(task_bundle.tasks)
     }
 
-    pub fn profiles(profiles: &UserProfiles) -> Result<()> {
-        let _request = Self::build_client()?
-            .post(format!(
-                "{}/api/v1/profiles/{}",
-                Config::get_web_api()?,
-                Config::get_uuid()?.unwrap()
-            ))
-            .json(profiles)
-            .send();
-        Ok(())
+    pub fn update_task(task_update: &TaskUpdate) -> Result<()> {
+        Self::post("/api/v1/tasks/{uuid}", task_update)
     }
 
-    pub fn licenses(licenses: &LicenseBundle) -> Result<()> {
-        let _request = Self::build_client()?
-            .post(format!(
-                "{}/api/v1/licenses/{}",
-                Config::get_web_api()?,
-                Config::get_uuid()?.unwrap()
-            ))
-            .json(licenses)
-            .send();
+    fn post<T: Serialize + ?Sized>(path: &str, body: &T) -> Result<()> {
+        Self::post_json(path, body)?;
         Ok(())
     }
 
-    pub fn status_volumes(volumes: &VolumeList) -> Result<()> {
-        let _request = Self::build_client()?
-            .post(format!(
-                "{}/api/v1/status/{}/volumes",
-                Config::get_web_api()?,
-                Config::get_uuid()?.unwrap()
-            ))
-            .json(volumes)
-            .send();
-        Ok(())
+    /// Posts `body` as JSON, compressed with zstd if it is large enough to be worth it.
+    fn post_json<T: Serialize + ?Sized>(path: &str, body: &T) -> Result<Response> {
+        let json = serde_json::to_vec(body)?;
+        let (body, encoding) = if json.len() > COMPRESS_ABOVE {
+            (zstd::encode_all(&json[..], ZSTD_LEVEL)?, Some("zstd"))
+        } else {
+            (json, None)
+        };
+        Self::send(path, |client, url| {
+            let request = client
+                .post(url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            match encoding {
+                Some(encoding) => request.header(CONTENT_ENCODING, encoding),
+                None => request,
+            }
+        })
     }
 
-    pub fn battery_status(battery_status: &BatteryStatus) -> Result<()> {
-        let _request = Self::build_client()?
-            .post(format!(
-                "{}/api/v1/status/{}/battery",
-                Config::get_web_api()?,
-                Config::get_uuid()?.unwrap()
-            ))
-            .json(battery_status)
-            .send();
-        Ok(())
+    /// Sends the request `build` creates for `path`, with `{uuid}` replaced by the
+    /// uuid of this client. If the server does not know this client (anymore), it
+    /// registers again and retries once.
+    fn send(path: &str, build: impl Fn(&Client, String) -> RequestBuilder) -> Result<Response> {
+        let client = Self::build_client()?;
+        match Self::check(build(&client, Self::client_url(path)?).send()?) {
+            Err(e) if Self::is_unknown_client(&e) => {
+                println!("Server does not know this client, registering again");
+                Self::register(&env::var("COMPUTERNAME")?)?;
+                Self::check(build(&client, Self::client_url(path)?).send()?)
+            }
+            result => result,
+        }
     }
 
-    pub fn get_tasks() -> Result<Vec<Task>> {
-        let response = Self::build_client()?
-            .get(format!(
-                "{}/api/v1/tasks/{}",
-                Config::get_web_api()?,
-                Config::get_uuid()?.unwrap()
-            ))
-            .send()?;
-        let task_bundle: TaskBundle = response.json()?;
-        Ok(task_bundle.tasks)
+    fn client_url(path: &str) -> Result<String> {
+        let uuid = Config::get_uuid()?.ok_or_else(|| anyhow!("Client is not registered yet"))?;
+        Ok(format!(
+            "{}{}",
+            Config::get_web_api()?,
+            path.replace("{uuid}", &uuid.to_string())
+        ))
     }
 
-    pub fn update_task(task_update: &TaskUpdate) -> Result<()> {
-        let _request = Self::build_client()?
-            .post(format!(
-                "{}/api/v1/tasks/{}",
-                Config::get_web_api()?,
-                Config::get_uuid()?.unwrap()
-            ))
-            .json(task_update)
-            .send();
-        Ok(())
+    /// Turns an error response into an [`ApiError`].
+    fn check(response: Response) -> Result<Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        match response.json::<ApiError>() {
+            Ok(error) => Err(error.into()),
+            Err(_) => bail!("Server answered {status}"),
+        }
+    }
+
+    fn is_unknown_client(error: &anyhow::Error) -> bool {
+        error
+            .downcast_ref::<ApiError>()
+            .is_some_and(|e| e.kind == ErrorKind::UnknownClient)
     }
 
     fn build_client() -> Result<Client> {
@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sit_lib::snapshot::{Section, Snapshot, SnapshotCheck};
+
+use crate::server::Server;
+
+/// Fills in the content hashes of `snapshot` and drops the sections the server
+/// reports as unchanged.
+pub fn skip_unchanged(snapshot: &mut Snapshot) -> Result<()> {
+    snapshot.hashes = section_hashes(snapshot)?;
+    let result = Server::snapshot_check(&SnapshotCheck {
+        schema_version: snapshot.schema_version,
+        hashes: snapshot.hashes.clone(),
+    })?;
+    let unchanged: Vec<Section> = snapshot
+        .hashes
+        .keys()
+        .filter(|s| !result.changed.contains(s))
+        .copied()
+        .collect();
+    for section in unchanged {
+        snapshot.remove(section);
+    }
+    Ok(())
+}
+
+fn section_hashes(snapshot: &Snapshot) -> Result<BTreeMap<Section, String>> {
+    let mut hashes = BTreeMap::new();
+    if let Some(os) = &snapshot.os {
+        hashes.insert(Section::Os, hash(os)?);
+    }
+    if let Some(hardware) = &snapshot.hardware {
+        hashes.insert(Section::Hardware, hash(hardware)?);
+    }
+    if let Some(software) = &snapshot.software {
+        hashes.insert(Section::Software, hash(software)?);
+    }
+    if let Some(profiles) = &snapshot.profiles {
+        hashes.insert(Section::Profiles, hash(profiles)?);
+    }
+    if let Some(volumes) = &snapshot.volumes {
+        hashes.insert(Section::Volumes, hash(volumes)?);
+    }
+    if let Some(licenses) = &snapshot.licenses {
+        hashes.insert(Section::Licenses, hash(licenses)?);
+    }
+    if let Some(battery) = &snapshot.battery {
+        hashes.insert(Section::Battery, hash(battery)?);
+    }
+    Ok(hashes)
+}
+
+/// SHA-256 of the JSON the section is uploaded as.
+fn hash<T: Serialize>(value: &T) -> Result<String> {
+    Ok(Sha256::digest(serde_json::to_vec(value)?)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
@@ -0,0 +1,85 @@
+//! Error bodies of the agent API.
+//!
+//! Every failed request is answered with an [`ApiError`] as JSON body and the
+//! status code of its [`ErrorKind`].
+
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The client UUID in the path is not registered (404), the agent has to register again.
+    UnknownClient,
+    /// Something else named in the path does not exist (404).
+    NotFound,
+    /// The body is not valid JSON (400).
+    MalformedPayload,
+    /// The body is valid JSON but does not match the expected structure (422).
+    InvalidPayload,
+    /// The body exceeds the size limit of the server (413).
+    PayloadTooLarge,
+    /// The body is compressed with an encoding the server does not support (415).
+    UnsupportedEncoding,
+    /// The request contradicts the current state on the server (409).
+    Conflict,
+    /// The server failed to handle a valid request (500).
+    Internal,
+}
+
+impl ErrorKind {
+    pub fn status_code(&self) -> u16 {
+        match self {
+            ErrorKind::UnknownClient | ErrorKind::NotFound => 404,
+            ErrorKind::MalformedPayload => 400,
+            ErrorKind::InvalidPayload => 422,
+            ErrorKind::PayloadTooLarge => 413,
+            ErrorKind::UnsupportedEncoding => 415,
+            ErrorKind::Conflict => 409,
+            ErrorKind::Internal => 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ApiError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// The offending fields of an invalid payload.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FieldError {
+    /// Path of the field in the payload, like `memory.sticks[0].capacity`.
+    pub field: String,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> ApiError {
+        ApiError {
+            kind,
+            message: message.into(),
+            fields: vec![],
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?})", self.message, self.kind)?;
+        for field in &self.fields {
+            write!(f, "; {}: {}", field.field, field.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
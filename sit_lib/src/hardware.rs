@@ -2,6 +2,7 @@ use serde::Deserialize;
 use serde::Serialize;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct HardwareInfo {
     pub model: ComputerModel,
     pub memory: PhysicalMemory,
@@ -13,6 +14,7 @@ pub struct HardwareInfo {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct HardwareInfoV2 {
     pub model: ComputerModel,
     pub memory: PhysicalMemory,
@@ -24,6 +26,7 @@ pub struct HardwareInfoV2 {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ComputerModel {
     pub manufacturer: String,
     pub model_family: String,
@@ -32,17 +35,20 @@ pub struct ComputerModel {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PhysicalMemory {
     pub sticks: Vec<MemoryStick>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MemoryStick {
     pub bank_label: String,
     pub capacity: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Processor {
     pub name: String,
     pub manufacturer: String,
@@ -53,11 +59,13 @@ pub struct Processor {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Disks {
     pub drives: Vec<DiskDrive>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct DiskDrive {
     pub model: String,
     pub serial_number: String,
@@ -68,11 +76,13 @@ pub struct DiskDrive {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Network {
     pub adapter: Vec<NetworkAdapter>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct NetworkAdapter {
     pub name: String,
     pub mac_address: Option<String>,
@@ -80,11 +90,13 @@ pub struct NetworkAdapter {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GraphicsCard {
     pub name: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct BIOS {
     pub manufacturer: String,
     pub name: String,
@@ -92,11 +104,13 @@ pub struct BIOS {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct BatteryStatus {
     pub batteries: Vec<Battery>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Battery {
     pub id: String,
     pub manufacturer: String,
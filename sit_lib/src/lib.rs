@@ -1,7 +1,9 @@
+pub mod error;
 pub mod hardware;
 pub mod licenses;
 pub mod os;
 pub mod server;
+pub mod snapshot;
 pub mod software;
 pub mod system_status;
 pub mod task;
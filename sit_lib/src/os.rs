@@ -3,6 +3,7 @@ use serde::Deserialize;
 use serde::Serialize;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct WinOsInfo {
     pub operating_system: String,
     pub os_version: String,
@@ -11,11 +12,13 @@ pub struct WinOsInfo {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct UserProfiles {
     pub profiles: Vec<ProfileInfo>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ProfileInfo {
     #[serde(default)]
     pub domain: Option<String>,
@@ -34,6 +37,7 @@ pub struct ProfileInfo {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PathInfo {
     pub path: String,
     pub size: u64,
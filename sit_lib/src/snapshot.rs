@@ -0,0 +1,149 @@
+//! Inventory snapshots of the agent API v3.
+//!
+//! A [`Snapshot`] carries any subset of the inventory sections of a client in one
+//! document. The server queues every section on its own and answers with a
+//! [`SectionResult`] per section, so one failing section does not discard the others.
+//!
+//! To save bandwidth the agent first sends a [`SnapshotCheck`] with a content hash
+//! per section and then only uploads the sections the server does not have yet.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::hardware::{BatteryStatus, HardwareInfoV2};
+use crate::licenses::LicenseBundle;
+use crate::os::{UserProfiles, WinOsInfo};
+use crate::software::SoftwareLibrary;
+use crate::system_status::VolumeList;
+
+/// The snapshot schema this version of the crate writes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Snapshot {
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os: Option<WinOsInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardware: Option<HardwareInfoV2>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub software: Option<SoftwareLibrary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<UserProfiles>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volumes: Option<VolumeList>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub licenses: Option<LicenseBundle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery: Option<BatteryStatus>,
+    /// Content hashes of the sections, remembered by the server for [`SnapshotCheck`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hashes: BTreeMap<Section, String>,
+}
+
+impl Snapshot {
+    pub fn new() -> Snapshot {
+        Snapshot {
+            schema_version: SCHEMA_VERSION,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.os.is_none()
+            && self.hardware.is_none()
+            && self.software.is_none()
+            && self.profiles.is_none()
+            && self.volumes.is_none()
+            && self.licenses.is_none()
+            && self.battery.is_none()
+    }
+
+    /// Drops `section` and its hash from the snapshot.
+    pub fn remove(&mut self, section: Section) {
+        match section {
+            Section::Os => self.os = None,
+            Section::Hardware => self.hardware = None,
+            Section::Software => self.software = None,
+            Section::Profiles => self.profiles = None,
+            Section::Volumes => self.volumes = None,
+            Section::Licenses => self.licenses = None,
+            Section::Battery => self.battery = None,
+        }
+        self.hashes.remove(&section);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    Os,
+    Hardware,
+    Software,
+    Profiles,
+    Volumes,
+    Licenses,
+    Battery,
+}
+
+impl Section {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Section::Os => "os",
+            Section::Hardware => "hardware",
+            Section::Software => "software",
+            Section::Profiles => "profiles",
+            Section::Volumes => "volumes",
+            Section::Licenses => "licenses",
+            Section::Battery => "battery",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SectionStatus {
+    /// The section was accepted and will be stored shortly.
+    Queued,
+    /// The server already has this content, see [`Snapshot::hashes`].
+    Unchanged,
+    /// Queueing the section failed, it has to be uploaded again.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SectionResult {
+    pub section: Section,
+    pub status: SectionStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Answer to a [`Snapshot`], with a result for every section it contained.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnapshotResult {
+    pub schema_version: u32,
+    pub sections: Vec<SectionResult>,
+}
+
+/// Asks the server which sections changed since they were last accepted.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnapshotCheck {
+    pub schema_version: u32,
+    pub hashes: BTreeMap<Section, String>,
+}
+
+/// Answer to a [`SnapshotCheck`]. The other sections count as seen.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnapshotCheckResult {
+    pub changed: Vec<Section>,
+}
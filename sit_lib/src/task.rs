@@ -3,11 +3,13 @@ use serde::{Serialize, Deserialize};
 use serde_json::Value;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TaskBundle {
     pub tasks: Vec<Task>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Task {
     pub id: i32,
     pub task: Value,
@@ -15,6 +17,7 @@ pub struct Task {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TaskUpdate {
     pub id: i32,
     pub time_downloaded: Option<DateTime<Utc>>,
@@ -23,6 +26,7 @@ pub struct TaskUpdate {
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum TaskStatus {
     Created,
     Downloaded,
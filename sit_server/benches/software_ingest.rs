@@ -0,0 +1,70 @@
+//! Ingest of a software list as sent by a typical client.
+//!
+//! Runs against a SQLite database in the temp directory, or against
+//! `BENCH_DATABASE_URL` if set.
+//!
+//! On SQLite, against the per-program find-or-create it replaced, with the
+//! previous implementation swapped into the same harness:
+//!
+//! | case        | per program | set-based |
+//! |-------------|-------------|-----------|
+//! | `unchanged` | 10.2 ms     | 1.04 ms   |
+//! | `updated`   | 37.3 ms     | 36.0 ms   |
+
+use std::env;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use sit_lib::software::{SoftwareEntry, SoftwareLibrary};
+use sit_server::database::Database;
+use uuid::Uuid;
+
+const PROGRAMS: usize = 300;
+
+/// The software list of a client. Every `release` ships a new version of a tenth
+/// of the programs.
+fn library(release: usize) -> SoftwareLibrary {
+    SoftwareLibrary {
+        software: (0..PROGRAMS)
+            .map(|n| SoftwareEntry {
+                name: format!("Program {n}"),
+                version: format!("{}.{}", n % 7, if n % 10 == 0 { release } else { 0 }),
+                publisher: Some(format!("Publisher {}", n % 25)),
+            })
+            .collect(),
+    }
+}
+
+fn software_ingest(c: &mut Criterion) {
+    let database_url = env::var("BENCH_DATABASE_URL").unwrap_or_else(|_| {
+        let path = env::temp_dir().join(format!("sit_bench_{}.sqlite", Uuid::new_v4()));
+        format!("sqlite://{}", path.display())
+    });
+    let database = Database::connect(&database_url, 2).expect("Could not connect to the benchmark database");
+    let client = database.create_client(&Uuid::new_v4()).unwrap();
+    database.create_os_info(&client, "PC-BENCH").unwrap();
+    database.update_software_lib(client.id, library(0), None).unwrap();
+
+    let mut group = c.benchmark_group("software_ingest");
+    group.bench_function("unchanged", |b| {
+        b.iter_batched(
+            || library(0),
+            |software| database.update_software_lib(client.id, software, None).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    let mut release = 0;
+    group.bench_function("updated", |b| {
+        b.iter_batched(
+            || {
+                release += 1;
+                library(release)
+            },
+            |software| database.update_software_lib(client.id, software, None).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, software_ingest);
+criterion_main!(benches);
@@ -0,0 +1,2 @@
+DROP TABLE "client_group_member";
+DROP TABLE "client_group";
@@ -0,0 +1,37 @@
+CREATE TABLE "site" (
+    "id" SERIAL,
+    "name" TEXT NOT NULL UNIQUE,
+    PRIMARY KEY ("id")
+);
+CREATE TABLE "building" (
+    "id" SERIAL,
+    "site_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    PRIMARY KEY ("id"),
+    UNIQUE ("site_id", "name"),
+    CONSTRAINT "FK_building_site" FOREIGN KEY ("site_id") REFERENCES "site" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "room" (
+    "id" SERIAL,
+    "building_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    PRIMARY KEY ("id"),
+    UNIQUE ("building_id", "name"),
+    CONSTRAINT "FK_room_building" FOREIGN KEY ("building_id") REFERENCES "building" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "room_subnet" (
+    "id" SERIAL,
+    "room_id" INTEGER NOT NULL,
+    "subnet" TEXT NOT NULL UNIQUE,
+    PRIMARY KEY ("id"),
+    CONSTRAINT "FK_room_subnet_room" FOREIGN KEY ("room_id") REFERENCES "room" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "client_location" (
+    "client_id" INTEGER NOT NULL,
+    "room_id" INTEGER NOT NULL,
+    "manual" BOOLEAN NOT NULL,
+    PRIMARY KEY ("client_id"),
+    CONSTRAINT "FK_client_location_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT "FK_client_location_room" FOREIGN KEY ("room_id") REFERENCES "room" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_client_location_room_id" ON "client_location" ("room_id");
@@ -0,0 +1,2 @@
+DROP TABLE "client_attribute";
+DROP TABLE "attribute_definition";
@@ -0,0 +1,16 @@
+CREATE TABLE "ingest_job" (
+    "id" SERIAL,
+    "client_id" INTEGER NOT NULL,
+    "kind" TEXT NOT NULL,
+    "payload" TEXT NOT NULL,
+    "hash" TEXT NULL,
+    "status" TEXT NOT NULL DEFAULT 'pending',
+    "received" TIMESTAMP NOT NULL,
+    "available" TIMESTAMP NOT NULL,
+    "attempts" INTEGER NOT NULL DEFAULT 0,
+    "last_error" TEXT NULL,
+    PRIMARY KEY ("id"),
+    CONSTRAINT "FK_ingest_job_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_ingest_job_status" ON "ingest_job" ("status", "id");
+CREATE INDEX "INDEX_ingest_job_client_id" ON "ingest_job" ("client_id");
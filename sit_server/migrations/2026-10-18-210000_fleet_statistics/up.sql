@@ -0,0 +1,73 @@
+-- Cached aggregates of the overview pages, see database/statistics.rs.
+-- "group_id" is NULL for the statistics of the whole fleet.
+CREATE TABLE "statistics_state" (
+    "id" INTEGER NOT NULL,
+    "refreshed" TIMESTAMP NULL,
+    "changed" TIMESTAMP NULL,
+    PRIMARY KEY ("id")
+);
+INSERT INTO "statistics_state" ("id", "refreshed", "changed") VALUES (1, NULL, NULL);
+CREATE TABLE "stat_processor" (
+    "id" SERIAL,
+    "group_id" INTEGER NULL,
+    "name" TEXT NOT NULL,
+    "manufacturer" TEXT NOT NULL,
+    "cores" BIGINT NULL,
+    "logical_cores" BIGINT NULL,
+    "clock_speed" BIGINT NULL,
+    "address_width" INTEGER NULL,
+    "count" BIGINT NOT NULL,
+    PRIMARY KEY ("id"),
+    CONSTRAINT "FK_stat_processor_group" FOREIGN KEY ("group_id") REFERENCES "client_group" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_stat_processor_group_id" ON "stat_processor" ("group_id");
+CREATE TABLE "stat_memory" (
+    "id" SERIAL,
+    "group_id" INTEGER NULL,
+    "capacity" NUMERIC(20, 0) NULL,
+    "sticks" BIGINT NULL,
+    "count" BIGINT NOT NULL,
+    PRIMARY KEY ("id"),
+    CONSTRAINT "FK_stat_memory_group" FOREIGN KEY ("group_id") REFERENCES "client_group" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_stat_memory_group_id" ON "stat_memory" ("group_id");
+CREATE TABLE "stat_disk" (
+    "id" SERIAL,
+    "group_id" INTEGER NULL,
+    "model" TEXT NOT NULL,
+    "size" NUMERIC(20, 0) NULL,
+    "count" BIGINT NOT NULL,
+    PRIMARY KEY ("id"),
+    CONSTRAINT "FK_stat_disk_group" FOREIGN KEY ("group_id") REFERENCES "client_group" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_stat_disk_group_id" ON "stat_disk" ("group_id");
+CREATE TABLE "stat_computer_model" (
+    "id" SERIAL,
+    "group_id" INTEGER NULL,
+    "manufacturer" TEXT NOT NULL,
+    "model_family" TEXT NOT NULL,
+    "count" BIGINT NOT NULL,
+    PRIMARY KEY ("id"),
+    CONSTRAINT "FK_stat_computer_model_group" FOREIGN KEY ("group_id") REFERENCES "client_group" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_stat_computer_model_group_id" ON "stat_computer_model" ("group_id");
+CREATE TABLE "stat_os" (
+    "id" SERIAL,
+    "group_id" INTEGER NULL,
+    "os" TEXT NULL,
+    "count" BIGINT NOT NULL,
+    PRIMARY KEY ("id"),
+    CONSTRAINT "FK_stat_os_group" FOREIGN KEY ("group_id") REFERENCES "client_group" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_stat_os_group_id" ON "stat_os" ("group_id");
+CREATE TABLE "stat_software_version" (
+    "id" SERIAL,
+    "group_id" INTEGER NULL,
+    "version_id" INTEGER NOT NULL,
+    "count" BIGINT NOT NULL,
+    PRIMARY KEY ("id"),
+    CONSTRAINT "FK_stat_software_version_group" FOREIGN KEY ("group_id") REFERENCES "client_group" ("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT "FK_stat_software_version_version" FOREIGN KEY ("version_id") REFERENCES "software_version" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_stat_software_version_group_id" ON "stat_software_version" ("group_id");
+CREATE INDEX "INDEX_stat_software_version_version_id" ON "stat_software_version" ("version_id");
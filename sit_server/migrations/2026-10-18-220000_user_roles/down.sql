@@ -0,0 +1,2 @@
+DROP TABLE "auth_user_group";
+ALTER TABLE "auth_user" DROP COLUMN "role";
@@ -0,0 +1,2 @@
+-- Disabled users can't log in, their sessions and access tokens stop working.
+ALTER TABLE "auth_user" ADD COLUMN "disabled" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,12 @@
+-- Every login attempt, for throttling and as audit trail. The username is kept
+-- as entered, so attempts for unknown users are recorded as well.
+CREATE TABLE "login_attempt" (
+    "id" SERIAL,
+    "username" TEXT NOT NULL,
+    "ip" TEXT NULL,
+    "attempted" TIMESTAMP NOT NULL,
+    "success" BOOLEAN NOT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX "INDEX_login_attempt_username" ON "login_attempt" ("username", "attempted");
+CREATE INDEX "INDEX_login_attempt_ip" ON "login_attempt" ("ip", "attempted");
@@ -0,0 +1,23 @@
+DROP TABLE "battery";
+DROP TABLE "client_task";
+DROP TABLE "auth_sessions";
+DROP TABLE "auth_user";
+DROP TABLE "license_key";
+DROP TABLE "volume_status";
+DROP TABLE "bios";
+DROP TABLE "graphics_card";
+DROP TABLE "network_adapter_ip";
+DROP TABLE "network_adapter";
+DROP TABLE "disks";
+DROP TABLE "processor";
+DROP VIEW "memory";
+DROP TABLE "memory_stick";
+DROP TABLE "computer_model";
+DROP TABLE "userprofile_paths";
+DROP TABLE "userprofile";
+DROP TABLE "user";
+DROP TABLE "software_list";
+DROP TABLE "software_version";
+DROP TABLE "software_info";
+DROP TABLE "os_info";
+DROP TABLE "client";
@@ -0,0 +1,183 @@
+-- Equivalent of the Postgres migrations up to 2025-01-28-150708_allow_multiple_graphics_card.
+-- Uuids, JSON documents and task states are stored as text.
+CREATE TABLE "client" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "uuid" TEXT NOT NULL,
+    CONSTRAINT "uuid" UNIQUE ("uuid")
+);
+CREATE TABLE "os_info" (
+    "client_id" INTEGER NOT NULL,
+    "os" TEXT NULL,
+    "os_version" TEXT NULL,
+    "computer_name" TEXT NOT NULL,
+    "domain" TEXT NULL,
+    PRIMARY KEY ("client_id"),
+    CONSTRAINT "FK_os_info_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "software_info" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "publisher" TEXT NULL
+);
+CREATE TABLE "software_version" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "software_id" INTEGER NOT NULL,
+    "version" TEXT NOT NULL,
+    CONSTRAINT "FK_software_version_software_info" FOREIGN KEY ("software_id") REFERENCES "software_info" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "software_list" (
+    "client_id" INTEGER NOT NULL,
+    "software_id" INTEGER NOT NULL,
+    PRIMARY KEY ("client_id", "software_id"),
+    CONSTRAINT "FK_software_list_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT "FK_software_list_software_version" FOREIGN KEY ("software_id") REFERENCES "software_version" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "user" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "sid" TEXT NOT NULL UNIQUE,
+    "username" TEXT NULL,
+    "domain" TEXT NULL
+);
+CREATE TABLE "userprofile" (
+    "client_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "health_status" SMALLINT NOT NULL,
+    "roaming_configured" BOOLEAN NOT NULL,
+    "roaming_path" TEXT NULL,
+    "roaming_preference" BOOLEAN NULL,
+    "last_use_time" TIMESTAMP NULL,
+    "last_download_time" TIMESTAMP NULL,
+    "last_upload_time" TIMESTAMP NULL,
+    "status" BIGINT NOT NULL,
+    "size" NUMERIC(20, 0) NULL,
+    PRIMARY KEY ("client_id", "user_id"),
+    CONSTRAINT "FK_userprofile_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT "FK_userprofile_user" FOREIGN KEY ("user_id") REFERENCES "user" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "userprofile_paths" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "client_id" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "path" TEXT NOT NULL,
+    "size" NUMERIC(20, 0) NOT NULL,
+    CONSTRAINT "FK_userprofile_paths_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT "FK_userprofile_paths_user" FOREIGN KEY ("user_id") REFERENCES "user" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_userprofile_paths_client_id" ON "userprofile_paths" ("client_id");
+CREATE INDEX "INDEX_userprofile_paths_user_id" ON "userprofile_paths" ("user_id");
+CREATE TABLE "computer_model" (
+    "client_id" INTEGER NOT NULL,
+    "manufacturer" TEXT NOT NULL,
+    "model_family" TEXT NOT NULL,
+    "serial_number" TEXT NOT NULL,
+    PRIMARY KEY ("client_id"),
+    CONSTRAINT "FK_computer_model_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "memory_stick" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "client_id" INTEGER NOT NULL,
+    "capacity" NUMERIC(20, 0) NULL,
+    "bank_label" TEXT NOT NULL,
+    CONSTRAINT "FK_memory_stick_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE VIEW "memory" AS SELECT client_id, SUM(capacity) AS capacity, COUNT(capacity) AS sticks FROM memory_stick GROUP BY client_id;
+CREATE TABLE "processor" (
+    "client_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    "manufacturer" TEXT NOT NULL,
+    "cores" BIGINT NOT NULL,
+    "logical_cores" BIGINT NOT NULL,
+    "clock_speed" BIGINT NOT NULL,
+    "address_width" INTEGER NOT NULL,
+    PRIMARY KEY ("client_id"),
+    CONSTRAINT "FK_processor_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "disks" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "client_id" INTEGER NOT NULL,
+    "model" TEXT NOT NULL,
+    "serial_number" TEXT NOT NULL,
+    "size" NUMERIC(20, 0) NULL,
+    "device_id" TEXT NOT NULL,
+    "status" TEXT NOT NULL,
+    "media_type" TEXT NOT NULL,
+    CONSTRAINT "FK_disks_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "network_adapter" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "client_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    "mac_address" TEXT NULL,
+    CONSTRAINT "FK_network_adapter_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "network_adapter_ip" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "adapter_id" INTEGER NOT NULL,
+    "ip" TEXT NOT NULL,
+    CONSTRAINT "FK_network_adapter_ip_adapter_id" FOREIGN KEY ("adapter_id") REFERENCES "network_adapter" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "graphics_card" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "client_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    CONSTRAINT "FK_graphics_card_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "bios" (
+    "client_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    "manufacturer" TEXT NOT NULL,
+    "version" TEXT NOT NULL,
+    PRIMARY KEY ("client_id"),
+    CONSTRAINT "FK_bios_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "volume_status" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "client_id" INTEGER NOT NULL,
+    "drive_letter" TEXT NOT NULL,
+    "label" TEXT NULL,
+    "file_system" TEXT NOT NULL,
+    "capacity" NUMERIC(20, 0) NOT NULL,
+    "free_space" NUMERIC(20, 0) NOT NULL,
+    CONSTRAINT "FK_volume_status_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "license_key" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "client_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    "key" TEXT NOT NULL,
+    CONSTRAINT "FK_license_key_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "auth_user" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "username" TEXT NOT NULL,
+    "password" TEXT NOT NULL
+);
+CREATE TABLE "auth_sessions" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "session_id" TEXT NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "valid_until" TIMESTAMP NOT NULL,
+    CONSTRAINT "FK_auth_sessions_auth_user" FOREIGN KEY ("user_id") REFERENCES "auth_user" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "client_task" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "client_id" INTEGER NOT NULL,
+    "task" TEXT NOT NULL,
+    "time_start" TIMESTAMP NULL,
+    "time_download" TIMESTAMP NULL,
+    "task_status" TEXT NULL CHECK ("task_status" IN ('created', 'downloaded', 'running', 'successful', 'failed')),
+    "task_result" TEXT NULL,
+    CONSTRAINT "FK_client_task_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "battery" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "client_id" INTEGER NOT NULL,
+    "battery_id" TEXT NOT NULL,
+    "manufacturer" TEXT NOT NULL,
+    "serial_number" TEXT NOT NULL,
+    "chemistry" TEXT NOT NULL,
+    "cycle_count" BIGINT NOT NULL,
+    "designed_capacity" BIGINT NOT NULL,
+    "full_charged_capacity" BIGINT NOT NULL,
+    CONSTRAINT "FK_battery_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+CREATE TABLE "client_group" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL UNIQUE,
+    "description" TEXT NULL
+);
+CREATE TABLE "client_group_member" (
+    "group_id" INTEGER NOT NULL,
+    "client_id" INTEGER NOT NULL,
+    PRIMARY KEY ("group_id", "client_id"),
+    CONSTRAINT "FK_client_group_member_client_group" FOREIGN KEY ("group_id") REFERENCES "client_group" ("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT "FK_client_group_member_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_client_group_member_client_id" ON "client_group_member" ("client_id");
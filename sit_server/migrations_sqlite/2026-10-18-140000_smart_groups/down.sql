@@ -0,0 +1 @@
+ALTER TABLE "client_group" DROP COLUMN "rule";
@@ -0,0 +1 @@
+ALTER TABLE "client_group" ADD COLUMN "rule" TEXT NULL;
@@ -0,0 +1,5 @@
+DROP TABLE "client_location";
+DROP TABLE "room_subnet";
+DROP TABLE "room";
+DROP TABLE "building";
+DROP TABLE "site";
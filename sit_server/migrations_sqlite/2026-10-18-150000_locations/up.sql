@@ -0,0 +1,33 @@
+CREATE TABLE "site" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL UNIQUE
+);
+CREATE TABLE "building" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "site_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    UNIQUE ("site_id", "name"),
+    CONSTRAINT "FK_building_site" FOREIGN KEY ("site_id") REFERENCES "site" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "room" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "building_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    UNIQUE ("building_id", "name"),
+    CONSTRAINT "FK_room_building" FOREIGN KEY ("building_id") REFERENCES "building" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "room_subnet" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "room_id" INTEGER NOT NULL,
+    "subnet" TEXT NOT NULL UNIQUE,
+    CONSTRAINT "FK_room_subnet_room" FOREIGN KEY ("room_id") REFERENCES "room" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE TABLE "client_location" (
+    "client_id" INTEGER NOT NULL,
+    "room_id" INTEGER NOT NULL,
+    "manual" BOOLEAN NOT NULL,
+    PRIMARY KEY ("client_id"),
+    CONSTRAINT "FK_client_location_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT "FK_client_location_room" FOREIGN KEY ("room_id") REFERENCES "room" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_client_location_room_id" ON "client_location" ("room_id");
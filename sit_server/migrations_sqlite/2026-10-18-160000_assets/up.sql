@@ -0,0 +1,12 @@
+CREATE TABLE "asset" (
+    "client_id" INTEGER NOT NULL,
+    "inventory_number" TEXT NULL,
+    "purchase_date" DATE NULL,
+    "supplier" TEXT NULL,
+    "price" NUMERIC(12, 2) NULL,
+    "warranty_end" DATE NULL,
+    "responsible" TEXT NULL,
+    PRIMARY KEY ("client_id"),
+    CONSTRAINT "FK_asset_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_asset_warranty_end" ON "asset" ("warranty_end");
@@ -0,0 +1,15 @@
+CREATE TABLE "attribute_definition" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL UNIQUE,
+    "kind" TEXT NOT NULL,
+    "options" TEXT NULL
+);
+CREATE TABLE "client_attribute" (
+    "client_id" INTEGER NOT NULL,
+    "attribute_id" INTEGER NOT NULL,
+    "value" TEXT NOT NULL,
+    PRIMARY KEY ("client_id", "attribute_id"),
+    CONSTRAINT "FK_client_attribute_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT "FK_client_attribute_attribute_definition" FOREIGN KEY ("attribute_id") REFERENCES "attribute_definition" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_client_attribute_attribute_id" ON "client_attribute" ("attribute_id");
@@ -0,0 +1,12 @@
+CREATE TABLE "access_token" (
+    "id" INTEGER NOT NULL PRIMARY KEY,
+    "user_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    "token_hash" TEXT NOT NULL UNIQUE,
+    "scopes" TEXT NOT NULL,
+    "created" TIMESTAMP NOT NULL,
+    "expires" TIMESTAMP NULL,
+    "last_used" TIMESTAMP NULL,
+    CONSTRAINT "FK_access_token_auth_user" FOREIGN KEY ("user_id") REFERENCES "auth_user" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_access_token_user_id" ON "access_token" ("user_id");
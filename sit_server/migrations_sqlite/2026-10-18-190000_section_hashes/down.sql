@@ -0,0 +1 @@
+DROP TABLE "section_hash";
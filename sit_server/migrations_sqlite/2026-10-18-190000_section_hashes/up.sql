@@ -0,0 +1,9 @@
+CREATE TABLE "section_hash" (
+    "client_id" INTEGER NOT NULL,
+    "section" TEXT NOT NULL,
+    "hash" TEXT NOT NULL,
+    "accepted" TIMESTAMP NOT NULL,
+    "last_seen" TIMESTAMP NOT NULL,
+    PRIMARY KEY ("client_id", "section"),
+    CONSTRAINT "FK_section_hash_client" FOREIGN KEY ("client_id") REFERENCES "client" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+DROP TABLE "stat_software_version";
+DROP TABLE "stat_os";
+DROP TABLE "stat_computer_model";
+DROP TABLE "stat_disk";
+DROP TABLE "stat_memory";
+DROP TABLE "stat_processor";
+DROP TABLE "statistics_state";
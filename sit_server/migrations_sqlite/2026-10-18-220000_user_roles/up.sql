@@ -0,0 +1,11 @@
+-- Existing users keep full access.
+ALTER TABLE "auth_user" ADD COLUMN "role" TEXT NOT NULL DEFAULT 'administrator';
+-- The client groups a user is restricted to, no rows for all clients.
+CREATE TABLE "auth_user_group" (
+    "user_id" INTEGER NOT NULL,
+    "group_id" INTEGER NOT NULL,
+    PRIMARY KEY ("user_id", "group_id"),
+    CONSTRAINT "FK_auth_user_group_auth_user" FOREIGN KEY ("user_id") REFERENCES "auth_user" ("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT "FK_auth_user_group_client_group" FOREIGN KEY ("group_id") REFERENCES "client_group" ("id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+CREATE INDEX "INDEX_auth_user_group_group_id" ON "auth_user_group" ("group_id");
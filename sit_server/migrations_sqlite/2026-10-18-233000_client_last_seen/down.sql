@@ -0,0 +1 @@
+ALTER TABLE "client" DROP COLUMN "last_seen";
@@ -0,0 +1,2 @@
+-- Last time the client sent a payload, NULL until the next contact.
+ALTER TABLE "client" ADD COLUMN "last_seen" TIMESTAMP NULL;
@@ -1,4 +1,7 @@
-use anyhow::{bail, Result};
+use std::fmt;
+use std::net::IpAddr;
+
+use anyhow::{anyhow, bail, Result};
 use argon2::Argon2;
 use chrono::{Duration, NaiveDateTime, Utc};
 use password_hash::{rand_core::OsRng, PasswordHash, SaltString};
@@ -12,7 +15,14 @@ use rocket::{
 };
 use serde::Serialize;
 
-use crate::database::Database;
+use crate::database::{AuthUserChange, Database};
+
+pub use self::policy::{LoginPolicy, PasswordPolicy};
+pub use self::role::{GroupRestriction, Permission, Permissions, Role};
+
+pub mod policy;
+pub mod role;
+pub mod token;
 
 const COOKIE_SESSION_ID: &str = "SIT_SESSION";
 
@@ -20,6 +30,41 @@ const COOKIE_SESSION_ID: &str = "SIT_SESSION";
 pub struct User {
     pub user_id: i32,
     pub username: String,
+    pub role: Role,
+    pub permissions: Permissions,
+    pub groups: GroupRestriction,
+}
+
+impl User {
+    /// Loads the user `user_id` with role and group restriction. Users with an
+    /// unknown role get the least privileged one, disabled users fail.
+    pub fn load(db: &Database, user_id: i32) -> Result<User> {
+        let auth_user = db.get_auth_user_by_id(user_id)?;
+        if auth_user.disabled {
+            bail!("The user is disabled.");
+        }
+        let role = auth_user.role.parse().unwrap_or(Role::Auditor);
+        Ok(User {
+            user_id: auth_user.id,
+            role,
+            permissions: Permissions::from(role),
+            groups: GroupRestriction::new(db.get_auth_user_groups(auth_user.id)?),
+            username: auth_user.username,
+        })
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        self.role.permits(permission)
+    }
+
+    /// `Forbidden` unless the role of the user grants `permission`.
+    pub fn require(&self, permission: Permission) -> Result<(), Status> {
+        if self.can(permission) {
+            Ok(())
+        } else {
+            Err(Status::Forbidden)
+        }
+    }
 }
 
 #[rocket::async_trait]
@@ -28,49 +73,110 @@ impl<'r> FromRequest<'r> for User {
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<User, ()> {
         let db: &State<Database> = try_outcome!(request.guard::<&State<Database>>().await);
-        if let Some(mut cookie) = request.cookies().get_private(COOKIE_SESSION_ID) {
-            if let Ok(session) = db.get_auth_session_by_session_id(cookie.value()) {
+        let Some(mut cookie) = request.cookies().get_private(COOKIE_SESSION_ID) else {
+            return Outcome::Forward(Status::SeeOther);
+        };
+        let session_id = cookie.value().to_owned();
+        let expiration = calc_current_exp_time().ok();
+        let user = db
+            .run(move |db| {
+                let session = db.get_auth_session_by_session_id(&session_id).ok()?;
                 if Utc::now()
                     .naive_utc()
                     .signed_duration_since(session.valid_until)
                     .gt(&Duration::zero())
                 {
-                    return Outcome::Forward(Status::SeeOther);
+                    return None;
                 }
-                if let Ok(user) = db.get_auth_user_by_id(session.user_id) {
-                    if let Ok((naive, offset)) = calc_current_exp_time() {
-                        cookie.set_expires(Expiration::from(offset));
-                        request.cookies().add_private(cookie);
-                        let _ = db.update_session_exp(&session.session_id, naive);
-                    }
-                    return Outcome::Success(User {
-                        user_id: user.id,
-                        username: user.username,
-                    });
+                let user = User::load(db, session.user_id).ok()?;
+                if let Some((naive, _)) = expiration {
+                    let _ = db.update_session_exp(&session.session_id, naive);
                 }
+                Some(user)
+            })
+            .await;
+        match user {
+            Some(user) => {
+                if let Some((_, offset)) = expiration {
+                    cookie.set_expires(Expiration::from(offset));
+                    request.cookies().add_private(cookie);
+                }
+                Outcome::Success(user)
             }
-            Outcome::Forward(Status::SeeOther)
-        } else {
-            Outcome::Forward(Status::SeeOther)
+            None => Outcome::Forward(Status::SeeOther),
         }
     }
 }
 
-pub fn login(db: &Database, username: &str, password: &str, cookie_jar: &CookieJar) -> Result<()> {
-    let user = check_password(db, username, password)?;
-    let session_id = get_new_session_id(db);
+/// Why [`login`] failed.
+#[derive(Debug)]
+pub enum LoginError {
+    /// Unknown username, wrong password or a disabled user.
+    Invalid,
+    /// Too many failed logins for the account or from the address, see [`LoginPolicy`].
+    Locked,
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for LoginError {
+    fn from(e: anyhow::Error) -> LoginError {
+        LoginError::Internal(e)
+    }
+}
+
+impl fmt::Display for LoginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoginError::Invalid => f.write_str("Invalid username/password."),
+            LoginError::Locked => f.write_str("Too many failed logins."),
+            LoginError::Internal(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// Checks the password and starts a session. Every attempt is recorded with
+/// `ip`, while the account or the address is locked the password isn't even
+/// checked.
+pub async fn login(
+    db: &Database,
+    policy: &LoginPolicy,
+    username: &str,
+    password: &str,
+    ip: Option<IpAddr>,
+    cookie_jar: &CookieJar<'_>,
+) -> Result<(), LoginError> {
+    let (username, password) = (username.to_owned(), password.to_owned());
+    let ip = ip.map(|ip| ip.to_string());
+    let policy = policy.clone();
     let (naive, offset) = calc_current_exp_time()?;
-    let mut cookie = Cookie::new(COOKIE_SESSION_ID, session_id.clone());
+    let session_id = db
+        .run(move |db| -> Result<String, LoginError> {
+            let now = Utc::now().naive_utc();
+            let attempts = db.login_attempt_manager();
+            // Recorded as failed before the checks, so concurrent attempts can't
+            // all pass the lock.
+            let attempt_id = attempts.add_attempt(&username, ip.as_deref(), now, false)?;
+            if policy.is_locked(db, &username, ip.as_deref(), now)? {
+                return Err(LoginError::Locked);
+            }
+            let user = check_password(db, &username, &password).map_err(|_| LoginError::Invalid)?;
+            attempts.set_success(attempt_id)?;
+            let session_id = get_new_session_id(db);
+            db.add_new_session(user.user_id, &session_id, naive)?;
+            Ok(session_id)
+        })
+        .await?;
+    let mut cookie = Cookie::new(COOKIE_SESSION_ID, session_id);
     cookie.set_expires(Expiration::from(offset));
     cookie_jar.add_private(cookie);
-    db.add_new_session(user.user_id, &session_id, naive)?;
     Ok(())
 }
 
-pub fn logout(db: &Database, jar: &CookieJar) -> Result<()> {
+pub async fn logout(db: &Database, jar: &CookieJar<'_>) -> Result<()> {
     if let Some(cookie) = jar.get_private(COOKIE_SESSION_ID) {
         jar.remove_private(Cookie::from(COOKIE_SESSION_ID));
-        db.delete_session(cookie.value())?;
+        let session_id = cookie.value().to_owned();
+        db.run(move |db| db.delete_session(&session_id)).await?;
     }
     Ok(())
 }
@@ -82,10 +188,7 @@ pub fn check_password(db: &Database, username: &str, password: &str) -> Result<U
                 .verify_password(&[&Argon2::default()], password)
                 .is_ok()
             {
-                Ok(User {
-                    user_id: user.id,
-                    username: user.username,
-                })
+                User::load(db, user.id)
             } else {
                 bail!("Invalid username/password.")
             }
@@ -97,32 +200,117 @@ pub fn check_password(db: &Database, username: &str, password: &str) -> Result<U
     }
 }
 
-pub fn set_new_password(db: &Database, username: &str, new_password: &str) -> Result<()> {
+pub fn set_new_password(db: &Database, policy: &PasswordPolicy, username: &str, new_password: &str) -> Result<()> {
+    policy.check(new_password)?;
     let user = db.get_auth_user_by_username(username)?;
+    db.set_auth_user_password(user.id, &hash_password(new_password)?)?;
+    Ok(())
+}
+
+/// Changes the password of the user `user_id` after checking the current one.
+pub fn change_password(
+    db: &Database,
+    policy: &PasswordPolicy,
+    user_id: i32,
+    current_password: &str,
+    new_password: &str,
+) -> Result<()> {
+    let user = db.get_auth_user_by_id(user_id)?;
+    let hash = PasswordHash::new(&user.password).map_err(|_| anyhow!("Internal Server Error"))?;
+    if hash
+        .verify_password(&[&Argon2::default()], current_password)
+        .is_err()
+    {
+        bail!("The current password is wrong.");
+    }
+    policy.check(new_password)?;
+    db.set_auth_user_password(user.id, &hash_password(new_password)?)?;
+    Ok(())
+}
+
+pub fn create_new_user(
+    db: &Database,
+    policy: &PasswordPolicy,
+    username: &str,
+    password: &str,
+    role: Role,
+) -> Result<()> {
+    policy.check(password)?;
+    db.new_auth_user(username, &hash_password(password)?, role.as_str())?;
+    Ok(())
+}
+
+/// Like [`create_new_user`], but only while no user exists. Returns whether the
+/// user was created.
+pub fn create_first_user(
+    db: &Database,
+    policy: &PasswordPolicy,
+    username: &str,
+    password: &str,
+    role: Role,
+) -> Result<bool> {
+    policy.check(password)?;
+    Ok(db
+        .new_first_auth_user(username, &hash_password(password)?, role.as_str())?
+        .is_some())
+}
+
+fn hash_password(password: &str) -> Result<String> {
     let salt = SaltString::generate(OsRng);
-    if let Ok(hash) = PasswordHash::generate(Argon2::default(), new_password, &salt) {
-        let password_hash_string = hash.to_string();
-        db.set_auth_user_password(user.id, &password_hash_string)?;
-        Ok(())
+    match PasswordHash::generate(Argon2::default(), password, &salt) {
+        Ok(hash) => Ok(hash.to_string()),
+        Err(_) => bail!("Error generating password hash!"),
+    }
+}
+
+/// Applies `change` to the user `user_id`, fails if the user is the last
+/// administrator who can log in.
+fn change_unless_last_administrator(db: &Database, user_id: i32, change: AuthUserChange) -> Result<()> {
+    if !db.change_auth_user_unless_last(user_id, Role::Administrator.as_str(), change)? {
+        bail!("The last administrator can't be demoted, disabled or deleted.");
+    }
+    Ok(())
+}
+
+/// Changes the role of the user `user_id`. The last administrator keeps the role.
+pub fn set_role(db: &Database, user_id: i32, role: Role) -> Result<()> {
+    if role == Role::Administrator {
+        db.set_auth_user_role(user_id, role.as_str())?;
     } else {
-        bail!("Error generating password hash!")
+        change_unless_last_administrator(db, user_id, AuthUserChange::Role(role.as_str()))?;
     }
+    Ok(())
 }
 
-pub fn create_new_user(db: &Database, username: &str, password: &str) -> Result<()> {
-    let salt = SaltString::generate(OsRng);
-    if let Ok(hash) = PasswordHash::generate(Argon2::default(), password, &salt) {
-        let password_hash_string = hash.to_string();
-        db.new_auth_user(username, &password_hash_string)?;
-        Ok(())
+/// Disables or enables the user `user_id`. Disabling logs the user out, the
+/// last administrator can't be disabled.
+pub fn set_disabled(db: &Database, user_id: i32, disabled: bool) -> Result<()> {
+    if disabled {
+        change_unless_last_administrator(db, user_id, AuthUserChange::Disable)?;
+        db.delete_sessions_of_user(user_id)?;
     } else {
-        bail!("Error generating password hash!")
+        db.set_auth_user_disabled(user_id, false)?;
     }
+    Ok(())
+}
+
+/// Gives the user `username` back administrator access with `new_password`,
+/// for when nobody can log in anymore. Enables the user and logs it out.
+pub fn reset_administrator(db: &Database, policy: &PasswordPolicy, username: &str, new_password: &str) -> Result<()> {
+    policy.check(new_password)?;
+    let user = db.get_auth_user_by_username(username)?;
+    db.set_auth_user_password(user.id, &hash_password(new_password)?)?;
+    db.set_auth_user_role(user.id, Role::Administrator.as_str())?;
+    db.set_auth_user_disabled(user.id, false)?;
+    db.delete_sessions_of_user(user.id)?;
+    Ok(())
 }
 
+/// Deletes the user `username` with sessions and access tokens. The last
+/// administrator can't be deleted.
 pub fn delete_user(db: &Database, username: &str) -> Result<()> {
     let user = db.get_auth_user_by_username(username)?;
-    db.delete_auth_user(user.id)?;
+    change_unless_last_administrator(db, user.id, AuthUserChange::Delete)?;
     Ok(())
 }
 
@@ -0,0 +1,126 @@
+//! Throttling of failed logins and the requirements for new passwords.
+//!
+//! Both are read from the Rocket config by [`fairing`], as the tables
+//! `login_policy` and `password_policy`:
+//!
+//! ```toml
+//! [default.login_policy]
+//! max_failures = 5
+//! max_failures_per_ip = 20
+//! lockout_minutes = 15
+//!
+//! [default.password_policy]
+//! min_length = 10
+//! breached_passwords = "/etc/sit/breached_passwords.txt"
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use chrono::{Duration, NaiveDateTime};
+use rocket::fairing::AdHoc;
+use serde::Deserialize;
+
+use crate::database::Database;
+
+/// Common passwords that are always refused, one per line.
+const BREACHED_PASSWORDS: &str = include_str!("breached_passwords.txt");
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LoginPolicy {
+    /// Failed logins of one account within the lockout time before it is
+    /// locked. 0 for no limit.
+    pub max_failures: i64,
+    /// Failed logins from one address within the lockout time before it is
+    /// locked. 0 for no limit.
+    pub max_failures_per_ip: i64,
+    pub lockout_minutes: i64,
+}
+
+impl Default for LoginPolicy {
+    fn default() -> LoginPolicy {
+        LoginPolicy {
+            max_failures: 5,
+            max_failures_per_ip: 20,
+            lockout_minutes: 15,
+        }
+    }
+}
+
+impl LoginPolicy {
+    /// Whether logins for `username` or from `ip` are locked at `now`. The
+    /// attempt being made is expected to be recorded as failed already, so
+    /// concurrent attempts count against each other. A successful login resets
+    /// the count of the account, not that of the address.
+    pub fn is_locked(&self, db: &Database, username: &str, ip: Option<&str>, now: NaiveDateTime) -> Result<bool> {
+        let since = now - Duration::minutes(self.lockout_minutes);
+        let attempts = db.login_attempt_manager();
+        if self.max_failures > 0 && attempts.count_failures_by_username(username, since)? > self.max_failures {
+            return Ok(true);
+        }
+        if let Some(ip) = ip {
+            if self.max_failures_per_ip > 0 && attempts.count_failures_by_ip(ip, since)? > self.max_failures_per_ip {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    /// Counted in characters.
+    pub min_length: usize,
+    /// A file with known breached passwords, one per line, refused in
+    /// addition to the built-in list.
+    pub breached_passwords: Option<PathBuf>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 10,
+            breached_passwords: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Fails with the reason if `password` doesn't meet the policy.
+    pub fn check(&self, password: &str) -> Result<()> {
+        if password.chars().count() < self.min_length {
+            bail!("The password must be at least {} characters long.", self.min_length);
+        }
+        if self.is_breached(password)? {
+            bail!("The password is known from data breaches, please choose another one.");
+        }
+        Ok(())
+    }
+
+    /// Compares case-insensitively, a breached password stays guessable with
+    /// other capitalization.
+    fn is_breached(&self, password: &str) -> Result<bool> {
+        let password = password.to_lowercase();
+        let contains = |list: &str| list.lines().any(|line| line.trim().to_lowercase() == password);
+        if contains(BREACHED_PASSWORDS) {
+            return Ok(true);
+        }
+        match &self.breached_passwords {
+            Some(path) => Ok(contains(&fs::read_to_string(path)?)),
+            None => Ok(false),
+        }
+    }
+}
+
+/// Manages the [`LoginPolicy`] and [`PasswordPolicy`] from the config, with
+/// the defaults where they are missing.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_ignite("Login and password policy", |rocket| async move {
+        let login_policy: LoginPolicy = rocket.figment().extract_inner("login_policy").unwrap_or_default();
+        let password_policy: PasswordPolicy = rocket.figment().extract_inner("password_policy").unwrap_or_default();
+        rocket.manage(login_policy).manage(password_policy)
+    })
+}
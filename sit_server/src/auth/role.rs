@@ -0,0 +1,170 @@
+//! Roles of web users and what they permit.
+//!
+//! Every user has one [`Role`], which grants a fixed set of [`Permission`]s.
+//! Viewing the inventory needs no permission. A user can additionally be
+//! restricted to some client groups, see [`GroupRestriction`].
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use rocket::http::Status;
+use serde::Serialize;
+
+use crate::database::Database;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Administrator,
+    Technician,
+    Auditor,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Administrator, Role::Technician, Role::Auditor];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Administrator => "administrator",
+            Role::Technician => "technician",
+            Role::Auditor => "auditor",
+        }
+    }
+
+    /// Shown in the web UI.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Administrator => "Administrator",
+            Role::Technician => "Techniker",
+            Role::Auditor => "Prüfer (nur lesen)",
+        }
+    }
+
+    pub fn permits(&self, permission: Permission) -> bool {
+        match self {
+            Role::Administrator => true,
+            Role::Technician => matches!(
+                permission,
+                Permission::EditInventory | Permission::RunTasks | Permission::ViewLicenseKeys
+            ),
+            Role::Auditor => false,
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Role::ALL
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| anyhow!("Unknown role: '{s}'"))
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Permission {
+    /// Create, edit and delete web users.
+    ManageUsers,
+    /// Groups, attributes, the ingest queue and the software cleanup.
+    ManageSettings,
+    /// Group, location, asset and attribute assignments, locations and asset imports.
+    EditInventory,
+    /// Tasks sent to the clients, such as deleting profiles.
+    RunTasks,
+    ViewLicenseKeys,
+}
+
+/// What a role permits, for showing only the usable links and forms in the templates.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Permissions {
+    pub manage_users: bool,
+    pub manage_settings: bool,
+    pub edit_inventory: bool,
+    pub run_tasks: bool,
+    pub view_license_keys: bool,
+}
+
+impl From<Role> for Permissions {
+    fn from(role: Role) -> Permissions {
+        Permissions {
+            manage_users: role.permits(Permission::ManageUsers),
+            manage_settings: role.permits(Permission::ManageSettings),
+            edit_inventory: role.permits(Permission::EditInventory),
+            run_tasks: role.permits(Permission::RunTasks),
+            view_license_keys: role.permits(Permission::ViewLicenseKeys),
+        }
+    }
+}
+
+/// The client groups a user sees. Empty for all clients.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GroupRestriction {
+    groups: Vec<i32>,
+}
+
+impl GroupRestriction {
+    pub fn new(groups: Vec<i32>) -> GroupRestriction {
+        GroupRestriction { groups }
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        !self.groups.is_empty()
+    }
+
+    pub fn allows_group(&self, group_id: i32) -> bool {
+        !self.is_restricted() || self.groups.contains(&group_id)
+    }
+
+    /// The group a list is shown for when `requested` is asked for. Restricted
+    /// users get their first group instead of all clients or another group.
+    pub fn scope(&self, requested: Option<i32>) -> Option<i32> {
+        match requested {
+            Some(group_id) if self.allows_group(group_id) => Some(group_id),
+            _ if self.is_restricted() => self.groups.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// `Forbidden` for pages that show clients of every group.
+    pub fn require_unrestricted(&self) -> Result<(), Status> {
+        if self.is_restricted() {
+            Err(Status::Forbidden)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// `Forbidden` unless the group `group_id` is one of the allowed groups.
+    pub fn require_group(&self, group_id: i32) -> Result<(), Status> {
+        if self.allows_group(group_id) {
+            Ok(())
+        } else {
+            Err(Status::Forbidden)
+        }
+    }
+
+    /// `Forbidden` unless the client `client_id` is a member of an allowed group.
+    pub fn require_client(&self, db: &Database, client_id: i32) -> Result<(), Status> {
+        if !self.is_restricted() {
+            return Ok(());
+        }
+        let groups = db
+            .group_manager()
+            .get_client_groups(client_id)
+            .map_err(|_| Status::InternalServerError)?;
+        if groups.iter().any(|g| self.groups.contains(&g.id)) {
+            Ok(())
+        } else {
+            Err(Status::Forbidden)
+        }
+    }
+}
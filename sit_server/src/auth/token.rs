@@ -0,0 +1,227 @@
+//! Personal access tokens for the query API.
+//!
+//! A token is sent as `Authorization: Bearer <token>` and grants read access to
+//! the resources of its scopes until it expires or is revoked.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{NaiveDateTime, Utc};
+use rand::{distr::Alphanumeric, rng, Rng};
+use rocket::{
+    http::Status,
+    outcome::try_outcome,
+    request::{FromRequest, Outcome, Request},
+    State,
+};
+use rocket_okapi::{
+    gen::OpenApiGenerator,
+    okapi::openapi3::{Object, SecurityRequirement, SecurityScheme, SecuritySchemeData},
+    request::{OpenApiFromRequest, RequestHeaderInput},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::{GroupRestriction, Permission, Role, User};
+use crate::database::Database;
+
+const TOKEN_PREFIX: &str = "sit_";
+/// Name of the security scheme in the OpenAPI document.
+const SECURITY_SCHEME: &str = "AccessToken";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Clients,
+    Hardware,
+    Software,
+    Profiles,
+    Volumes,
+    Licenses,
+    Tasks,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 7] = [
+        Scope::Clients,
+        Scope::Hardware,
+        Scope::Software,
+        Scope::Profiles,
+        Scope::Volumes,
+        Scope::Licenses,
+        Scope::Tasks,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Clients => "clients",
+            Scope::Hardware => "hardware",
+            Scope::Software => "software",
+            Scope::Profiles => "profiles",
+            Scope::Volumes => "volumes",
+            Scope::Licenses => "licenses",
+            Scope::Tasks => "tasks",
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Scope::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| anyhow!("Unknown scope: '{s}'"))
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A valid access token presented with the request. It is limited by the role
+/// and the group restriction of its user, too.
+pub struct ApiToken {
+    pub token_id: i32,
+    pub user_id: i32,
+    pub scopes: Vec<Scope>,
+    pub role: Role,
+    pub groups: GroupRestriction,
+}
+
+impl ApiToken {
+    /// `Forbidden` unless the token was granted `scope`.
+    pub fn require(&self, scope: Scope) -> Result<(), Status> {
+        if self.scopes.contains(&scope) {
+            Ok(())
+        } else {
+            Err(Status::Forbidden)
+        }
+    }
+
+    /// `Forbidden` unless the role of the token's user grants `permission`.
+    pub fn require_permission(&self, permission: Permission) -> Result<(), Status> {
+        if self.role.permits(permission) {
+            Ok(())
+        } else {
+            Err(Status::Forbidden)
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiToken {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<ApiToken, ()> {
+        let db: &State<Database> = try_outcome!(request.guard::<&State<Database>>().await);
+        let Some(token) = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+        else {
+            return Outcome::Error((Status::Unauthorized, ()));
+        };
+        let hash = hash_token(token.trim());
+        let token = db
+            .run(move |db| {
+                let access_token = db.access_token_manager().get_token_by_hash(&hash).ok()?;
+                let now = Utc::now().naive_utc();
+                if access_token.expires.is_some_and(|expires| expires <= now) {
+                    return None;
+                }
+                let user = User::load(db, access_token.user_id).ok()?;
+                let _ = db.access_token_manager().set_last_used(access_token.id, now);
+                Some(ApiToken {
+                    token_id: access_token.id,
+                    user_id: access_token.user_id,
+                    scopes: parse_scopes(&access_token.scopes),
+                    role: user.role,
+                    groups: user.groups,
+                })
+            })
+            .await;
+        match token {
+            Some(token) => Outcome::Success(token),
+            None => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+impl<'r> OpenApiFromRequest<'r> for ApiToken {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        let scheme = SecurityScheme {
+            description: Some("Personal access token, created on /auth.".to_owned()),
+            data: SecuritySchemeData::Http {
+                scheme: "bearer".to_owned(),
+                bearer_format: None,
+            },
+            extensions: Object::default(),
+        };
+        let mut requirement = SecurityRequirement::new();
+        requirement.insert(SECURITY_SCHEME.to_owned(), vec![]);
+        Ok(RequestHeaderInput::Security(
+            SECURITY_SCHEME.to_owned(),
+            scheme,
+            requirement,
+        ))
+    }
+}
+
+/// Creates a token for the user `user_id` and returns it. Only its hash is stored,
+/// so this is the only time the token can be shown.
+pub fn create_token(
+    db: &Database,
+    user_id: i32,
+    name: &str,
+    scopes: &[Scope],
+    expires: Option<NaiveDateTime>,
+) -> Result<String> {
+    if name.trim().is_empty() {
+        bail!("A token needs a name");
+    }
+    if scopes.is_empty() {
+        bail!("A token needs at least one scope");
+    }
+    let token = generate_token();
+    let scopes: Vec<&str> = scopes.iter().map(Scope::as_str).collect();
+    db.access_token_manager().create_token(
+        user_id,
+        name.trim(),
+        &hash_token(&token),
+        &scopes.join(" "),
+        Utc::now().naive_utc(),
+        expires,
+    )?;
+    Ok(token)
+}
+
+/// Scopes of a stored token, unknown ones are dropped.
+pub fn parse_scopes(scopes: &str) -> Vec<Scope> {
+    scopes.split_whitespace().filter_map(|s| s.parse().ok()).collect()
+}
+
+fn generate_token() -> String {
+    let secret: String = rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(|c| c as char)
+        .collect();
+    format!("{TOKEN_PREFIX}{secret}")
+}
+
+/// Tokens are random enough that a plain SHA-256 is sufficient, unlike passwords.
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
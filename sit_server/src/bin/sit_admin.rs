@@ -0,0 +1,147 @@
+//! Administration of an installation from the command line.
+//!
+//! Usage: `sit_admin <command> [arguments]`, see [`USAGE`]. The database is
+//! taken from `DATABASE_URL` like the server does and is migrated on connect.
+//! Passwords are read from the first line of stdin, so they don't end up in
+//! the shell history, and have to meet the `password_policy` of the Rocket
+//! config.
+
+use std::env;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{Duration, Utc};
+use dotenv::dotenv;
+use sit_server::auth::{self, PasswordPolicy, Role};
+use sit_server::database::Database;
+
+const USAGE: &str = "Usage: sit_admin <command> [arguments]
+
+Commands:
+  create-admin <username>  Creates an administrator with a password read from stdin
+  reset-admin <username>   Sets a password read from stdin, makes the user an enabled
+                           administrator and logs it out everywhere
+  migrate                  Brings the database schema up to date
+  list-clients             Lists the clients with computer name and last contact
+  purge-stale <days>       Deletes clients that sent nothing for <days> days,
+                           expired sessions and older login attempts
+  software-cleanup         Deletes software versions no client has installed and
+                           software without versions";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let command = match args.as_slice() {
+        ["create-admin", username] => Command::CreateAdmin(username),
+        ["reset-admin", username] => Command::ResetAdmin(username),
+        ["migrate"] => Command::Migrate,
+        ["list-clients"] => Command::ListClients,
+        ["purge-stale", days] => match days.parse::<i64>() {
+            Ok(days) if days > 0 => Command::PurgeStale(days),
+            _ => {
+                println!("[ERROR] <days> must be a positive number");
+                return ExitCode::FAILURE;
+            }
+        },
+        ["software-cleanup"] => Command::SoftwareCleanup,
+        _ => {
+            println!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match connect().and_then(|database| command.run(&database)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            println!("[ERROR] {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+enum Command<'a> {
+    CreateAdmin(&'a str),
+    ResetAdmin(&'a str),
+    Migrate,
+    ListClients,
+    PurgeStale(i64),
+    SoftwareCleanup,
+}
+
+impl Command<'_> {
+    fn run(&self, database: &Database) -> Result<()> {
+        match self {
+            Command::CreateAdmin(username) => {
+                let password = read_password()?;
+                auth::create_new_user(database, &password_policy(), username, &password, Role::Administrator)?;
+                println!("Created administrator {username}");
+            }
+            Command::ResetAdmin(username) => {
+                let password = read_password()?;
+                auth::reset_administrator(database, &password_policy(), username, &password)?;
+                println!("Reset administrator {username}");
+            }
+            // Connecting already ran the pending migrations.
+            Command::Migrate => println!("Database schema is up to date"),
+            Command::ListClients => {
+                for (client, os_info) in database.get_clients_with_os_info(None)? {
+                    println!(
+                        "{}\t{}\t{}",
+                        client.uuid,
+                        os_info.map(|o| o.computer_name).unwrap_or_default(),
+                        client
+                            .last_seen
+                            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_else(|| "-".to_string()),
+                    );
+                }
+            }
+            Command::PurgeStale(days) => {
+                let now = Utc::now().naive_utc();
+                let cutoff = now - Duration::days(*days);
+                let clients = database.delete_clients_not_seen_since(cutoff)?;
+                let sessions = database.delete_expired_sessions(now)?;
+                let attempts = database.login_attempt_manager().delete_attempts_before(cutoff)?;
+                println!("Deleted {clients} clients, {sessions} expired sessions and {attempts} login attempts");
+                if clients > 0 {
+                    database.statistics_manager().mark_changed(now)?;
+                }
+            }
+            Command::SoftwareCleanup => {
+                let versions = database.delete_unused_software_versions()?;
+                let software = database.delete_software_without_versions()?;
+                println!("Deleted {versions} software versions and {software} software entries");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn connect() -> Result<Database> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").map_err(|_| anyhow!("DATABASE_URL must be set"))?;
+    Database::connect(&database_url, 1)
+}
+
+/// The policy the server applies, from `Rocket.toml` and `ROCKET_` variables.
+fn password_policy() -> PasswordPolicy {
+    rocket::Config::figment()
+        .extract_inner("password_policy")
+        .unwrap_or_default()
+}
+
+fn read_password() -> Result<String> {
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        print!("Password: ");
+        io::stdout().flush()?;
+    }
+    let mut password = String::new();
+    stdin.lock().read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        bail!("The password must not be empty");
+    }
+    Ok(password)
+}
@@ -0,0 +1,36 @@
+//! Moves a SQLite based installation to Postgres.
+//!
+//! Usage: `sit_migrate_to_postgres sqlite://<path> postgres://<url>`
+//!
+//! The Postgres database gets migrated first and has to be empty. Stop the server
+//! before running this, clients reporting in during the copy would be lost.
+
+use std::env;
+use std::process::ExitCode;
+
+use sit_server::database::Database;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    let [_, source_url, target_url] = args.as_slice() else {
+        println!("Usage: sit_migrate_to_postgres sqlite://<path> postgres://<url>");
+        return ExitCode::FAILURE;
+    };
+
+    let result = Database::connect(source_url, 1)
+        .and_then(|source| Ok((source, Database::connect(target_url, 1)?)))
+        .and_then(|(source, target)| source.copy_into(&target));
+
+    match result {
+        Ok(copied) => {
+            for (table, count) in copied {
+                println!("{table}: {count} rows");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("[ERROR] Migration failed: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
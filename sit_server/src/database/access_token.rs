@@ -0,0 +1,80 @@
+//! Personal access tokens for the query API.
+//!
+//! Only a hash of each token is stored, the token itself is shown once when it
+//! is created.
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use super::{model::*, schema::*, DbPool};
+
+pub struct AccessTokenManager {
+    pool: DbPool,
+}
+
+impl AccessTokenManager {
+    pub fn new(pool: DbPool) -> AccessTokenManager {
+        AccessTokenManager { pool }
+    }
+
+    /// Tokens of the user `user_id`, newest first.
+    pub fn get_tokens(&self, user_id: i32) -> Result<Vec<AccessToken>> {
+        with_conn!(self.pool, |conn| {
+            Ok(access_token::table
+                .filter(access_token::user_id.eq(user_id))
+                .order_by((access_token::created.desc(), access_token::id.desc()))
+                .load(&mut conn)?)
+        })
+    }
+
+    pub fn get_token_by_hash(&self, token_hash: &str) -> Result<AccessToken> {
+        with_conn!(self.pool, |conn| {
+            Ok(access_token::table
+                .filter(access_token::token_hash.eq(token_hash))
+                .get_result(&mut conn)?)
+        })
+    }
+
+    pub fn create_token(
+        &self,
+        user_id: i32,
+        name: &str,
+        token_hash: &str,
+        scopes: &str,
+        created: NaiveDateTime,
+        expires: Option<NaiveDateTime>,
+    ) -> Result<AccessToken> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::insert_into(access_token::table)
+                .values(NewAccessToken {
+                    user_id,
+                    name,
+                    token_hash,
+                    scopes,
+                    created,
+                    expires,
+                })
+                .get_result(&mut conn)?)
+        })
+    }
+
+    pub fn set_last_used(&self, token_id: i32, last_used: NaiveDateTime) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::update(access_token::table)
+                .filter(access_token::id.eq(token_id))
+                .set(access_token::last_used.eq(last_used))
+                .execute(&mut conn)?)
+        })
+    }
+
+    /// Deletes the token `token_id` if it belongs to the user `user_id`.
+    pub fn revoke_token(&self, user_id: i32, token_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(access_token::table)
+                .filter(access_token::id.eq(token_id))
+                .filter(access_token::user_id.eq(user_id))
+                .execute(&mut conn)?)
+        })
+    }
+}
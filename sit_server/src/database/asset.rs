@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use bigdecimal::{BigDecimal, RoundingMode};
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use super::{model::*, schema::*, DbPool};
+
+/// A client with its asset record, os info and model where known.
+pub type ClientAsset = (Asset, (Client, Option<OsInfo>, Option<ComputerModel>));
+
+/// Outcome of an asset import, `skipped` holds a message per rejected line.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AssetImport {
+    pub imported: usize,
+    pub skipped: Vec<String>,
+}
+
+/// Asset fields as entered by a user. `None` keeps the stored value, an empty
+/// string clears it.
+#[derive(Clone, Debug, Default)]
+pub struct AssetInput<'a> {
+    pub inventory_number: Option<&'a str>,
+    pub purchase_date: Option<&'a str>,
+    pub supplier: Option<&'a str>,
+    pub price: Option<&'a str>,
+    pub warranty_end: Option<&'a str>,
+    pub responsible: Option<&'a str>,
+}
+
+/// Columns an asset import understands, matched case-insensitively against the header line.
+const IMPORT_COLUMNS: [&str; 7] = [
+    "serial_number",
+    "inventory_number",
+    "purchase_date",
+    "supplier",
+    "price",
+    "warranty_end",
+    "responsible",
+];
+
+/// Parses a date as `YYYY-MM-DD` or `DD.MM.YYYY`, empty input is no date.
+fn parse_date(s: &str) -> Result<Option<NaiveDate>> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%d.%m.%Y"))
+        .map(Some)
+        .map_err(|_| anyhow!("Invalid date: '{s}'"))
+}
+
+/// Parses a price with `.` or `,` as decimal separator, rounded to cents. Empty
+/// input is no price.
+fn parse_price(s: &str) -> Result<Option<BigDecimal>> {
+    let s = s.trim().trim_end_matches('€').trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    BigDecimal::from_str(&s.replace(',', "."))
+        .map(|p| Some(p.with_scale_round(2, RoundingMode::HalfUp)))
+        .map_err(|_| anyhow!("Invalid price: '{s}'"))
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    Some(s.trim().to_owned()).filter(|s| !s.is_empty())
+}
+
+/// Splits one CSV line at `delimiter`, fields may be quoted with `"`.
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+pub struct AssetManager {
+    pool: DbPool,
+}
+
+impl AssetManager {
+    pub fn new(pool: DbPool) -> AssetManager {
+        AssetManager { pool }
+    }
+
+    pub fn get_asset(&self, client_id: i32) -> Result<Option<Asset>> {
+        with_conn!(self.pool, |conn| {
+            Ok(asset::table
+                .filter(asset::client_id.eq(client_id))
+                .get_result(&mut conn)
+                .optional()?)
+        })
+    }
+
+    /// Creates or replaces the asset record of `asset.client_id`.
+    pub fn set_asset(&self, asset: &Asset) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            diesel::insert_into(asset::table)
+                .values(asset)
+                .on_conflict(asset::client_id)
+                .do_update()
+                .set(asset)
+                .execute(&mut conn)?;
+            Ok(())
+        })
+    }
+
+    /// Asset records of all clients, or of the members of `group`.
+    pub fn get_assets(&self, group: Option<i32>) -> Result<Vec<ClientAsset>> {
+        with_conn!(self.pool, |conn| {
+            Ok(asset::table
+                .inner_join(
+                    client::table
+                        .left_join(os_info::table)
+                        .left_join(computer_model::table),
+                )
+                .filter(in_group!(asset::client_id, group))
+                .order_by(os_info::computer_name)
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Clients whose warranty ends on or before `until`, including already expired ones,
+    /// soonest first.
+    pub fn get_expiring_warranties(&self, until: NaiveDate, group: Option<i32>) -> Result<Vec<ClientAsset>> {
+        with_conn!(self.pool, |conn| {
+            Ok(asset::table
+                .inner_join(
+                    client::table
+                        .left_join(os_info::table)
+                        .left_join(computer_model::table),
+                )
+                .filter(asset::warranty_end.le(until))
+                .filter(in_group!(asset::client_id, group))
+                .order_by((asset::warranty_end, os_info::computer_name))
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Imports asset records from CSV, matched to clients by the serial number the
+    /// agent reported. The first line names the columns (see [`IMPORT_COLUMNS`]),
+    /// `serial_number` is required, unknown columns are ignored. Fields are separated
+    /// by `;` or `,`, whichever the header uses. Lines of clients that fail
+    /// `allows_client` are skipped.
+    pub fn import_csv(&self, csv: &str, allows_client: impl Fn(i32) -> bool) -> Result<AssetImport> {
+        let mut lines = csv
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        let (_, header) = lines.next().ok_or_else(|| anyhow!("Empty file"))?;
+        let header = header.trim_start_matches('\u{feff}');
+        let delimiter = if header.contains(';') { ';' } else { ',' };
+        let columns: HashMap<&str, usize> = split_csv_line(header, delimiter)
+            .iter()
+            .enumerate()
+            .filter_map(|(i, name)| {
+                let name = name.trim().to_lowercase();
+                IMPORT_COLUMNS.iter().find(|c| **c == name).map(|c| (*c, i))
+            })
+            .collect();
+        if !columns.contains_key("serial_number") {
+            return Err(anyhow!("Column 'serial_number' missing"));
+        }
+
+        let serials: HashMap<String, i32> = with_conn!(self.pool, |conn| {
+            computer_model::table
+                .select((computer_model::serial_number, computer_model::client_id))
+                .load::<(String, i32)>(&mut conn)
+        })?
+        .into_iter()
+        .map(|(serial, client_id)| (serial.trim().to_uppercase(), client_id))
+        .collect();
+
+        let mut import = AssetImport::default();
+        for (index, line) in lines {
+            let fields = split_csv_line(line, delimiter);
+            let field = |name: &str| columns.get(name).and_then(|i| fields.get(*i)).map(|f| f.as_str());
+            let serial = field("serial_number").unwrap_or_default().trim().to_uppercase();
+            let Some(client_id) = serials.get(&serial) else {
+                import.skipped.push(format!("Line {}: unknown serial number '{serial}'", index + 1));
+                continue;
+            };
+            if !allows_client(*client_id) {
+                import.skipped.push(format!("Line {}: client outside your groups", index + 1));
+                continue;
+            }
+            let input = AssetInput {
+                inventory_number: field("inventory_number"),
+                purchase_date: field("purchase_date"),
+                supplier: field("supplier"),
+                price: field("price"),
+                warranty_end: field("warranty_end"),
+                responsible: field("responsible"),
+            };
+            match self.update_asset(*client_id, &input) {
+                Ok(()) => import.imported += 1,
+                Err(e) => import.skipped.push(format!("Line {}: {e}", index + 1)),
+            }
+        }
+        Ok(import)
+    }
+
+    /// Applies `input` to the asset record of `client_id`, creating it if needed.
+    pub fn update_asset(&self, client_id: i32, input: &AssetInput) -> Result<()> {
+        let mut asset = self.get_asset(client_id)?.unwrap_or(Asset {
+            client_id,
+            ..Default::default()
+        });
+        if let Some(f) = input.inventory_number {
+            asset.inventory_number = non_empty(f);
+        }
+        if let Some(f) = input.purchase_date {
+            asset.purchase_date = parse_date(f)?;
+        }
+        if let Some(f) = input.supplier {
+            asset.supplier = non_empty(f);
+        }
+        if let Some(f) = input.price {
+            asset.price = parse_price(f)?;
+        }
+        if let Some(f) = input.warranty_end {
+            asset.warranty_end = parse_date(f)?;
+        }
+        if let Some(f) = input.responsible {
+            asset.responsible = non_empty(f);
+        }
+        self.set_asset(&asset)
+    }
+}
@@ -0,0 +1,219 @@
+//! User-defined attributes on clients.
+//!
+//! Values are stored as text in a canonical form per kind, so they compare the
+//! same way in the web UI and in group rules: numbers as plain decimals, dates as
+//! `YYYY-MM-DD`, booleans as `true`/`false` and enum values exactly as defined.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use super::{model::*, schema::*, DbPool};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeKind {
+    Text,
+    Number,
+    Date,
+    Enum,
+    Boolean,
+}
+
+impl AttributeKind {
+    pub const ALL: [AttributeKind; 5] = [
+        AttributeKind::Text,
+        AttributeKind::Number,
+        AttributeKind::Date,
+        AttributeKind::Enum,
+        AttributeKind::Boolean,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttributeKind::Text => "text",
+            AttributeKind::Number => "number",
+            AttributeKind::Date => "date",
+            AttributeKind::Enum => "enum",
+            AttributeKind::Boolean => "boolean",
+        }
+    }
+}
+
+impl FromStr for AttributeKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        AttributeKind::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| anyhow!("Unknown attribute kind: '{s}'"))
+    }
+}
+
+impl fmt::Display for AttributeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AttributeDefinition {
+    /// Kinds unknown to this version are treated as text.
+    pub fn kind(&self) -> AttributeKind {
+        self.kind.parse().unwrap_or(AttributeKind::Text)
+    }
+
+    /// The allowed values of an enum attribute.
+    pub fn option_list(&self) -> Vec<&str> {
+        self.options
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|o| !o.is_empty())
+            .collect()
+    }
+
+    /// The canonical form of `value`, `None` if it is empty.
+    pub fn normalize(&self, value: &str) -> Result<Option<String>> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(None);
+        }
+        let normalized = match self.kind() {
+            AttributeKind::Text => value.to_owned(),
+            AttributeKind::Number => {
+                let number = BigDecimal::from_str(&value.replace(',', "."))
+                    .map_err(|_| anyhow!("Not a number: '{value}'"))?
+                    .to_plain_string();
+                if number.contains('.') {
+                    number.trim_end_matches('0').trim_end_matches('.').to_owned()
+                } else {
+                    number
+                }
+            }
+            AttributeKind::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .or_else(|_| NaiveDate::parse_from_str(value, "%d.%m.%Y"))
+                .map_err(|_| anyhow!("Invalid date: '{value}'"))?
+                .to_string(),
+            AttributeKind::Enum => self
+                .option_list()
+                .into_iter()
+                .find(|o| o.eq_ignore_ascii_case(value))
+                .ok_or_else(|| anyhow!("'{value}' is not an option of {}", self.name))?
+                .to_owned(),
+            AttributeKind::Boolean => match value.to_lowercase().as_str() {
+                "true" | "1" | "ja" | "yes" | "on" => "true".to_owned(),
+                "false" | "0" | "nein" | "no" | "off" => "false".to_owned(),
+                _ => return Err(anyhow!("Not a boolean: '{value}'")),
+            },
+        };
+        Ok(Some(normalized))
+    }
+}
+
+pub struct AttributeManager {
+    pool: DbPool,
+}
+
+impl AttributeManager {
+    pub fn new(pool: DbPool) -> AttributeManager {
+        AttributeManager { pool }
+    }
+
+    pub fn get_definitions(&self) -> Result<Vec<AttributeDefinition>> {
+        with_conn!(self.pool, |conn| {
+            Ok(attribute_definition::table
+                .order_by(attribute_definition::name)
+                .load(&mut conn)?)
+        })
+    }
+
+    pub fn get_definition(&self, attribute_id: i32) -> Result<AttributeDefinition> {
+        with_conn!(self.pool, |conn| {
+            Ok(attribute_definition::table
+                .filter(attribute_definition::id.eq(attribute_id))
+                .get_result(&mut conn)?)
+        })
+    }
+
+    /// Creates an attribute. `options` lists the values of an enum attribute, one per
+    /// line, and is ignored for other kinds.
+    pub fn create_definition(&self, name: &str, kind: AttributeKind, options: &str) -> Result<AttributeDefinition> {
+        let options: Vec<&str> = options.lines().map(str::trim).filter(|o| !o.is_empty()).collect();
+        let options = match kind {
+            AttributeKind::Enum if options.is_empty() => {
+                return Err(anyhow!("An enum attribute needs at least one option"))
+            }
+            AttributeKind::Enum => Some(options.join("\n")),
+            _ => None,
+        };
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::insert_into(attribute_definition::table)
+                .values(NewAttributeDefinition {
+                    name,
+                    kind: kind.as_str(),
+                    options: options.as_deref(),
+                })
+                .get_result(&mut conn)?)
+        })
+    }
+
+    pub fn delete_definition(&self, attribute_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(attribute_definition::table)
+                .filter(attribute_definition::id.eq(attribute_id))
+                .execute(&mut conn)?)
+        })
+    }
+
+    pub fn get_client_attributes(&self, client_id: i32) -> Result<Vec<ClientAttribute>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_attribute::table
+                .filter(client_attribute::client_id.eq(client_id))
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Attribute values of the clients `client_ids`.
+    pub fn get_attributes_of_clients(&self, client_ids: &[i32]) -> Result<Vec<ClientAttribute>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_attribute::table
+                .filter(client_attribute::client_id.eq_any(client_ids))
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Sets the value of an attribute on a client, an empty value removes it.
+    pub fn set_client_attribute(&self, client_id: i32, attribute_id: i32, value: &str) -> Result<()> {
+        let value = self.get_definition(attribute_id)?.normalize(value)?;
+        with_conn!(self.pool, |conn| {
+            match value {
+                Some(value) => {
+                    diesel::insert_into(client_attribute::table)
+                        .values(ClientAttribute {
+                            client_id,
+                            attribute_id,
+                            value: value.clone(),
+                        })
+                        .on_conflict((client_attribute::client_id, client_attribute::attribute_id))
+                        .do_update()
+                        .set(client_attribute::value.eq(value))
+                        .execute(&mut conn)?;
+                }
+                None => {
+                    diesel::delete(client_attribute::table)
+                        .filter(client_attribute::client_id.eq(client_id))
+                        .filter(client_attribute::attribute_id.eq(attribute_id))
+                        .execute(&mut conn)?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,150 @@
+//! The flat "all clients with all hardware" export.
+//!
+//! Every table is loaded once for the whole group and assigned to the clients
+//! in memory, instead of querying per client.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use bigdecimal::BigDecimal;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use super::{model::*, schema::*, Database};
+
+/// One client with its hardware, asset record and custom attributes.
+#[derive(Debug)]
+pub struct InventoryRow {
+    pub uuid: Uuid,
+    pub computer_name: String,
+    pub domain: String,
+    pub os: String,
+    pub os_version: String,
+    pub model: Option<ComputerModel>,
+    pub bios: Option<Bios>,
+    pub processor: Option<Processor>,
+    pub memory_capacity: Option<BigDecimal>,
+    pub memory_sticks: Option<i64>,
+    pub disks: Vec<Disk>,
+    pub graphics_cards: Vec<String>,
+    pub network_adapters: Vec<NetworkAdapter>,
+    pub ip_addresses: Vec<String>,
+    pub asset: Option<Asset>,
+    /// Custom attribute values by attribute id.
+    pub attributes: HashMap<i32, String>,
+}
+
+impl Database {
+    /// All clients of `group`, ordered by name.
+    pub fn get_inventory_rows(&self, group: Option<i32>) -> Result<Vec<InventoryRow>> {
+        with_conn!(self.pool, |conn| {
+            let clients = client::table
+                .left_join(os_info::table)
+                .filter(in_group!(client::id, group))
+                .order_by((os_info::computer_name, client::id))
+                .load::<(Client, Option<OsInfo>)>(&mut conn)?;
+
+            let mut models: HashMap<i32, ComputerModel> = computer_model::table
+                .filter(in_group!(computer_model::client_id, group))
+                .load::<ComputerModel>(&mut conn)?
+                .into_iter()
+                .map(|m| (m.client_id, m))
+                .collect();
+            let mut bios: HashMap<i32, Bios> = bios::table
+                .filter(in_group!(bios::client_id, group))
+                .load::<Bios>(&mut conn)?
+                .into_iter()
+                .map(|b| (b.client_id, b))
+                .collect();
+            let mut processors: HashMap<i32, Processor> = processor::table
+                .filter(in_group!(processor::client_id, group))
+                .load::<Processor>(&mut conn)?
+                .into_iter()
+                .map(|p| (p.client_id, p))
+                .collect();
+            let mut memory: HashMap<i32, (Option<BigDecimal>, Option<i64>)> = memory::table
+                .filter(in_group!(memory::client_id, group))
+                .select((memory::client_id, memory::capacity, memory::sticks))
+                .load::<(i32, Option<BigDecimal>, Option<i64>)>(&mut conn)?
+                .into_iter()
+                .map(|(client_id, capacity, sticks)| (client_id, (capacity, sticks)))
+                .collect();
+            let mut disks: HashMap<i32, Vec<Disk>> = HashMap::new();
+            for disk in disks::table
+                .filter(in_group!(disks::client_id, group))
+                .order_by(disks::device_id)
+                .load::<Disk>(&mut conn)?
+            {
+                disks.entry(disk.client_id).or_default().push(disk);
+            }
+            let mut graphics_cards: HashMap<i32, Vec<String>> = HashMap::new();
+            for (client_id, name) in graphics_card::table
+                .filter(in_group!(graphics_card::client_id, group))
+                .select((graphics_card::client_id, graphics_card::name))
+                .order_by(graphics_card::name)
+                .load::<(i32, String)>(&mut conn)?
+            {
+                graphics_cards.entry(client_id).or_default().push(name);
+            }
+            let mut network_adapters: HashMap<i32, Vec<NetworkAdapter>> = HashMap::new();
+            for adapter in network_adapter::table
+                .filter(in_group!(network_adapter::client_id, group))
+                .order_by(network_adapter::name)
+                .load::<NetworkAdapter>(&mut conn)?
+            {
+                network_adapters.entry(adapter.client_id).or_default().push(adapter);
+            }
+            let mut ip_addresses: HashMap<i32, Vec<String>> = HashMap::new();
+            for (client_id, ip) in network_adapter_ip::table
+                .inner_join(network_adapter::table)
+                .filter(in_group!(network_adapter::client_id, group))
+                .select((network_adapter::client_id, network_adapter_ip::ip))
+                .order_by(network_adapter_ip::ip)
+                .load::<(i32, String)>(&mut conn)?
+            {
+                ip_addresses.entry(client_id).or_default().push(ip);
+            }
+            let mut assets: HashMap<i32, Asset> = asset::table
+                .filter(in_group!(asset::client_id, group))
+                .load::<Asset>(&mut conn)?
+                .into_iter()
+                .map(|a| (a.client_id, a))
+                .collect();
+            let mut attributes: HashMap<i32, HashMap<i32, String>> = HashMap::new();
+            for value in client_attribute::table
+                .filter(in_group!(client_attribute::client_id, group))
+                .load::<ClientAttribute>(&mut conn)?
+            {
+                attributes
+                    .entry(value.client_id)
+                    .or_default()
+                    .insert(value.attribute_id, value.value);
+            }
+
+            Ok(clients
+                .into_iter()
+                .map(|(c, os_info)| {
+                    let (memory_capacity, memory_sticks) = memory.remove(&c.id).unwrap_or_default();
+                    InventoryRow {
+                        uuid: c.uuid,
+                        computer_name: os_info.as_ref().map(|o| o.computer_name.clone()).unwrap_or_default(),
+                        domain: os_info.as_ref().and_then(|o| o.domain.clone()).unwrap_or_default(),
+                        os: os_info.as_ref().and_then(|o| o.os.clone()).unwrap_or_default(),
+                        os_version: os_info.and_then(|o| o.os_version).unwrap_or_default(),
+                        model: models.remove(&c.id),
+                        bios: bios.remove(&c.id),
+                        processor: processors.remove(&c.id),
+                        memory_capacity,
+                        memory_sticks,
+                        disks: disks.remove(&c.id).unwrap_or_default(),
+                        graphics_cards: graphics_cards.remove(&c.id).unwrap_or_default(),
+                        network_adapters: network_adapters.remove(&c.id).unwrap_or_default(),
+                        ip_addresses: ip_addresses.remove(&c.id).unwrap_or_default(),
+                        asset: assets.remove(&c.id),
+                        attributes: attributes.remove(&c.id).unwrap_or_default(),
+                    }
+                })
+                .collect())
+        })
+    }
+}
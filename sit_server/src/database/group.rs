@@ -0,0 +1,289 @@
+use super::group_rule::{GroupRule, RuleField};
+use super::statistics::StatisticsManager;
+use super::{model::*, schema::*, DbPool};
+use anyhow::Result;
+use chrono::Utc;
+use diesel::dsl::count;
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Nullable, Text};
+
+/// Rows per INSERT when memberships are rewritten.
+const CHUNK_SIZE: usize = 500;
+
+define_sql_function! { fn lower(x: Nullable<Text>) -> Nullable<Text>; }
+
+pub struct GroupManager {
+    pool: DbPool,
+    /// Told about changed memberships, as the statistics are kept per group.
+    statistics: StatisticsManager,
+}
+
+impl GroupManager {
+    pub fn new(pool: DbPool) -> GroupManager {
+        GroupManager {
+            statistics: StatisticsManager::new(pool.clone()),
+            pool,
+        }
+    }
+
+    pub fn get_groups(&self) -> Result<Vec<ClientGroupWithCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_group::table
+                .left_join(client_group_member::table)
+                .group_by(client_group::id)
+                .select((
+                    client_group::id,
+                    client_group::name,
+                    client_group::description,
+                    client_group::rule,
+                    count(client_group_member::client_id.nullable()),
+                ))
+                .order_by(client_group::name)
+                .load::<ClientGroupWithCount>(&mut conn)?)
+        })
+    }
+
+    pub fn get_group(&self, group_id: i32) -> Result<ClientGroup> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_group::table
+                .filter(client_group::id.eq(group_id))
+                .get_result(&mut conn)?)
+        })
+    }
+
+    pub fn create_group(&self, name: &str, description: Option<&str>) -> Result<ClientGroup> {
+        let group = with_conn!(self.pool, |conn| {
+            diesel::insert_into(client_group::table)
+                .values(NewClientGroup { name, description })
+                .get_result(&mut conn)
+        })?;
+        // So the new group gets statistics of its own.
+        self.statistics.mark_changed(Utc::now().naive_utc())?;
+        Ok(group)
+    }
+
+    pub fn delete_group(&self, group_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(client_group::table)
+                .filter(client_group::id.eq(group_id))
+                .execute(&mut conn)?)
+        })
+    }
+
+    pub fn get_client_groups(&self, client_id: i32) -> Result<Vec<ClientGroup>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_group::table
+                .inner_join(client_group_member::table)
+                .filter(client_group_member::client_id.eq(client_id))
+                .select(client_group::all_columns)
+                .order_by(client_group::name)
+                .load::<ClientGroup>(&mut conn)?)
+        })
+    }
+
+    pub fn add_client(&self, group_id: i32, client_id: i32) -> Result<()> {
+        let added = with_conn!(self.pool, |conn| {
+            diesel::insert_into(client_group_member::table)
+                .values(ClientGroupMember { group_id, client_id })
+                .on_conflict_do_nothing()
+                .execute(&mut conn)
+        })?;
+        self.members_changed(added)
+    }
+
+    pub fn remove_client(&self, group_id: i32, client_id: i32) -> Result<()> {
+        let removed = with_conn!(self.pool, |conn| {
+            diesel::delete(client_group_member::table)
+                .filter(client_group_member::group_id.eq(group_id))
+                .filter(client_group_member::client_id.eq(client_id))
+                .execute(&mut conn)
+        })?;
+        self.members_changed(removed)
+    }
+
+    /// Marks the statistics as due if `rows` memberships changed.
+    fn members_changed(&self, rows: usize) -> Result<()> {
+        if rows > 0 {
+            self.statistics.mark_changed(Utc::now().naive_utc())?;
+        }
+        Ok(())
+    }
+
+    /// Stores the rule of a smart group and recomputes its members. `None` turns it
+    /// back into a manually maintained group, keeping the current members.
+    pub fn set_rule(&self, group_id: i32, rule: Option<&GroupRule>) -> Result<()> {
+        if let Some(rule) = rule {
+            rule.validate()?;
+        }
+        let value = rule.map(serde_json::to_value).transpose()?;
+        with_conn!(self.pool, |conn| {
+            diesel::update(client_group::table)
+                .filter(client_group::id.eq(group_id))
+                .set(client_group::rule.eq(value))
+                .execute(&mut conn)?;
+            Ok::<(), anyhow::Error>(())
+        })?;
+        match rule {
+            Some(rule) => self.refresh_group(group_id, rule),
+            None => Ok(()),
+        }
+    }
+
+    /// Lists the clients a rule would select, with their os info where known.
+    pub fn preview(&self, rule: &GroupRule) -> Result<Vec<(Client, Option<OsInfo>)>> {
+        let client_ids = self.matching_clients(rule, None)?;
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .left_join(os_info::table)
+                .filter(client::id.eq_any(client_ids))
+                .order_by(os_info::computer_name)
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Re-evaluates every smart group for one client, called after its inventory changed.
+    pub fn refresh_client(&self, client_id: i32) -> Result<()> {
+        let smart_groups: Vec<ClientGroup> = with_conn!(self.pool, |conn| {
+            client_group::table
+                .filter(client_group::rule.is_not_null())
+                .load(&mut conn)
+        })?;
+        for group in smart_groups {
+            let Some(rule) = group.rule else { continue };
+            let rule: GroupRule = serde_json::from_value(rule)?;
+            if self.matching_clients(&rule, Some(client_id))?.is_empty() {
+                self.remove_client(group.id, client_id)?;
+            } else {
+                self.add_client(group.id, client_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn refresh_group(&self, group_id: i32, rule: &GroupRule) -> Result<()> {
+        let members: Vec<ClientGroupMember> = self
+            .matching_clients(rule, None)?
+            .into_iter()
+            .map(|client_id| ClientGroupMember { group_id, client_id })
+            .collect();
+        with_conn!(self.pool, |conn| {
+            conn.transaction::<(), diesel::result::Error, _>(|c| {
+                diesel::delete(client_group_member::table)
+                    .filter(client_group_member::group_id.eq(group_id))
+                    .execute(c)?;
+                for chunk in members.chunks(CHUNK_SIZE) {
+                    diesel::insert_into(client_group_member::table)
+                        .values(chunk)
+                        .execute(c)?;
+                }
+                Ok(())
+            })?;
+            Ok::<(), anyhow::Error>(())
+        })?;
+        self.statistics.mark_changed(Utc::now().naive_utc())
+    }
+
+    /// Ids of the clients matching every condition of `rule`, limited to `only_client`
+    /// if given.
+    fn matching_clients(&self, rule: &GroupRule, only_client: Option<i32>) -> Result<Vec<i32>> {
+        with_conn!(self.pool, |conn| {
+            let mut query = client::table
+                .select(client::id)
+                .filter(
+                    client::id
+                        .nullable()
+                        .eq(only_client)
+                        .or(only_client.is_none().into_sql::<Bool>()),
+                )
+                .into_boxed();
+            for condition in &rule.conditions {
+                let pattern = condition.like_pattern();
+                query = match condition.field {
+                    RuleField::OsName => query.filter(
+                        client::id.eq_any(
+                            os_info::table
+                                .select(os_info::client_id)
+                                .filter(lower(os_info::os).like(pattern).escape('\\')),
+                        ),
+                    ),
+                    RuleField::OsVersion => query.filter(
+                        client::id.eq_any(
+                            os_info::table
+                                .select(os_info::client_id)
+                                .filter(lower(os_info::os_version).like(pattern).escape('\\')),
+                        ),
+                    ),
+                    RuleField::Domain => query.filter(
+                        client::id.eq_any(
+                            os_info::table
+                                .select(os_info::client_id)
+                                .filter(lower(os_info::domain).like(pattern).escape('\\')),
+                        ),
+                    ),
+                    RuleField::ComputerName => query.filter(
+                        client::id.eq_any(
+                            os_info::table.select(os_info::client_id).filter(
+                                lower(os_info::computer_name.nullable())
+                                    .like(pattern)
+                                    .escape('\\'),
+                            ),
+                        ),
+                    ),
+                    RuleField::Model => query.filter(
+                        client::id.eq_any(
+                            computer_model::table.select(computer_model::client_id).filter(
+                                lower(
+                                    computer_model::manufacturer
+                                        .concat(" ")
+                                        .concat(computer_model::model_family)
+                                        .nullable(),
+                                )
+                                .like(pattern)
+                                .escape('\\'),
+                            ),
+                        ),
+                    ),
+                    RuleField::Software => {
+                        let installed = software_list::table
+                            .inner_join(software_version::table.inner_join(software_info::table))
+                            .select(software_list::client_id)
+                            .filter(lower(software_info::name.nullable()).like(pattern).escape('\\'));
+                        match condition.version_pattern() {
+                            Some(version) => query.filter(
+                                client::id.eq_any(installed.filter(
+                                    lower(software_version::version.nullable())
+                                        .like(version)
+                                        .escape('\\'),
+                                )),
+                            ),
+                            None => query.filter(client::id.eq_any(installed)),
+                        }
+                    }
+                    RuleField::MemoryMin => query.filter(
+                        client::id.eq_any(
+                            memory::table
+                                .select(memory::client_id)
+                                .filter(memory::capacity.ge(condition.memory_bytes()?)),
+                        ),
+                    ),
+                    RuleField::MemoryMax => query.filter(
+                        client::id.eq_any(
+                            memory::table
+                                .select(memory::client_id)
+                                .filter(memory::capacity.le(condition.memory_bytes()?)),
+                        ),
+                    ),
+                    RuleField::Attribute(attribute_id) => query.filter(
+                        client::id.eq_any(
+                            client_attribute::table
+                                .select(client_attribute::client_id)
+                                .filter(client_attribute::attribute_id.eq(attribute_id))
+                                .filter(lower(client_attribute::value.nullable()).like(pattern).escape('\\')),
+                        ),
+                    ),
+                };
+            }
+            Ok(query.load::<i32>(&mut conn)?)
+        })
+    }
+}
@@ -0,0 +1,134 @@
+//! Membership rules of smart groups.
+//!
+//! A rule is stored as JSON in `client_group.rule`. A client is a member if it
+//! matches every condition. Text patterns are case-insensitive, `*` matches any
+//! number of characters and `?` exactly one.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleField {
+    OsName,
+    OsVersion,
+    Domain,
+    ComputerName,
+    /// Manufacturer and model family, separated by a space.
+    Model,
+    /// Name of an installed software, optionally restricted to a version.
+    Software,
+    /// Total memory in GiB, at least the value.
+    MemoryMin,
+    /// Total memory in GiB, at most the value.
+    MemoryMax,
+    /// Value of the custom attribute with the given id, in its canonical form.
+    Attribute(i32),
+}
+
+impl FromStr for RuleField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "os_name" => Ok(RuleField::OsName),
+            "os_version" => Ok(RuleField::OsVersion),
+            "domain" => Ok(RuleField::Domain),
+            "computer_name" => Ok(RuleField::ComputerName),
+            "model" => Ok(RuleField::Model),
+            "software" => Ok(RuleField::Software),
+            "memory_min" => Ok(RuleField::MemoryMin),
+            "memory_max" => Ok(RuleField::MemoryMax),
+            _ => s
+                .strip_prefix("attribute:")
+                .and_then(|id| id.parse().ok())
+                .map(RuleField::Attribute)
+                .ok_or_else(|| anyhow!("Unknown rule field: '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for RuleField {
+    /// The key understood by [`RuleField::from_str`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleField::OsName => f.write_str("os_name"),
+            RuleField::OsVersion => f.write_str("os_version"),
+            RuleField::Domain => f.write_str("domain"),
+            RuleField::ComputerName => f.write_str("computer_name"),
+            RuleField::Model => f.write_str("model"),
+            RuleField::Software => f.write_str("software"),
+            RuleField::MemoryMin => f.write_str("memory_min"),
+            RuleField::MemoryMax => f.write_str("memory_max"),
+            RuleField::Attribute(id) => write!(f, "attribute:{id}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RuleCondition {
+    pub field: RuleField,
+    pub value: String,
+    /// Version pattern, only used with [`RuleField::Software`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct GroupRule {
+    pub conditions: Vec<RuleCondition>,
+}
+
+impl RuleCondition {
+    /// The value as pattern for `LIKE ... ESCAPE '\'`, lowercased.
+    pub(super) fn like_pattern(&self) -> String {
+        to_like_pattern(&self.value)
+    }
+
+    pub(super) fn version_pattern(&self) -> Option<String> {
+        self.version
+            .as_deref()
+            .filter(|v| !v.is_empty())
+            .map(to_like_pattern)
+    }
+
+    /// The value of a memory condition in bytes.
+    pub(super) fn memory_bytes(&self) -> Result<BigDecimal> {
+        let gib = BigDecimal::from_str(self.value.trim())
+            .map_err(|_| anyhow!("Not a number: '{}'", self.value))?;
+        Ok(gib * BigDecimal::from(1u64 << 30))
+    }
+}
+
+impl GroupRule {
+    /// Checks the memory values up front, so a broken rule is rejected when it is
+    /// saved instead of on the next ingest.
+    pub fn validate(&self) -> Result<()> {
+        for condition in &self.conditions {
+            if matches!(condition.field, RuleField::MemoryMin | RuleField::MemoryMax) {
+                condition.memory_bytes()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_like_pattern(value: &str) -> String {
+    let mut pattern = String::with_capacity(value.len());
+    for c in value.trim().to_lowercase().chars() {
+        match c {
+            '\\' | '%' | '_' => {
+                pattern.push('\\');
+                pattern.push(c);
+            }
+            '*' => pattern.push('%'),
+            '?' => pattern.push('_'),
+            c => pattern.push(c),
+        }
+    }
+    pattern
+}
@@ -0,0 +1,329 @@
+//! Ingest of the hardware of a client.
+//!
+//! One upload is applied in a single transaction. The stored rows are compared
+//! with the uploaded ones and only what differs is written, so unchanged rows
+//! keep their ids and readers never see a client with half of its hardware.
+
+use anyhow::Result;
+use bigdecimal::BigDecimal;
+use diesel::prelude::*;
+use sit_lib::hardware::{ComputerModel, Disks, GraphicsCard, HardwareInfo, HardwareInfoV2, Network, PhysicalMemory, Processor, BIOS};
+
+use super::{model, model::*, schema::*, Database};
+
+/// The parts of a hardware upload, common to both API versions.
+struct Hardware<'a> {
+    model: &'a ComputerModel,
+    memory: &'a PhysicalMemory,
+    processor: &'a Processor,
+    disks: &'a Disks,
+    network: &'a Network,
+    graphics: &'a [GraphicsCard],
+    bios: &'a BIOS,
+}
+
+/// Pairs every uploaded row with a stored row `same` holds for, each stored row
+/// at most once. Returns the pairs, the uploaded rows without a stored one and
+/// the stored rows without an uploaded one.
+fn pair<'e, 'u, E, U>(
+    existing: &'e [E],
+    uploaded: &'u [U],
+    same: impl Fn(&E, &U) -> bool,
+) -> (Vec<(&'e E, &'u U)>, Vec<&'u U>, Vec<&'e E>) {
+    let mut unused: Vec<&E> = existing.iter().collect();
+    let mut pairs = vec![];
+    let mut added = vec![];
+    for u in uploaded {
+        match unused.iter().position(|e| same(e, u)) {
+            Some(i) => pairs.push((unused.remove(i), u)),
+            None => added.push(u),
+        }
+    }
+    (pairs, added, unused)
+}
+
+macro_rules! upsert_singles {
+    ($conn:expr, $client_id:expr, $hardware:expr) => {{
+        let model = $hardware.model;
+        diesel::insert_into(computer_model::table)
+            .values(NewComputerModel {
+                client_id: &$client_id,
+                manufacturer: &model.manufacturer,
+                model_family: &model.model_family,
+                serial_number: &model.serial_number,
+            })
+            .on_conflict(computer_model::client_id)
+            .do_update()
+            .set((
+                computer_model::manufacturer.eq(&model.manufacturer),
+                computer_model::model_family.eq(&model.model_family),
+                computer_model::serial_number.eq(&model.serial_number),
+            ))
+            .execute($conn)?;
+
+        let processor = $hardware.processor;
+        diesel::insert_into(processor::table)
+            .values(NewProcessor {
+                client_id: &$client_id,
+                name: &processor.name,
+                manufacturer: &processor.manufacturer,
+                cores: &(processor.cores as i64),
+                logical_cores: &(processor.logical_cores as i64),
+                clock_speed: &(processor.clock_speed as i64),
+                address_width: &(processor.address_width as i32),
+            })
+            .on_conflict(processor::client_id)
+            .do_update()
+            .set((
+                processor::name.eq(&processor.name),
+                processor::manufacturer.eq(&processor.manufacturer),
+                processor::cores.eq(&(processor.cores as i64)),
+                processor::logical_cores.eq(&(processor.logical_cores as i64)),
+                processor::clock_speed.eq(&(processor.clock_speed as i64)),
+                processor::address_width.eq(&(processor.address_width as i32)),
+            ))
+            .execute($conn)?;
+
+        let bios = $hardware.bios;
+        diesel::insert_into(bios::table)
+            .values(NewBios {
+                client_id: &$client_id,
+                name: &bios.name,
+                manufacturer: &bios.manufacturer,
+                version: &bios.version,
+            })
+            .on_conflict(bios::client_id)
+            .do_update()
+            .set((
+                bios::name.eq(&bios.name),
+                bios::manufacturer.eq(&bios.manufacturer),
+                bios::version.eq(&bios.version),
+            ))
+            .execute($conn)?;
+    }};
+}
+
+/// Memory sticks are matched by bank, a changed capacity is updated.
+macro_rules! sync_memory_sticks {
+    ($conn:expr, $client_id:expr, $memory:expr) => {{
+        let existing: Vec<model::MemoryStick> = memory_stick::table
+            .filter(memory_stick::client_id.eq($client_id))
+            .order_by(memory_stick::id)
+            .load($conn)?;
+        let (pairs, added, removed) = pair(&existing, &$memory.sticks, |e, u| e.bank_label == u.bank_label);
+        for (e, u) in pairs {
+            let capacity = BigDecimal::from(u.capacity);
+            if e.capacity.as_ref() != Some(&capacity) {
+                diesel::update(memory_stick::table)
+                    .filter(memory_stick::id.eq(e.id))
+                    .set(memory_stick::capacity.eq(capacity))
+                    .execute($conn)?;
+            }
+        }
+        let capacities: Vec<BigDecimal> = added.iter().map(|u| BigDecimal::from(u.capacity)).collect();
+        let new: Vec<NewMemoryStick> = added
+            .iter()
+            .zip(&capacities)
+            .map(|(u, capacity)| NewMemoryStick {
+                client_id: &$client_id,
+                capacity,
+                bank_label: &u.bank_label,
+            })
+            .collect();
+        if !new.is_empty() {
+            diesel::insert_into(memory_stick::table).values(new).execute($conn)?;
+        }
+        if !removed.is_empty() {
+            diesel::delete(memory_stick::table)
+                .filter(memory_stick::id.eq_any(removed.iter().map(|e| e.id)))
+                .execute($conn)?;
+        }
+    }};
+}
+
+/// Disks are matched by device id and serial number, other changes are updated.
+macro_rules! sync_disks {
+    ($conn:expr, $client_id:expr, $disks:expr) => {{
+        let existing: Vec<Disk> = disks::table
+            .filter(disks::client_id.eq($client_id))
+            .order_by(disks::id)
+            .load($conn)?;
+        let (pairs, added, removed) = pair(&existing, &$disks.drives, |e, u| {
+            e.device_id == u.device_id && e.serial_number == u.serial_number
+        });
+        for (e, u) in pairs {
+            let size = BigDecimal::from(u.size);
+            if e.model != u.model
+                || e.size.as_ref() != Some(&size)
+                || e.status != u.status
+                || e.media_type != u.media_type
+            {
+                diesel::update(disks::table)
+                    .filter(disks::id.eq(e.id))
+                    .set((
+                        disks::model.eq(&u.model),
+                        disks::size.eq(size),
+                        disks::status.eq(&u.status),
+                        disks::media_type.eq(&u.media_type),
+                    ))
+                    .execute($conn)?;
+            }
+        }
+        let new: Vec<NewDisk> = added
+            .iter()
+            .map(|u| NewDisk {
+                client_id: &$client_id,
+                model: &u.model,
+                serial_number: &u.serial_number,
+                size: Some(BigDecimal::from(u.size)),
+                device_id: &u.device_id,
+                status: &u.status,
+                media_type: &u.media_type,
+            })
+            .collect();
+        if !new.is_empty() {
+            diesel::insert_into(disks::table).values(new).execute($conn)?;
+        }
+        if !removed.is_empty() {
+            diesel::delete(disks::table)
+                .filter(disks::id.eq_any(removed.iter().map(|e| e.id)))
+                .execute($conn)?;
+        }
+    }};
+}
+
+/// Adapters are matched by name and MAC address, their addresses by value. The
+/// addresses of removed adapters go with them by cascade.
+macro_rules! sync_network_adapters {
+    ($conn:expr, $client_id:expr, $network:expr) => {{
+        let existing: Vec<model::NetworkAdapter> = network_adapter::table
+            .filter(network_adapter::client_id.eq($client_id))
+            .order_by(network_adapter::id)
+            .load($conn)?;
+        let existing_ips: Vec<NetworkAdapterIp> = network_adapter_ip::table
+            .filter(network_adapter_ip::adapter_id.eq_any(existing.iter().map(|e| e.id)))
+            .order_by(network_adapter_ip::id)
+            .load($conn)?;
+        let (pairs, added, removed) = pair(&existing, &$network.adapter, |e, u| {
+            e.name == u.name && e.mac_address == u.mac_address
+        });
+
+        let mut adapters: Vec<(i32, &sit_lib::hardware::NetworkAdapter)> =
+            pairs.into_iter().map(|(e, u)| (e.id, u)).collect();
+        for u in added {
+            let id: i32 = diesel::insert_into(network_adapter::table)
+                .values(NewNetworkAdapter {
+                    client_id: &$client_id,
+                    name: &u.name,
+                    mac_address: u.mac_address.as_ref(),
+                })
+                .returning(network_adapter::id)
+                .get_result($conn)?;
+            adapters.push((id, u));
+        }
+
+        let mut new_ips: Vec<NewNetworkAdapterIp> = vec![];
+        let mut removed_ips: Vec<i32> = vec![];
+        for (id, u) in &adapters {
+            let stored: Vec<&NetworkAdapterIp> = existing_ips.iter().filter(|ip| ip.adapter_id == *id).collect();
+            let uploaded = u.ip_addresses.as_deref().unwrap_or_default();
+            let (_, added, removed) = pair(&stored, uploaded, |e, u| &e.ip == u);
+            new_ips.extend(added.into_iter().map(|ip| NewNetworkAdapterIp { adapter_id: id, ip }));
+            removed_ips.extend(removed.into_iter().map(|e| e.id));
+        }
+        if !removed_ips.is_empty() {
+            diesel::delete(network_adapter_ip::table)
+                .filter(network_adapter_ip::id.eq_any(removed_ips))
+                .execute($conn)?;
+        }
+        if !new_ips.is_empty() {
+            diesel::insert_into(network_adapter_ip::table).values(new_ips).execute($conn)?;
+        }
+        if !removed.is_empty() {
+            diesel::delete(network_adapter::table)
+                .filter(network_adapter::id.eq_any(removed.iter().map(|e| e.id)))
+                .execute($conn)?;
+        }
+    }};
+}
+
+/// Graphics cards are matched by name.
+macro_rules! sync_graphics_cards {
+    ($conn:expr, $client_id:expr, $graphics:expr) => {{
+        let existing: Vec<model::GraphicsCard> = graphics_card::table
+            .filter(graphics_card::client_id.eq($client_id))
+            .order_by(graphics_card::id)
+            .load($conn)?;
+        let (_, added, removed) = pair(&existing, $graphics, |e, u| e.name == u.name);
+        let new: Vec<NewGraphicsCard> = added
+            .iter()
+            .map(|u| NewGraphicsCard {
+                client_id: &$client_id,
+                name: &u.name,
+            })
+            .collect();
+        if !new.is_empty() {
+            diesel::insert_into(graphics_card::table).values(new).execute($conn)?;
+        }
+        if !removed.is_empty() {
+            diesel::delete(graphics_card::table)
+                .filter(graphics_card::id.eq_any(removed.iter().map(|e| e.id)))
+                .execute($conn)?;
+        }
+    }};
+}
+
+impl Database {
+    pub fn create_hardware_info(&self, client_id: i32, hardware_info: HardwareInfo) -> Result<()> {
+        self.update_hardware(
+            client_id,
+            Hardware {
+                model: &hardware_info.model,
+                memory: &hardware_info.memory,
+                processor: &hardware_info.processor,
+                disks: &hardware_info.disks,
+                network: &hardware_info.network,
+                graphics: std::slice::from_ref(&hardware_info.graphics),
+                bios: &hardware_info.bios,
+            },
+            None,
+        )
+    }
+
+    pub fn create_hardware_info_v2(
+        &self,
+        client_id: i32,
+        hardware_info: HardwareInfoV2,
+        hash: Option<&SectionHash>,
+    ) -> Result<()> {
+        self.update_hardware(
+            client_id,
+            Hardware {
+                model: &hardware_info.model,
+                memory: &hardware_info.memory,
+                processor: &hardware_info.processor,
+                disks: &hardware_info.disks,
+                network: &hardware_info.network,
+                graphics: &hardware_info.graphics,
+                bios: &hardware_info.bios,
+            },
+            hash,
+        )
+    }
+
+    fn update_hardware(&self, client_id: i32, hardware: Hardware, hash: Option<&SectionHash>) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            conn.transaction::<(), diesel::result::Error, _>(|c| {
+                upsert_singles!(c, client_id, hardware);
+                sync_memory_sticks!(c, client_id, hardware.memory);
+                sync_disks!(c, client_id, hardware.disks);
+                sync_network_adapters!(c, client_id, hardware.network);
+                sync_graphics_cards!(c, client_id, hardware.graphics);
+                store_section_hash!(c, hash);
+                Ok(())
+            })?;
+        });
+        self.location_manager.assign_by_subnet(Some(client_id))?;
+        self.group_manager.refresh_client(client_id)
+    }
+}
@@ -0,0 +1,221 @@
+//! Agent payloads waiting to be applied to the inventory tables, see [`crate::ingest`].
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::dsl::{count_star, exists, not};
+use diesel::prelude::*;
+
+use super::{model::*, schema::*, DbPool};
+
+pub const STATUS_PENDING: &str = "pending";
+/// Claimed by a worker until `available`, then it can be claimed again.
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_FAILED: &str = "failed";
+
+/// The jobs that can be claimed at `$now`, oldest first: pending or abandoned by
+/// their worker, with no earlier job of the client waiting and none in progress.
+macro_rules! claimable_jobs {
+    ($now:expr) => {{
+        let other = diesel::alias!(ingest_job as other);
+        ingest_job::table
+            .filter(ingest_job::status.eq_any([STATUS_PENDING, STATUS_RUNNING]))
+            .filter(ingest_job::available.le($now))
+            .filter(not(exists(
+                other
+                    .filter(other.field(ingest_job::client_id).eq(ingest_job::client_id))
+                    .filter(
+                        other
+                            .field(ingest_job::status)
+                            .eq_any([STATUS_PENDING, STATUS_RUNNING])
+                            .and(other.field(ingest_job::id).lt(ingest_job::id))
+                            .or(other
+                                .field(ingest_job::status)
+                                .eq(STATUS_RUNNING)
+                                .and(other.field(ingest_job::available).gt($now))),
+                    ),
+            )))
+            .order_by(ingest_job::id)
+            .select(ingest_job::id)
+    }};
+}
+
+pub struct IngestManager {
+    pool: DbPool,
+}
+
+impl IngestManager {
+    pub fn new(pool: DbPool) -> IngestManager {
+        IngestManager { pool }
+    }
+
+    pub fn enqueue(
+        &self,
+        client_id: i32,
+        kind: &str,
+        payload: &str,
+        hash: Option<&str>,
+        received: NaiveDateTime,
+    ) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            diesel::insert_into(ingest_job::table)
+                .values(NewIngestJob {
+                    client_id,
+                    kind,
+                    payload,
+                    hash,
+                    received,
+                    available: received,
+                })
+                .execute(&mut conn)?;
+            Ok(())
+        })
+    }
+
+    /// Claims the next job available at `now` for a worker, see [`STATUS_RUNNING`].
+    /// It stays claimed until `until`, and the claim counts as an attempt. Jobs
+    /// locked by a concurrent claim are skipped on Postgres, SQLite claims one at a
+    /// time.
+    pub fn claim(&self, now: NaiveDateTime, until: NaiveDateTime) -> Result<Option<i32>> {
+        macro_rules! mark_running {
+            ($conn:expr, $job_id:expr) => {
+                diesel::update(ingest_job::table)
+                    .filter(ingest_job::id.eq($job_id))
+                    .filter(ingest_job::status.eq_any([STATUS_PENDING, STATUS_RUNNING]))
+                    .filter(ingest_job::available.le(now))
+                    .set((
+                        ingest_job::status.eq(STATUS_RUNNING),
+                        ingest_job::available.eq(until),
+                        ingest_job::attempts.eq(ingest_job::attempts + 1),
+                    ))
+                    .execute($conn)
+            };
+        }
+        let claimed = match &self.pool {
+            DbPool::Postgres(pool) => pool.get()?.transaction::<_, diesel::result::Error, _>(|c| {
+                let job_id: Option<i32> = claimable_jobs!(now)
+                    .for_update()
+                    .skip_locked()
+                    .first(c)
+                    .optional()?;
+                match job_id {
+                    Some(job_id) => Ok((mark_running!(c, job_id)? == 1).then_some(job_id)),
+                    None => Ok(None),
+                }
+            })?,
+            DbPool::Sqlite(pool) => pool.get()?.immediate_transaction::<_, diesel::result::Error, _>(|c| {
+                let job_id: Option<i32> = claimable_jobs!(now).first(c).optional()?;
+                match job_id {
+                    Some(job_id) => Ok((mark_running!(c, job_id)? == 1).then_some(job_id)),
+                    None => Ok(None),
+                }
+            })?,
+        };
+        Ok(claimed)
+    }
+
+    pub fn get_job(&self, job_id: i32) -> Result<IngestJob> {
+        with_conn!(self.pool, |conn| {
+            Ok(ingest_job::table
+                .filter(ingest_job::id.eq(job_id))
+                .get_result(&mut conn)?)
+        })
+    }
+
+    /// Jobs with `status` and the client they belong to, oldest first.
+    pub fn get_jobs(&self, status: &str) -> Result<Vec<(IngestJob, Client, Option<OsInfo>)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(ingest_job::table
+                .inner_join(client::table)
+                .left_join(os_info::table.on(os_info::client_id.eq(ingest_job::client_id)))
+                .filter(ingest_job::status.eq(status))
+                .order_by(ingest_job::id)
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Number of jobs per `(status, kind)`.
+    pub fn get_depth(&self) -> Result<Vec<(String, String, i64)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(ingest_job::table
+                .group_by((ingest_job::status, ingest_job::kind))
+                .select((ingest_job::status, ingest_job::kind, count_star()))
+                .order_by((ingest_job::status, ingest_job::kind))
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Records a failed attempt. The job is retried from `available` on, or marked
+    /// as failed if `give_up`.
+    pub fn record_failure(
+        &self,
+        job_id: i32,
+        attempts: i32,
+        error: &str,
+        available: NaiveDateTime,
+        give_up: bool,
+    ) -> Result<usize> {
+        let status = if give_up { STATUS_FAILED } else { STATUS_PENDING };
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::update(ingest_job::table)
+                .filter(ingest_job::id.eq(job_id))
+                .set((
+                    ingest_job::attempts.eq(attempts),
+                    ingest_job::last_error.eq(error),
+                    ingest_job::available.eq(available),
+                    ingest_job::status.eq(status),
+                ))
+                .execute(&mut conn)?)
+        })
+    }
+
+    /// Queues the failed job `job_id` again, with a fresh number of attempts.
+    /// Nothing happens if a newer job of the client and kind is queued, the old
+    /// payload would only be overwritten by it. Failed jobs of which a newer one was
+    /// applied are gone already, see [`IngestManager::complete_job`].
+    pub fn retry_job(&self, job_id: i32, now: NaiveDateTime) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            let newer = diesel::alias!(ingest_job as newer);
+            Ok(diesel::update(ingest_job::table)
+                .filter(ingest_job::id.eq(job_id))
+                .filter(ingest_job::status.eq(STATUS_FAILED))
+                .filter(not(exists(
+                    newer
+                        .filter(newer.field(ingest_job::client_id).eq(ingest_job::client_id))
+                        .filter(newer.field(ingest_job::kind).eq(ingest_job::kind))
+                        .filter(newer.field(ingest_job::id).gt(ingest_job::id)),
+                )))
+                .set((
+                    ingest_job::status.eq(STATUS_PENDING),
+                    ingest_job::attempts.eq(0),
+                    ingest_job::available.eq(now),
+                ))
+                .execute(&mut conn)?)
+        })
+    }
+
+    /// Removes the applied job `job_id`, along with the failed jobs of the client
+    /// and kind it supersedes.
+    pub fn complete_job(&self, job_id: i32, client_id: i32, kind: &str) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(ingest_job::table)
+                .filter(ingest_job::client_id.eq(client_id))
+                .filter(ingest_job::kind.eq(kind))
+                .filter(
+                    ingest_job::id
+                        .eq(job_id)
+                        .or(ingest_job::id.lt(job_id).and(ingest_job::status.eq(STATUS_FAILED))),
+                )
+                .execute(&mut conn)?)
+        })
+    }
+
+    /// Discards the failed job `job_id`.
+    pub fn delete_job(&self, job_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(ingest_job::table)
+                .filter(ingest_job::id.eq(job_id))
+                .filter(ingest_job::status.eq(STATUS_FAILED))
+                .execute(&mut conn)?)
+        })
+    }
+}
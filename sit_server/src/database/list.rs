@@ -0,0 +1,523 @@
+//! Paging, sorting and column filters of the list pages.
+//!
+//! A [`ListQuery`] names the page, the column to sort by and a filter text per
+//! column. Every list knows its own column keys, unknown keys are ignored. Text
+//! filters match case-insensitively as substring.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use diesel::dsl::{count, count_star, sql};
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Integer, Nullable, Numeric, Text};
+use serde::Serialize;
+
+use super::search::contains_pattern;
+use super::{coalesce, model::*, schema::*, AttributeKind, Database};
+
+pub const DEFAULT_PER_PAGE: i64 = 50;
+pub const MAX_PER_PAGE: i64 = 500;
+
+define_sql_function! { fn lower(x: Nullable<Text>) -> Nullable<Text>; }
+
+/// The requested part of a list.
+#[derive(Clone, Debug)]
+pub struct ListQuery {
+    /// Starts at 1.
+    pub page: i64,
+    pub per_page: i64,
+    /// Key of the column to sort by, each list falls back to its natural order.
+    pub sort: Option<String>,
+    pub desc: bool,
+    /// Filter text by column key.
+    pub filters: HashMap<String, String>,
+}
+
+impl Default for ListQuery {
+    fn default() -> Self {
+        ListQuery {
+            page: 1,
+            per_page: DEFAULT_PER_PAGE,
+            sort: None,
+            desc: false,
+            filters: HashMap::new(),
+        }
+    }
+}
+
+impl ListQuery {
+    /// The whole list with the same sorting and filters, as used for exports.
+    pub fn unpaged(&self) -> ListQuery {
+        ListQuery {
+            page: 1,
+            per_page: i64::MAX,
+            ..self.clone()
+        }
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.per_page.max(1)
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page.max(1) - 1).saturating_mul(self.limit())
+    }
+
+    /// The trimmed filter on `column`, `None` if it is empty.
+    pub fn filter(&self, column: &str) -> Option<&str> {
+        self.filters.get(column).map(|f| f.trim()).filter(|f| !f.is_empty())
+    }
+
+    /// The filter on `column` as pattern for `LIKE ... ESCAPE '\'`.
+    fn pattern(&self, column: &str) -> Option<String> {
+        self.filter(column).map(contains_pattern)
+    }
+}
+
+/// One page of a list together with the size of the whole (filtered) list.
+#[derive(Clone, Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub pages: i64,
+}
+
+impl<T> Page<T> {
+    fn new(items: Vec<T>, total: i64, list: &ListQuery) -> Page<T> {
+        Page {
+            items,
+            total,
+            page: list.page.max(1),
+            pages: if total == 0 { 1 } else { (total - 1) / list.limit() + 1 },
+        }
+    }
+
+    /// `list`'s page of the complete list `items`, for lists that are not paged in SQL.
+    pub fn paginate(items: Vec<T>, list: &ListQuery) -> Page<T> {
+        let total = items.len() as i64;
+        let items = items
+            .into_iter()
+            .skip(usize::try_from(list.offset()).unwrap_or(usize::MAX))
+            .take(usize::try_from(list.limit()).unwrap_or(usize::MAX))
+            .collect();
+        Page::new(items, total, list)
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            pages: self.pages,
+        }
+    }
+
+    /// Shown when a list could not be loaded.
+    pub fn empty() -> Page<T> {
+        Page {
+            items: vec![],
+            total: 0,
+            page: 1,
+            pages: 1,
+        }
+    }
+}
+
+/// Orders the boxed `$query` by the column `$list.sort` names, or by `$default`.
+macro_rules! order_by_list {
+    ($query:expr, $list:expr, $default:expr, { $($key:literal => $column:expr),+ $(,)? }) => {
+        match $list.sort.as_deref() {
+            $(
+                Some($key) if $list.desc => $query.order_by($column.desc()),
+                Some($key) => $query.order_by($column.asc()),
+            )+
+            _ => $query.order_by($default),
+        }
+    };
+}
+
+/// Restricts the boxed `$query` to rows whose `$column` contains the filter on `$key`.
+macro_rules! filter_column {
+    ($query:ident, $list:expr, $key:literal, $column:expr) => {
+        if let Some(pattern) = $list.pattern($key) {
+            $query = $query.filter(lower($column.nullable()).like(pattern).escape('\\'));
+        }
+    };
+}
+
+/// How a filter on a custom attribute column is applied.
+enum AttributeFilter {
+    Contains(String),
+    Equals(String),
+}
+
+impl Database {
+    /// Clients with their OS info. Columns: `name`, `domain`, `os`, `os_version`
+    /// and `attribute-<id>` for custom attributes.
+    pub fn get_client_page(&self, group: Option<i32>, list: &ListQuery) -> Result<Page<(Client, Option<OsInfo>)>> {
+        let definitions = self.attribute_manager().get_definitions()?;
+        let attribute_filters: Vec<(i32, AttributeFilter)> = definitions
+            .iter()
+            .filter_map(|d| {
+                let filter = list.filter(&format!("attribute-{}", d.id))?;
+                let filter = match d.kind() {
+                    AttributeKind::Text => AttributeFilter::Contains(contains_pattern(filter)),
+                    // Values that can't be normalized match nothing.
+                    _ => AttributeFilter::Equals(d.normalize(filter).ok().flatten().unwrap_or_default()),
+                };
+                Some((d.id, filter))
+            })
+            .collect();
+        let sort_attribute = list
+            .sort
+            .as_deref()
+            .and_then(|s| s.strip_prefix("attribute-"))
+            .and_then(|id| id.parse::<i32>().ok())
+            .and_then(|id| definitions.iter().find(|d| d.id == id));
+
+        with_conn!(self.pool, |conn| {
+            let filtered = || {
+                let mut query = client::table
+                    .left_join(os_info::table)
+                    .filter(in_group!(client::id, group))
+                    .select((client::all_columns, os_info::all_columns.nullable()))
+                    .into_boxed();
+                filter_column!(query, list, "name", os_info::computer_name);
+                filter_column!(query, list, "domain", os_info::domain);
+                filter_column!(query, list, "os", os_info::os);
+                filter_column!(query, list, "os_version", os_info::os_version);
+                for (attribute_id, filter) in &attribute_filters {
+                    let matching = client_attribute::table
+                        .select(client_attribute::client_id)
+                        .filter(client_attribute::attribute_id.eq(*attribute_id))
+                        .into_boxed();
+                    let matching = match filter {
+                        AttributeFilter::Contains(pattern) => matching.filter(
+                            lower(client_attribute::value.nullable())
+                                .like(pattern.clone())
+                                .escape('\\'),
+                        ),
+                        AttributeFilter::Equals(value) => matching.filter(client_attribute::value.eq(value.clone())),
+                    };
+                    query = query.filter(client::id.eq_any(matching));
+                }
+                query
+            };
+
+            let total: i64 = filtered().count().get_result(&mut conn)?;
+            let query = filtered();
+            let query = match sort_attribute {
+                Some(definition) => {
+                    let value = || {
+                        client_attribute::table
+                            .select(client_attribute::value)
+                            .filter(client_attribute::client_id.eq(client::id))
+                            .filter(client_attribute::attribute_id.eq(definition.id))
+                            .single_value()
+                    };
+                    // Clients without a value go last in both directions.
+                    let query = query.order_by(value().is_null());
+                    match (definition.kind(), list.desc) {
+                        (AttributeKind::Number, desc) => {
+                            let number = sql::<Nullable<Numeric>>(
+                                "(SELECT CAST(client_attribute.value AS NUMERIC) FROM client_attribute \
+                                 WHERE client_attribute.client_id = client.id AND client_attribute.attribute_id = ",
+                            )
+                            .bind::<Integer, _>(definition.id)
+                            .sql(")");
+                            if desc {
+                                query.then_order_by(number.desc())
+                            } else {
+                                query.then_order_by(number.asc())
+                            }
+                        }
+                        (AttributeKind::Text | AttributeKind::Enum, true) => query.then_order_by(lower(value()).desc()),
+                        (AttributeKind::Text | AttributeKind::Enum, false) => query.then_order_by(lower(value()).asc()),
+                        (_, true) => query.then_order_by(value().desc()),
+                        (_, false) => query.then_order_by(value().asc()),
+                    }
+                }
+                None => order_by_list!(query, list, os_info::computer_name.asc(), {
+                    "name" => os_info::computer_name,
+                    "domain" => os_info::domain,
+                    "os" => os_info::os,
+                    "os_version" => os_info::os_version,
+                }),
+            };
+            let items = query
+                .then_order_by(os_info::computer_name)
+                .then_order_by(client::id)
+                .limit(list.limit())
+                .offset(list.offset())
+                .load::<(Client, Option<OsInfo>)>(&mut conn)?;
+            Ok(Page::new(items, total, list))
+        })
+    }
+
+    /// Software installed in `group`. Columns: `name`, `publisher`.
+    pub fn get_software_page(&self, group: Option<i32>, list: &ListQuery) -> Result<Page<SoftwareInfo>> {
+        with_conn!(self.pool, |conn| {
+            let filtered = || {
+                let mut query = software_info::table
+                    .filter(
+                        software_info::id
+                            .eq_any(
+                                software_version::table
+                                    .inner_join(software_list::table)
+                                    .select(software_version::software_id)
+                                    .filter(in_group!(software_list::client_id, group)),
+                            )
+                            .or(group.is_none().into_sql::<Bool>()),
+                    )
+                    .into_boxed();
+                filter_column!(query, list, "name", software_info::name);
+                filter_column!(query, list, "publisher", software_info::publisher);
+                query
+            };
+
+            let total: i64 = filtered().count().get_result(&mut conn)?;
+            let query = filtered();
+            let items = order_by_list!(query, list, lower(software_info::name.nullable()).asc(), {
+                "name" => lower(software_info::name.nullable()),
+                "publisher" => lower(software_info::publisher),
+            })
+            .then_order_by(software_info::id)
+            .limit(list.limit())
+            .offset(list.offset())
+            .load::<SoftwareInfo>(&mut conn)?;
+            Ok(Page::new(items, total, list))
+        })
+    }
+
+    /// Users with the number of their profiles. Columns: `username`, `domain`, `count`.
+    pub fn get_profile_page(&self, list: &ListQuery) -> Result<Page<UserWithProfileCount>> {
+        with_conn!(self.pool, |conn| {
+            let filtered = || {
+                let mut query = user::table
+                    .select((
+                        user::id,
+                        user::sid,
+                        user::username,
+                        user::domain,
+                        coalesce(
+                            userprofile::table
+                                .filter(userprofile::user_id.eq(user::id))
+                                .count()
+                                .single_value(),
+                            0,
+                        ),
+                    ))
+                    .into_boxed();
+                filter_column!(query, list, "username", user::username);
+                filter_column!(query, list, "domain", user::domain);
+                query
+            };
+
+            let total: i64 = filtered().count().get_result(&mut conn)?;
+            let query = filtered();
+            let profile_count = coalesce(
+                userprofile::table
+                    .filter(userprofile::user_id.eq(user::id))
+                    .count()
+                    .single_value(),
+                0,
+            );
+            let items = order_by_list!(query, list, lower(user::username).asc(), {
+                "username" => lower(user::username),
+                "domain" => lower(user::domain),
+                "count" => profile_count,
+            })
+            .then_order_by(user::id)
+            .limit(list.limit())
+            .offset(list.offset())
+            .load::<UserWithProfileCount>(&mut conn)?;
+            Ok(Page::new(items, total, list))
+        })
+    }
+
+    /// Columns: `name`, `manufacturer`, `cores`, `logical_cores`, `clock_speed`,
+    /// `address_width`, `count`. Read from the statistics.
+    pub fn get_processors_page(&self, group: Option<i32>, list: &ListQuery) -> Result<Page<ProcessorCount>> {
+        with_conn!(self.pool, |conn| {
+            let filtered = || {
+                let mut query = stat_processor::table
+                    .filter(in_scope!(stat_processor::group_id, group))
+                    .into_boxed();
+                filter_column!(query, list, "name", stat_processor::name);
+                filter_column!(query, list, "manufacturer", stat_processor::manufacturer);
+                query
+            };
+
+            let total: i64 = filtered().count().get_result(&mut conn)?;
+            let query = filtered().select((
+                stat_processor::name,
+                stat_processor::manufacturer,
+                stat_processor::cores,
+                stat_processor::logical_cores,
+                stat_processor::clock_speed,
+                stat_processor::address_width,
+                stat_processor::count,
+            ));
+            let items = order_by_list!(query, list, stat_processor::name.asc(), {
+                "name" => stat_processor::name,
+                "manufacturer" => stat_processor::manufacturer,
+                "cores" => stat_processor::cores,
+                "logical_cores" => stat_processor::logical_cores,
+                "clock_speed" => stat_processor::clock_speed,
+                "address_width" => stat_processor::address_width,
+                "count" => stat_processor::count,
+            })
+            .then_order_by((stat_processor::name, stat_processor::manufacturer))
+            .limit(list.limit())
+            .offset(list.offset())
+            .load::<ProcessorCount>(&mut conn)?;
+            Ok(Page::new(items, total, list))
+        })
+    }
+
+    /// Columns: `capacity`, `sticks`, `count`. Read from the statistics.
+    pub fn get_memory_page(&self, group: Option<i32>, list: &ListQuery) -> Result<Page<MemoryCount>> {
+        with_conn!(self.pool, |conn| {
+            let filtered = || {
+                stat_memory::table
+                    .filter(in_scope!(stat_memory::group_id, group))
+                    .into_boxed()
+            };
+
+            let total: i64 = filtered().count().get_result(&mut conn)?;
+            let query = filtered().select((stat_memory::capacity, stat_memory::sticks, stat_memory::count));
+            let items = order_by_list!(query, list, stat_memory::capacity.asc(), {
+                "capacity" => stat_memory::capacity,
+                "sticks" => stat_memory::sticks,
+                "count" => stat_memory::count,
+            })
+            .then_order_by((stat_memory::capacity, stat_memory::sticks))
+            .limit(list.limit())
+            .offset(list.offset())
+            .load::<MemoryCount>(&mut conn)?;
+            Ok(Page::new(items, total, list))
+        })
+    }
+
+    /// Columns: `name`, `count`.
+    pub fn get_graphics_cards_page(&self, group: Option<i32>, list: &ListQuery) -> Result<Page<GraphicsCardCount>> {
+        with_conn!(self.pool, |conn| {
+            let filtered = || {
+                let mut query = graphics_card::table
+                    .filter(in_group!(graphics_card::client_id, group))
+                    .into_boxed();
+                filter_column!(query, list, "name", graphics_card::name);
+                query
+            };
+
+            let total: i64 = filtered()
+                .select(count(graphics_card::name).aggregate_distinct())
+                .get_result(&mut conn)?;
+            let mut query = graphics_card::table
+                .filter(in_group!(graphics_card::client_id, group))
+                .group_by(graphics_card::name)
+                .select((graphics_card::name, count_star()))
+                .into_boxed();
+            filter_column!(query, list, "name", graphics_card::name);
+            let items = order_by_list!(query, list, graphics_card::name.asc(), {
+                "name" => graphics_card::name,
+                "count" => count_star(),
+            })
+            .then_order_by(graphics_card::name)
+            .limit(list.limit())
+            .offset(list.offset())
+            .load::<GraphicsCardCount>(&mut conn)?;
+            Ok(Page::new(items, total, list))
+        })
+    }
+
+    /// Columns: `model`, `size`, `count`. Read from the statistics.
+    pub fn get_disks_page(&self, group: Option<i32>, list: &ListQuery) -> Result<Page<DiskCount>> {
+        with_conn!(self.pool, |conn| {
+            let filtered = || {
+                let mut query = stat_disk::table
+                    .filter(in_scope!(stat_disk::group_id, group))
+                    .into_boxed();
+                filter_column!(query, list, "model", stat_disk::model);
+                query
+            };
+
+            let total: i64 = filtered().count().get_result(&mut conn)?;
+            let query = filtered().select((stat_disk::model, stat_disk::size, stat_disk::count));
+            let items = order_by_list!(query, list, stat_disk::model.asc(), {
+                "model" => stat_disk::model,
+                "size" => stat_disk::size,
+                "count" => stat_disk::count,
+            })
+            .then_order_by((stat_disk::model, stat_disk::size))
+            .limit(list.limit())
+            .offset(list.offset())
+            .load::<DiskCount>(&mut conn)?;
+            Ok(Page::new(items, total, list))
+        })
+    }
+
+    /// Columns: `manufacturer`, `model_family`, `count`. Read from the statistics.
+    pub fn get_computer_models_page(&self, group: Option<i32>, list: &ListQuery) -> Result<Page<ComputerModelCount>> {
+        with_conn!(self.pool, |conn| {
+            let filtered = || {
+                let mut query = stat_computer_model::table
+                    .filter(in_scope!(stat_computer_model::group_id, group))
+                    .into_boxed();
+                filter_column!(query, list, "manufacturer", stat_computer_model::manufacturer);
+                filter_column!(query, list, "model_family", stat_computer_model::model_family);
+                query
+            };
+
+            let total: i64 = filtered().count().get_result(&mut conn)?;
+            let query = filtered().select((
+                stat_computer_model::manufacturer,
+                stat_computer_model::model_family,
+                stat_computer_model::count,
+            ));
+            let items = order_by_list!(query, list, stat_computer_model::manufacturer.asc(), {
+                "manufacturer" => stat_computer_model::manufacturer,
+                "model_family" => stat_computer_model::model_family,
+                "count" => stat_computer_model::count,
+            })
+            .then_order_by((stat_computer_model::manufacturer, stat_computer_model::model_family))
+            .limit(list.limit())
+            .offset(list.offset())
+            .load::<ComputerModelCount>(&mut conn)?;
+            Ok(Page::new(items, total, list))
+        })
+    }
+
+    /// Columns: `name`, `count`.
+    pub fn get_network_adapters_page(&self, group: Option<i32>, list: &ListQuery) -> Result<Page<NetworkAdapterCount>> {
+        with_conn!(self.pool, |conn| {
+            let filtered = || {
+                let mut query = network_adapter::table
+                    .filter(in_group!(network_adapter::client_id, group))
+                    .into_boxed();
+                filter_column!(query, list, "name", network_adapter::name);
+                query
+            };
+
+            let total: i64 = filtered()
+                .select(count(network_adapter::name).aggregate_distinct())
+                .get_result(&mut conn)?;
+            let mut query = network_adapter::table
+                .filter(in_group!(network_adapter::client_id, group))
+                .group_by(network_adapter::name)
+                .select((network_adapter::name, count_star()))
+                .into_boxed();
+            filter_column!(query, list, "name", network_adapter::name);
+            let items = order_by_list!(query, list, network_adapter::name.asc(), {
+                "name" => network_adapter::name,
+                "count" => count_star(),
+            })
+            .then_order_by(network_adapter::name)
+            .limit(list.limit())
+            .offset(list.offset())
+            .load::<NetworkAdapterCount>(&mut conn)?;
+            Ok(Page::new(items, total, list))
+        })
+    }
+}
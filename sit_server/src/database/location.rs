@@ -0,0 +1,349 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+
+use super::{model::*, schema::*, DbPool};
+
+/// An IPv4 or IPv6 network in CIDR notation, e.g. `10.20.4.0/24`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Subnet {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Subnet {
+    fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Subnet {
+    type Err = anyhow::Error;
+
+    /// Parses `address/prefix`, host bits of the address are cleared. A bare
+    /// address is a network of just that host.
+    fn from_str(s: &str) -> Result<Self> {
+        let (address, prefix) = match s.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let address = IpAddr::from_str(address).map_err(|_| anyhow!("Invalid address: '{s}'"))?;
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max_prefix)
+                .ok_or_else(|| anyhow!("Invalid prefix length: '{s}'"))?,
+            None => max_prefix,
+        };
+        let network = match address {
+            IpAddr::V4(a) => {
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                IpAddr::from(std::net::Ipv4Addr::from(u32::from(a) & mask))
+            }
+            IpAddr::V6(a) => {
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                IpAddr::from(std::net::Ipv6Addr::from(u128::from(a) & mask))
+            }
+        };
+        Ok(Subnet { network, prefix })
+    }
+}
+
+impl fmt::Display for Subnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// A room with the building and site it belongs to.
+pub type RoomPath = (Room, (Building, Site));
+
+/// A located client with its os info and model where known.
+pub type LocatedClient = (ClientLocation, (Client, Option<OsInfo>, Option<ComputerModel>));
+
+pub struct LocationManager {
+    pool: DbPool,
+}
+
+impl LocationManager {
+    pub fn new(pool: DbPool) -> LocationManager {
+        LocationManager { pool }
+    }
+
+    pub fn get_sites(&self) -> Result<Vec<Site>> {
+        with_conn!(self.pool, |conn| {
+            Ok(site::table.order_by(site::name).load(&mut conn)?)
+        })
+    }
+
+    pub fn get_buildings(&self) -> Result<Vec<Building>> {
+        with_conn!(self.pool, |conn| {
+            Ok(building::table.order_by(building::name).load(&mut conn)?)
+        })
+    }
+
+    pub fn get_rooms(&self) -> Result<Vec<Room>> {
+        with_conn!(self.pool, |conn| {
+            Ok(room::table.order_by(room::name).load(&mut conn)?)
+        })
+    }
+
+    pub fn get_room(&self, room_id: i32) -> Result<RoomPath> {
+        with_conn!(self.pool, |conn| {
+            Ok(room::table
+                .inner_join(building::table.inner_join(site::table))
+                .filter(room::id.eq(room_id))
+                .get_result(&mut conn)?)
+        })
+    }
+
+    /// Number of clients per room id.
+    pub fn get_room_client_counts(&self) -> Result<Vec<(i32, i64)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_location::table
+                .group_by(client_location::room_id)
+                .select((client_location::room_id, count_star()))
+                .load(&mut conn)?)
+        })
+    }
+
+    pub fn create_site(&self, name: &str) -> Result<Site> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::insert_into(site::table)
+                .values(NewSite { name })
+                .get_result(&mut conn)?)
+        })
+    }
+
+    pub fn create_building(&self, site_id: i32, name: &str) -> Result<Building> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::insert_into(building::table)
+                .values(NewBuilding { site_id, name })
+                .get_result(&mut conn)?)
+        })
+    }
+
+    pub fn create_room(&self, building_id: i32, name: &str) -> Result<Room> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::insert_into(room::table)
+                .values(NewRoom { building_id, name })
+                .get_result(&mut conn)?)
+        })
+    }
+
+    pub fn delete_site(&self, site_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(site::table)
+                .filter(site::id.eq(site_id))
+                .execute(&mut conn)?)
+        })
+    }
+
+    pub fn delete_building(&self, building_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(building::table)
+                .filter(building::id.eq(building_id))
+                .execute(&mut conn)?)
+        })
+    }
+
+    pub fn delete_room(&self, room_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(room::table)
+                .filter(room::id.eq(room_id))
+                .execute(&mut conn)?)
+        })
+    }
+
+    pub fn get_room_subnets(&self, room_id: i32) -> Result<Vec<RoomSubnet>> {
+        with_conn!(self.pool, |conn| {
+            Ok(room_subnet::table
+                .filter(room_subnet::room_id.eq(room_id))
+                .order_by(room_subnet::subnet)
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Adds a subnet in CIDR notation to a room and reassigns all clients not placed by hand.
+    pub fn add_room_subnet(&self, room_id: i32, subnet: &str) -> Result<()> {
+        let subnet = Subnet::from_str(subnet)?.to_string();
+        with_conn!(self.pool, |conn| {
+            diesel::insert_into(room_subnet::table)
+                .values(NewRoomSubnet {
+                    room_id,
+                    subnet: &subnet,
+                })
+                .execute(&mut conn)?;
+            Ok::<(), anyhow::Error>(())
+        })?;
+        self.assign_by_subnet(None)
+    }
+
+    pub fn delete_room_subnet(&self, subnet_id: i32) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            diesel::delete(room_subnet::table)
+                .filter(room_subnet::id.eq(subnet_id))
+                .execute(&mut conn)?;
+            Ok::<(), anyhow::Error>(())
+        })?;
+        self.assign_by_subnet(None)
+    }
+
+    pub fn get_room_clients(&self, room_id: i32) -> Result<Vec<LocatedClient>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_location::table
+                .inner_join(
+                    client::table
+                        .left_join(os_info::table)
+                        .left_join(computer_model::table),
+                )
+                .filter(client_location::room_id.eq(room_id))
+                .order_by(os_info::computer_name)
+                .load(&mut conn)?)
+        })
+    }
+
+    pub fn get_client_location(&self, client_id: i32) -> Result<Option<(ClientLocation, RoomPath)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_location::table
+                .inner_join(room::table.inner_join(building::table.inner_join(site::table)))
+                .filter(client_location::client_id.eq(client_id))
+                .get_result(&mut conn)
+                .optional()?)
+        })
+    }
+
+    /// Addresses of the network adapters of the given clients as (client id, ip).
+    pub fn get_client_ips(&self, client_ids: &[i32]) -> Result<Vec<(i32, String)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(network_adapter_ip::table
+                .inner_join(network_adapter::table)
+                .filter(network_adapter::client_id.eq_any(client_ids))
+                .select((network_adapter::client_id, network_adapter_ip::ip))
+                .order_by(network_adapter_ip::ip)
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Places a client in a room by hand, subnet rules no longer move it. With `None`
+    /// the client goes back to being placed by subnet.
+    pub fn set_client_room(&self, client_id: i32, room_id: Option<i32>) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            match room_id {
+                Some(room_id) => {
+                    let location = ClientLocation {
+                        client_id,
+                        room_id,
+                        manual: true,
+                    };
+                    diesel::insert_into(client_location::table)
+                        .values(&location)
+                        .on_conflict(client_location::client_id)
+                        .do_update()
+                        .set((
+                            client_location::room_id.eq(room_id),
+                            client_location::manual.eq(true),
+                        ))
+                        .execute(&mut conn)?;
+                }
+                None => {
+                    diesel::delete(client_location::table)
+                        .filter(client_location::client_id.eq(client_id))
+                        .execute(&mut conn)?;
+                }
+            }
+            Ok::<(), anyhow::Error>(())
+        })?;
+        match room_id {
+            Some(_) => Ok(()),
+            None => self.assign_by_subnet(Some(client_id)),
+        }
+    }
+
+    /// Places clients not placed by hand in the room whose subnet contains one of
+    /// their addresses, the most specific subnet wins. Clients without a match lose
+    /// their room. Covers all clients, or only `only_client` if given.
+    pub fn assign_by_subnet(&self, only_client: Option<i32>) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            conn.transaction::<(), anyhow::Error, _>(|c| {
+                let subnets: Vec<(Subnet, i32)> = room_subnet::table
+                    .load::<RoomSubnet>(c)?
+                    .into_iter()
+                    .filter_map(|s| Some((Subnet::from_str(&s.subnet).ok()?, s.room_id)))
+                    .collect();
+                let manual: HashSet<i32> = client_location::table
+                    .filter(client_location::manual.eq(true))
+                    .select(client_location::client_id)
+                    .load::<i32>(c)?
+                    .into_iter()
+                    .collect();
+                let ips: Vec<(i32, String)> = network_adapter_ip::table
+                    .inner_join(network_adapter::table)
+                    .filter(
+                        network_adapter::client_id
+                            .nullable()
+                            .eq(only_client)
+                            .or(only_client.is_none().into_sql::<diesel::sql_types::Bool>()),
+                    )
+                    .select((network_adapter::client_id, network_adapter_ip::ip))
+                    .load(c)?;
+
+                let mut rooms: HashMap<i32, (u8, i32)> = HashMap::new();
+                for (client_id, ip) in ips {
+                    if manual.contains(&client_id) {
+                        continue;
+                    }
+                    let Ok(ip) = IpAddr::from_str(&ip) else { continue };
+                    for (subnet, room_id) in subnets.iter().filter(|(s, _)| s.contains(&ip)) {
+                        let best = rooms.entry(client_id).or_insert((subnet.prefix, *room_id));
+                        if subnet.prefix > best.0 {
+                            *best = (subnet.prefix, *room_id);
+                        }
+                    }
+                }
+
+                diesel::delete(client_location::table)
+                    .filter(client_location::manual.eq(false))
+                    .filter(
+                        client_location::client_id
+                            .nullable()
+                            .eq(only_client)
+                            .or(only_client.is_none().into_sql::<diesel::sql_types::Bool>()),
+                    )
+                    .execute(c)?;
+                let locations: Vec<ClientLocation> = rooms
+                    .into_iter()
+                    .map(|(client_id, (_, room_id))| ClientLocation {
+                        client_id,
+                        room_id,
+                        manual: false,
+                    })
+                    .collect();
+                for chunk in locations.chunks(500) {
+                    diesel::insert_into(client_location::table)
+                        .values(chunk)
+                        .execute(c)?;
+                }
+                Ok(())
+            })
+        })
+    }
+}
+
@@ -0,0 +1,95 @@
+//! Login attempts of the web UI, for throttling failed logins and as an audit
+//! trail of who tried to log in from where.
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::dsl::{count_star, max};
+use diesel::prelude::*;
+
+use super::{model::*, schema::*, DbPool};
+
+pub struct LoginAttemptManager {
+    pool: DbPool,
+}
+
+impl LoginAttemptManager {
+    pub fn new(pool: DbPool) -> LoginAttemptManager {
+        LoginAttemptManager { pool }
+    }
+
+    /// Returns the id of the new attempt.
+    pub fn add_attempt(&self, username: &str, ip: Option<&str>, attempted: NaiveDateTime, success: bool) -> Result<i32> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::insert_into(login_attempt::table)
+                .values(NewLoginAttempt {
+                    username,
+                    ip,
+                    attempted,
+                    success,
+                })
+                .returning(login_attempt::id)
+                .get_result(&mut conn)?)
+        })
+    }
+
+    /// Marks the attempt `attempt_id`, recorded as failed, as successful.
+    pub fn set_success(&self, attempt_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::update(login_attempt::table)
+                .filter(login_attempt::id.eq(attempt_id))
+                .set(login_attempt::success.eq(true))
+                .execute(&mut conn)?)
+        })
+    }
+
+    /// Failed attempts for `username` since `since` and since its last
+    /// successful login.
+    pub fn count_failures_by_username(&self, username: &str, since: NaiveDateTime) -> Result<i64> {
+        with_conn!(self.pool, |conn| {
+            let last_success: Option<NaiveDateTime> = login_attempt::table
+                .filter(login_attempt::username.eq(username))
+                .filter(login_attempt::success.eq(true))
+                .select(max(login_attempt::attempted))
+                .first(&mut conn)?;
+            let since = last_success.map_or(since, |t| t.max(since));
+            Ok(login_attempt::table
+                .filter(login_attempt::username.eq(username))
+                .filter(login_attempt::success.eq(false))
+                .filter(login_attempt::attempted.gt(since))
+                .select(count_star())
+                .first(&mut conn)?)
+        })
+    }
+
+    /// Failed attempts from `ip` since `since`. Successful logins from there don't
+    /// reset the count, an attacker with one valid account could do so at will.
+    pub fn count_failures_by_ip(&self, ip: &str, since: NaiveDateTime) -> Result<i64> {
+        with_conn!(self.pool, |conn| {
+            Ok(login_attempt::table
+                .filter(login_attempt::ip.eq(ip))
+                .filter(login_attempt::success.eq(false))
+                .filter(login_attempt::attempted.gt(since))
+                .select(count_star())
+                .first(&mut conn)?)
+        })
+    }
+
+    /// The latest failed attempts, newest first.
+    pub fn get_failures(&self, limit: i64) -> Result<Vec<LoginAttempt>> {
+        with_conn!(self.pool, |conn| {
+            Ok(login_attempt::table
+                .filter(login_attempt::success.eq(false))
+                .order_by((login_attempt::attempted.desc(), login_attempt::id.desc()))
+                .limit(limit)
+                .load(&mut conn)?)
+        })
+    }
+
+    pub fn delete_attempts_before(&self, cutoff: NaiveDateTime) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(login_attempt::table)
+                .filter(login_attempt::attempted.lt(cutoff))
+                .execute(&mut conn)?)
+        })
+    }
+}
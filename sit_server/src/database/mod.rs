@@ -1,40 +1,225 @@
 extern crate dotenv;
 
 use std::env;
-use anyhow::Result;
+use std::time::Duration;
+use anyhow::{anyhow, Result};
 use bigdecimal::BigDecimal;
 use chrono::NaiveDateTime;
 use diesel::dsl::{count, count_star, max, sum};
+use diesel::connection::SimpleConnection;
 use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool};
 use diesel::sql_types::{BigInt, Nullable};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dotenv::dotenv;
-use sit_lib::hardware::{BatteryStatus, Disks, HardwareInfo, HardwareInfoV2, Network, PhysicalMemory, BIOS};
+use sit_lib::hardware::BatteryStatus;
 use sit_lib::licenses::LicenseBundle;
 use sit_lib::os::WinOsInfo;
-use sit_lib::software::SoftwareLibrary;
 use sit_lib::system_status::VolumeList;
 use uuid::Uuid;
 
 use crate::database::model::*;
 use crate::database::schema::*;
+use crate::database::types::DbUuid;
+use self::access_token::AccessTokenManager;
+use self::asset::AssetManager;
+use self::attribute::AttributeManager;
 use self::domain_user::UserManager;
+use self::group::GroupManager;
+use self::ingest::IngestManager;
+use self::location::LocationManager;
+use self::login_attempt::LoginAttemptManager;
+use self::search::SearchManager;
+use self::section_hash::SectionHashManager;
+use self::statistics::StatisticsManager;
 use self::task::TaskManager;
 
+/// Checks out a connection from a [`DbPool`] and evaluates `$body` with it bound
+/// to `$conn`. The body is expanded once per backend, so every query in it has to
+/// build for Postgres and SQLite alike.
+macro_rules! with_conn {
+    ($pool:expr, |$conn:ident| $body:expr) => {
+        match &$pool {
+            $crate::database::DbPool::Postgres(pool) => {
+                #[allow(unused_mut)]
+                let mut $conn = pool.get()?;
+                $body
+            }
+            $crate::database::DbPool::Sqlite(pool) => {
+                #[allow(unused_mut)]
+                let mut $conn = pool.get()?;
+                $body
+            }
+        }
+    };
+}
+
+/// Restricts `$client_id` to the members of the client group `$group`.
+/// With `None` as group every client passes.
+macro_rules! in_group {
+    ($client_id:expr, $group:expr) => {
+        $client_id
+            .eq_any(
+                client_group_member::table
+                    .select(client_group_member::client_id)
+                    .filter(client_group_member::group_id.nullable().eq($group)),
+            )
+            .or($group.is_none().into_sql::<diesel::sql_types::Bool>())
+    };
+}
+
+/// Restricts the nullable `$group_id` column of a statistics table to the rows
+/// of the client group `$group`, or to those of the whole fleet with `None`.
+macro_rules! in_scope {
+    ($group_id:expr, $group:expr) => {
+        $group_id
+            .eq($group)
+            .or($group_id
+                .is_null()
+                .and($group.is_none().into_sql::<diesel::sql_types::Bool>()))
+    };
+}
+
+/// Stores the [`SectionHash`] `$hash`, if there is one, on `$conn`. Used in the
+/// transaction that applies the section, so the hash is only remembered together
+/// with the content it stands for.
+macro_rules! store_section_hash {
+    ($conn:expr, $hash:expr) => {
+        if let Some(hash) = $hash {
+            use $crate::database::schema::section_hash;
+            diesel::insert_into(section_hash::table)
+                .values(hash)
+                .on_conflict((section_hash::client_id, section_hash::section))
+                .do_update()
+                .set((
+                    section_hash::hash.eq(&hash.hash),
+                    section_hash::accepted.eq(hash.accepted),
+                    section_hash::last_seen.eq(hash.last_seen),
+                ))
+                .execute($conn)?;
+        }
+    };
+}
+
+mod access_token;
+mod asset;
+mod attribute;
 mod domain_user;
+mod export;
+mod group;
+mod group_rule;
+mod hardware;
+mod ingest;
+mod list;
+mod location;
+mod login_attempt;
 mod model;
 mod schema;
+mod search;
+mod section_hash;
+mod software;
+mod statistics;
 mod task;
+mod transfer;
+mod types;
+
+pub use self::asset::{AssetImport, AssetInput, ClientAsset};
+pub use self::attribute::AttributeKind;
+pub use self::export::InventoryRow;
+pub use self::group_rule::{GroupRule, RuleCondition, RuleField};
+pub use self::ingest::{STATUS_FAILED, STATUS_PENDING, STATUS_RUNNING};
+pub use self::list::{ListQuery, Page, MAX_PER_PAGE};
+pub use self::model::{IngestJob, SectionHash};
+pub use self::search::SearchResults;
 
 define_sql_function! { fn coalesce(x: Nullable<BigInt>, y: BigInt) -> BigInt; }
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+pub const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_sqlite");
+
+#[derive(Clone)]
+pub enum DbPool {
+    Postgres(Pool<ConnectionManager<PgConnection>>),
+    Sqlite(Pool<ConnectionManager<SqliteConnection>>),
+}
 
+/// Size and timeouts of the connection pool.
+#[derive(Clone, Debug)]
+pub struct PoolConfig {
+    pub max_size: u32,
+    /// How long to wait for a free connection before giving up.
+    pub connection_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> PoolConfig {
+        PoolConfig {
+            max_size: 10,
+            connection_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A change to a user that can take away administrator access.
+pub enum AuthUserChange<'a> {
+    Role(&'a str),
+    Disable,
+    Delete,
+}
+
+impl PoolConfig {
+    /// Reads `DATABASE_POOL_SIZE` and `DATABASE_TIMEOUT` (in seconds), falling
+    /// back to the defaults for unset values.
+    pub fn from_env() -> Result<PoolConfig> {
+        let mut config = PoolConfig::default();
+        if let Ok(size) = env::var("DATABASE_POOL_SIZE") {
+            config.max_size = size
+                .parse()
+                .map_err(|e| anyhow!("DATABASE_POOL_SIZE '{size}': {e}"))?;
+        }
+        if let Ok(timeout) = env::var("DATABASE_TIMEOUT") {
+            config.connection_timeout = Duration::from_secs(
+                timeout
+                    .parse()
+                    .map_err(|e| anyhow!("DATABASE_TIMEOUT '{timeout}': {e}"))?,
+            );
+        }
+        Ok(config)
+    }
+}
+
+/// Applied to every pooled SQLite connection, as these settings are per connection.
+#[derive(Debug)]
+struct SqliteConnectionOptions;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for SqliteConnectionOptions {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Clones share the connection pool.
 pub struct Database {
-    pool: Pool<ConnectionManager<PgConnection>>,
+    pool: DbPool,
     user_manager: UserManager,
     task_manager: TaskManager,
+    group_manager: GroupManager,
+    location_manager: LocationManager,
+    asset_manager: AssetManager,
+    attribute_manager: AttributeManager,
+    search_manager: SearchManager,
+    access_token_manager: AccessTokenManager,
+    login_attempt_manager: LoginAttemptManager,
+    section_hash_manager: SectionHashManager,
+    ingest_manager: IngestManager,
+    statistics_manager: StatisticsManager,
+}
+
+impl Clone for Database {
+    fn clone(&self) -> Database {
+        Database::from_pool(self.pool.clone())
+    }
 }
 
 impl Database {
@@ -42,23 +227,90 @@ impl Database {
         dotenv().ok();
 
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let pool_config = PoolConfig::from_env().unwrap_or_else(|e| panic!("Invalid pool configuration: {e}"));
 
-        PgConnection::establish(&database_url)
-            .unwrap_or_else(|_| panic!("Error connection to {database_url}"))
-            .run_pending_migrations(MIGRATIONS)
-            .expect("Migrations failed");
+        Self::connect_with(&database_url, &pool_config)
+            .unwrap_or_else(|e| panic!("Error connection to {database_url}: {e}"))
+    }
+
+    /// Connects with a pool of at most `max_pool_size` connections, see [`Database::connect_with`].
+    pub fn connect(database_url: &str, max_pool_size: u32) -> Result<Database> {
+        Self::connect_with(
+            database_url,
+            &PoolConfig {
+                max_size: max_pool_size,
+                ..PoolConfig::default()
+            },
+        )
+    }
 
-        let manager = ConnectionManager::<PgConnection>::new(database_url);
+    /// Connects to the database named by `database_url` and brings its schema up to date.
+    ///
+    /// `postgres://` and `postgresql://` URLs select the Postgres backend,
+    /// `sqlite://<path>` selects SQLite with the database file at `<path>`.
+    pub fn connect_with(database_url: &str, pool_config: &PoolConfig) -> Result<Database> {
+        let pool = if let Some(path) = database_url.strip_prefix("sqlite://") {
+            let mut conn = SqliteConnection::establish(path)?;
+            conn.batch_execute("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
+            conn.run_pending_migrations(SQLITE_MIGRATIONS)
+                .map_err(|e| anyhow!("Migrations failed: {e}"))?;
+
+            DbPool::Sqlite(
+                Pool::builder()
+                    .max_size(pool_config.max_size)
+                    .connection_timeout(pool_config.connection_timeout)
+                    .test_on_check_out(true)
+                    .connection_customizer(Box::new(SqliteConnectionOptions))
+                    .build(ConnectionManager::<SqliteConnection>::new(path))?,
+            )
+        } else if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+            PgConnection::establish(database_url)?
+                .run_pending_migrations(MIGRATIONS)
+                .map_err(|e| anyhow!("Migrations failed: {e}"))?;
+
+            DbPool::Postgres(
+                Pool::builder()
+                    .max_size(pool_config.max_size)
+                    .connection_timeout(pool_config.connection_timeout)
+                    .test_on_check_out(true)
+                    .build(ConnectionManager::<PgConnection>::new(database_url))?,
+            )
+        } else {
+            return Err(anyhow!("Unsupported database url, expected postgres:// or sqlite://"));
+        };
 
-        let pool = Pool::builder()
-            .test_on_check_out(true)
-            .build(manager)
-            .expect("Could not build connection pool");
+        Ok(Database::from_pool(pool))
+    }
 
+    fn from_pool(pool: DbPool) -> Database {
         Database {
             pool: pool.clone(),
             user_manager: UserManager::new(pool.clone()),
-            task_manager: TaskManager::new(pool),
+            task_manager: TaskManager::new(pool.clone()),
+            group_manager: GroupManager::new(pool.clone()),
+            location_manager: LocationManager::new(pool.clone()),
+            asset_manager: AssetManager::new(pool.clone()),
+            attribute_manager: AttributeManager::new(pool.clone()),
+            search_manager: SearchManager::new(pool.clone()),
+            access_token_manager: AccessTokenManager::new(pool.clone()),
+            login_attempt_manager: LoginAttemptManager::new(pool.clone()),
+            section_hash_manager: SectionHashManager::new(pool.clone()),
+            ingest_manager: IngestManager::new(pool.clone()),
+            statistics_manager: StatisticsManager::new(pool),
+        }
+    }
+
+    /// Runs `f` on the blocking thread pool, so that waiting for a connection or
+    /// a query does not stall the async executor. Panics in `f` are passed on.
+    pub async fn run<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&Database) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let database = self.clone();
+        match rocket::tokio::task::spawn_blocking(move || f(&database)).await {
+            Ok(result) => result,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
         }
     }
 
@@ -70,300 +322,154 @@ impl Database {
         &self.task_manager
     }
 
-    pub fn create_client(&self, uuid: &Uuid) -> Result<Client> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::insert_into(client::table)
-            .values(NewClient { uuid })
-            .on_conflict(client::uuid)
-            .do_update()
-            .set(client::uuid.eq(uuid))
-            .get_result(&mut conn)?)
+    pub fn group_manager(&self) -> &GroupManager {
+        &self.group_manager
     }
 
-    pub fn get_client(&self, uuid: &Uuid) -> Result<Client> {
-        let mut conn = self.pool.get()?;
-        Ok(client::table
-            .filter(client::uuid.eq(uuid))
-            .get_result(&mut conn)?)
+    pub fn location_manager(&self) -> &LocationManager {
+        &self.location_manager
     }
 
-    pub fn create_os_info(&self, client: &Client, computer_name: &str) -> Result<OsInfo> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::insert_into(os_info::table)
-            .values(NewOsInfo {
-                client_id: &client.id,
-                computer_name,
-            })
-            .on_conflict(os_info::client_id)
-            .do_update()
-            .set(os_info::computer_name.eq(computer_name))
-            .get_result(&mut conn)?)
-    }
-
-    pub fn update_os_info(&self, client_id: i32, win_os_info: WinOsInfo) -> Result<usize> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::update(os_info::table)
-            .set(UpdateOsInfo {
-                os: Some(&win_os_info.operating_system),
-                os_version: Some(&win_os_info.os_version),
-                computer_name: Some(&win_os_info.computer_name),
-                domain: Some(&win_os_info.domain),
-            })
-            .filter(os_info::client_id.eq(client_id))
-            .execute(&mut conn)?)
-    }
-
-    pub fn create_hardware_info(&self, client_id: i32, hardware_info: HardwareInfo) -> Result<()> {
-        self.update_computer_model(&client_id, &hardware_info.model)?;
-        self.update_memory_stick(&client_id, &hardware_info.memory)?;
-        self.update_processor(&client_id, &hardware_info.processor)?;
-        self.update_disks(&client_id, &hardware_info.disks)?;
-        self.update_network_adapter(&client_id, &hardware_info.network)?;
-        self.update_graphics_card(&client_id, hardware_info.graphics)?;
-        self.update_bios(&client_id, &hardware_info.bios)?;
-        Ok(())
-    }
-
-    pub fn create_hardware_info_v2(&self, client_id: i32, hardware_info: HardwareInfoV2) -> Result<()> {
-        self.update_computer_model(&client_id, &hardware_info.model)?;
-        self.update_memory_stick(&client_id, &hardware_info.memory)?;
-        self.update_processor(&client_id, &hardware_info.processor)?;
-        self.update_disks(&client_id, &hardware_info.disks)?;
-        self.update_network_adapter(&client_id, &hardware_info.network)?;
-        self.update_graphics_card_v2(&client_id, &hardware_info.graphics)?;
-        self.update_bios(&client_id, &hardware_info.bios)?;
-        Ok(())
-    }
-
-    fn update_bios(&self, client_id: &i32, bios: &BIOS) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        diesel::insert_into(bios::table)
-            .values(NewBios {
-                client_id: &client_id,
-                name: &bios.name,
-                manufacturer: &bios.manufacturer,
-                version: &bios.version,
-            })
-            .on_conflict(bios::client_id)
-            .do_update()
-            .set((
-                bios::name.eq(&bios.name),
-                bios::manufacturer.eq(&bios.manufacturer),
-                bios::version.eq(&bios.version),
-            ))
-            .execute(&mut conn)?;
-        Ok(())
-    }
-
-    fn update_graphics_card(&self, client_id: &i32, graphics: sit_lib::hardware::GraphicsCard) -> Result<()> {
-        let g_vec = vec![graphics];
-        self.update_graphics_card_v2(client_id, &g_vec)
-    }
-
-    fn update_graphics_card_v2(&self, client_id: &i32, graphics: &Vec<sit_lib::hardware::GraphicsCard>) -> Result<()> {
-        self.pool
-            .get()?
-            .transaction::<(), diesel::result::Error, _>(|conn| {
-                let existing: Vec<GraphicsCard> = graphics_card::table
-                    .filter(graphics_card::client_id.eq(client_id))
-                    .load::<GraphicsCard>(conn)?;
-                let mut to_add: Vec<NewGraphicsCard> = vec![];
-                let mut to_delete: Vec<i32> = vec![];
+    pub fn asset_manager(&self) -> &AssetManager {
+        &self.asset_manager
+    }
 
-                for g in graphics {
-                    if !existing.iter().any(|i| i.name.eq(&g.name)) {
-                        to_add.push(NewGraphicsCard {
-                            client_id: &client_id,
-                            name: &g.name,
-                        });
-                    }
-                }
+    pub fn attribute_manager(&self) -> &AttributeManager {
+        &self.attribute_manager
+    }
 
-                for g in existing {
-                    if !&graphics.iter().any(|i| i.name.eq(&g.name)) {
-                        to_delete.push(g.id);
-                    }
-                }
+    pub fn search_manager(&self) -> &SearchManager {
+        &self.search_manager
+    }
 
-                if !to_add.is_empty() {
-                    diesel::insert_into(graphics_card::table)
-                        .values(to_add)
-                        .execute(conn)?;
-                }
+    pub fn access_token_manager(&self) -> &AccessTokenManager {
+        &self.access_token_manager
+    }
 
-                if !to_delete.is_empty() {
-                    diesel::delete(graphics_card::table)
-                        .filter(graphics_card::id.eq_any(to_delete))
-                        .execute(conn)?;
-                }
-                Ok(())
-            })?;
-        Ok(())
-    }
-
-    fn update_network_adapter(&self, client_id: &i32, network: &Network) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        diesel::delete(network_adapter::table.filter(network_adapter::client_id.eq(client_id)))
-            .execute(&mut conn)?;
-        for na in &network.adapter {
-            let db_na: NetworkAdapter = diesel::insert_into(network_adapter::table)
-                .values(NewNetworkAdapter {
-                    client_id: &client_id,
-                    name: &na.name,
-                    mac_address: na.mac_address.as_ref(),
-                })
-                .get_result(&mut conn)?;
-            diesel::delete(
-                network_adapter_ip::table.filter(network_adapter_ip::adapter_id.eq(db_na.id)),
-            )
-                .execute(&mut conn)?;
-            if let Some(ips) = &na.ip_addresses {
-                for nai in ips {
-                    diesel::insert_into(network_adapter_ip::table)
-                        .values(NewNetworkAdapterIp {
-                            adapter_id: &db_na.id,
-                            ip: &nai,
-                        })
-                        .execute(&mut conn)?;
-                }
-            }
-        }
-        Ok(())
-    }
-
-    fn update_disks(&self, client_id: &i32, disks: &Disks) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        diesel::delete(disks::table.filter(disks::client_id.eq(client_id))).execute(&mut conn)?;
-        for disk in &disks.drives {
-            diesel::insert_into(disks::table)
-                .values(NewDisk {
-                    client_id: &client_id,
-                    model: &disk.model,
-                    serial_number: &disk.serial_number,
-                    size: Some(BigDecimal::from(disk.size)),
-                    device_id: &disk.device_id,
-                    status: &disk.status,
-                    media_type: &disk.media_type,
-                })
-                .execute(&mut conn)?;
-        }
-        Ok(())
-    }
-
-    fn update_processor(&self, client_id: &i32, processor: &sit_lib::hardware::Processor) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        diesel::insert_into(processor::table)
-            .values(NewProcessor {
-                client_id: &client_id,
-                name: &processor.name,
-                manufacturer: &processor.manufacturer,
-                cores: &(processor.cores as i64),
-                logical_cores: &(processor.logical_cores as i64),
-                clock_speed: &(processor.clock_speed as i64),
-                address_width: &(processor.address_width as i32),
-            })
-            .on_conflict(processor::client_id)
-            .do_update()
-            .set((
-                processor::name.eq(&processor.name),
-                processor::manufacturer.eq(&processor.manufacturer),
-                processor::cores.eq(&(processor.cores as i64)),
-                processor::logical_cores.eq(&(processor.logical_cores as i64)),
-                processor::clock_speed.eq(&(processor.clock_speed as i64)),
-                processor::address_width.eq(&(processor.address_width as i32)),
-            ))
-            .execute(&mut conn)?;
-        Ok(())
-    }
-
-    fn update_memory_stick(&self, client_id: &i32, memory: &PhysicalMemory) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        diesel::delete(memory_stick::table.filter(memory_stick::client_id.eq(client_id)))
-            .execute(&mut conn)?;
-        for stick in &memory.sticks {
-            diesel::insert_into(memory_stick::table)
-                .values(NewMemoryStick {
-                    client_id: &client_id,
-                    capacity: &BigDecimal::from(stick.capacity),
-                    bank_label: &stick.bank_label,
-                })
-                .execute(&mut conn)?;
-        }
-        Ok(())
+    pub fn login_attempt_manager(&self) -> &LoginAttemptManager {
+        &self.login_attempt_manager
     }
 
-    fn update_computer_model(&self, client_id: &i32, model: &sit_lib::hardware::ComputerModel) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        diesel::insert_into(computer_model::table)
-            .values(NewComputerModel {
-                client_id: &client_id,
-                manufacturer: &model.manufacturer,
-                model_family: &model.model_family,
-                serial_number: &model.serial_number,
-            })
-            .on_conflict(computer_model::client_id)
-            .do_update()
-            .set((
-                computer_model::manufacturer.eq(&model.manufacturer),
-                computer_model::model_family.eq(&model.model_family),
-                computer_model::serial_number.eq(&model.serial_number),
-            ))
-            .execute(&mut conn)?;
-        Ok(())
-    }
-
-    pub fn update_software_lib(&self, client_id: i32, software_lib: SoftwareLibrary) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        let sl: Vec<SoftwareVersion> = software_lib
-            .software
-            .iter()
-            .map(|e| self.get_software_entry(&e.name, &e.version, e.publisher.clone()))
-            .filter_map(Result::ok)
-            .collect();
-        conn.transaction::<(), diesel::result::Error, _>(|c| {
-            diesel::delete(software_list::table)
-                .filter(software_list::client_id.eq(client_id))
-                .execute(c)?;
-            for s in sl {
-                diesel::insert_into(software_list::table)
-                    .values(NewSoftwareList {
-                        client_id: &client_id,
-                        software_id: &s.id,
+    pub fn section_hash_manager(&self) -> &SectionHashManager {
+        &self.section_hash_manager
+    }
+
+    pub fn ingest_manager(&self) -> &IngestManager {
+        &self.ingest_manager
+    }
+
+    pub fn statistics_manager(&self) -> &StatisticsManager {
+        &self.statistics_manager
+    }
+
+    pub fn create_client(&self, uuid: &Uuid) -> Result<Client> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::insert_into(client::table)
+                .values(NewClient { uuid })
+                .on_conflict(client::uuid)
+                .do_update()
+                .set(client::uuid.eq(DbUuid(*uuid)))
+                .get_result(&mut conn)?)
+        })
+    }
+
+    /// Records a payload of the client `client_id` received at `now`.
+    pub fn set_client_last_seen(&self, client_id: i32, now: NaiveDateTime) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::update(client::table)
+                .filter(client::id.eq(client_id))
+                .set(client::last_seen.eq(now))
+                .execute(&mut conn)?)
+        })
+    }
+
+    /// Deletes the clients with all their data that sent nothing since `cutoff`.
+    /// Clients without a recorded contact are kept.
+    pub fn delete_clients_not_seen_since(&self, cutoff: NaiveDateTime) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(client::table)
+                .filter(client::last_seen.lt(cutoff))
+                .execute(&mut conn)?)
+        })
+    }
+
+    pub fn get_client(&self, uuid: &Uuid) -> Result<Client> {
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .filter(client::uuid.eq(DbUuid(*uuid)))
+                .get_result(&mut conn)?)
+        })
+    }
+
+    pub fn create_os_info(&self, client: &Client, computer_name: &str) -> Result<OsInfo> {
+        let os_info: OsInfo = with_conn!(self.pool, |conn| {
+            diesel::insert_into(os_info::table)
+                .values(NewOsInfo {
+                    client_id: &client.id,
+                    computer_name,
+                })
+                .on_conflict(os_info::client_id)
+                .do_update()
+                .set(os_info::computer_name.eq(computer_name))
+                .get_result(&mut conn)
+        })?;
+        self.group_manager.refresh_client(client.id)?;
+        Ok(os_info)
+    }
+
+    pub fn update_os_info(&self, client_id: i32, win_os_info: WinOsInfo, hash: Option<&SectionHash>) -> Result<usize> {
+        let updated = with_conn!(self.pool, |conn| {
+            conn.transaction::<usize, diesel::result::Error, _>(|c| {
+                let updated = diesel::update(os_info::table)
+                    .set(UpdateOsInfo {
+                        os: Some(&win_os_info.operating_system),
+                        os_version: Some(&win_os_info.os_version),
+                        computer_name: Some(&win_os_info.computer_name),
+                        domain: Some(&win_os_info.domain),
                     })
+                    .filter(os_info::client_id.eq(client_id))
                     .execute(c)?;
-            }
-            Ok(())
+                store_section_hash!(c, hash);
+                Ok(updated)
+            })
         })?;
-        Ok(())
-    }
-
-    pub fn update_status_volumes(&self, client_id: i32, volumes: VolumeList) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        conn.transaction::<(), diesel::result::Error, _>(|c| {
-            diesel::delete(volume_status::table)
-                .filter(volume_status::client_id.eq(client_id))
-                .execute(c)?;
-            for v in volumes.volumes {
-                diesel::insert_into(volume_status::table)
-                    .values(NewVolumeStatus {
-                        client_id: &client_id,
-                        drive_letter: &v.drive_letter,
-                        label: v.label.as_ref(),
-                        file_system: &v.file_system,
-                        capacity: BigDecimal::from(v.capacity),
-                        free_space: BigDecimal::from(v.free_space),
-                    })
+        self.group_manager.refresh_client(client_id)?;
+        Ok(updated)
+    }
+
+    pub fn update_status_volumes(&self, client_id: i32, volumes: VolumeList, hash: Option<&SectionHash>) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            conn.transaction::<(), diesel::result::Error, _>(|c| {
+                diesel::delete(volume_status::table)
+                    .filter(volume_status::client_id.eq(client_id))
                     .execute(c)?;
-            }
+                for v in volumes.volumes {
+                    diesel::insert_into(volume_status::table)
+                        .values(NewVolumeStatus {
+                            client_id: &client_id,
+                            drive_letter: &v.drive_letter,
+                            label: v.label.as_ref(),
+                            file_system: &v.file_system,
+                            capacity: BigDecimal::from(v.capacity),
+                            free_space: BigDecimal::from(v.free_space),
+                        })
+                        .execute(c)?;
+                }
+                store_section_hash!(c, hash);
+                Ok(())
+            })?;
             Ok(())
-        })?;
-        Ok(())
+        })
     }
 
-    pub fn update_license_keys(&self, client_id: i32, license_bundles: LicenseBundle) -> Result<()> {
-        self.pool
-            .get()?
-            .transaction::<(), diesel::result::Error, _>(|conn| {
+    pub fn update_license_keys(
+        &self,
+        client_id: i32,
+        license_bundles: LicenseBundle,
+        hash: Option<&SectionHash>,
+    ) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            conn.transaction::<(), diesel::result::Error, _>(|conn| {
                 let existing: Vec<LicenseKey> = license_key::table
                     .filter(license_key::client_id.eq(client_id))
                     .load::<LicenseKey>(conn)?;
@@ -411,576 +517,747 @@ impl Database {
                         .filter(license_key::name.eq(name))
                         .execute(conn)?;
                 }
+                store_section_hash!(conn, hash);
                 Ok(())
             })?;
-        Ok(())
+            Ok(())
+        })
     }
 
-    fn get_software_entry(
-        &self,
-        name: &String,
-        version: &String,
-        publisher: Option<String>,
-    ) -> Result<SoftwareVersion> {
-        let publisher = &publisher.unwrap_or_default();
-        let mut conn = self.pool.get()?;
-        let entries: Option<SoftwareInfo> = software_info::table
-            .filter(software_info::name.eq(name))
-            .filter(software_info::publisher.eq(publisher))
-            .first::<SoftwareInfo>(&mut conn)
-            .optional()?;
-        let software_info = if let Some(entry) = entries {
-            Ok(entry)
-        } else {
-            self.create_software_entry(name, publisher)
-        };
-        if let Ok(software_info) = software_info {
-            let software_version: Option<SoftwareVersion> = software_version::table
-                .filter(software_version::software_id.eq(software_info.id))
-                .filter(software_version::version.eq(version))
-                .first(&mut conn)
-                .optional()?;
-            if let Some(software_version) = software_version {
-                Ok(software_version)
-            } else {
-                self.create_software_version(software_info.id, version)
-            }
-        } else {
-            Err(software_info.unwrap_err())
-        }
+    pub fn get_clients_with_os_info(&self, group: Option<i32>) -> Result<Vec<(Client, Option<OsInfo>)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .left_join(os_info::table)
+                .filter(in_group!(client::id, group))
+                .order_by(os_info::computer_name)
+                .load::<(Client, Option<OsInfo>)>(&mut conn)?)
+        })
     }
 
-    fn create_software_entry(&self, name: &String, publisher: &String) -> Result<SoftwareInfo> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::insert_into(software_info::table)
-            .values(NewSoftwareInfo {
-                name,
-                publisher: Some(publisher),
-            })
-            .get_result(&mut conn)?)
+    pub fn get_software_list(&self, group: Option<i32>) -> Result<Vec<SoftwareInfo>> {
+        with_conn!(self.pool, |conn| {
+            Ok(software_info::table
+                .filter(
+                    software_info::id
+                        .eq_any(
+                            software_version::table
+                                .inner_join(software_list::table)
+                                .select(software_version::software_id)
+                                .filter(in_group!(software_list::client_id, group)),
+                        )
+                        .or(group.is_none().into_sql::<diesel::sql_types::Bool>()),
+                )
+                .order_by(software_info::name)
+                .load::<SoftwareInfo>(&mut conn)?)
+        })
     }
 
-    fn create_software_version(
-        &self,
-        software_id: i32,
-        version: &String,
-    ) -> Result<SoftwareVersion> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::insert_into(software_version::table)
-            .values(NewSoftwareVersion {
-                software_id: &software_id,
-                version,
-            })
-            .get_result(&mut conn)?)
+    pub fn get_software_info(&self, software_id: i32) -> Result<SoftwareInfo> {
+        with_conn!(self.pool, |conn| {
+            Ok(software_info::table
+                .filter(software_info::id.eq(software_id))
+                .get_result::<SoftwareInfo>(&mut conn)?)
+        })
     }
 
-    pub fn get_clients_with_os_info(&self) -> Result<Vec<(Client, Option<OsInfo>)>> {
-        let mut conn = self.pool.get()?;
-        Ok(client::table
-            .left_join(os_info::table)
-            .order_by(os_info::computer_name)
-            .load::<(Client, Option<OsInfo>)>(&mut conn)?)
+    pub fn delete_software_info(&self, software_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(software_info::table)
+                .filter(software_info::id.eq(software_id))
+                .execute(&mut conn)?)
+        })
     }
 
-    pub fn get_software_list(&self) -> Result<Vec<SoftwareInfo>> {
-        let mut conn = self.pool.get()?;
-        Ok(software_info::table
-            .order_by(software_info::name)
-            .load::<SoftwareInfo>(&mut conn)?)
+    pub fn get_software_versions(&self, software_id: i32, group: Option<i32>) -> Result<Vec<SoftwareVersionWithCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(software_version::table
+                .select((
+                    software_version::id,
+                    software_version::software_id,
+                    software_version::version,
+                    coalesce(
+                        software_list::table
+                            .filter(software_list::software_id.eq(software_version::id))
+                            .filter(in_group!(software_list::client_id, group))
+                            .count()
+                            .single_value(),
+                        0,
+                    ),
+                ))
+                .filter(software_version::software_id.eq(software_id))
+                .order_by(software_version::version)
+                .load::<SoftwareVersionWithCount>(&mut conn)?)
+        })
     }
 
-    pub fn get_software_info(&self, software_id: i32) -> Result<SoftwareInfo> {
-        let mut conn = self.pool.get()?;
-        Ok(software_info::table
-            .filter(software_info::id.eq(software_id))
-            .get_result::<SoftwareInfo>(&mut conn)?)
+    pub fn delete_software_version(&self, version_id: i32) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(software_version::table)
+                .filter(software_version::id.eq(version_id))
+                .execute(&mut conn)?)
+        })
     }
 
-    pub fn delete_software_info(&self, software_id: i32) -> Result<usize> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::delete(software_info::table)
-            .filter(software_info::id.eq(software_id))
-            .execute(&mut conn)?)
-    }
-
-    pub fn get_software_versions(&self, software_id: i32) -> Result<Vec<SoftwareVersionWithCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(software_version::table
-            .select((
-                software_version::id,
-                software_version::software_id,
-                software_version::version,
-                coalesce(
-                    software_list::table
-                        .filter(software_list::software_id.eq(software_version::id))
-                        .count()
-                        .single_value(),
-                    0,
-                ),
-            ))
-            .filter(software_version::software_id.eq(software_id))
-            .order_by(software_version::version)
-            .load::<SoftwareVersionWithCount>(&mut conn)?)
+    /// Deletes the software versions no client has installed.
+    pub fn delete_unused_software_versions(&self) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(software_version::table)
+                .filter(diesel::dsl::not(diesel::dsl::exists(
+                    software_list::table.filter(software_list::software_id.eq(software_version::id)),
+                )))
+                .execute(&mut conn)?)
+        })
     }
 
-    pub fn delete_software_version(&self, version_id: i32) -> Result<usize> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::delete(software_version::table)
-            .filter(software_version::id.eq(version_id))
-            .execute(&mut conn)?)
+    /// Deletes the software without any version.
+    pub fn delete_software_without_versions(&self) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(software_info::table)
+                .filter(diesel::dsl::not(diesel::dsl::exists(
+                    software_version::table.filter(software_version::software_id.eq(software_info::id)),
+                )))
+                .execute(&mut conn)?)
+        })
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn get_software_computer_list(
         &self,
         software_id: i32,
+        group: Option<i32>,
     ) -> Result<Vec<(SoftwareList, SoftwareVersion, (Client, OsInfo))>> {
-        let mut conn = self.pool.get()?;
-        Ok(software_list::table
-            .filter(software_version::software_id.eq(software_id))
-            .inner_join(software_version::table)
-            .inner_join(client::table.inner_join(os_info::table))
-            .order_by((software_version::version, os_info::computer_name))
-            .load::<(SoftwareList, SoftwareVersion, (Client, OsInfo))>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(software_list::table
+                .filter(software_version::software_id.eq(software_id))
+                .filter(in_group!(software_list::client_id, group))
+                .inner_join(software_version::table)
+                .inner_join(client::table.inner_join(os_info::table))
+                .order_by((software_version::version, os_info::computer_name))
+                .load::<(SoftwareList, SoftwareVersion, (Client, OsInfo))>(&mut conn)?)
+        })
     }
 
     pub fn get_software_version(&self, version_id: i32) -> Result<SoftwareVersion> {
-        let mut conn = self.pool.get()?;
-        Ok(software_version::table
-            .filter(software_version::id.eq(version_id))
-            .get_result::<SoftwareVersion>(&mut conn)?)
-    }
-
-    pub fn get_software_version_clients(&self, version_id: i32) -> Result<Vec<(Client, OsInfo)>> {
-        let mut conn = self.pool.get()?;
-        Ok(client::table
-            .filter(
-                client::id.eq_any(
-                    software_list::table
-                        .select(software_list::client_id)
-                        .filter(software_list::software_id.eq(version_id)),
-                ),
-            )
-            .inner_join(os_info::table)
-            .order_by(os_info::computer_name)
-            .load::<(Client, OsInfo)>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(software_version::table
+                .filter(software_version::id.eq(version_id))
+                .get_result::<SoftwareVersion>(&mut conn)?)
+        })
+    }
+
+    pub fn get_software_version_clients(&self, version_id: i32, group: Option<i32>) -> Result<Vec<(Client, OsInfo)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .filter(
+                    client::id.eq_any(
+                        software_list::table
+                            .select(software_list::client_id)
+                            .filter(software_list::software_id.eq(version_id)),
+                    ),
+                )
+                .filter(in_group!(client::id, group))
+                .inner_join(os_info::table)
+                .order_by(os_info::computer_name)
+                .load::<(Client, OsInfo)>(&mut conn)?)
+        })
     }
 
-    pub fn get_license_list(&self) -> Result<Vec<LicenseKeyCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(license_key::table
-            .group_by(license_key::name)
-            .select((license_key::name, count_star()))
-            .load::<LicenseKeyCount>(&mut conn)?)
+    pub fn get_license_list(&self, group: Option<i32>) -> Result<Vec<LicenseKeyCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(license_key::table
+                .filter(in_group!(license_key::client_id, group))
+                .group_by(license_key::name)
+                .select((license_key::name, count_star()))
+                .load::<LicenseKeyCount>(&mut conn)?)
+        })
     }
 
     pub fn get_license_with_computers(
         &self,
         name: &String,
+        group: Option<i32>,
     ) -> Result<Vec<(LicenseKey, (Client, OsInfo))>> {
-        let mut conn = self.pool.get()?;
-        Ok(license_key::table
-            .filter(license_key::name.eq(name))
-            .inner_join(client::table.inner_join(os_info::table))
-            .order_by(os_info::computer_name)
-            .load::<(LicenseKey, (Client, OsInfo))>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(license_key::table
+                .filter(license_key::name.eq(name))
+                .filter(in_group!(license_key::client_id, group))
+                .inner_join(client::table.inner_join(os_info::table))
+                .order_by(os_info::computer_name)
+                .load::<(LicenseKey, (Client, OsInfo))>(&mut conn)?)
+        })
     }
 
     pub fn get_client_os_info(&self, uuid: &Uuid) -> Result<OsInfo> {
-        let mut conn = self.pool.get()?;
-        Ok(os_info::table
-            .filter(
-                os_info::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .get_result::<OsInfo>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(os_info::table
+                .filter(
+                    os_info::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(*uuid)))
+                        .single_value()),
+                )
+                .get_result::<OsInfo>(&mut conn)?)
+        })
     }
 
     pub fn get_client_profiles(&self, uuid: &Uuid) -> Result<Vec<(UserProfile, User)>> {
-        let mut conn = self.pool.get()?;
-        Ok(userprofile::table
-            .filter(
-                userprofile::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .inner_join(user::table)
-            .order_by(user::username)
-            .load::<(UserProfile, User)>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(userprofile::table
+                .filter(
+                    userprofile::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(*uuid)))
+                        .single_value()),
+                )
+                .inner_join(user::table)
+                .order_by(user::username)
+                .load::<(UserProfile, User)>(&mut conn)?)
+        })
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn get_client_software(
         &self,
         uuid: Uuid,
     ) -> Result<Vec<(SoftwareList, Client, (SoftwareVersion, SoftwareInfo))>> {
-        let mut conn = self.pool.get()?;
-        let software_version_list: Vec<(SoftwareList, Client, (SoftwareVersion, SoftwareInfo))> =
-            software_list::table
-                .filter(client::uuid.eq(uuid))
-                .inner_join(client::table)
-                .inner_join(software_version::table.inner_join(software_info::table))
-                .order_by(software_info::name)
-                .load::<(SoftwareList, Client, (SoftwareVersion, SoftwareInfo))>(&mut conn)?;
-        Ok(software_version_list)
-    }
-
-    pub fn get_processors_count(&self) -> Result<Vec<ProcessorCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(processor::table
-            .group_by((processor::name, processor::manufacturer))
-            .select((
-                processor::name,
-                processor::manufacturer,
-                max(processor::cores),
-                max(processor::logical_cores),
-                max(processor::clock_speed),
-                max(processor::address_width),
-                count(processor::name),
-            ))
-            .order_by(processor::name)
-            .load::<ProcessorCount>(&mut conn)?)
-    }
-
-    pub fn get_processor_clients(&self, processor: &String) -> Result<Vec<(Client, OsInfo)>> {
-        let mut conn = self.pool.get()?;
-        Ok(client::table
-            .filter(
-                client::id.eq_any(
-                    processor::table
-                        .select(processor::client_id)
-                        .filter(processor::name.eq(processor)),
-                ),
-            )
-            .inner_join(os_info::table)
-            .order_by(os_info::computer_name)
-            .load::<(Client, OsInfo)>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            let software_version_list: Vec<(SoftwareList, Client, (SoftwareVersion, SoftwareInfo))> =
+                software_list::table
+                    .filter(client::uuid.eq(DbUuid(uuid)))
+                    .inner_join(client::table)
+                    .inner_join(software_version::table.inner_join(software_info::table))
+                    .order_by(software_info::name)
+                    .load::<(SoftwareList, Client, (SoftwareVersion, SoftwareInfo))>(&mut conn)?;
+            Ok(software_version_list)
+        })
+    }
+
+    pub fn get_processors_count(&self, group: Option<i32>) -> Result<Vec<ProcessorCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(processor::table
+                .filter(in_group!(processor::client_id, group))
+                .group_by((processor::name, processor::manufacturer))
+                .select((
+                    processor::name,
+                    processor::manufacturer,
+                    max(processor::cores),
+                    max(processor::logical_cores),
+                    max(processor::clock_speed),
+                    max(processor::address_width),
+                    count(processor::name),
+                ))
+                .order_by(processor::name)
+                .load::<ProcessorCount>(&mut conn)?)
+        })
+    }
+
+    pub fn get_processor_clients(&self, processor: &String, group: Option<i32>) -> Result<Vec<(Client, OsInfo)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .filter(
+                    client::id.eq_any(
+                        processor::table
+                            .select(processor::client_id)
+                            .filter(processor::name.eq(processor)),
+                    ),
+                )
+                .filter(in_group!(client::id, group))
+                .inner_join(os_info::table)
+                .order_by(os_info::computer_name)
+                .load::<(Client, OsInfo)>(&mut conn)?)
+        })
     }
 
     pub fn get_client_processors(&self, uuid: Uuid) -> Result<Vec<Processor>> {
-        let mut conn = self.pool.get()?;
-        Ok(processor::table
-            .filter(
-                processor::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .load::<Processor>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(processor::table
+                .filter(
+                    processor::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .load::<Processor>(&mut conn)?)
+        })
     }
 
-    pub fn get_memory_count(&self) -> Result<Vec<MemoryCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::sql_query(
-            "SELECT capacity, sticks, COUNT(*) FROM memory GROUP BY capacity, sticks ORDER BY capacity, sticks;",
-        )
-        .load(&mut conn)?)
-    }
-
-    pub fn get_memory_clients(&self, size: u64, stick_count: i64) -> Result<Vec<(Client, OsInfo)>> {
-        let mut conn = self.pool.get()?;
-        Ok(client::table
-            .filter(
-                client::id.eq_any(
-                    memory_stick::table
-                        .group_by(memory_stick::client_id)
-                        .select(memory_stick::client_id)
-                        .having(
-                            sum(memory_stick::capacity)
-                                .eq(BigDecimal::from(size))
-                                .and(count(memory_stick::capacity).eq(stick_count)),
-                        ),
-                ),
-            )
-            .inner_join(os_info::table)
-            .order_by(os_info::computer_name)
-            .load::<(Client, OsInfo)>(&mut conn)?)
+    pub fn get_memory_count(&self, group: Option<i32>) -> Result<Vec<MemoryCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(memory::table
+                .filter(in_group!(memory::client_id, group))
+                .group_by((memory::capacity, memory::sticks))
+                .select((memory::capacity, memory::sticks, count_star()))
+                .order_by((memory::capacity, memory::sticks))
+                .load::<MemoryCount>(&mut conn)?)
+        })
+    }
+
+    pub fn get_memory_clients(&self, size: u64, stick_count: i64, group: Option<i32>) -> Result<Vec<(Client, OsInfo)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .filter(
+                    client::id.eq_any(
+                        memory_stick::table
+                            .group_by(memory_stick::client_id)
+                            .select(memory_stick::client_id)
+                            .having(
+                                sum(memory_stick::capacity)
+                                    .eq(BigDecimal::from(size))
+                                    .and(count(memory_stick::capacity).eq(stick_count)),
+                            ),
+                    ),
+                )
+                .filter(in_group!(client::id, group))
+                .inner_join(os_info::table)
+                .order_by(os_info::computer_name)
+                .load::<(Client, OsInfo)>(&mut conn)?)
+        })
     }
 
     pub fn get_client_memory(&self, uuid: Uuid) -> Result<Vec<Memory>> {
-        let mut conn = self.pool.get()?;
-        Ok(memory_stick::table
-            .group_by(memory_stick::client_id)
-            .select((
-                memory_stick::client_id,
-                sum(memory_stick::capacity),
-                count(memory_stick::capacity),
-            ))
-            .filter(
-                memory_stick::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .load::<Memory>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(memory_stick::table
+                .group_by(memory_stick::client_id)
+                .select((
+                    memory_stick::client_id,
+                    sum(memory_stick::capacity),
+                    count(memory_stick::capacity),
+                ))
+                .filter(
+                    memory_stick::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .load::<Memory>(&mut conn)?)
+        })
     }
 
     pub fn get_client_memory_sticks(&self, uuid: Uuid) -> Result<Vec<MemoryStick>> {
-        let mut conn = self.pool.get()?;
-        Ok(memory_stick::table
-            .filter(
-                memory_stick::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .load::<MemoryStick>(&mut conn)?)
-    }
-
-    pub fn get_graphics_cards_count(&self) -> Result<Vec<GraphicsCardCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(graphics_card::table
-            .group_by(graphics_card::name)
-            .select((graphics_card::name, count_star()))
-            .order_by(graphics_card::name)
-            .load::<GraphicsCardCount>(&mut conn)?)
-    }
-
-    pub fn get_graphics_card_clients(&self, card: &String) -> Result<Vec<(Client, OsInfo)>> {
-        let mut conn = self.pool.get()?;
-        Ok(client::table
-            .filter(
-                client::id.eq_any(
-                    graphics_card::table
-                        .select(graphics_card::client_id)
-                        .filter(graphics_card::name.eq(card)),
-                ),
-            )
-            .inner_join(os_info::table)
-            .order_by(os_info::computer_name)
-            .load::<(Client, OsInfo)>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(memory_stick::table
+                .filter(
+                    memory_stick::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .load::<MemoryStick>(&mut conn)?)
+        })
+    }
+
+    pub fn get_graphics_cards_count(&self, group: Option<i32>) -> Result<Vec<GraphicsCardCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(graphics_card::table
+                .filter(in_group!(graphics_card::client_id, group))
+                .group_by(graphics_card::name)
+                .select((graphics_card::name, count_star()))
+                .order_by(graphics_card::name)
+                .load::<GraphicsCardCount>(&mut conn)?)
+        })
+    }
+
+    pub fn get_graphics_card_clients(&self, card: &String, group: Option<i32>) -> Result<Vec<(Client, OsInfo)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .filter(
+                    client::id.eq_any(
+                        graphics_card::table
+                            .select(graphics_card::client_id)
+                            .filter(graphics_card::name.eq(card)),
+                    ),
+                )
+                .filter(in_group!(client::id, group))
+                .inner_join(os_info::table)
+                .order_by(os_info::computer_name)
+                .load::<(Client, OsInfo)>(&mut conn)?)
+        })
     }
 
     pub fn get_client_graphics_cards(&self, uuid: Uuid) -> Result<Vec<GraphicsCard>> {
-        let mut conn = self.pool.get()?;
-        Ok(graphics_card::table
-            .filter(
-                graphics_card::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .load::<GraphicsCard>(&mut conn)?)
-    }
-
-    pub fn get_disks_count(&self) -> Result<Vec<DiskCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(disks::table
-            .group_by((disks::model, disks::size))
-            .select((disks::model, disks::size, count_star()))
-            .order_by(disks::model)
-            .load::<DiskCount>(&mut conn)?)
-    }
-
-    pub fn get_disk_clients(&self, model: &String, size: u64) -> Result<Vec<(Client, OsInfo)>> {
-        let mut conn = self.pool.get()?;
-        Ok(client::table
-            .filter(
-                client::id.eq_any(
-                    disks::table
-                        .select(disks::client_id)
-                        .filter(disks::model.eq(model))
-                        .filter(disks::size.eq(BigDecimal::from(size))),
-                ),
-            )
-            .inner_join(os_info::table)
-            .order_by(os_info::computer_name)
-            .load::<(Client, OsInfo)>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(graphics_card::table
+                .filter(
+                    graphics_card::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .load::<GraphicsCard>(&mut conn)?)
+        })
+    }
+
+    pub fn get_disks_count(&self, group: Option<i32>) -> Result<Vec<DiskCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(disks::table
+                .filter(in_group!(disks::client_id, group))
+                .group_by((disks::model, disks::size))
+                .select((disks::model, disks::size, count_star()))
+                .order_by(disks::model)
+                .load::<DiskCount>(&mut conn)?)
+        })
+    }
+
+    pub fn get_disk_clients(&self, model: &String, size: u64, group: Option<i32>) -> Result<Vec<(Client, OsInfo)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .filter(
+                    client::id.eq_any(
+                        disks::table
+                            .select(disks::client_id)
+                            .filter(disks::model.eq(model))
+                            .filter(disks::size.eq(BigDecimal::from(size))),
+                    ),
+                )
+                .filter(in_group!(client::id, group))
+                .inner_join(os_info::table)
+                .order_by(os_info::computer_name)
+                .load::<(Client, OsInfo)>(&mut conn)?)
+        })
     }
 
     pub fn get_client_disks(&self, uuid: Uuid) -> Result<Vec<Disk>> {
-        let mut conn = self.pool.get()?;
-        Ok(disks::table
-            .filter(
-                disks::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .load::<Disk>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(disks::table
+                .filter(
+                    disks::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .load::<Disk>(&mut conn)?)
+        })
     }
 
-    pub fn get_computer_models_count(&self) -> Result<Vec<ComputerModelCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(computer_model::table
-            .group_by((computer_model::model_family, computer_model::manufacturer))
-            .select((
-                computer_model::manufacturer,
-                computer_model::model_family,
-                count_star(),
-            ))
-            .order_by((computer_model::manufacturer, computer_model::model_family))
-            .load::<ComputerModelCount>(&mut conn)?)
+    pub fn get_computer_models_count(&self, group: Option<i32>) -> Result<Vec<ComputerModelCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(computer_model::table
+                .filter(in_group!(computer_model::client_id, group))
+                .group_by((computer_model::model_family, computer_model::manufacturer))
+                .select((
+                    computer_model::manufacturer,
+                    computer_model::model_family,
+                    count_star(),
+                ))
+                .order_by((computer_model::manufacturer, computer_model::model_family))
+                .load::<ComputerModelCount>(&mut conn)?)
+        })
     }
 
     pub fn get_computer_model_clients(
         &self,
         model: &String,
         manufacturer: &String,
+        group: Option<i32>,
     ) -> Result<Vec<(Client, OsInfo, Bios, ComputerModel)>> {
-        let mut conn = self.pool.get()?;
-        Ok(client::table
-            .inner_join(os_info::table)
-            .inner_join(bios::table)
-            .inner_join(computer_model::table)
-            .filter(computer_model::model_family.eq(model))
-            .filter(computer_model::manufacturer.eq(manufacturer))
-            .order_by(os_info::computer_name)
-            .load::<(Client, OsInfo, Bios, ComputerModel)>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .inner_join(os_info::table)
+                .inner_join(bios::table)
+                .inner_join(computer_model::table)
+                .filter(computer_model::model_family.eq(model))
+                .filter(computer_model::manufacturer.eq(manufacturer))
+                .filter(in_group!(client::id, group))
+                .order_by(os_info::computer_name)
+                .load::<(Client, OsInfo, Bios, ComputerModel)>(&mut conn)?)
+        })
     }
 
     pub fn get_client_computer_model(&self, uuid: Uuid) -> Result<Vec<ComputerModel>> {
-        let mut conn = self.pool.get()?;
-        Ok(computer_model::table
-            .filter(
-                computer_model::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .load::<ComputerModel>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(computer_model::table
+                .filter(
+                    computer_model::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .load::<ComputerModel>(&mut conn)?)
+        })
     }
 
     pub fn get_client_bios(&self, uuid: Uuid) -> Result<Vec<Bios>> {
-        let mut conn = self.pool.get()?;
-        Ok(bios::table
-            .filter(
-                bios::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .load::<Bios>(&mut conn)?)
-    }
-
-    pub fn get_network_adapters_count(&self) -> Result<Vec<NetworkAdapterCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(network_adapter::table
-            .group_by(network_adapter::name)
-            .select((network_adapter::name, count_star()))
-            .order_by(network_adapter::name)
-            .load::<NetworkAdapterCount>(&mut conn)?)
-    }
-
-    pub fn get_network_adapter_clients(&self, name: &String) -> Result<Vec<(Client, OsInfo)>> {
-        let mut conn = self.pool.get()?;
-        Ok(client::table
-            .filter(
-                client::id.eq_any(
-                    network_adapter::table
-                        .select(network_adapter::client_id)
-                        .filter(network_adapter::name.eq(name)),
-                ),
-            )
-            .inner_join(os_info::table)
-            .order_by(os_info::computer_name)
-            .load::<(Client, OsInfo)>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(bios::table
+                .filter(
+                    bios::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .load::<Bios>(&mut conn)?)
+        })
+    }
+
+    pub fn get_network_adapters_count(&self, group: Option<i32>) -> Result<Vec<NetworkAdapterCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(network_adapter::table
+                .filter(in_group!(network_adapter::client_id, group))
+                .group_by(network_adapter::name)
+                .select((network_adapter::name, count_star()))
+                .order_by(network_adapter::name)
+                .load::<NetworkAdapterCount>(&mut conn)?)
+        })
+    }
+
+    pub fn get_network_adapter_clients(&self, name: &String, group: Option<i32>) -> Result<Vec<(Client, OsInfo)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client::table
+                .filter(
+                    client::id.eq_any(
+                        network_adapter::table
+                            .select(network_adapter::client_id)
+                            .filter(network_adapter::name.eq(name)),
+                    ),
+                )
+                .filter(in_group!(client::id, group))
+                .inner_join(os_info::table)
+                .order_by(os_info::computer_name)
+                .load::<(Client, OsInfo)>(&mut conn)?)
+        })
     }
 
     pub fn get_client_network_adapters(&self, uuid: Uuid) -> Result<Vec<NetworkAdapter>> {
-        let mut conn = self.pool.get()?;
-        Ok(network_adapter::table
-            .filter(
-                network_adapter::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .load::<NetworkAdapter>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(network_adapter::table
+                .filter(
+                    network_adapter::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .load::<NetworkAdapter>(&mut conn)?)
+        })
     }
 
     pub fn get_client_licenses(&self, uuid: Uuid) -> Result<Vec<LicenseKey>> {
-        let mut conn = self.pool.get()?;
-        Ok(license_key::table
-            .filter(
-                license_key::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .order_by(license_key::name)
-            .load::<LicenseKey>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(license_key::table
+                .filter(
+                    license_key::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .order_by(license_key::name)
+                .load::<LicenseKey>(&mut conn)?)
+        })
     }
 
     pub fn get_client_volume_status(&self, uuid: Uuid) -> Result<Vec<VolumeStatus>> {
-        let mut conn = self.pool.get()?;
-        Ok(volume_status::table
-            .filter(
-                volume_status::client_id.nullable().eq(client::table
-                    .select(client::id)
-                    .filter(client::uuid.eq(uuid))
-                    .single_value()),
-            )
-            .order_by(volume_status::drive_letter)
-            .load::<VolumeStatus>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(volume_status::table
+                .filter(
+                    volume_status::client_id.nullable().eq(client::table
+                        .select(client::id)
+                        .filter(client::uuid.eq(DbUuid(uuid)))
+                        .single_value()),
+                )
+                .order_by(volume_status::drive_letter)
+                .load::<VolumeStatus>(&mut conn)?)
+        })
     }
 
-    pub fn get_system_status_volume_crit(&self) -> Result<Vec<(VolumeStatus, (Client, OsInfo))>> {
-        let mut conn = self.pool.get()?;
-        Ok(volume_status::table
-            .inner_join(client::table.inner_join(os_info::table))
-            .filter(
-                (volume_status::free_space / volume_status::capacity)
-                    .lt(BigDecimal::try_from(0.1)?),
-            )
-            .or_filter(volume_status::free_space.lt(BigDecimal::from(5_000_000_000_u64)))
-            .order_by(os_info::computer_name)
-            .load::<(VolumeStatus, (Client, OsInfo))>(&mut conn)?)
+    pub fn get_system_status_volume_crit(&self, group: Option<i32>) -> Result<Vec<(VolumeStatus, (Client, OsInfo))>> {
+        with_conn!(self.pool, |conn| {
+            Ok(volume_status::table
+                .inner_join(client::table.inner_join(os_info::table))
+                // Less than 10% free, written without division as SQLite divides integers.
+                .filter(
+                    (volume_status::free_space * BigDecimal::from(10))
+                        .lt(volume_status::capacity),
+                )
+                .or_filter(volume_status::free_space.lt(BigDecimal::from(5_000_000_000_u64)))
+                .filter(in_group!(client::id, group))
+                .order_by(os_info::computer_name)
+                .load::<(VolumeStatus, (Client, OsInfo))>(&mut conn)?)
+        })
     }
 
-    pub fn new_auth_user(&self, username: &str, password_hash: &str) -> Result<AuthUser> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::insert_into(auth_user::table)
-            .values(NewAuthUser {
-                username,
-                password: password_hash,
-            })
-            .get_result(&mut conn)?)
+    pub fn new_auth_user(&self, username: &str, password_hash: &str, role: &str) -> Result<AuthUser> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::insert_into(auth_user::table)
+                .values(NewAuthUser {
+                    username,
+                    password: password_hash,
+                    role,
+                })
+                .get_result(&mut conn)?)
+        })
+    }
+
+    /// Creates the user unless any user exists, with the check and the insert in
+    /// one transaction that concurrent calls wait for. `None` if a user exists.
+    pub fn new_first_auth_user(&self, username: &str, password_hash: &str, role: &str) -> Result<Option<AuthUser>> {
+        macro_rules! insert_if_empty {
+            ($conn:expr) => {{
+                let count: i64 = auth_user::table.count().get_result($conn)?;
+                if count > 0 {
+                    return Ok(None);
+                }
+                diesel::insert_into(auth_user::table)
+                    .values(NewAuthUser {
+                        username,
+                        password: password_hash,
+                        role,
+                    })
+                    .get_result($conn)
+                    .map(Some)
+            }};
+        }
+        let user = match &self.pool {
+            DbPool::Postgres(pool) => pool.get()?.transaction::<_, diesel::result::Error, _>(|c| {
+                diesel::sql_query("LOCK TABLE auth_user IN SHARE ROW EXCLUSIVE MODE").execute(c)?;
+                insert_if_empty!(c)
+            })?,
+            DbPool::Sqlite(pool) => pool
+                .get()?
+                .immediate_transaction::<_, diesel::result::Error, _>(|c| insert_if_empty!(c))?,
+        };
+        Ok(user)
     }
 
     pub fn get_auth_users(&self) -> Result<Vec<AuthUser>> {
-        let mut conn = self.pool.get()?;
-        Ok(auth_user::table
-            .order_by(auth_user::username)
-            .load::<AuthUser>(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(auth_user::table
+                .order_by(auth_user::username)
+                .load::<AuthUser>(&mut conn)?)
+        })
     }
 
     pub fn get_auth_user_by_username(&self, username: &str) -> Result<AuthUser> {
-        let mut conn = self.pool.get()?;
-        Ok(auth_user::table
-            .filter(auth_user::username.eq(username))
-            .get_result(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(auth_user::table
+                .filter(auth_user::username.eq(username))
+                .get_result(&mut conn)?)
+        })
     }
 
     pub fn get_auth_user_by_id(&self, id: i32) -> Result<AuthUser> {
-        let mut conn = self.pool.get()?;
-        Ok(auth_user::table
-            .filter(auth_user::id.eq(id))
-            .get_result(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(auth_user::table
+                .filter(auth_user::id.eq(id))
+                .get_result(&mut conn)?)
+        })
     }
 
     pub fn set_auth_user_password(&self, user_id: i32, password_hash: &str) -> Result<usize> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::update(auth_user::table)
-            .filter(auth_user::id.eq(user_id))
-            .set(auth_user::password.eq(password_hash))
-            .execute(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::update(auth_user::table)
+                .filter(auth_user::id.eq(user_id))
+                .set(auth_user::password.eq(password_hash))
+                .execute(&mut conn)?)
+        })
+    }
+
+    pub fn set_auth_user_role(&self, user_id: i32, role: &str) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::update(auth_user::table)
+                .filter(auth_user::id.eq(user_id))
+                .set(auth_user::role.eq(role))
+                .execute(&mut conn)?)
+        })
+    }
+
+    pub fn set_auth_user_disabled(&self, user_id: i32, disabled: bool) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::update(auth_user::table)
+                .filter(auth_user::id.eq(user_id))
+                .set(auth_user::disabled.eq(disabled))
+                .execute(&mut conn)?)
+        })
+    }
+
+    pub fn count_auth_users(&self) -> Result<i64> {
+        with_conn!(self.pool, |conn| {
+            Ok(auth_user::table.count().get_result(&mut conn)?)
+        })
+    }
+
+    /// Users with `role` that are not disabled.
+    /// Applies `change` to the user `user_id` unless it is the last active user
+    /// with `admin_role`, with the check and the change in one transaction. The
+    /// administrators are locked on Postgres, SQLite runs one such transaction at
+    /// a time. `false` if the user is the last administrator.
+    pub fn change_auth_user_unless_last(&self, user_id: i32, admin_role: &str, change: AuthUserChange) -> Result<bool> {
+        macro_rules! change_unless_last {
+            ($conn:expr, $admins:expr) => {{
+                let admins: Vec<i32> = $admins;
+                if admins == [user_id] {
+                    return Ok(false);
+                }
+                let user = auth_user::table.filter(auth_user::id.eq(user_id));
+                match change {
+                    AuthUserChange::Role(role) => diesel::update(user).set(auth_user::role.eq(role)).execute($conn)?,
+                    AuthUserChange::Disable => diesel::update(user).set(auth_user::disabled.eq(true)).execute($conn)?,
+                    AuthUserChange::Delete => diesel::delete(user).execute($conn)?,
+                };
+                Ok(true)
+            }};
+        }
+        let admins = auth_user::table
+            .filter(auth_user::role.eq(admin_role))
+            .filter(auth_user::disabled.eq(false))
+            .select(auth_user::id);
+        let changed = match &self.pool {
+            DbPool::Postgres(pool) => pool.get()?.transaction::<_, diesel::result::Error, _>(|c| {
+                change_unless_last!(c, admins.for_update().load(c)?)
+            })?,
+            DbPool::Sqlite(pool) => pool
+                .get()?
+                .immediate_transaction::<_, diesel::result::Error, _>(|c| change_unless_last!(c, admins.load(c)?))?,
+        };
+        Ok(changed)
+    }
+
+    /// The client groups the user is restricted to, empty if not restricted.
+    pub fn get_auth_user_groups(&self, user_id: i32) -> Result<Vec<i32>> {
+        with_conn!(self.pool, |conn| {
+            Ok(auth_user_group::table
+                .filter(auth_user_group::user_id.eq(user_id))
+                .select(auth_user_group::group_id)
+                .order_by(auth_user_group::group_id)
+                .load(&mut conn)?)
+        })
+    }
+
+    pub fn set_auth_user_groups(&self, user_id: i32, group_ids: &[i32]) -> Result<()> {
+        let mut group_ids = group_ids.to_vec();
+        group_ids.sort_unstable();
+        group_ids.dedup();
+        let rows: Vec<AuthUserGroup> = group_ids
+            .into_iter()
+            .map(|group_id| AuthUserGroup { user_id, group_id })
+            .collect();
+        with_conn!(self.pool, |conn| {
+            conn.transaction::<(), diesel::result::Error, _>(|c| {
+                diesel::delete(auth_user_group::table)
+                    .filter(auth_user_group::user_id.eq(user_id))
+                    .execute(c)?;
+                if !rows.is_empty() {
+                    diesel::insert_into(auth_user_group::table).values(&rows).execute(c)?;
+                }
+                Ok(())
+            })?;
+            Ok(())
+        })
     }
 
     pub fn delete_auth_user(&self, user_id: i32) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        diesel::delete(auth_user::table)
-            .filter(auth_user::id.eq(user_id))
-            .execute(&mut conn)?;
-        Ok(())
+        with_conn!(self.pool, |conn| {
+            diesel::delete(auth_user::table)
+                .filter(auth_user::id.eq(user_id))
+                .execute(&mut conn)?;
+            Ok(())
+        })
     }
 
     pub fn get_auth_session_by_session_id(&self, session_id: &str) -> Result<AuthSessions> {
-        let mut conn = self.pool.get()?;
-        Ok(auth_sessions::table
-            .filter(auth_sessions::session_id.eq(session_id))
-            .get_result(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(auth_sessions::table
+                .filter(auth_sessions::session_id.eq(session_id))
+                .get_result(&mut conn)?)
+        })
     }
 
     pub fn update_session_exp(
@@ -988,11 +1265,12 @@ impl Database {
         session_id: &str,
         valid_until: NaiveDateTime,
     ) -> Result<usize> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::update(auth_sessions::table)
-            .filter(auth_sessions::session_id.eq(session_id))
-            .set(auth_sessions::valid_until.eq(valid_until))
-            .execute(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::update(auth_sessions::table)
+                .filter(auth_sessions::session_id.eq(session_id))
+                .set(auth_sessions::valid_until.eq(valid_until))
+                .execute(&mut conn)?)
+        })
     }
 
     pub fn add_new_session(
@@ -1001,89 +1279,124 @@ impl Database {
         session_id: &str,
         valid_until: NaiveDateTime,
     ) -> Result<AuthSessions> {
-        let mut conn = self.pool.get()?;
-        Ok(diesel::insert_into(auth_sessions::table)
-            .values(NewAuthSessions {
-                user_id: &user_id,
-                session_id,
-                valid_until,
-            })
-            .get_result(&mut conn)?)
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::insert_into(auth_sessions::table)
+                .values(NewAuthSessions {
+                    user_id: &user_id,
+                    session_id,
+                    valid_until,
+                })
+                .get_result(&mut conn)?)
+        })
     }
 
     pub fn delete_session(&self, session_id: &str) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        diesel::delete(auth_sessions::table)
-            .filter(auth_sessions::session_id.eq(session_id))
-            .execute(&mut conn)?;
-        Ok(())
-    }
-
-    pub fn update_battery_status(&self, client_id: i32, battery_status: BatteryStatus) -> Result<()>{
-        let mut conn = self.pool.get()?;
-        conn.transaction::<(), diesel::result::Error, _>(|c| {
-            diesel::delete(battery::table)
-                .filter(battery::client_id.eq(client_id))
-                .execute(c)?;
-            for b in battery_status.batteries.into_iter() {
-                diesel::insert_into(battery::table)
-                    .values(NewBattery {
-                        client_id: &client_id,
-                        battery_id: b.id,
-                        manufacturer: b.manufacturer,
-                        serial_number: b.serial_number,
-                        chemistry: b.chemistry,
-                        cycle_count: b.cycle_count as i64,
-                        designed_capacity: b.designed_capacity as i64,
-                        full_charged_capacity: b.full_charged_capacity as i64,
-                    })
+        with_conn!(self.pool, |conn| {
+            diesel::delete(auth_sessions::table)
+                .filter(auth_sessions::session_id.eq(session_id))
+                .execute(&mut conn)?;
+            Ok(())
+        })
+    }
+
+    pub fn delete_expired_sessions(&self, now: NaiveDateTime) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::delete(auth_sessions::table)
+                .filter(auth_sessions::valid_until.lt(now))
+                .execute(&mut conn)?)
+        })
+    }
+
+    /// Logs the user `user_id` out everywhere.
+    pub fn delete_sessions_of_user(&self, user_id: i32) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            diesel::delete(auth_sessions::table)
+                .filter(auth_sessions::user_id.eq(user_id))
+                .execute(&mut conn)?;
+            Ok(())
+        })
+    }
+
+    pub fn update_battery_status(
+        &self,
+        client_id: i32,
+        battery_status: BatteryStatus,
+        hash: Option<&SectionHash>,
+    ) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            conn.transaction::<(), diesel::result::Error, _>(|c| {
+                diesel::delete(battery::table)
+                    .filter(battery::client_id.eq(client_id))
                     .execute(c)?;
-            }
+                for b in battery_status.batteries.into_iter() {
+                    diesel::insert_into(battery::table)
+                        .values(NewBattery {
+                            client_id: &client_id,
+                            battery_id: b.id,
+                            manufacturer: b.manufacturer,
+                            serial_number: b.serial_number,
+                            chemistry: b.chemistry,
+                            cycle_count: b.cycle_count as i64,
+                            designed_capacity: b.designed_capacity as i64,
+                            full_charged_capacity: b.full_charged_capacity as i64,
+                        })
+                        .execute(c)?;
+                }
+                store_section_hash!(c, hash);
+                Ok(())
+            })?;
             Ok(())
-        })?;
-        Ok(())
-    }
-
-    pub fn get_os_list(&self) -> Result<Vec<OsCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(os_info::table
-            .group_by(os_info::os)
-            .select((os_info::os, count_star()))
-            .order_by(os_info::os)
-            .load::<OsCount>(&mut conn)?)
-    }
-
-    pub fn get_os_client_list(&self, os_name: String) -> Result<Vec<OsList>> {
-        let mut conn = self.pool.get()?;
-        Ok(os_info::table
-            .filter(os_info::os.eq(os_name))
-            .inner_join(client::table)
-            .order_by((os_info::os_version, os_info::computer_name))
-            .load::<OsList>(&mut conn)?)
-    }
-
-    pub fn get_os_versions(&self, os_name: String) -> Result<Vec<OsVersionCount>> {
-        let mut conn = self.pool.get()?;
-        Ok(os_info::table
-            .filter(os_info::os.eq(os_name))
-            .group_by(os_info::os_version)
-            .select((os_info::os_version, count_star()))
-            .order_by(os_info::os_version)
-            .load::<OsVersionCount>(&mut conn)?)
-    }
-
-    pub fn get_os_version_client_list(&self, os_name: String, os_version_name: String) ->Result<OsVersionList> {
-        let mut conn = self.pool.get()?;
-        let list = os_info::table
-            .filter(os_info::os.eq(os_name.clone()))
-            .filter(os_info::os_version.eq(os_version_name.clone()))
-            .inner_join(client::table)
-            .order_by(os_info::computer_name)
-            .load::<(OsInfo, Client)>(&mut conn)?;
-        Ok(OsVersionList {
-            os: os_name,
-            os_version: os_version_name,
-            list,
+        })
+    }
+
+    pub fn get_os_list(&self, group: Option<i32>) -> Result<Vec<OsCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(os_info::table
+                .filter(in_group!(os_info::client_id, group))
+                .group_by(os_info::os)
+                .select((os_info::os, count_star()))
+                .order_by(os_info::os)
+                .load::<OsCount>(&mut conn)?)
+        })
+    }
+
+    pub fn get_os_client_list(&self, os_name: String, group: Option<i32>) -> Result<Vec<OsList>> {
+        with_conn!(self.pool, |conn| {
+            Ok(os_info::table
+                .filter(os_info::os.eq(os_name))
+                .filter(in_group!(os_info::client_id, group))
+                .inner_join(client::table)
+                .order_by((os_info::os_version, os_info::computer_name))
+                .load::<OsList>(&mut conn)?)
+        })
+    }
+
+    pub fn get_os_versions(&self, os_name: String, group: Option<i32>) -> Result<Vec<OsVersionCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(os_info::table
+                .filter(os_info::os.eq(os_name))
+                .filter(in_group!(os_info::client_id, group))
+                .group_by(os_info::os_version)
+                .select((os_info::os_version, count_star()))
+                .order_by(os_info::os_version)
+                .load::<OsVersionCount>(&mut conn)?)
+        })
+    }
+
+    pub fn get_os_version_client_list(&self, os_name: String, os_version_name: String, group: Option<i32>) ->Result<OsVersionList> {
+        with_conn!(self.pool, |conn| {
+            let list = os_info::table
+                .filter(os_info::os.eq(os_name.clone()))
+                .filter(os_info::os_version.eq(os_version_name.clone()))
+                .filter(in_group!(os_info::client_id, group))
+                .inner_join(client::table)
+                .order_by(os_info::computer_name)
+                .load::<(OsInfo, Client)>(&mut conn)?;
+            Ok(OsVersionList {
+                os: os_name,
+                os_version: os_version_name,
+                list,
+            })
         })
     }
 }
@@ -1,11 +1,11 @@
-use diesel::sql_types::{Int8, Nullable, Numeric};
 use diesel_derive_enum::DbEnum;
 use serde_json::Value;
 use uuid::Uuid;
 
 use super::schema::*;
+use super::types::DbUuid;
 use bigdecimal::BigDecimal;
-use chrono::naive::NaiveDateTime;
+use chrono::naive::{NaiveDate, NaiveDateTime};
 use rocket::serde::Serialize;
 
 #[derive(Debug, PartialEq, DbEnum, Clone, Serialize)]
@@ -18,19 +18,159 @@ pub enum TaskStatus {
     Failed,
 }
 
-#[derive(Debug, Queryable, Serialize, Clone)]
+#[derive(Debug, Queryable, Insertable, Serialize, Clone)]
+#[diesel(table_name = client)]
 pub struct Client {
     pub id: i32,
+    #[diesel(serialize_as = DbUuid)]
     pub uuid: Uuid,
+    pub last_seen: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable)]
 #[diesel(table_name = client)]
 pub struct NewClient<'a> {
+    #[diesel(serialize_as = DbUuid)]
     pub uuid: &'a Uuid,
 }
 
-#[derive(Debug, Queryable, Serialize, Clone)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = client_group)]
+pub struct ClientGroup {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub rule: Option<Value>,
+}
+
+#[derive(Clone, Debug, Queryable, Serialize)]
+pub struct ClientGroupWithCount {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub rule: Option<Value>,
+    pub count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = client_group)]
+pub struct NewClientGroup<'a> {
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = client_group_member)]
+pub struct ClientGroupMember {
+    pub group_id: i32,
+    pub client_id: i32,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = site)]
+pub struct Site {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = site)]
+pub struct NewSite<'a> {
+    pub name: &'a str,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = building)]
+pub struct Building {
+    pub id: i32,
+    pub site_id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = building)]
+pub struct NewBuilding<'a> {
+    pub site_id: i32,
+    pub name: &'a str,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = room)]
+pub struct Room {
+    pub id: i32,
+    pub building_id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = room)]
+pub struct NewRoom<'a> {
+    pub building_id: i32,
+    pub name: &'a str,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = room_subnet)]
+pub struct RoomSubnet {
+    pub id: i32,
+    pub room_id: i32,
+    pub subnet: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = room_subnet)]
+pub struct NewRoomSubnet<'a> {
+    pub room_id: i32,
+    pub subnet: &'a str,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = client_location)]
+pub struct ClientLocation {
+    pub client_id: i32,
+    pub room_id: i32,
+    pub manual: bool,
+}
+
+#[derive(Clone, Debug, Default, Queryable, Insertable, AsChangeset, Serialize)]
+#[diesel(table_name = asset, treat_none_as_null = true)]
+pub struct Asset {
+    pub client_id: i32,
+    pub inventory_number: Option<String>,
+    pub purchase_date: Option<NaiveDate>,
+    pub supplier: Option<String>,
+    pub price: Option<BigDecimal>,
+    pub warranty_end: Option<NaiveDate>,
+    pub responsible: Option<String>,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = attribute_definition)]
+pub struct AttributeDefinition {
+    pub id: i32,
+    pub name: String,
+    pub kind: String,
+    pub options: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = attribute_definition)]
+pub struct NewAttributeDefinition<'a> {
+    pub name: &'a str,
+    pub kind: &'a str,
+    pub options: Option<&'a str>,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = client_attribute)]
+pub struct ClientAttribute {
+    pub client_id: i32,
+    pub attribute_id: i32,
+    pub value: String,
+}
+
+#[derive(Debug, Queryable, Insertable, Serialize, Clone)]
+#[diesel(table_name = os_info)]
 pub struct OsInfo {
     pub client_id: i32,
     pub os: Option<String>,
@@ -80,7 +220,8 @@ pub struct OsVersionList {
     pub list: Vec<(OsInfo, Client)>,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = software_info)]
 pub struct SoftwareInfo {
     pub id: i32,
     pub name: String,
@@ -94,7 +235,8 @@ pub struct NewSoftwareInfo<'a> {
     pub publisher: Option<&'a String>,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = software_version)]
 pub struct SoftwareVersion {
     pub id: i32,
     pub software_id: i32,
@@ -116,7 +258,8 @@ pub struct NewSoftwareVersion<'a> {
     pub version: &'a String,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = software_list)]
 pub struct SoftwareList {
     pub client_id: i32,
     pub software_id: i32,
@@ -129,7 +272,8 @@ pub struct NewSoftwareList<'a> {
     pub software_id: &'a i32,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = user)]
 pub struct User {
     pub id: i32,
     pub sid: String,
@@ -154,7 +298,8 @@ pub struct NewUser<'a> {
     pub domain: Option<&'a String>,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = userprofile)]
 pub struct UserProfile {
     pub client_id: i32,
     pub user_id: i32,
@@ -200,7 +345,8 @@ pub struct NewUserProfileWithoutSize<'a> {
     pub status: &'a i64,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = computer_model)]
 pub struct ComputerModel {
     pub client_id: i32,
     pub manufacturer: String,
@@ -231,17 +377,15 @@ pub struct Memory {
     pub stick_count: i64,
 }
 
-#[derive(Clone, Debug, QueryableByName, Serialize)]
+#[derive(Clone, Debug, Queryable, Serialize)]
 pub struct MemoryCount {
-    #[diesel(sql_type = Nullable<Numeric>)]
     pub capacity: Option<BigDecimal>,
-    #[diesel(sql_type = Nullable<Int8>)]
     pub sticks: Option<i64>,
-    #[diesel(sql_type = Int8)]
     pub count: i64,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = memory_stick)]
 pub struct MemoryStick {
     pub id: i32,
     pub client_id: i32,
@@ -257,7 +401,8 @@ pub struct NewMemoryStick<'a> {
     pub bank_label: &'a String,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = processor)]
 pub struct Processor {
     pub client_id: i32,
     pub name: String,
@@ -291,7 +436,8 @@ pub struct NewProcessor<'a> {
     pub address_width: &'a i32,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = disks)]
 pub struct Disk {
     pub id: i32,
     pub client_id: i32,
@@ -322,7 +468,8 @@ pub struct NewDisk<'a> {
     pub media_type: &'a String,
 }
 
-#[derive(Debug, Queryable, Serialize)]
+#[derive(Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = network_adapter)]
 pub struct NetworkAdapter {
     pub id: i32,
     pub client_id: i32,
@@ -344,6 +491,14 @@ pub struct NewNetworkAdapter<'a> {
     pub mac_address: Option<&'a String>,
 }
 
+#[derive(Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = network_adapter_ip)]
+pub struct NetworkAdapterIp {
+    pub id: i32,
+    pub adapter_id: i32,
+    pub ip: String,
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = network_adapter_ip)]
 pub struct NewNetworkAdapterIp<'a> {
@@ -351,7 +506,8 @@ pub struct NewNetworkAdapterIp<'a> {
     pub ip: &'a String,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = graphics_card)]
 pub struct GraphicsCard {
     pub client_id: i32,
     pub name: String,
@@ -371,7 +527,8 @@ pub struct NewGraphicsCard<'a> {
     pub name: &'a String,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = bios)]
 pub struct Bios {
     pub client_id: i32,
     pub name: String,
@@ -388,7 +545,8 @@ pub struct NewBios<'a> {
     pub version: &'a String,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = volume_status)]
 pub struct VolumeStatus {
     pub id: i32,
     pub client_id: i32,
@@ -410,7 +568,8 @@ pub struct NewVolumeStatus<'a> {
     pub free_space: BigDecimal,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = license_key)]
 pub struct LicenseKey {
     pub id: i32,
     pub client_id: i32,
@@ -432,7 +591,8 @@ pub struct NewLicenseKey<'a> {
     pub key: &'a String,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = userprofile_paths)]
 pub struct UserProfilePaths {
     pub id: i32,
     pub client_id: i32,
@@ -455,13 +615,25 @@ pub struct NewUserProfilePaths<'a> {
 pub struct NewAuthUser<'a> {
     pub username: &'a str,
     pub password: &'a str,
+    pub role: &'a str,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = auth_user)]
 pub struct AuthUser {
     pub id: i32,
     pub username: String,
+    #[serde(skip)]
     pub password: String,
+    pub role: String,
+    pub disabled: bool,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = auth_user_group)]
+pub struct AuthUserGroup {
+    pub user_id: i32,
+    pub group_id: i32,
 }
 
 #[derive(Insertable)]
@@ -472,7 +644,8 @@ pub struct NewAuthSessions<'a> {
     pub valid_until: NaiveDateTime,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = auth_sessions)]
 pub struct AuthSessions {
     pub id: i32,
     pub session_id: String,
@@ -480,6 +653,87 @@ pub struct AuthSessions {
     pub valid_until: NaiveDateTime,
 }
 
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = access_token)]
+pub struct AccessToken {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    #[serde(skip)]
+    pub token_hash: String,
+    pub scopes: String,
+    pub created: NaiveDateTime,
+    pub expires: Option<NaiveDateTime>,
+    pub last_used: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = access_token)]
+pub struct NewAccessToken<'a> {
+    pub user_id: i32,
+    pub name: &'a str,
+    pub token_hash: &'a str,
+    pub scopes: &'a str,
+    pub created: NaiveDateTime,
+    pub expires: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = login_attempt)]
+pub struct LoginAttempt {
+    pub id: i32,
+    pub username: String,
+    pub ip: Option<String>,
+    pub attempted: NaiveDateTime,
+    pub success: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = login_attempt)]
+pub struct NewLoginAttempt<'a> {
+    pub username: &'a str,
+    pub ip: Option<&'a str>,
+    pub attempted: NaiveDateTime,
+    pub success: bool,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = section_hash)]
+pub struct SectionHash {
+    pub client_id: i32,
+    pub section: String,
+    pub hash: String,
+    pub accepted: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = ingest_job)]
+pub struct IngestJob {
+    pub id: i32,
+    pub client_id: i32,
+    pub kind: String,
+    #[serde(skip)]
+    pub payload: String,
+    pub hash: Option<String>,
+    pub status: String,
+    pub received: NaiveDateTime,
+    pub available: NaiveDateTime,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = ingest_job)]
+pub struct NewIngestJob<'a> {
+    pub client_id: i32,
+    pub kind: &'a str,
+    pub payload: &'a str,
+    pub hash: Option<&'a str>,
+    pub received: NaiveDateTime,
+    pub available: NaiveDateTime,
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = client_task)]
 pub struct NewTask {
@@ -491,7 +745,8 @@ pub struct NewTask {
     pub task_result: Option<Value>,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = client_task)]
 pub struct Task {
     pub id: i32,
     pub client_id: i32,
@@ -517,7 +772,8 @@ pub struct NewBattery <'a> {
     pub full_charged_capacity: i64,
 }
 
-#[derive(Clone, Debug, Queryable, Serialize)]
+#[derive(Clone, Debug, Queryable, Insertable, Serialize)]
+#[diesel(table_name = battery)]
 pub struct Battery {
     pub id: i32,
     pub client_id: i32,
@@ -531,3 +787,66 @@ pub struct Battery {
     pub designed_capacity: i64,
     pub full_charged_capacity: i64,
 }
+
+#[derive(Clone, Debug, Queryable, Serialize)]
+pub struct StatisticsState {
+    pub id: i32,
+    pub refreshed: Option<NaiveDateTime>,
+    pub changed: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = stat_processor)]
+pub struct NewStatProcessor<'a> {
+    pub group_id: Option<i32>,
+    pub name: &'a String,
+    pub manufacturer: &'a String,
+    pub cores: Option<i64>,
+    pub logical_cores: Option<i64>,
+    pub clock_speed: Option<i64>,
+    pub address_width: Option<i32>,
+    pub count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = stat_memory)]
+pub struct NewStatMemory<'a> {
+    pub group_id: Option<i32>,
+    pub capacity: Option<&'a BigDecimal>,
+    pub sticks: Option<i64>,
+    pub count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = stat_disk)]
+pub struct NewStatDisk<'a> {
+    pub group_id: Option<i32>,
+    pub model: &'a String,
+    pub size: Option<&'a BigDecimal>,
+    pub count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = stat_computer_model)]
+pub struct NewStatComputerModel<'a> {
+    pub group_id: Option<i32>,
+    pub manufacturer: &'a String,
+    pub model_family: &'a String,
+    pub count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = stat_os)]
+pub struct NewStatOs<'a> {
+    pub group_id: Option<i32>,
+    pub os: Option<&'a String>,
+    pub count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = stat_software_version)]
+pub struct NewStatSoftwareVersion {
+    pub group_id: Option<i32>,
+    pub version_id: i32,
+    pub count: i64,
+}
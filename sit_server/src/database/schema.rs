@@ -3,7 +3,47 @@
 pub mod sql_types {
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "task_status"))]
+    #[diesel(sqlite_type(name = "Text"))]
     pub struct TaskStatus;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(oid = 2950, array_oid = 2951))]
+    #[diesel(sqlite_type(name = "Text"))]
+    pub struct Uuid;
+}
+
+diesel::table! {
+    access_token (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Text,
+        token_hash -> Text,
+        scopes -> Text,
+        created -> Timestamp,
+        expires -> Nullable<Timestamp>,
+        last_used -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    asset (client_id) {
+        client_id -> Int4,
+        inventory_number -> Nullable<Text>,
+        purchase_date -> Nullable<Date>,
+        supplier -> Nullable<Text>,
+        price -> Nullable<Numeric>,
+        warranty_end -> Nullable<Date>,
+        responsible -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    attribute_definition (id) {
+        id -> Int4,
+        name -> Text,
+        kind -> Text,
+        options -> Nullable<Text>,
+    }
 }
 
 diesel::table! {
@@ -20,6 +60,15 @@ diesel::table! {
         id -> Int4,
         username -> Text,
         password -> Text,
+        role -> Text,
+        disabled -> Bool,
+    }
+}
+
+diesel::table! {
+    auth_user_group (user_id, group_id) {
+        user_id -> Int4,
+        group_id -> Int4,
     }
 }
 
@@ -47,9 +96,53 @@ diesel::table! {
 }
 
 diesel::table! {
+    building (id) {
+        id -> Int4,
+        site_id -> Int4,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Uuid;
+
     client (id) {
         id -> Int4,
         uuid -> Uuid,
+        last_seen -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    client_attribute (client_id, attribute_id) {
+        client_id -> Int4,
+        attribute_id -> Int4,
+        value -> Text,
+    }
+}
+
+diesel::table! {
+    client_group (id) {
+        id -> Int4,
+        name -> Text,
+        description -> Nullable<Text>,
+        rule -> Nullable<Json>,
+    }
+}
+
+diesel::table! {
+    client_group_member (group_id, client_id) {
+        group_id -> Int4,
+        client_id -> Int4,
+    }
+}
+
+diesel::table! {
+    client_location (client_id) {
+        client_id -> Int4,
+        room_id -> Int4,
+        manual -> Bool,
     }
 }
 
@@ -98,6 +191,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    ingest_job (id) {
+        id -> Int4,
+        client_id -> Int4,
+        kind -> Text,
+        payload -> Text,
+        hash -> Nullable<Text>,
+        status -> Text,
+        received -> Timestamp,
+        available -> Timestamp,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     license_key (id) {
         id -> Int4,
@@ -107,6 +215,25 @@ diesel::table! {
     }
 }
 
+// View over memory_stick, not generated by the Diesel CLI.
+diesel::table! {
+    login_attempt (id) {
+        id -> Int4,
+        username -> Text,
+        ip -> Nullable<Text>,
+        attempted -> Timestamp,
+        success -> Bool,
+    }
+}
+
+diesel::table! {
+    memory (client_id) {
+        client_id -> Int4,
+        capacity -> Nullable<Numeric>,
+        sticks -> Nullable<Int8>,
+    }
+}
+
 diesel::table! {
     memory_stick (id) {
         id -> Int4,
@@ -155,6 +282,39 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    room (id) {
+        id -> Int4,
+        building_id -> Int4,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    room_subnet (id) {
+        id -> Int4,
+        room_id -> Int4,
+        subnet -> Text,
+    }
+}
+
+diesel::table! {
+    section_hash (client_id, section) {
+        client_id -> Int4,
+        section -> Text,
+        hash -> Text,
+        accepted -> Timestamp,
+        last_seen -> Timestamp,
+    }
+}
+
+diesel::table! {
+    site (id) {
+        id -> Int4,
+        name -> Text,
+    }
+}
+
 diesel::table! {
     software_info (id) {
         id -> Int4,
@@ -178,6 +338,76 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    stat_computer_model (id) {
+        id -> Int4,
+        group_id -> Nullable<Int4>,
+        manufacturer -> Text,
+        model_family -> Text,
+        count -> Int8,
+    }
+}
+
+diesel::table! {
+    stat_disk (id) {
+        id -> Int4,
+        group_id -> Nullable<Int4>,
+        model -> Text,
+        size -> Nullable<Numeric>,
+        count -> Int8,
+    }
+}
+
+diesel::table! {
+    stat_memory (id) {
+        id -> Int4,
+        group_id -> Nullable<Int4>,
+        capacity -> Nullable<Numeric>,
+        sticks -> Nullable<Int8>,
+        count -> Int8,
+    }
+}
+
+diesel::table! {
+    stat_os (id) {
+        id -> Int4,
+        group_id -> Nullable<Int4>,
+        os -> Nullable<Text>,
+        count -> Int8,
+    }
+}
+
+diesel::table! {
+    stat_processor (id) {
+        id -> Int4,
+        group_id -> Nullable<Int4>,
+        name -> Text,
+        manufacturer -> Text,
+        cores -> Nullable<Int8>,
+        logical_cores -> Nullable<Int8>,
+        clock_speed -> Nullable<Int8>,
+        address_width -> Nullable<Int4>,
+        count -> Int8,
+    }
+}
+
+diesel::table! {
+    stat_software_version (id) {
+        id -> Int4,
+        group_id -> Nullable<Int4>,
+        version_id -> Int4,
+        count -> Int8,
+    }
+}
+
+diesel::table! {
+    statistics_state (id) {
+        id -> Int4,
+        refreshed -> Nullable<Timestamp>,
+        changed -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     user (id) {
         id -> Int4,
@@ -225,22 +455,44 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(asset -> client (client_id));
+diesel::joinable!(access_token -> auth_user (user_id));
 diesel::joinable!(auth_sessions -> auth_user (user_id));
+diesel::joinable!(auth_user_group -> auth_user (user_id));
+diesel::joinable!(auth_user_group -> client_group (group_id));
 diesel::joinable!(battery -> client (client_id));
 diesel::joinable!(bios -> client (client_id));
+diesel::joinable!(building -> site (site_id));
+diesel::joinable!(client_attribute -> attribute_definition (attribute_id));
+diesel::joinable!(client_attribute -> client (client_id));
+diesel::joinable!(client_group_member -> client (client_id));
+diesel::joinable!(client_group_member -> client_group (group_id));
+diesel::joinable!(client_location -> client (client_id));
+diesel::joinable!(client_location -> room (room_id));
 diesel::joinable!(client_task -> client (client_id));
 diesel::joinable!(computer_model -> client (client_id));
 diesel::joinable!(disks -> client (client_id));
 diesel::joinable!(graphics_card -> client (client_id));
+diesel::joinable!(ingest_job -> client (client_id));
 diesel::joinable!(license_key -> client (client_id));
 diesel::joinable!(memory_stick -> client (client_id));
 diesel::joinable!(network_adapter -> client (client_id));
 diesel::joinable!(network_adapter_ip -> network_adapter (adapter_id));
 diesel::joinable!(os_info -> client (client_id));
 diesel::joinable!(processor -> client (client_id));
+diesel::joinable!(room -> building (building_id));
+diesel::joinable!(room_subnet -> room (room_id));
+diesel::joinable!(section_hash -> client (client_id));
 diesel::joinable!(software_list -> client (client_id));
 diesel::joinable!(software_list -> software_version (software_id));
 diesel::joinable!(software_version -> software_info (software_id));
+diesel::joinable!(stat_computer_model -> client_group (group_id));
+diesel::joinable!(stat_disk -> client_group (group_id));
+diesel::joinable!(stat_memory -> client_group (group_id));
+diesel::joinable!(stat_os -> client_group (group_id));
+diesel::joinable!(stat_processor -> client_group (group_id));
+diesel::joinable!(stat_software_version -> client_group (group_id));
+diesel::joinable!(stat_software_version -> software_version (version_id));
 diesel::joinable!(userprofile -> client (client_id));
 diesel::joinable!(userprofile -> user (user_id));
 diesel::joinable!(userprofile_paths -> client (client_id));
@@ -248,24 +500,47 @@ diesel::joinable!(userprofile_paths -> user (user_id));
 diesel::joinable!(volume_status -> client (client_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    access_token,
+    asset,
+    attribute_definition,
     auth_sessions,
     auth_user,
+    auth_user_group,
     battery,
     bios,
+    building,
     client,
+    client_attribute,
+    client_group,
+    client_group_member,
+    client_location,
     client_task,
     computer_model,
     disks,
     graphics_card,
+    ingest_job,
     license_key,
+    login_attempt,
+    memory,
     memory_stick,
     network_adapter,
     network_adapter_ip,
     os_info,
     processor,
+    room,
+    room_subnet,
+    section_hash,
+    site,
     software_info,
     software_list,
     software_version,
+    stat_computer_model,
+    stat_disk,
+    stat_memory,
+    stat_os,
+    stat_processor,
+    stat_software_version,
+    statistics_state,
     user,
     userprofile,
     userprofile_paths,
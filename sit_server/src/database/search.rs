@@ -0,0 +1,242 @@
+//! Global search over the inventory.
+//!
+//! Text is matched case-insensitively as substring. A query that is a complete
+//! UUID also finds the client with that UUID.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use diesel::prelude::*;
+use diesel::sql_types::{Nullable, Text};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::types::DbUuid;
+use super::{model::*, schema::*, DbPool};
+
+/// Shorter queries return nothing, they would match most of the inventory.
+const MIN_QUERY_LENGTH: usize = 2;
+
+/// Maximum number of results per category.
+const RESULT_LIMIT: i64 = 50;
+
+define_sql_function! { fn lower(x: Nullable<Text>) -> Nullable<Text>; }
+
+/// A client found through one of its properties.
+#[derive(Clone, Debug, Serialize)]
+pub struct ClientHit {
+    pub client: Client,
+    pub computer_name: String,
+    pub domain: String,
+    /// The value that matched the query.
+    pub matched: String,
+}
+
+/// A user with the clients it has a profile on.
+#[derive(Clone, Debug, Serialize)]
+pub struct UserHit {
+    pub user: User,
+    pub clients: Vec<ClientHit>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SearchResults {
+    pub clients: Vec<ClientHit>,
+    pub serial_numbers: Vec<ClientHit>,
+    pub disk_serial_numbers: Vec<ClientHit>,
+    pub mac_addresses: Vec<ClientHit>,
+    pub ip_addresses: Vec<ClientHit>,
+    pub users: Vec<UserHit>,
+    pub software: Vec<SoftwareInfo>,
+    pub licenses: Vec<ClientHit>,
+}
+
+impl SearchResults {
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+            && self.serial_numbers.is_empty()
+            && self.disk_serial_numbers.is_empty()
+            && self.mac_addresses.is_empty()
+            && self.ip_addresses.is_empty()
+            && self.users.is_empty()
+            && self.software.is_empty()
+            && self.licenses.is_empty()
+    }
+}
+
+fn hit((client, os_info, matched): (Client, Option<OsInfo>, String)) -> ClientHit {
+    ClientHit {
+        client,
+        computer_name: os_info.as_ref().map(|o| o.computer_name.clone()).unwrap_or_default(),
+        domain: os_info.and_then(|o| o.domain).unwrap_or_default(),
+        matched,
+    }
+}
+
+/// The query as pattern for `LIKE ... ESCAPE '\'`, matching it anywhere, lowercased.
+pub(super) fn contains_pattern(query: &str) -> String {
+    let mut pattern = String::with_capacity(query.len() + 2);
+    pattern.push('%');
+    for c in query.to_lowercase().chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+pub struct SearchManager {
+    pool: DbPool,
+}
+
+impl SearchManager {
+    pub fn new(pool: DbPool) -> SearchManager {
+        SearchManager { pool }
+    }
+
+    pub fn search(&self, query: &str) -> Result<SearchResults> {
+        let query = query.trim();
+        if query.chars().count() < MIN_QUERY_LENGTH {
+            return Ok(SearchResults::default());
+        }
+        let pattern = contains_pattern(query);
+        let pattern = pattern.as_str();
+        // MAC addresses are stored with colons, but are often written with dashes.
+        let mac_pattern = contains_pattern(&query.replace('-', ":"));
+        let mac_pattern = mac_pattern.as_str();
+        let uuid = Uuid::parse_str(query).ok();
+
+        with_conn!(self.pool, |conn| {
+            let mut clients: Vec<ClientHit> = client::table
+                .left_join(os_info::table)
+                .filter(
+                    lower(os_info::computer_name.nullable())
+                        .like(pattern)
+                        .escape('\\')
+                        .or(lower(os_info::domain).like(pattern).escape('\\')),
+                )
+                .order_by(os_info::computer_name)
+                .limit(RESULT_LIMIT)
+                .load::<(Client, Option<OsInfo>)>(&mut conn)?
+                .into_iter()
+                .map(|(c, o)| {
+                    let matched = o.as_ref().map(|o| o.computer_name.clone()).unwrap_or_default();
+                    hit((c, o, matched))
+                })
+                .collect();
+            if let Some(uuid) = uuid {
+                let by_uuid = client::table
+                    .left_join(os_info::table)
+                    .filter(client::uuid.eq(DbUuid(uuid)))
+                    .load::<(Client, Option<OsInfo>)>(&mut conn)?;
+                clients.extend(by_uuid.into_iter().map(|(c, o)| hit((c, o, uuid.to_string()))));
+            }
+
+            let serial_numbers: Vec<ClientHit> = computer_model::table
+                .inner_join(client::table.left_join(os_info::table))
+                .filter(lower(computer_model::serial_number.nullable()).like(pattern).escape('\\'))
+                .select((
+                    client::all_columns,
+                    os_info::all_columns.nullable(),
+                    computer_model::serial_number,
+                ))
+                .order_by(os_info::computer_name)
+                .limit(RESULT_LIMIT)
+                .load::<(Client, Option<OsInfo>, String)>(&mut conn)?
+                .into_iter()
+                .map(hit)
+                .collect();
+            let disk_serial_numbers: Vec<ClientHit> = disks::table
+                .inner_join(client::table.left_join(os_info::table))
+                .filter(lower(disks::serial_number.nullable()).like(pattern).escape('\\'))
+                .select((client::all_columns, os_info::all_columns.nullable(), disks::serial_number))
+                .order_by(os_info::computer_name)
+                .limit(RESULT_LIMIT)
+                .load::<(Client, Option<OsInfo>, String)>(&mut conn)?
+                .into_iter()
+                .map(hit)
+                .collect();
+
+            let mac_addresses: Vec<ClientHit> = network_adapter::table
+                .inner_join(client::table.left_join(os_info::table))
+                .filter(lower(network_adapter::mac_address).like(mac_pattern).escape('\\'))
+                .select((
+                    client::all_columns,
+                    os_info::all_columns.nullable(),
+                    network_adapter::mac_address,
+                ))
+                .order_by(os_info::computer_name)
+                .limit(RESULT_LIMIT)
+                .load::<(Client, Option<OsInfo>, Option<String>)>(&mut conn)?
+                .into_iter()
+                .map(|(c, o, mac)| hit((c, o, mac.unwrap_or_default())))
+                .collect();
+
+            let ip_addresses: Vec<ClientHit> = network_adapter_ip::table
+                .inner_join(network_adapter::table.inner_join(client::table.left_join(os_info::table)))
+                .filter(lower(network_adapter_ip::ip.nullable()).like(pattern).escape('\\'))
+                .select((client::all_columns, os_info::all_columns.nullable(), network_adapter_ip::ip))
+                .order_by(os_info::computer_name)
+                .limit(RESULT_LIMIT)
+                .load::<(Client, Option<OsInfo>, String)>(&mut conn)?
+                .into_iter()
+                .map(hit)
+                .collect();
+
+            let profiles: Vec<(User, Client, Option<OsInfo>)> = userprofile::table
+                .inner_join(user::table)
+                .inner_join(client::table.left_join(os_info::table))
+                .filter(
+                    lower(user::username)
+                        .like(pattern)
+                        .escape('\\')
+                        .or(lower(user::sid.nullable()).like(pattern).escape('\\')),
+                )
+                .select((user::all_columns, client::all_columns, os_info::all_columns.nullable()))
+                .order_by((user::username, os_info::computer_name))
+                .limit(RESULT_LIMIT)
+                .load(&mut conn)?;
+            let mut users: BTreeMap<i32, UserHit> = BTreeMap::new();
+            for (u, c, o) in profiles {
+                let entry = users.entry(u.id).or_insert_with(|| UserHit {
+                    user: u,
+                    clients: vec![],
+                });
+                let matched = entry.user.username.clone().unwrap_or_default();
+                entry.clients.push(hit((c, o, matched)));
+            }
+            let mut users: Vec<UserHit> = users.into_values().collect();
+            users.sort_by(|a, b| a.user.username.cmp(&b.user.username));
+
+            let software: Vec<SoftwareInfo> = software_info::table
+                .filter(lower(software_info::name.nullable()).like(pattern).escape('\\'))
+                .order_by(software_info::name)
+                .limit(RESULT_LIMIT)
+                .load(&mut conn)?;
+
+            let licenses: Vec<ClientHit> = license_key::table
+                .inner_join(client::table.left_join(os_info::table))
+                .filter(lower(license_key::key.nullable()).like(pattern).escape('\\'))
+                .select((client::all_columns, os_info::all_columns.nullable(), license_key::key))
+                .order_by(os_info::computer_name)
+                .limit(RESULT_LIMIT)
+                .load::<(Client, Option<OsInfo>, String)>(&mut conn)?
+                .into_iter()
+                .map(hit)
+                .collect();
+
+            Ok(SearchResults {
+                clients,
+                serial_numbers,
+                disk_serial_numbers,
+                mac_addresses,
+                ip_addresses,
+                users,
+                software,
+                licenses,
+            })
+        })
+    }
+}
@@ -0,0 +1,37 @@
+//! Content hashes of the last accepted snapshot sections per client, so agents
+//! can skip uploading sections that did not change.
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use super::{model::*, schema::*, DbPool};
+
+pub struct SectionHashManager {
+    pool: DbPool,
+}
+
+impl SectionHashManager {
+    pub fn new(pool: DbPool) -> SectionHashManager {
+        SectionHashManager { pool }
+    }
+
+    pub fn get_hashes(&self, client_id: i32) -> Result<Vec<SectionHash>> {
+        with_conn!(self.pool, |conn| {
+            Ok(section_hash::table
+                .filter(section_hash::client_id.eq(client_id))
+                .load(&mut conn)?)
+        })
+    }
+
+    /// Marks `sections` of the client as reported unchanged at `now`.
+    pub fn set_last_seen(&self, client_id: i32, sections: &[&str], now: NaiveDateTime) -> Result<usize> {
+        with_conn!(self.pool, |conn| {
+            Ok(diesel::update(section_hash::table)
+                .filter(section_hash::client_id.eq(client_id))
+                .filter(section_hash::section.eq_any(sections))
+                .set(section_hash::last_seen.eq(now))
+                .execute(&mut conn)?)
+        })
+    }
+}
@@ -0,0 +1,164 @@
+//! Ingest of the software list of a client.
+//!
+//! Instead of a find-or-create per program, the known software and versions are
+//! loaded in bulk, the missing ones inserted in bulk and the list of the client
+//! is updated by difference, all in one transaction.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+use diesel::prelude::*;
+use sit_lib::software::SoftwareLibrary;
+
+use super::{model::*, schema::*, Database};
+
+/// Values per statement, well below the bind parameter limit of both backends.
+const CHUNK_SIZE: usize = 500;
+
+/// Ids of the software with one of `$names`, by name and publisher. Duplicates
+/// resolve to the oldest entry.
+macro_rules! load_software_ids {
+    ($conn:expr, $names:expr) => {{
+        let mut ids: HashMap<(String, String), i32> = HashMap::new();
+        for chunk in $names.chunks(CHUNK_SIZE) {
+            let rows: Vec<SoftwareInfo> = software_info::table
+                .filter(software_info::name.eq_any(chunk))
+                .order_by(software_info::id)
+                .load($conn)?;
+            for row in rows {
+                if let Some(publisher) = row.publisher {
+                    ids.entry((row.name, publisher)).or_insert(row.id);
+                }
+            }
+        }
+        ids
+    }};
+}
+
+/// Ids of the versions of `$software_ids`, by software id and version.
+macro_rules! load_version_ids {
+    ($conn:expr, $software_ids:expr) => {{
+        let mut ids: HashMap<(i32, String), i32> = HashMap::new();
+        for chunk in $software_ids.chunks(CHUNK_SIZE) {
+            let rows: Vec<SoftwareVersion> = software_version::table
+                .filter(software_version::software_id.eq_any(chunk))
+                .order_by(software_version::id)
+                .load($conn)?;
+            for row in rows {
+                ids.entry((row.software_id, row.version)).or_insert(row.id);
+            }
+        }
+        ids
+    }};
+}
+
+impl Database {
+    /// Replaces the software list of the client `client_id` with `software_lib`.
+    pub fn update_software_lib(
+        &self,
+        client_id: i32,
+        software_lib: SoftwareLibrary,
+        hash: Option<&SectionHash>,
+    ) -> Result<()> {
+        // (name, publisher, version) of every program, without duplicates.
+        let entries: BTreeSet<(String, String, String)> = software_lib
+            .software
+            .into_iter()
+            .map(|e| (e.name, e.publisher.unwrap_or_default(), e.version))
+            .collect();
+        let names: Vec<String> = entries
+            .iter()
+            .map(|(name, _, _)| name.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        with_conn!(self.pool, |conn| {
+            conn.transaction::<(), anyhow::Error, _>(|c| {
+                let mut software_ids = load_software_ids!(c, names);
+                let missing: BTreeSet<(&String, &String)> = entries
+                    .iter()
+                    .map(|(name, publisher, _)| (name, publisher))
+                    .filter(|(name, publisher)| {
+                        !software_ids.contains_key(&(name.to_string(), publisher.to_string()))
+                    })
+                    .collect();
+                if !missing.is_empty() {
+                    let rows: Vec<NewSoftwareInfo> = missing
+                        .into_iter()
+                        .map(|(name, publisher)| NewSoftwareInfo {
+                            name,
+                            publisher: Some(publisher),
+                        })
+                        .collect();
+                    for chunk in rows.chunks(CHUNK_SIZE) {
+                        diesel::insert_into(software_info::table).values(chunk).execute(c)?;
+                    }
+                    software_ids = load_software_ids!(c, names);
+                }
+
+                // (software id, version) of every program.
+                let wanted: BTreeSet<(i32, &String)> = entries
+                    .iter()
+                    .filter_map(|(name, publisher, version)| {
+                        software_ids
+                            .get(&(name.clone(), publisher.clone()))
+                            .map(|id| (*id, version))
+                    })
+                    .collect();
+                let ids: Vec<i32> = wanted
+                    .iter()
+                    .map(|(id, _)| *id)
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect();
+                let mut version_ids = load_version_ids!(c, ids);
+                let missing: Vec<NewSoftwareVersion> = wanted
+                    .iter()
+                    .filter(|(id, version)| !version_ids.contains_key(&(*id, version.to_string())))
+                    .map(|(id, version)| NewSoftwareVersion {
+                        software_id: id,
+                        version,
+                    })
+                    .collect();
+                if !missing.is_empty() {
+                    for chunk in missing.chunks(CHUNK_SIZE) {
+                        diesel::insert_into(software_version::table).values(chunk).execute(c)?;
+                    }
+                    version_ids = load_version_ids!(c, ids);
+                }
+
+                let list: BTreeSet<i32> = wanted
+                    .iter()
+                    .filter_map(|(id, version)| version_ids.get(&(*id, version.to_string())).copied())
+                    .collect();
+                let current: BTreeSet<i32> = software_list::table
+                    .filter(software_list::client_id.eq(client_id))
+                    .select(software_list::software_id)
+                    .load::<i32>(c)?
+                    .into_iter()
+                    .collect();
+                let removed: Vec<i32> = current.difference(&list).copied().collect();
+                for chunk in removed.chunks(CHUNK_SIZE) {
+                    diesel::delete(software_list::table)
+                        .filter(software_list::client_id.eq(client_id))
+                        .filter(software_list::software_id.eq_any(chunk))
+                        .execute(c)?;
+                }
+                let added: Vec<NewSoftwareList> = list
+                    .difference(&current)
+                    .map(|software_id| NewSoftwareList {
+                        client_id: &client_id,
+                        software_id,
+                    })
+                    .collect();
+                for chunk in added.chunks(CHUNK_SIZE) {
+                    diesel::insert_into(software_list::table).values(chunk).execute(c)?;
+                }
+                store_section_hash!(c, hash);
+                Ok(())
+            })
+        })?;
+        self.group_manager.refresh_client(client_id)
+    }
+}
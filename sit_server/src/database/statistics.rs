@@ -0,0 +1,216 @@
+//! Fleet statistics for the overview pages.
+//!
+//! Counting processors, memory, disks, models, operating systems and software
+//! versions over every client is too slow for a page view on a large fleet.
+//! The counts are computed by [`Database::refresh_statistics`] for the whole
+//! fleet and for every client group and stored in the `stat_*` tables, which the
+//! overview pages read. `statistics_state` records when they were refreshed and
+//! when the inventory last changed, see `crate::statistics` for the schedule.
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+
+use super::{coalesce, model::*, schema::*, Database, DbPool};
+
+/// Rows per INSERT when the statistics are rewritten.
+const CHUNK_SIZE: usize = 500;
+
+/// The only row of `statistics_state`.
+const STATE_ID: i32 = 1;
+
+/// The counts of the whole fleet or of one client group.
+pub struct FleetStatistics {
+    pub processors: Vec<ProcessorCount>,
+    pub memory: Vec<MemoryCount>,
+    pub disks: Vec<DiskCount>,
+    pub computer_models: Vec<ComputerModelCount>,
+    pub os: Vec<OsCount>,
+    /// Installations by software version id.
+    pub software_versions: Vec<(i32, i64)>,
+}
+
+/// Inserts `$rows` into `$table` in chunks.
+macro_rules! insert_chunked {
+    ($conn:expr, $table:expr, $rows:expr) => {
+        for chunk in $rows.chunks(CHUNK_SIZE) {
+            diesel::insert_into($table).values(chunk).execute($conn)?;
+        }
+    };
+}
+
+pub struct StatisticsManager {
+    pool: DbPool,
+}
+
+impl StatisticsManager {
+    pub fn new(pool: DbPool) -> StatisticsManager {
+        StatisticsManager { pool }
+    }
+
+    pub fn get_state(&self) -> Result<StatisticsState> {
+        with_conn!(self.pool, |conn| {
+            Ok(statistics_state::table
+                .filter(statistics_state::id.eq(STATE_ID))
+                .get_result(&mut conn)?)
+        })
+    }
+
+    /// Records that the inventory changed at `now`, so the statistics are due.
+    pub fn mark_changed(&self, now: NaiveDateTime) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            diesel::update(statistics_state::table)
+                .filter(statistics_state::id.eq(STATE_ID))
+                .set(statistics_state::changed.eq(now))
+                .execute(&mut conn)?;
+            Ok(())
+        })
+    }
+
+    /// Replaces all statistics with `scopes`, by group (`None` for the whole
+    /// fleet), as computed from the inventory at `now`.
+    fn replace(&self, scopes: &[(Option<i32>, FleetStatistics)], now: NaiveDateTime) -> Result<()> {
+        let mut processors = vec![];
+        let mut memory = vec![];
+        let mut disks = vec![];
+        let mut computer_models = vec![];
+        let mut os = vec![];
+        let mut software_versions = vec![];
+        for (group_id, statistics) in scopes {
+            let group_id = *group_id;
+            processors.extend(statistics.processors.iter().map(|p| NewStatProcessor {
+                group_id,
+                name: &p.name,
+                manufacturer: &p.manufacturer,
+                cores: p.cores,
+                logical_cores: p.logical_cores,
+                clock_speed: p.clock_speed,
+                address_width: p.address_width,
+                count: p.count,
+            }));
+            memory.extend(statistics.memory.iter().map(|m| NewStatMemory {
+                group_id,
+                capacity: m.capacity.as_ref(),
+                sticks: m.sticks,
+                count: m.count,
+            }));
+            disks.extend(statistics.disks.iter().map(|d| NewStatDisk {
+                group_id,
+                model: &d.model,
+                size: d.size.as_ref(),
+                count: d.count,
+            }));
+            computer_models.extend(statistics.computer_models.iter().map(|m| NewStatComputerModel {
+                group_id,
+                manufacturer: &m.manufacturer,
+                model_family: &m.model_family,
+                count: m.count,
+            }));
+            os.extend(statistics.os.iter().map(|o| NewStatOs {
+                group_id,
+                os: o.os.as_ref(),
+                count: o.count,
+            }));
+            software_versions.extend(statistics.software_versions.iter().map(|(version_id, count)| {
+                NewStatSoftwareVersion {
+                    group_id,
+                    version_id: *version_id,
+                    count: *count,
+                }
+            }));
+        }
+
+        with_conn!(self.pool, |conn| {
+            conn.transaction::<(), diesel::result::Error, _>(|c| {
+                diesel::delete(stat_processor::table).execute(c)?;
+                diesel::delete(stat_memory::table).execute(c)?;
+                diesel::delete(stat_disk::table).execute(c)?;
+                diesel::delete(stat_computer_model::table).execute(c)?;
+                diesel::delete(stat_os::table).execute(c)?;
+                diesel::delete(stat_software_version::table).execute(c)?;
+                insert_chunked!(c, stat_processor::table, processors);
+                insert_chunked!(c, stat_memory::table, memory);
+                insert_chunked!(c, stat_disk::table, disks);
+                insert_chunked!(c, stat_computer_model::table, computer_models);
+                insert_chunked!(c, stat_os::table, os);
+                insert_chunked!(c, stat_software_version::table, software_versions);
+                diesel::update(statistics_state::table)
+                    .filter(statistics_state::id.eq(STATE_ID))
+                    .set(statistics_state::refreshed.eq(now))
+                    .execute(c)?;
+                Ok(())
+            })?;
+            Ok(())
+        })
+    }
+
+    /// Operating systems with the number of clients running them.
+    pub fn get_os_list(&self, group: Option<i32>) -> Result<Vec<OsCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(stat_os::table
+                .filter(in_scope!(stat_os::group_id, group))
+                .select((stat_os::os, stat_os::count))
+                .order_by(stat_os::os)
+                .load::<OsCount>(&mut conn)?)
+        })
+    }
+
+    /// Versions of the software `software_id` with the number of clients having
+    /// them installed.
+    pub fn get_software_versions(&self, software_id: i32, group: Option<i32>) -> Result<Vec<SoftwareVersionWithCount>> {
+        with_conn!(self.pool, |conn| {
+            Ok(software_version::table
+                .select((
+                    software_version::id,
+                    software_version::software_id,
+                    software_version::version,
+                    coalesce(
+                        stat_software_version::table
+                            .select(stat_software_version::count)
+                            .filter(stat_software_version::version_id.eq(software_version::id))
+                            .filter(in_scope!(stat_software_version::group_id, group))
+                            .single_value(),
+                        0,
+                    ),
+                ))
+                .filter(software_version::software_id.eq(software_id))
+                .order_by(software_version::version)
+                .load::<SoftwareVersionWithCount>(&mut conn)?)
+        })
+    }
+}
+
+impl Database {
+    /// Recomputes the statistics of the fleet and of every client group from the
+    /// inventory.
+    pub fn refresh_statistics(&self, now: NaiveDateTime) -> Result<()> {
+        let mut scopes = vec![(None, self.fleet_statistics(None)?)];
+        for group in self.group_manager.get_groups()? {
+            scopes.push((Some(group.id), self.fleet_statistics(Some(group.id))?));
+        }
+        self.statistics_manager.replace(&scopes, now)
+    }
+
+    fn fleet_statistics(&self, group: Option<i32>) -> Result<FleetStatistics> {
+        Ok(FleetStatistics {
+            processors: self.get_processors_count(group)?,
+            memory: self.get_memory_count(group)?,
+            disks: self.get_disks_count(group)?,
+            computer_models: self.get_computer_models_count(group)?,
+            os: self.get_os_list(group)?,
+            software_versions: self.get_software_version_counts(group)?,
+        })
+    }
+
+    /// Installations by software version id, counted from the inventory.
+    pub fn get_software_version_counts(&self, group: Option<i32>) -> Result<Vec<(i32, i64)>> {
+        with_conn!(self.pool, |conn| {
+            Ok(software_list::table
+                .filter(in_group!(software_list::client_id, group))
+                .group_by(software_list::software_id)
+                .select((software_list::software_id, count_star()))
+                .load::<(i32, i64)>(&mut conn)?)
+        })
+    }
+}
@@ -1,7 +1,6 @@
-use super::{model::*, schema::*};
+use super::{model::*, schema::*, DbPool};
 use anyhow::Result;
 use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, Pool};
 use serde::Serialize;
 use serde_json::{json, Value};
 use sit_lib::task::TaskUpdate;
@@ -12,70 +11,100 @@ pub struct TaskOptions {
     pub parameters: Value,
 }
 
-#[derive(Clone, Debug, Serialize)]
-pub struct TaskResult {
-    pub result: String,
+impl Task {
+    /// Finished tasks take no further updates.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.task_status,
+            Some(TaskStatus::Successful) | Some(TaskStatus::Failed)
+        )
+    }
 }
 
 pub struct TaskManager {
-    pool: Pool<ConnectionManager<PgConnection>>,
+    pool: DbPool,
 }
 
 impl TaskManager {
-    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> TaskManager {
+    pub fn new(pool: DbPool) -> TaskManager {
         TaskManager { pool }
     }
 
     pub fn delete_user_profile(&self, client_id: i32, sid_string: String) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        let task_options = TaskOptions {
-            name: "delete-user-profile".to_owned(),
-            parameters: json!({"sid": sid_string}),
-        };
-        let task = NewTask {
-            client_id,
-            task: serde_json::to_value(task_options)?,
-            time_start: None,
-            time_download: None,
-            task_status: Some(TaskStatus::Created),
-            task_result: None,
-        };
-        diesel::insert_into(client_task::table)
-            .values(task)
-            .execute(&mut conn)?;
-        Ok(())
+        with_conn!(self.pool, |conn| {
+            let task_options = TaskOptions {
+                name: "delete-user-profile".to_owned(),
+                parameters: json!({"sid": sid_string}),
+            };
+            let task = NewTask {
+                client_id,
+                task: serde_json::to_value(task_options)?,
+                time_start: None,
+                time_download: None,
+                task_status: Some(TaskStatus::Created),
+                task_result: None,
+            };
+            diesel::insert_into(client_task::table)
+                .values(task)
+                .execute(&mut conn)?;
+            Ok(())
+        })
     }
 
-    pub fn update_task_status(&self, client_id: i32, task_update: TaskUpdate) -> Result<()> {
-        let mut conn = self.pool.get()?;
-
-        if let Some(time_downloaded) = task_update.time_downloaded {
-            diesel::update(client_task::table)
-                .set(client_task::time_download.eq(time_downloaded.naive_utc()))
+    /// The task `task_id` if it belongs to the client `client_id`.
+    pub fn get_task(&self, client_id: i32, task_id: i32) -> Result<Option<Task>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_task::table
+                .filter(client_task::id.eq(task_id))
                 .filter(client_task::client_id.eq(client_id))
-                .execute(&mut conn)?;
-        }
-        if let Some(task_result) = task_update.task_result {
+                .get_result(&mut conn)
+                .optional()?)
+        })
+    }
+
+    pub fn update_task_status(&self, client_id: i32, task_update: TaskUpdate) -> Result<()> {
+        with_conn!(self.pool, |conn| {
+            if let Some(time_downloaded) = task_update.time_downloaded {
+                diesel::update(client_task::table)
+                    .set(client_task::time_download.eq(time_downloaded.naive_utc()))
+                    .filter(client_task::client_id.eq(client_id))
+                    .filter(client_task::id.eq(task_update.id))
+                    .execute(&mut conn)?;
+            }
+            if let Some(task_result) = task_update.task_result {
+                diesel::update(client_task::table)
+                    .set(client_task::task_result.eq(task_result))
+                    .filter(client_task::client_id.eq(client_id))
+                    .filter(client_task::id.eq(task_update.id))
+                    .execute(&mut conn)?;
+            }
             diesel::update(client_task::table)
-                .set(client_task::task_result.eq(task_result))
+                .set(client_task::task_status.eq(Self::convert_task_status(task_update.task_status)))
                 .filter(client_task::client_id.eq(client_id))
+                .filter(client_task::id.eq(task_update.id))
                 .execute(&mut conn)?;
-        }
-        diesel::update(client_task::table)
-            .set(client_task::task_status.eq(Self::convert_task_status(task_update.task_status)))
-            .filter(client_task::client_id.eq(client_id))
-            .execute(&mut conn)?;
 
-        Ok(())
+            Ok(())
+        })
     }
 
     pub fn get_new_tasks_for_client(&self, client_id: i32) -> Result<Vec<Task>> {
-        let mut conn = self.pool.get()?;
+        with_conn!(self.pool, |conn| {
+            Ok(client_task::table
+                .filter(client_task::client_id.eq(client_id))
+                .filter(client_task::task_status.eq(TaskStatus::Created))
+                .load(&mut conn)?)
+        })
+    }
 
-        Ok(client_task::table
-            .filter(client_task::client_id.eq(client_id))
-            .filter(client_task::task_status.eq(TaskStatus::Created))
-            .load(&mut conn)?)
+    /// All tasks of the client `client_id`, newest first.
+    pub fn get_client_tasks(&self, client_id: i32) -> Result<Vec<Task>> {
+        with_conn!(self.pool, |conn| {
+            Ok(client_task::table
+                .filter(client_task::client_id.eq(client_id))
+                .order_by(client_task::id.desc())
+                .load(&mut conn)?)
+        })
     }
 
     fn convert_task_status(task_status: sit_lib::task::TaskStatus) -> TaskStatus {
@@ -0,0 +1,123 @@
+use anyhow::{anyhow, Result};
+use diesel::connection::SimpleConnection;
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+
+use super::{model::*, schema::*, Database, DbPool};
+
+/// Rows per INSERT, well below the bind parameter limit of both backends.
+const CHUNK_SIZE: usize = 500;
+
+/// Tables with a `SERIAL` id whose sequence has to follow the copied ids.
+const SERIAL_TABLES: &[&str] = &[
+    "access_token",
+    "attribute_definition",
+    "auth_sessions",
+    "auth_user",
+    "battery",
+    "building",
+    "client",
+    "client_group",
+    "client_task",
+    "disks",
+    "graphics_card",
+    "ingest_job",
+    "license_key",
+    "login_attempt",
+    "memory_stick",
+    "network_adapter",
+    "network_adapter_ip",
+    "room",
+    "room_subnet",
+    "site",
+    "software_info",
+    "software_version",
+    "user",
+    "userprofile_paths",
+    "volume_status",
+];
+
+macro_rules! copy_table {
+    ($src:expr, $dst:expr, $table:ident, $row:ty) => {{
+        let rows = $table::table.load::<$row>($src)?;
+        let count = rows.len();
+        let mut rows = rows.into_iter().peekable();
+        while rows.peek().is_some() {
+            let chunk: Vec<$row> = rows.by_ref().take(CHUNK_SIZE).collect();
+            diesel::insert_into($table::table).values(chunk).execute($dst)?;
+        }
+        (stringify!($table), count)
+    }};
+}
+
+impl Database {
+    /// Copies the whole content of this SQLite database into `target`, which has to be a
+    /// migrated but otherwise empty Postgres database. Ids are kept as they are.
+    /// The fleet statistics are not copied, the target computes them on its first refresh.
+    /// Returns the number of copied rows per table.
+    pub fn copy_into(&self, target: &Database) -> Result<Vec<(&'static str, usize)>> {
+        let (DbPool::Sqlite(source), DbPool::Postgres(dest)) = (&self.pool, &target.pool) else {
+            return Err(anyhow!("Only copying from SQLite to Postgres is supported"));
+        };
+        let mut src = source.get()?;
+        let mut dst = dest.get()?;
+
+        let existing: i64 = client::table.select(count_star()).get_result(&mut dst)?;
+        let existing_users: i64 = auth_user::table.select(count_star()).get_result(&mut dst)?;
+        if existing > 0 || existing_users > 0 {
+            return Err(anyhow!("Target database is not empty"));
+        }
+
+        src.transaction::<_, anyhow::Error, _>(|src| {
+            dst.transaction::<_, anyhow::Error, _>(|dst| {
+                // Parents before children, so foreign keys are satisfied at every step.
+                let copied = vec![
+                    copy_table!(src, dst, client, Client),
+                    copy_table!(src, dst, client_group, ClientGroup),
+                    copy_table!(src, dst, client_group_member, ClientGroupMember),
+                    copy_table!(src, dst, site, Site),
+                    copy_table!(src, dst, building, Building),
+                    copy_table!(src, dst, room, Room),
+                    copy_table!(src, dst, room_subnet, RoomSubnet),
+                    copy_table!(src, dst, client_location, ClientLocation),
+                    copy_table!(src, dst, asset, Asset),
+                    copy_table!(src, dst, attribute_definition, AttributeDefinition),
+                    copy_table!(src, dst, client_attribute, ClientAttribute),
+                    copy_table!(src, dst, os_info, OsInfo),
+                    copy_table!(src, dst, computer_model, ComputerModel),
+                    copy_table!(src, dst, bios, Bios),
+                    copy_table!(src, dst, processor, Processor),
+                    copy_table!(src, dst, memory_stick, MemoryStick),
+                    copy_table!(src, dst, disks, Disk),
+                    copy_table!(src, dst, graphics_card, GraphicsCard),
+                    copy_table!(src, dst, network_adapter, NetworkAdapter),
+                    copy_table!(src, dst, network_adapter_ip, NetworkAdapterIp),
+                    copy_table!(src, dst, volume_status, VolumeStatus),
+                    copy_table!(src, dst, license_key, LicenseKey),
+                    copy_table!(src, dst, battery, Battery),
+                    copy_table!(src, dst, section_hash, SectionHash),
+                    copy_table!(src, dst, ingest_job, IngestJob),
+                    copy_table!(src, dst, software_info, SoftwareInfo),
+                    copy_table!(src, dst, software_version, SoftwareVersion),
+                    copy_table!(src, dst, software_list, SoftwareList),
+                    copy_table!(src, dst, user, User),
+                    copy_table!(src, dst, userprofile, UserProfile),
+                    copy_table!(src, dst, userprofile_paths, UserProfilePaths),
+                    copy_table!(src, dst, client_task, Task),
+                    copy_table!(src, dst, auth_user, AuthUser),
+                    copy_table!(src, dst, auth_sessions, AuthSessions),
+                    copy_table!(src, dst, auth_user_group, AuthUserGroup),
+                    copy_table!(src, dst, access_token, AccessToken),
+                    copy_table!(src, dst, login_attempt, LoginAttempt),
+                ];
+
+                for table in SERIAL_TABLES {
+                    dst.batch_execute(&format!(
+                        "SELECT setval(pg_get_serial_sequence('\"{table}\"', 'id'), COALESCE(MAX(\"id\"), 1), MAX(\"id\") IS NOT NULL) FROM \"{table}\";"
+                    ))?;
+                }
+                Ok(copied)
+            })
+        })
+    }
+}
@@ -0,0 +1,90 @@
+//! Mappings for the column types that diesel does not support on every backend.
+//!
+//! Postgres stores these natively (`uuid`, the `task_status` enum), SQLite stores
+//! them as text.
+
+use diesel::deserialize::{self, FromSql};
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sqlite::{Sqlite, SqliteValue};
+use diesel::AsExpression;
+
+use super::model::TaskStatus;
+use super::schema::sql_types;
+
+/// Binds a [`uuid::Uuid`] as a query parameter for a `client.uuid` column.
+/// Reading uuids needs no wrapper, they load straight into [`uuid::Uuid`].
+#[derive(Debug, AsExpression)]
+#[diesel(sql_type = sql_types::Uuid)]
+pub struct DbUuid(pub uuid::Uuid);
+
+impl From<uuid::Uuid> for DbUuid {
+    fn from(uuid: uuid::Uuid) -> Self {
+        DbUuid(uuid)
+    }
+}
+
+impl From<&uuid::Uuid> for DbUuid {
+    fn from(uuid: &uuid::Uuid) -> Self {
+        DbUuid(*uuid)
+    }
+}
+
+impl ToSql<sql_types::Uuid, Pg> for DbUuid {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <uuid::Uuid as ToSql<diesel::sql_types::Uuid, Pg>>::to_sql(&self.0, out)
+    }
+}
+
+impl ToSql<sql_types::Uuid, Sqlite> for DbUuid {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.0.hyphenated().to_string());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<sql_types::Uuid, Pg> for uuid::Uuid {
+    fn from_sql(value: PgValue<'_>) -> deserialize::Result<Self> {
+        <uuid::Uuid as FromSql<diesel::sql_types::Uuid, Pg>>::from_sql(value)
+    }
+}
+
+impl FromSql<sql_types::Uuid, Sqlite> for uuid::Uuid {
+    fn from_sql(mut value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        Ok(uuid::Uuid::parse_str(value.read_text())?)
+    }
+}
+
+impl FromSql<sql_types::TaskStatus, Sqlite> for TaskStatus {
+    fn from_sql(mut value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        match value.read_text() {
+            "created" => Ok(TaskStatus::Created),
+            "downloaded" => Ok(TaskStatus::Downloaded),
+            "running" => Ok(TaskStatus::Running),
+            "successful" => Ok(TaskStatus::Successful),
+            "failed" => Ok(TaskStatus::Failed),
+            v => Err(format!("Unrecognized task status: '{v}'").into()),
+        }
+    }
+}
+
+impl ToSql<sql_types::TaskStatus, Sqlite> for TaskStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(match self {
+            TaskStatus::Created => "created",
+            TaskStatus::Downloaded => "downloaded",
+            TaskStatus::Running => "running",
+            TaskStatus::Successful => "successful",
+            TaskStatus::Failed => "failed",
+        });
+        Ok(IsNull::No)
+    }
+}
+
+impl diesel::Queryable<sql_types::TaskStatus, Sqlite> for TaskStatus {
+    type Row = Self;
+
+    fn build(row: Self::Row) -> deserialize::Result<Self> {
+        Ok(row)
+    }
+}
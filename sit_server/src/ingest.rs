@@ -0,0 +1,293 @@
+//! Background ingest of agent payloads.
+//!
+//! The agent endpoints only validate a payload and queue it, the actual work on
+//! the inventory tables is done by worker threads. Jobs are claimed in the
+//! database, so workers of several server instances can share the queue. Jobs of
+//! one client are applied one at a time and in the order they arrived. A job that
+//! keeps failing is set aside as failed after [`MAX_ATTEMPTS`], it can be retried
+//! on the queue page. Attempts are counted when a job is claimed, so a job that
+//! takes the server down, e.g. by panicking in a release build, which aborts on
+//! panic, is set aside as well once it was claimed that often.
+//!
+//! The number of workers is the `ingest_workers` value of the Rocket config.
+
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{TimeDelta, Utc};
+use rocket::fairing::AdHoc;
+use serde::Serialize;
+use sit_lib::snapshot::Section;
+
+use crate::database::{Database, IngestJob, SectionHash};
+
+const DEFAULT_WORKERS: usize = 4;
+pub const MAX_ATTEMPTS: i32 = 5;
+/// Multiplied with the number of attempts so far.
+const RETRY_DELAY: TimeDelta = TimeDelta::seconds(30);
+/// How long a claimed job is left to its worker before another one may take it
+/// over, e.g. after the server stopped in the middle of it.
+const CLAIM_TIMEOUT: TimeDelta = TimeDelta::minutes(15);
+const IDLE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IngestKind {
+    Os,
+    HardwareV1,
+    Hardware,
+    Software,
+    Profiles,
+    Volumes,
+    Licenses,
+    Battery,
+}
+
+impl IngestKind {
+    pub const ALL: [IngestKind; 8] = [
+        IngestKind::Os,
+        IngestKind::HardwareV1,
+        IngestKind::Hardware,
+        IngestKind::Software,
+        IngestKind::Profiles,
+        IngestKind::Volumes,
+        IngestKind::Licenses,
+        IngestKind::Battery,
+    ];
+
+    /// Matches [`Section::as_str`] for the kinds a snapshot can contain.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IngestKind::Os => "os",
+            IngestKind::HardwareV1 => "hardware_v1",
+            IngestKind::Hardware => "hardware",
+            IngestKind::Software => "software",
+            IngestKind::Profiles => "profiles",
+            IngestKind::Volumes => "volumes",
+            IngestKind::Licenses => "licenses",
+            IngestKind::Battery => "battery",
+        }
+    }
+}
+
+impl From<Section> for IngestKind {
+    fn from(section: Section) -> IngestKind {
+        match section {
+            Section::Os => IngestKind::Os,
+            Section::Hardware => IngestKind::Hardware,
+            Section::Software => IngestKind::Software,
+            Section::Profiles => IngestKind::Profiles,
+            Section::Volumes => IngestKind::Volumes,
+            Section::Licenses => IngestKind::Licenses,
+            Section::Battery => IngestKind::Battery,
+        }
+    }
+}
+
+impl FromStr for IngestKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        IngestKind::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| anyhow!("Unknown ingest kind: '{s}'"))
+    }
+}
+
+impl fmt::Display for IngestKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Queues `payload` to be applied for the client `client_id`. With a `hash`, it is
+/// remembered for the section together with the applied payload. The client counts
+/// as seen now.
+pub fn enqueue<T: Serialize>(
+    database: &Database,
+    client_id: i32,
+    kind: IngestKind,
+    payload: &T,
+    hash: Option<&str>,
+) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    database
+        .ingest_manager()
+        .enqueue(client_id, kind.as_str(), &serde_json::to_string(payload)?, hash, now)?;
+    database.set_client_last_seen(client_id, now)?;
+    Ok(())
+}
+
+/// Applies `payload` and stores `hash` in the same transaction.
+fn apply(
+    database: &Database,
+    client_id: i32,
+    kind: IngestKind,
+    payload: &str,
+    hash: Option<&SectionHash>,
+) -> Result<()> {
+    match kind {
+        IngestKind::Os => database
+            .update_os_info(client_id, serde_json::from_str(payload)?, hash)
+            .map(|_| ()),
+        IngestKind::HardwareV1 => database.create_hardware_info(client_id, serde_json::from_str(payload)?),
+        IngestKind::Hardware => database.create_hardware_info_v2(client_id, serde_json::from_str(payload)?, hash),
+        IngestKind::Software => database.update_software_lib(client_id, serde_json::from_str(payload)?, hash),
+        IngestKind::Profiles => database
+            .user_manager()
+            .update_profiles(client_id, serde_json::from_str(payload)?, hash),
+        IngestKind::Volumes => database.update_status_volumes(client_id, serde_json::from_str(payload)?, hash),
+        IngestKind::Licenses => database.update_license_keys(client_id, serde_json::from_str(payload)?, hash),
+        IngestKind::Battery => database.update_battery_status(client_id, serde_json::from_str(payload)?, hash),
+    }
+}
+
+/// Hands out jobs to the workers.
+#[derive(Clone, Default)]
+pub struct IngestQueue;
+
+impl IngestQueue {
+    pub fn new() -> IngestQueue {
+        IngestQueue
+    }
+
+    /// Applies the next job, if there is one available. A job claimed more than
+    /// [`MAX_ATTEMPTS`] times was abandoned by its workers and is set aside. Where
+    /// panics unwind, a panic while applying a job sets it aside right away.
+    pub fn process_next(&self, database: &Database) -> Result<bool> {
+        let now = Utc::now().naive_utc();
+        let Some(job_id) = database.ingest_manager().claim(now, now + CLAIM_TIMEOUT)? else {
+            return Ok(false);
+        };
+        let job = database.ingest_manager().get_job(job_id)?;
+        if job.attempts > MAX_ATTEMPTS {
+            println!("[ERROR] In ingest job {job_id} ({}) abandoned {MAX_ATTEMPTS} times, giving up", job.kind);
+            database.ingest_manager().record_failure(
+                job_id,
+                MAX_ATTEMPTS,
+                "The server stopped while applying the job.",
+                now,
+                true,
+            )?;
+            return Ok(true);
+        }
+        let attempts = job.attempts;
+        match panic::catch_unwind(AssertUnwindSafe(|| process_job(database, job))) {
+            Ok(result) => result?,
+            Err(panic) => {
+                let message = panic_message(panic.as_ref());
+                println!("[ERROR] In ingest job {job_id} panicked, giving up {message}");
+                database.ingest_manager().record_failure(
+                    job_id,
+                    attempts,
+                    &format!("panicked: {message}"),
+                    Utc::now().naive_utc(),
+                    true,
+                )?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Applies jobs until none is available anymore. Returns how many were processed.
+    pub fn process_pending(&self, database: &Database) -> Result<usize> {
+        let mut processed = 0;
+        while self.process_next(database)? {
+            processed += 1;
+        }
+        Ok(processed)
+    }
+
+    fn work(&self, database: &Database) {
+        loop {
+            match self.process_next(database) {
+                Ok(true) => {}
+                Ok(false) => thread::sleep(IDLE_DELAY),
+                Err(e) => {
+                    println!("[ERROR] In ingest worker {e:?}");
+                    thread::sleep(IDLE_DELAY);
+                }
+            }
+        }
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Applies the claimed `job`, its attempt is counted already.
+fn process_job(database: &Database, job: IngestJob) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let hash = job.hash.as_ref().map(|hash| SectionHash {
+        client_id: job.client_id,
+        section: job.kind.clone(),
+        hash: hash.clone(),
+        accepted: now,
+        last_seen: now,
+    });
+    let result = job
+        .kind
+        .parse()
+        .and_then(|kind| apply(database, job.client_id, kind, &job.payload, hash.as_ref()));
+    match result {
+        Ok(()) => {
+            database.ingest_manager().complete_job(job.id, job.client_id, &job.kind)?;
+            database.statistics_manager().mark_changed(now)?;
+        }
+        Err(e) => {
+            let attempts = job.attempts;
+            let give_up = attempts >= MAX_ATTEMPTS;
+            println!(
+                "[ERROR] In ingest job {} ({}) attempt {attempts}{} {e:?}",
+                job.id,
+                job.kind,
+                if give_up { ", giving up" } else { "" }
+            );
+            database.ingest_manager().record_failure(
+                job.id,
+                attempts,
+                &format!("{e:#}"),
+                now + RETRY_DELAY * attempts,
+                give_up,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Starts the ingest workers once the server is up.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Ingest workers", |rocket| {
+        Box::pin(async move {
+            let workers: usize = rocket
+                .figment()
+                .extract_inner("ingest_workers")
+                .unwrap_or(DEFAULT_WORKERS);
+            let (Some(database), Some(queue)) = (rocket.state::<Database>(), rocket.state::<IngestQueue>()) else {
+                return;
+            };
+            for n in 0..workers {
+                let database = database.clone();
+                let queue = queue.clone();
+                if let Err(e) = thread::Builder::new()
+                    .name(format!("ingest-{n}"))
+                    .spawn(move || queue.work(&database))
+                {
+                    println!("[ERROR] In ingest fairing {e:?}");
+                }
+            }
+        })
+    })
+}
@@ -0,0 +1,75 @@
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate rocket;
+
+use rocket::{fs::FileServer, response::Redirect, Build, Rocket};
+use rocket_dyn_templates::{context, Template};
+use rocket_okapi::{
+    mount_endpoints_and_merged_docs,
+    settings::OpenApiSettings,
+    swagger_ui::{make_swagger_ui, SwaggerUIConfig},
+};
+
+use crate::{auth::User, database::Database};
+
+pub mod auth;
+pub mod database;
+pub mod ingest;
+pub mod statistics;
+pub mod web;
+
+#[get("/")]
+fn index(user: User) -> Template {
+    Template::render("index", context! { user })
+}
+
+#[get("/", rank = 10)]
+fn non_user_index() -> Redirect {
+    Redirect::to(uri!("/auth/login"))
+}
+
+pub fn rocket(database: Database) -> Rocket<Build> {
+    let mut rocket = rocket::build()
+        .manage(database)
+        .manage(ingest::IngestQueue::new())
+        .attach(Template::fairing())
+        .attach(auth::policy::fairing())
+        .attach(ingest::fairing())
+        .attach(statistics::fairing())
+        .mount("/", routes![index, non_user_index])
+        .register("/api/v1", web::agent_error::catchers())
+        .register("/api/v2", web::agent_error::catchers())
+        .register("/api/v3", web::agent_error::catchers())
+        .register("/api/query/v1", web::api_query::catchers())
+        .mount(
+            "/api/docs",
+            make_swagger_ui(&SwaggerUIConfig {
+                url: "/api/openapi.json".to_owned(),
+                ..Default::default()
+            }),
+        );
+    // The JSON APIs are documented from their routes and the sit_lib types,
+    // served as /api/openapi.json.
+    let openapi_settings = OpenApiSettings::default();
+    mount_endpoints_and_merged_docs! {
+        rocket, "/api".to_owned(), openapi_settings,
+        "/v1" => web::api_v1::routes(&openapi_settings),
+        "/v2" => web::api_v2::routes(&openapi_settings),
+        "/v3" => web::api_v3::routes(&openapi_settings),
+        "/query/v1" => web::api_query::routes(&openapi_settings),
+    };
+    rocket
+        .mount("/assets/", web::assets::routes())
+        .mount("/auth", web::auth::routes())
+        .mount("/clients/", web::clients::routes())
+        .mount("/export", web::export::routes())
+        .mount("/hardware/", web::hardware::routes())
+        .mount("/locations/", web::locations::routes())
+        .mount("/profile/", web::profile::routes())
+        .mount("/search", web::search::routes())
+        .mount("/settings/", web::settings::routes())
+        .mount("/software/", web::software::routes())
+        .mount("/static", FileServer::from("static"))
+        .mount("/system-status", web::system_status::routes())
+}
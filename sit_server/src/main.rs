@@ -1,45 +1,11 @@
-#[macro_use]
-extern crate diesel;
-#[macro_use]
-extern crate rocket;
-
-use rocket::{fs::FileServer, response::Redirect};
-use rocket_dyn_templates::{context, Template};
-
-use crate::{auth::User, database::Database};
-
-mod auth;
-mod database;
-mod web;
-
-#[get("/")]
-fn index(user: User) -> Template {
-    Template::render("index", context! { user })
-}
-
-#[get("/", rank = 10)]
-fn non_user_index() -> Redirect {
-    Redirect::to(uri!("/auth/login"))
-}
+use sit_server::database::Database;
 
 #[rocket::main]
-async fn main() -> Result<(), rocket::Error> {
-    let _rocket = rocket::build()
-        .manage(Database::establish_connection())
-        .attach(Template::fairing())
-        .mount("/", routes![index, non_user_index])
-        .mount("/api/v1/", web::api_v1::routes())
-        .mount("/api/v2/", web::api_v2::routes())
-        .mount("/auth", web::auth::routes())
-        .mount("/clients/", web::clients::routes())
-        .mount("/hardware/", web::hardware::routes())
-        .mount("/profile/", web::profile::routes())
-        .mount("/settings/", web::settings::routes())
-        .mount("/software/", web::software::routes())
-        .mount("/static", FileServer::from("static"))
-        .mount("/system-status", web::system_status::routes())
+async fn main() -> Result<(), Box<rocket::Error>> {
+    let _rocket = sit_server::rocket(Database::establish_connection())
         .launch()
-        .await?;
+        .await
+        .map_err(Box::new)?;
 
     Ok(())
 }
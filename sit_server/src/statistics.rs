@@ -0,0 +1,81 @@
+//! Scheduled refresh of the fleet statistics.
+//!
+//! A background thread recomputes the statistics the overview pages read, see
+//! `database::statistics`. It refreshes once the inventory changed, but at most
+//! every [`MIN_AGE`] so a busy ingest does not keep it recounting, and in any case
+//! after the `statistics_interval` of the Rocket config (in seconds, 0 disables
+//! the thread).
+
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use rocket::fairing::AdHoc;
+
+use crate::database::Database;
+
+const DEFAULT_INTERVAL: u64 = 60 * 60;
+/// Statistics younger than this are not refreshed for changes.
+const MIN_AGE: TimeDelta = TimeDelta::seconds(60);
+const CHECK_DELAY: Duration = Duration::from_secs(10);
+
+/// Whether statistics last refreshed at `refreshed` are due at `now`.
+fn is_due(
+    refreshed: Option<NaiveDateTime>,
+    changed: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+    interval: TimeDelta,
+) -> bool {
+    let Some(refreshed) = refreshed else {
+        return true;
+    };
+    let age = now - refreshed;
+    age >= interval || (changed.is_some_and(|c| c >= refreshed) && age >= MIN_AGE)
+}
+
+/// Refreshes the statistics if they are due. Returns whether they were refreshed.
+pub fn refresh_if_due(database: &Database, interval: TimeDelta) -> Result<bool> {
+    let state = database.statistics_manager().get_state()?;
+    let now = Utc::now().naive_utc();
+    if !is_due(state.refreshed, state.changed, now, interval) {
+        return Ok(false);
+    }
+    database.refresh_statistics(now)?;
+    Ok(true)
+}
+
+fn work(database: &Database, interval: TimeDelta) {
+    loop {
+        if let Err(e) = refresh_if_due(database, interval) {
+            println!("[ERROR] In statistics refresh {e:?}");
+        }
+        thread::sleep(CHECK_DELAY);
+    }
+}
+
+/// Starts the statistics refresh once the server is up.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Statistics refresh", |rocket| {
+        Box::pin(async move {
+            let interval: u64 = rocket
+                .figment()
+                .extract_inner("statistics_interval")
+                .unwrap_or(DEFAULT_INTERVAL);
+            let Some(database) = rocket.state::<Database>() else {
+                return;
+            };
+            if interval == 0 {
+                return;
+            }
+            let database = database.clone();
+            let interval = TimeDelta::seconds(interval as i64);
+            if let Err(e) = thread::Builder::new()
+                .name("statistics".to_owned())
+                .spawn(move || work(&database, interval))
+            {
+                println!("[ERROR] In statistics fairing {e:?}");
+            }
+        })
+    })
+}
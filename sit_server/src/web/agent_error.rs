@@ -0,0 +1,267 @@
+//! Error responses of the agent API, see [`sit_lib::error`].
+
+use std::io::Read;
+
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use rocket::data::{self, ByteUnit, Data, FromData};
+use rocket::http::Status;
+use rocket::response::{self, status, Responder};
+use rocket::serde::json::Json;
+use rocket::{Catcher, Request};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{MediaType, RefOr, RequestBody, Response, Responses};
+use rocket_okapi::okapi::map;
+use rocket_okapi::request::OpenApiFromData;
+use rocket_okapi::response::OpenApiResponderInner;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use sit_lib::error::{ApiError, ErrorKind, FieldError};
+use sit_lib::hardware::{BatteryStatus, HardwareInfo, HardwareInfoV2};
+use sit_lib::licenses::LicenseBundle;
+use sit_lib::os::{UserProfiles, WinOsInfo};
+use sit_lib::server::Register;
+use sit_lib::snapshot::{Snapshot, SnapshotCheck};
+use sit_lib::software::SoftwareLibrary;
+use sit_lib::system_status::VolumeList;
+use sit_lib::task::TaskUpdate;
+use uuid::Uuid;
+
+use crate::database::Database;
+
+/// An [`ApiError`] sent with the status code of its kind.
+#[derive(Debug)]
+pub struct AgentError(pub ApiError);
+
+impl AgentError {
+    pub fn unknown_client(uuid: Uuid) -> AgentError {
+        AgentError(ApiError::new(
+            ErrorKind::UnknownClient,
+            format!("Client {uuid} is not registered"),
+        ))
+    }
+
+    pub fn not_found(message: impl Into<String>) -> AgentError {
+        AgentError(ApiError::new(ErrorKind::NotFound, message))
+    }
+
+    pub fn conflict(message: impl Into<String>) -> AgentError {
+        AgentError(ApiError::new(ErrorKind::Conflict, message))
+    }
+
+    /// Logs `error` under `context`, the agent only learns that the server failed.
+    pub fn internal(context: &str, error: anyhow::Error) -> AgentError {
+        println!("[ERROR] In {context} {error:?}");
+        AgentError(ApiError::new(ErrorKind::Internal, "Internal server error"))
+    }
+}
+
+impl<'r> Responder<'r, 'static> for AgentError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let status = Status::from_code(self.0.kind.status_code()).unwrap_or(Status::InternalServerError);
+        status::Custom(status, Json(self.0)).respond_to(request)
+    }
+}
+
+impl OpenApiResponderInner for AgentError {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let schema = gen.json_schema::<ApiError>();
+        let mut responses = Responses::default();
+        for (code, description) in [
+            ("400", "The body is not valid JSON."),
+            ("404", "The client is not registered (`unknown_client`) or something else does not exist."),
+            ("409", "The request conflicts with the state on the server."),
+            ("413", "The body is too large, after decompression."),
+            ("415", "The `Content-Encoding` of the body is not supported."),
+            ("422", "The body does not match the expected structure, see `fields`."),
+            ("500", "The server failed."),
+        ] {
+            responses.responses.insert(
+                code.to_owned(),
+                RefOr::Object(Response {
+                    description: description.to_owned(),
+                    content: map! {
+                        "application/json".to_owned() => MediaType {
+                            schema: Some(schema.clone()),
+                            ..Default::default()
+                        }
+                    },
+                    ..Default::default()
+                }),
+            );
+        }
+        Ok(responses)
+    }
+}
+
+/// The id of the client `uuid`, `unknown_client` if it is not registered.
+pub fn client_id(database: &Database, uuid: Uuid, context: &str) -> Result<i32, AgentError> {
+    match database.get_client(&uuid) {
+        Ok(client) => Ok(client.id),
+        Err(e) => match e.downcast_ref::<diesel::result::Error>() {
+            Some(diesel::result::Error::NotFound) => Err(AgentError::unknown_client(uuid)),
+            _ => Err(AgentError::internal(&format!("{context} get_client"), e)),
+        },
+    }
+}
+
+/// Size limit of an agent payload after decompression.
+///
+/// The default can be changed per payload in the Rocket config, like
+/// `limits."agent/software" = "16 MiB"`, or for all of them with `limits.agent`.
+pub trait PayloadLimit {
+    const NAME: &'static str;
+    const DEFAULT: ByteUnit;
+}
+
+macro_rules! payload_limits {
+    ($($ty:ty => $name:literal, $default:expr;)*) => {
+        $(
+            impl PayloadLimit for $ty {
+                const NAME: &'static str = $name;
+                const DEFAULT: ByteUnit = $default;
+            }
+        )*
+    };
+}
+
+payload_limits! {
+    Register => "agent/register", ByteUnit::Kibibyte(4);
+    WinOsInfo => "agent/os", ByteUnit::Kibibyte(64);
+    HardwareInfo => "agent/hardware", ByteUnit::Mebibyte(1);
+    HardwareInfoV2 => "agent/hardware", ByteUnit::Mebibyte(1);
+    SoftwareLibrary => "agent/software", ByteUnit::Mebibyte(8);
+    UserProfiles => "agent/profiles", ByteUnit::Mebibyte(16);
+    VolumeList => "agent/volumes", ByteUnit::Kibibyte(256);
+    LicenseBundle => "agent/licenses", ByteUnit::Mebibyte(1);
+    BatteryStatus => "agent/battery", ByteUnit::Kibibyte(64);
+    TaskUpdate => "agent/tasks", ByteUnit::Mebibyte(1);
+    Snapshot => "agent/snapshot", ByteUnit::Mebibyte(32);
+    SnapshotCheck => "agent/snapshot", ByteUnit::Kibibyte(16);
+}
+
+/// A JSON body, optionally compressed with `Content-Encoding: gzip` or `zstd`.
+/// Unlike [`Json`] it reports what is wrong with an invalid body: the error is
+/// kept in the request cache for the catcher.
+pub struct Payload<T>(pub T);
+
+impl<T> Payload<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+fn reject<'r, T>(request: &'r Request<'_>, error: ApiError) -> data::Outcome<'r, T, ()> {
+    let status = Status::from_code(error.kind.status_code()).unwrap_or(Status::BadRequest);
+    request.local_cache(|| Some(error));
+    data::Outcome::Error((status, ()))
+}
+
+fn too_large(limit: ByteUnit) -> ApiError {
+    ApiError::new(ErrorKind::PayloadTooLarge, format!("Body exceeds {limit}"))
+}
+
+/// Decompresses `reader`, but not beyond `limit`, to not be fooled by a tiny body
+/// expanding to gigabytes.
+fn decompress(reader: impl Read, limit: ByteUnit) -> Result<Vec<u8>, ApiError> {
+    let mut body = vec![];
+    reader
+        .take(limit.as_u64() + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| ApiError::new(ErrorKind::MalformedPayload, format!("Could not decompress body: {e}")))?;
+    if body.len() as u64 > limit.as_u64() {
+        return Err(too_large(limit));
+    }
+    Ok(body)
+}
+
+fn decode(encoding: Option<&str>, raw: Vec<u8>, limit: ByteUnit) -> Result<Vec<u8>, ApiError> {
+    match encoding.map(|e| e.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("identity") => Ok(raw),
+        Some("gzip") => decompress(GzDecoder::new(&raw[..]), limit),
+        Some("zstd") => {
+            let decoder = zstd::stream::read::Decoder::new(&raw[..])
+                .map_err(|e| ApiError::new(ErrorKind::MalformedPayload, format!("Could not decompress body: {e}")))?;
+            decompress(decoder, limit)
+        }
+        Some(other) => Err(ApiError::new(
+            ErrorKind::UnsupportedEncoding,
+            format!("Content-Encoding {other} is not supported, use gzip or zstd"),
+        )),
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned + PayloadLimit> FromData<'r> for Payload<T> {
+    type Error = ();
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = request.limits().get(T::NAME).unwrap_or(T::DEFAULT);
+        let raw = match data.open(limit).into_bytes().await {
+            Ok(raw) if raw.is_complete() => raw.into_inner(),
+            Ok(_) => return reject(request, too_large(limit)),
+            Err(e) => {
+                return reject(
+                    request,
+                    ApiError::new(ErrorKind::MalformedPayload, format!("Could not read body: {e}")),
+                )
+            }
+        };
+        let body = match decode(request.headers().get_one("Content-Encoding"), raw, limit) {
+            Ok(body) => body,
+            Err(error) => return reject(request, error),
+        };
+        let mut deserializer = serde_json::Deserializer::from_slice(&body);
+        match serde_path_to_error::deserialize(&mut deserializer) {
+            Ok(value) => data::Outcome::Success(Payload(value)),
+            Err(e) => {
+                let field = e.path().to_string();
+                let error = e.into_inner();
+                let (kind, message) = match error.classify() {
+                    serde_json::error::Category::Data => (ErrorKind::InvalidPayload, "Body does not match the expected structure"),
+                    _ => (ErrorKind::MalformedPayload, "Body is not valid JSON"),
+                };
+                reject(
+                    request,
+                    ApiError {
+                        kind,
+                        message: message.to_owned(),
+                        fields: vec![FieldError {
+                            field,
+                            message: error.to_string(),
+                        }],
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl<'r, T: DeserializeOwned + JsonSchema + PayloadLimit> OpenApiFromData<'r> for Payload<T> {
+    fn request_body(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<RequestBody> {
+        Json::<T>::request_body(gen)
+    }
+}
+
+/// Answers every failure without a handler response, like a rejected [`Payload`]
+/// or an unknown path, with an [`ApiError`] as well.
+#[catch(default)]
+fn default_catcher(status: Status, request: &Request) -> status::Custom<Json<ApiError>> {
+    if let Some(error) = request.local_cache(|| None::<ApiError>) {
+        return status::Custom(status, Json(error.clone()));
+    }
+    let kind = match status.code {
+        400 => ErrorKind::MalformedPayload,
+        404 => ErrorKind::NotFound,
+        409 => ErrorKind::Conflict,
+        413 => ErrorKind::PayloadTooLarge,
+        415 => ErrorKind::UnsupportedEncoding,
+        422 => ErrorKind::InvalidPayload,
+        _ => ErrorKind::Internal,
+    };
+    status::Custom(status, Json(ApiError::new(kind, status.reason().unwrap_or("Error"))))
+}
+
+pub fn catchers() -> Vec<Catcher> {
+    catchers![default_catcher]
+}
@@ -0,0 +1,394 @@
+//! Read-only JSON API for scripts and other tools, authenticated by personal
+//! access tokens (see [`crate::auth::token`]).
+//!
+//! Lists take the same `page`, `per_page`, `sort`, `desc`, `filter.<column>` and
+//! `group` parameters as the list pages of the web UI and answer with
+//! `{"items": [...], "total", "page", "pages"}`. Errors are answered with
+//! `{"error": "<reason>"}`.
+
+use anyhow::Result;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Catcher, Request, Route, State};
+use rocket_okapi::okapi::openapi3::OpenApi;
+use rocket_okapi::settings::OpenApiSettings;
+use rocket_okapi::{openapi, openapi_get_routes_spec};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::list::ListParams;
+use crate::auth::token::{ApiToken, Scope};
+use crate::auth::Permission;
+use crate::database::{Database, Page};
+
+type ApiResult = Result<Json<Value>, Status>;
+
+/// Serializes `value` as response body.
+fn respond<T: Serialize>(value: T) -> ApiResult {
+    serde_json::to_value(value).map(Json).map_err(|e| {
+        println!("[ERROR] In api_query serializing response: {e}");
+        Status::InternalServerError
+    })
+}
+
+/// `NotFound` if `result` failed because a row is missing, `InternalServerError` otherwise.
+fn load<T>(result: Result<T>, name: &str) -> Result<T, Status> {
+    result.map_err(|e| match e.downcast_ref::<diesel::result::Error>() {
+        Some(diesel::result::Error::NotFound) => Status::NotFound,
+        _ => {
+            println!("[ERROR] In api_query {name}: {e}");
+            Status::InternalServerError
+        }
+    })
+}
+
+/// Client id of the client `uuid`, `Forbidden` if it is outside the groups of the token's user.
+fn client_id(database: &Database, token: &ApiToken, uuid: Uuid) -> Result<i32, Status> {
+    let client_id = load(database.get_client(&uuid), "get_client")?.id;
+    token.groups.require_client(database, client_id)?;
+    Ok(client_id)
+}
+
+/// Clients with their OS info. Columns: `name`, `domain`, `os`, `os_version`.
+#[openapi(tag = "Query")]
+#[get("/clients?<group>&<list..>")]
+async fn clients(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Clients)?;
+        let group = token.groups.scope(group);
+        let page = load(database.get_client_page(group, &list.query()), "clients")?;
+        respond(page.map(|(c, os_info)| {
+            json!({
+                "uuid": c.uuid,
+                "computer_name": os_info.as_ref().map(|o| o.computer_name.clone()),
+                "domain": os_info.as_ref().and_then(|o| o.domain.clone()),
+                "os": os_info.as_ref().and_then(|o| o.os.clone()),
+                "os_version": os_info.and_then(|o| o.os_version),
+            })
+        }))
+    })
+    .await
+}
+
+/// OS info, asset record and custom attributes of a client.
+#[openapi(tag = "Query")]
+#[get("/clients/<uuid>")]
+async fn client(database: &State<Database>, token: ApiToken, uuid: Uuid) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Clients)?;
+        let client_id = client_id(database, &token, uuid)?;
+        let os_info = database.get_client_os_info(&uuid).ok();
+        let attributes = load(
+            database.attribute_manager().get_client_attributes(client_id),
+            "client attributes",
+        )?;
+        let definitions = load(database.attribute_manager().get_definitions(), "client attributes")?;
+        let attributes: serde_json::Map<String, Value> = attributes
+            .into_iter()
+            .filter_map(|a| {
+                let name = definitions.iter().find(|d| d.id == a.attribute_id)?.name.clone();
+                Some((name, Value::String(a.value)))
+            })
+            .collect();
+        respond(json!({
+            "uuid": uuid,
+            "os_info": os_info,
+            "asset": database.asset_manager().get_asset(client_id).ok().flatten(),
+            "attributes": attributes,
+        }))
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/clients/<uuid>/hardware")]
+async fn client_hardware(database: &State<Database>, token: ApiToken, uuid: Uuid) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Hardware)?;
+        client_id(database, &token, uuid)?;
+        respond(json!({
+            "model": load(database.get_client_computer_model(uuid), "computer model")?.into_iter().next(),
+            "bios": load(database.get_client_bios(uuid), "bios")?.into_iter().next(),
+            "processors": load(database.get_client_processors(uuid), "processors")?,
+            "memory": load(database.get_client_memory(uuid), "memory")?.into_iter().next(),
+            "memory_sticks": load(database.get_client_memory_sticks(uuid), "memory sticks")?,
+            "disks": load(database.get_client_disks(uuid), "disks")?,
+            "graphics_cards": load(database.get_client_graphics_cards(uuid), "graphics cards")?,
+            "network_adapters": load(database.get_client_network_adapters(uuid), "network adapters")?,
+        }))
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/clients/<uuid>/software")]
+async fn client_software(database: &State<Database>, token: ApiToken, uuid: Uuid) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Software)?;
+        client_id(database, &token, uuid)?;
+        let software = load(database.get_client_software(uuid), "client software")?;
+        respond(
+            software
+                .into_iter()
+                .map(|(_, _, (version, info))| {
+                    json!({
+                        "software_id": info.id,
+                        "name": info.name,
+                        "publisher": info.publisher,
+                        "version": version.version,
+                    })
+                })
+                .collect::<Vec<Value>>(),
+        )
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/clients/<uuid>/profiles")]
+async fn client_profiles(database: &State<Database>, token: ApiToken, uuid: Uuid) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Profiles)?;
+        client_id(database, &token, uuid)?;
+        let profiles = load(database.get_client_profiles(&uuid), "client profiles")?;
+        respond(
+            profiles
+                .into_iter()
+                .map(|(profile, user)| json!({ "user": user, "profile": profile }))
+                .collect::<Vec<Value>>(),
+        )
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/clients/<uuid>/volumes")]
+async fn client_volumes(database: &State<Database>, token: ApiToken, uuid: Uuid) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Volumes)?;
+        client_id(database, &token, uuid)?;
+        respond(load(database.get_client_volume_status(uuid), "client volumes")?)
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/clients/<uuid>/licenses")]
+async fn client_licenses(database: &State<Database>, token: ApiToken, uuid: Uuid) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Licenses)?;
+        token.require_permission(Permission::ViewLicenseKeys)?;
+        client_id(database, &token, uuid)?;
+        respond(load(database.get_client_licenses(uuid), "client licenses")?)
+    })
+    .await
+}
+
+/// Tasks of a client, newest first.
+#[openapi(tag = "Query")]
+#[get("/clients/<uuid>/tasks")]
+async fn client_tasks(database: &State<Database>, token: ApiToken, uuid: Uuid) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Tasks)?;
+        let client_id = client_id(database, &token, uuid)?;
+        respond(load(database.task_manager().get_client_tasks(client_id), "client tasks")?)
+    })
+    .await
+}
+
+/// Operating systems with the number of clients.
+#[openapi(tag = "Query")]
+#[get("/os?<group>&<list..>")]
+async fn os_list(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Clients)?;
+        let group = token.groups.scope(group);
+        let os_list = load(database.statistics_manager().get_os_list(group), "os list")?;
+        respond(Page::paginate(os_list, &list.query()))
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/hardware/processors?<group>&<list..>")]
+async fn processors(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Hardware)?;
+        let group = token.groups.scope(group);
+        respond(load(database.get_processors_page(group, &list.query()), "processors")?)
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/hardware/memory?<group>&<list..>")]
+async fn memory(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Hardware)?;
+        let group = token.groups.scope(group);
+        respond(load(database.get_memory_page(group, &list.query()), "memory")?)
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/hardware/graphics_cards?<group>&<list..>")]
+async fn graphics_cards(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Hardware)?;
+        let group = token.groups.scope(group);
+        respond(load(database.get_graphics_cards_page(group, &list.query()), "graphics cards")?)
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/hardware/disks?<group>&<list..>")]
+async fn disks(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Hardware)?;
+        let group = token.groups.scope(group);
+        respond(load(database.get_disks_page(group, &list.query()), "disks")?)
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/hardware/models?<group>&<list..>")]
+async fn models(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Hardware)?;
+        let group = token.groups.scope(group);
+        respond(load(database.get_computer_models_page(group, &list.query()), "computer models")?)
+    })
+    .await
+}
+
+#[openapi(tag = "Query")]
+#[get("/hardware/network_adapters?<group>&<list..>")]
+async fn network_adapters(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Hardware)?;
+        let group = token.groups.scope(group);
+        respond(load(database.get_network_adapters_page(group, &list.query()), "network adapters")?)
+    })
+    .await
+}
+
+/// Installed software. Columns: `name`, `publisher`.
+#[openapi(tag = "Query")]
+#[get("/software?<group>&<list..>")]
+async fn software_list(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Software)?;
+        let group = token.groups.scope(group);
+        respond(load(database.get_software_page(group, &list.query()), "software")?)
+    })
+    .await
+}
+
+/// A software with its versions and the number of clients per version.
+#[openapi(tag = "Query")]
+#[get("/software/<id>?<group>")]
+async fn software(database: &State<Database>, token: ApiToken, id: i32, group: Option<i32>) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Software)?;
+        let group = token.groups.scope(group);
+        let info = load(database.get_software_info(id), "software info")?;
+        let versions = load(
+            database.statistics_manager().get_software_versions(id, group),
+            "software versions",
+        )?;
+        respond(json!({ "software": info, "versions": versions }))
+    })
+    .await
+}
+
+/// Users with the number of their profiles. Columns: `username`, `domain`, `count`.
+#[openapi(tag = "Query")]
+#[get("/profiles?<list..>")]
+async fn profiles(database: &State<Database>, token: ApiToken, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Profiles)?;
+        token.groups.require_unrestricted()?;
+        respond(load(database.get_profile_page(&list.query()), "profiles")?)
+    })
+    .await
+}
+
+/// Volumes with less than 10% or 5 GB free space.
+#[openapi(tag = "Query")]
+#[get("/volumes/critical?<group>&<list..>")]
+async fn critical_volumes(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Volumes)?;
+        let group = token.groups.scope(group);
+        let volumes = load(database.get_system_status_volume_crit(group), "critical volumes")?;
+        let volumes: Vec<Value> = volumes
+            .into_iter()
+            .map(|(volume, (c, os_info))| {
+                json!({
+                    "uuid": c.uuid,
+                    "computer_name": os_info.computer_name,
+                    "volume": volume,
+                })
+            })
+            .collect();
+        respond(Page::paginate(volumes, &list.query()))
+    })
+    .await
+}
+
+/// License names with the number of clients.
+#[openapi(tag = "Query")]
+#[get("/licenses?<group>&<list..>")]
+async fn licenses(database: &State<Database>, token: ApiToken, group: Option<i32>, list: ListParams) -> ApiResult {
+    database.run(move |database| {
+        token.require(Scope::Licenses)?;
+        let group = token.groups.scope(group);
+        let licenses = load(database.get_license_list(group), "licenses")?;
+        respond(Page::paginate(licenses, &list.query()))
+    })
+    .await
+}
+
+#[catch(default)]
+fn default_catcher(status: Status, _request: &Request) -> Json<Value> {
+    let reason = match status.code {
+        401 => "missing, invalid or expired access token",
+        403 => "access token lacks the required scope or permission",
+        _ => status.reason().unwrap_or("error"),
+    };
+    Json(json!({ "error": reason }))
+}
+
+pub fn routes(settings: &OpenApiSettings) -> (Vec<Route>, OpenApi) {
+    openapi_get_routes_spec![
+        settings:
+        clients,
+        client,
+        client_hardware,
+        client_software,
+        client_profiles,
+        client_volumes,
+        client_licenses,
+        client_tasks,
+        os_list,
+        processors,
+        memory,
+        graphics_cards,
+        disks,
+        models,
+        network_adapters,
+        software_list,
+        software,
+        profiles,
+        critical_volumes,
+        licenses,
+    ]
+}
+
+pub fn catchers() -> Vec<Catcher> {
+    catchers![default_catcher]
+}
@@ -6,6 +6,9 @@ use rocket::response::status;
 use rocket::serde::json::Json;
 use rocket::Route;
 use rocket::State;
+use rocket_okapi::okapi::openapi3::OpenApi;
+use rocket_okapi::settings::OpenApiSettings;
+use rocket_okapi::{openapi, openapi_get_routes_spec};
 use sit_lib::hardware::{BatteryStatus, HardwareInfo};
 use sit_lib::licenses::LicenseBundle;
 use sit_lib::os::UserProfiles;
@@ -18,291 +21,210 @@ use sit_lib::task::TaskBundle;
 use sit_lib::task::TaskUpdate;
 use uuid::Uuid;
 
+use super::agent_error::{client_id, AgentError, Payload};
 use crate::database::Database;
+use crate::ingest::{self, IngestKind};
 
+/// Registers a client. Without a `uuid` in the body a new one is assigned and returned.
+#[openapi(tag = "Agent")]
 #[post("/register", data = "<input>")]
 async fn register(
     database: &State<Database>,
-    input: Json<Register>,
-) -> status::Custom<Json<Register>> {
-    let uuid = input.uuid.unwrap_or_else(Uuid::new_v4);
-    let computer_name = input.name.clone();
-    match database.create_client(&uuid) {
-        Ok(client) => match database.create_os_info(&client, &computer_name) {
-            Ok(_) => status::Custom(
-                Status::Created,
-                Json(Register {
-                    name: computer_name,
-                    uuid: Some(uuid),
-                }),
-            ),
-            Err(error) => {
-                println!("[ERROR] In api_v1 /register create_os_info {:?}", error);
-                status::Custom(
-                    Status::InternalServerError,
-                    Json(Register {
-                        name: computer_name,
-                        uuid: None,
-                    }),
-                )
-            }
-        },
-        Err(error) => {
-            println!("[ERROR] In api_v1 /register create_client {:?}", error);
-            status::Custom(
-                Status::InternalServerError,
-                Json(Register {
-                    name: computer_name,
-                    uuid: None,
-                }),
-            )
-        }
-    }
+    input: Payload<Register>,
+) -> Result<status::Custom<Json<Register>>, AgentError> {
+    database.run(move |database| {
+        let input = input.into_inner();
+        let uuid = input.uuid.unwrap_or_else(Uuid::new_v4);
+        let client = database
+            .create_client(&uuid)
+            .map_err(|e| AgentError::internal("api_v1 /register create_client", e))?;
+        database
+            .create_os_info(&client, &input.name)
+            .map_err(|e| AgentError::internal("api_v1 /register create_os_info", e))?;
+        Ok(status::Custom(
+            Status::Created,
+            Json(Register {
+                name: input.name,
+                uuid: Some(uuid),
+            }),
+        ))
+    })
+    .await
 }
 
+/// Uploads the operating system info of a client.
+#[openapi(tag = "Agent")]
 #[post("/os/<uuid>", data = "<input>")]
-async fn os(database: &State<Database>, uuid: Uuid, input: Json<WinOsInfo>) -> status::Custom<()> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.update_os_info(client.id, input.0) {
-            Ok(_) => status::Custom(Status::Ok, ()),
-            Err(error) => {
-                println!("[ERROR] In api_v1 /os/{} update_os_info {:?}", uuid, error);
-                status::Custom(Status::InternalServerError, ())
-            }
-        },
-        Err(error) => {
-            println!("[ERROR] In api_v1 /os/{} get_client {:?}", uuid, error);
-            status::Custom(Status::InternalServerError, ())
-        }
-    }
+async fn os(
+    database: &State<Database>,
+    uuid: Uuid,
+    input: Payload<WinOsInfo>,
+) -> Result<status::Accepted<()>, AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v1 /os/{uuid}"))?;
+        ingest::enqueue(database, client_id, IngestKind::Os, &input.into_inner(), None)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /os/{uuid} enqueue"), e))?;
+        Ok(status::Accepted(()))
+    })
+    .await
 }
 
+/// Uploads the hardware of a client with a single graphics card. Superseded by `/api/v2/hardware`.
+#[openapi(tag = "Agent")]
 #[post("/hardware/<uuid>", data = "<input>")]
 async fn hardware(
     database: &State<Database>,
     uuid: Uuid,
-    input: Json<HardwareInfo>,
-) -> status::Custom<()> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.create_hardware_info(client.id, input.0) {
-            Ok(_) => status::Custom(Status::Ok, ()),
-            Err(error) => {
-                println!(
-                    "[ERROR] In api_v1 /hardware/{} create_hardware_info {:?}",
-                    uuid, error
-                );
-                status::Custom(Status::InternalServerError, ())
-            }
-        },
-        Err(error) => {
-            println!(
-                "[ERROR] In api_v1 /hardware/{} get_client {:?}",
-                uuid, error
-            );
-            status::Custom(Status::InternalServerError, ())
-        }
-    }
+    input: Payload<HardwareInfo>,
+) -> Result<status::Accepted<()>, AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v1 /hardware/{uuid}"))?;
+        ingest::enqueue(database, client_id, IngestKind::HardwareV1, &input.into_inner(), None)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /hardware/{uuid} enqueue"), e))?;
+        Ok(status::Accepted(()))
+    })
+    .await
 }
 
+/// Uploads the installed software of a client, replacing the previous list.
+#[openapi(tag = "Agent")]
 #[post("/software/<uuid>", data = "<input>")]
 async fn software(
     database: &State<Database>,
     uuid: Uuid,
-    input: Json<SoftwareLibrary>,
-) -> status::Custom<()> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.update_software_lib(client.id, input.0) {
-            Ok(_) => status::Custom(Status::Ok, ()),
-            Err(error) => {
-                println!(
-                    "[ERROR] In api_v1 /software/{} update_software_lib {:?}",
-                    uuid, error
-                );
-                status::Custom(Status::InternalServerError, ())
-            }
-        },
-        Err(error) => {
-            println!(
-                "[ERROR] In api_v1 /software/{} get_client {:?}",
-                uuid, error
-            );
-            status::Custom(Status::InternalServerError, ())
-        }
-    }
+    input: Payload<SoftwareLibrary>,
+) -> Result<status::Accepted<()>, AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v1 /software/{uuid}"))?;
+        ingest::enqueue(database, client_id, IngestKind::Software, &input.into_inner(), None)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /software/{uuid} enqueue"), e))?;
+        Ok(status::Accepted(()))
+    })
+    .await
 }
 
+/// Uploads the user profiles of a client.
+#[openapi(tag = "Agent")]
 #[post("/profiles/<uuid>", data = "<input>")]
 async fn profiles(
     database: &State<Database>,
     uuid: Uuid,
-    input: Json<UserProfiles>,
-) -> status::Custom<()> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.user_manager().update_profiles(client.id, input.0) {
-            Ok(_) => status::Custom(Status::Ok, ()),
-            Err(error) => {
-                println!(
-                    "[ERROR] In api_v1 /profiles/{} update_profiles {:?}",
-                    uuid, error
-                );
-                status::Custom(Status::InternalServerError, ())
-            }
-        },
-        Err(error) => {
-            println!(
-                "[ERROR] In api_v1 /profiles/{} get_client {:?}",
-                uuid, error
-            );
-            status::Custom(Status::InternalServerError, ())
-        }
-    }
+    input: Payload<UserProfiles>,
+) -> Result<status::Accepted<()>, AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v1 /profiles/{uuid}"))?;
+        ingest::enqueue(database, client_id, IngestKind::Profiles, &input.into_inner(), None)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /profiles/{uuid} enqueue"), e))?;
+        Ok(status::Accepted(()))
+    })
+    .await
 }
 
+/// Uploads the volumes of a client with their free space.
+#[openapi(tag = "Agent")]
 #[post("/status/<uuid>/volumes", data = "<input>")]
 async fn status_volumes(
     database: &State<Database>,
     uuid: Uuid,
-    input: Json<VolumeList>,
-) -> status::Custom<()> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.update_status_volumes(client.id, input.0) {
-            Ok(_) => status::Custom(Status::Ok, ()),
-            Err(error) => {
-                println!(
-                    "[ERROR] In api_v1 /status/{}/volumes update_status_volumes {:?}",
-                    uuid, error
-                );
-                status::Custom(Status::InternalServerError, ())
-            }
-        },
-        Err(error) => {
-            println!(
-                "[ERROR] In api_v1 /status/{}/volumes get_client {:?}",
-                uuid, error
-            );
-            status::Custom(Status::InternalServerError, ())
-        }
-    }
+    input: Payload<VolumeList>,
+) -> Result<status::Accepted<()>, AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v1 /status/{uuid}/volumes"))?;
+        ingest::enqueue(database, client_id, IngestKind::Volumes, &input.into_inner(), None)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /status/{uuid}/volumes enqueue"), e))?;
+        Ok(status::Accepted(()))
+    })
+    .await
 }
 
+/// Uploads the battery status of a client.
+#[openapi(tag = "Agent")]
 #[post("/status/<uuid>/battery", data = "<input>")]
 async fn status_battery(
     database: &State<Database>,
     uuid: Uuid,
-    input: Json<BatteryStatus>,
-) -> status::Custom<()> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.update_battery_status(client.id, input.0) {
-            Ok(_) => status::Custom(Status::Ok, ()),
-            Err(error) => {
-                println!(
-                    "[ERROR] In api_v1 /status/{}/battery update_battery_status {:?}",
-                    uuid, error
-                );
-                status::Custom(Status::InternalServerError, ())
-            }
-        },
-        Err(error) => {
-            println!(
-                "[ERROR] In api_v1 /status/{}/battery get_client {:?}",
-                uuid, error
-            );
-            status::Custom(Status::InternalServerError, ())
-        }
-    }
+    input: Payload<BatteryStatus>,
+) -> Result<status::Accepted<()>, AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v1 /status/{uuid}/battery"))?;
+        ingest::enqueue(database, client_id, IngestKind::Battery, &input.into_inner(), None)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /status/{uuid}/battery enqueue"), e))?;
+        Ok(status::Accepted(()))
+    })
+    .await
 }
 
+/// Uploads the license keys found on a client.
+#[openapi(tag = "Agent")]
 #[post("/licenses/<uuid>", data = "<input>")]
 async fn licenses(
     database: &State<Database>,
     uuid: Uuid,
-    input: Json<LicenseBundle>,
-) -> status::Custom<()> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.update_license_keys(client.id, input.0) {
-            Ok(_) => status::Custom(Status::Ok, ()),
-            Err(error) => {
-                println!(
-                    "[ERROR] In api_v1 /licenses/{} update_license_keys {:?}",
-                    uuid, error
-                );
-                status::Custom(Status::InternalServerError, ())
-            }
-        },
-        Err(error) => {
-            println!(
-                "[ERROR] In api_v1 /licenses/{} get_client {:?}",
-                uuid, error
-            );
-            status::Custom(Status::InternalServerError, ())
-        }
-    }
+    input: Payload<LicenseBundle>,
+) -> Result<status::Accepted<()>, AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v1 /licenses/{uuid}"))?;
+        ingest::enqueue(database, client_id, IngestKind::Licenses, &input.into_inner(), None)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /licenses/{uuid} enqueue"), e))?;
+        Ok(status::Accepted(()))
+    })
+    .await
 }
 
+/// Tasks for the client that it has not downloaded yet.
+#[openapi(tag = "Agent")]
 #[get("/tasks/<uuid>")]
-async fn tasks_get(database: &State<Database>, uuid: Uuid) -> status::Custom<Json<TaskBundle>> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.task_manager().get_new_tasks_for_client(client.id) {
-            Ok(task_list) => status::Custom(
-                Status::Ok,
-                Json(TaskBundle {
-                    tasks: task_list
-                        .into_iter()
-                        .map(|t| Task {
-                            id: t.id,
-                            task: t.task,
-                            time_start: t.time_start.map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc.offset_from_utc_datetime(&dt))),
-                        })
-                        .collect(),
-                }),
-            ),
-            Err(error) => {
-                println!(
-                    "[ERROR] In api_v1 /licenses/{} update_license_keys {:?}",
-                    uuid, error
-                );
-                status::Custom(
-                    Status::InternalServerError,
-                    Json(TaskBundle { tasks: vec![] }),
-                )
-            }
-        },
-        Err(error) => {
-            println!("[ERROR] In api_v1 /tasks/{} get_client {:?}", uuid, error);
-            status::Custom(
-                Status::InternalServerError,
-                Json(TaskBundle { tasks: vec![] }),
-            )
-        }
-    }
+async fn tasks_get(database: &State<Database>, uuid: Uuid) -> Result<Json<TaskBundle>, AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v1 /tasks/{uuid}"))?;
+        let task_list = database
+            .task_manager()
+            .get_new_tasks_for_client(client_id)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /tasks/{uuid} get_new_tasks_for_client"), e))?;
+        Ok(Json(TaskBundle {
+            tasks: task_list
+                .into_iter()
+                .map(|t| Task {
+                    id: t.id,
+                    task: t.task,
+                    time_start: t.time_start.map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc.offset_from_utc_datetime(&dt))),
+                })
+                .collect(),
+        }))
+    })
+    .await
 }
 
+/// Reports the progress or result of a task. Finished tasks can't be updated anymore.
+#[openapi(tag = "Agent")]
 #[post("/tasks/<uuid>", data = "<input>")]
-async fn task_update(
-    database: &State<Database>,
-    uuid: Uuid,
-    input: Json<TaskUpdate>,
-) -> status::Custom<()> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.task_manager().update_task_status(client.id, input.0) {
-            Ok(_) => status::Custom(Status::Ok, ()),
-            Err(error) => {
-                println!(
-                    "[ERROR] In api_v1 /tasks/{} update_task_status {:?}",
-                    uuid, error
-                );
-                status::Custom(Status::InternalServerError, ())
+async fn task_update(database: &State<Database>, uuid: Uuid, input: Payload<TaskUpdate>) -> Result<(), AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v1 /tasks/{uuid}"))?;
+        let input = input.into_inner();
+        let task = database
+            .task_manager()
+            .get_task(client_id, input.id)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /tasks/{uuid} get_task"), e))?;
+        match task {
+            None => return Err(AgentError::not_found(format!("Client has no task {}", input.id))),
+            Some(task) if task.is_finished() => {
+                return Err(AgentError::conflict(format!("Task {} is already finished", input.id)))
             }
-        },
-        Err(error) => {
-            println!("[ERROR] In api_v1 /tasks/{} get_client {:?}", uuid, error);
-            status::Custom(Status::InternalServerError, ())
+            Some(_) => {}
         }
-    }
+        database
+            .task_manager()
+            .update_task_status(client_id, input)
+            .map_err(|e| AgentError::internal(&format!("api_v1 /tasks/{uuid} update_task_status"), e))?;
+        Ok(())
+    })
+    .await
 }
 
-pub fn routes() -> Vec<Route> {
-    routes![
+pub fn routes(settings: &OpenApiSettings) -> (Vec<Route>, OpenApi) {
+    openapi_get_routes_spec![
+        settings:
         register,
         os,
         hardware,
@@ -1,40 +1,34 @@
-use rocket::http::Status;
 use rocket::response::status;
-use rocket::serde::json::Json;
 use rocket::{Route, State};
+use rocket_okapi::okapi::openapi3::OpenApi;
+use rocket_okapi::settings::OpenApiSettings;
+use rocket_okapi::{openapi, openapi_get_routes_spec};
 use uuid::Uuid;
 use sit_lib::hardware::HardwareInfoV2;
+use super::agent_error::{client_id, AgentError, Payload};
 use crate::database::Database;
+use crate::ingest::{self, IngestKind};
 
+/// Uploads the hardware of a client, with any number of graphics cards.
+#[openapi(tag = "Agent")]
 #[post("/hardware/<uuid>", data = "<input>")]
 async fn hardware(
     database: &State<Database>,
     uuid: Uuid,
-    input: Json<HardwareInfoV2>,
-) -> status::Custom<()> {
-    match database.get_client(&uuid) {
-        Ok(client) => match database.create_hardware_info_v2(client.id, input.0) {
-            Ok(_) => status::Custom(Status::Ok, ()),
-            Err(error) => {
-                println!(
-                    "[ERROR] In api_v2 /hardware/{} create_hardware_info {:?}",
-                    uuid, error
-                );
-                status::Custom(Status::InternalServerError, ())
-            }
-        },
-        Err(error) => {
-            println!(
-                "[ERROR] In api_v2 /hardware/{} get_client {:?}",
-                uuid, error
-            );
-            status::Custom(Status::InternalServerError, ())
-        }
-    }
+    input: Payload<HardwareInfoV2>,
+) -> Result<status::Accepted<()>, AgentError> {
+    database.run(move |database| {
+        let client_id = client_id(database, uuid, &format!("api_v2 /hardware/{uuid}"))?;
+        ingest::enqueue(database, client_id, IngestKind::Hardware, &input.into_inner(), None)
+            .map_err(|e| AgentError::internal(&format!("api_v2 /hardware/{uuid} enqueue"), e))?;
+        Ok(status::Accepted(()))
+    })
+    .await
 }
 
-pub fn routes() -> Vec<Route> {
-    routes![
+pub fn routes(settings: &OpenApiSettings) -> (Vec<Route>, OpenApi) {
+    openapi_get_routes_spec![
+        settings:
         hardware,
     ]
 }
@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{TimeDelta, Utc};
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use rocket_okapi::okapi::openapi3::OpenApi;
+use rocket_okapi::settings::OpenApiSettings;
+use rocket_okapi::{openapi, openapi_get_routes_spec};
+use serde::Serialize;
+use sit_lib::error::{ApiError, ErrorKind, FieldError};
+use sit_lib::snapshot::{
+    Section, SectionResult, SectionStatus, Snapshot, SnapshotCheck, SnapshotCheckResult, SnapshotResult,
+    SCHEMA_VERSION,
+};
+use uuid::Uuid;
+
+use super::agent_error::{client_id, AgentError, Payload};
+use crate::database::Database;
+use crate::ingest;
+
+/// Hashes accepted longer ago count as changed, so every section is uploaded in
+/// full once a day and data removed on the server comes back.
+const MAX_HASH_AGE: TimeDelta = TimeDelta::hours(24);
+
+fn check_schema_version(schema_version: u32) -> Result<(), AgentError> {
+    if schema_version == 0 || schema_version > SCHEMA_VERSION {
+        return Err(AgentError(ApiError {
+            kind: ErrorKind::InvalidPayload,
+            message: format!("Snapshot schema version {schema_version} is not supported"),
+            fields: vec![FieldError {
+                field: "schema_version".to_owned(),
+                message: format!("Supported up to {SCHEMA_VERSION}"),
+            }],
+        }));
+    }
+    Ok(())
+}
+
+/// The sections of `hashes` the server already has with that content. They are
+/// marked as seen, as the agent will not upload them.
+fn unchanged_sections(database: &Database, client_id: i32, hashes: &BTreeMap<Section, String>) -> Result<Vec<Section>> {
+    let now = Utc::now().naive_utc();
+    let stored = database.section_hash_manager().get_hashes(client_id)?;
+    let unchanged: Vec<Section> = hashes
+        .iter()
+        .filter(|(section, hash)| {
+            stored
+                .iter()
+                .any(|s| s.section == section.as_str() && &s.hash == *hash && now - s.accepted < MAX_HASH_AGE)
+        })
+        .map(|(section, _)| *section)
+        .collect();
+    let names: Vec<&str> = unchanged.iter().map(Section::as_str).collect();
+    database.section_hash_manager().set_last_seen(client_id, &names, now)?;
+    database.set_client_last_seen(client_id, now)?;
+    Ok(unchanged)
+}
+
+/// Queues the sections of one snapshot and collects their results.
+struct SectionQueue<'a> {
+    database: &'a Database,
+    client_id: i32,
+    context: &'a str,
+    hashes: BTreeMap<Section, String>,
+    unchanged: Vec<Section>,
+    results: Vec<SectionResult>,
+}
+
+impl SectionQueue<'_> {
+    fn push<T: Serialize>(&mut self, section: Section, payload: &T) {
+        let (status, message) = if self.unchanged.contains(&section) {
+            (SectionStatus::Unchanged, None)
+        } else {
+            let hash = self.hashes.get(&section).map(String::as_str);
+            match ingest::enqueue(self.database, self.client_id, section.into(), payload, hash) {
+                Ok(()) => (SectionStatus::Queued, None),
+                Err(e) => {
+                    println!("[ERROR] In {} {section:?} enqueue {e:?}", self.context);
+                    (SectionStatus::Failed, Some(e.to_string()))
+                }
+            }
+        };
+        self.results.push(SectionResult {
+            section,
+            status,
+            message,
+        });
+    }
+}
+
+/// Asks which sections changed since the server last accepted them, by their
+/// content hashes. Unchanged sections are marked as seen.
+#[openapi(tag = "Agent")]
+#[post("/snapshot/<uuid>/check", data = "<input>")]
+async fn snapshot_check(
+    database: &State<Database>,
+    uuid: Uuid,
+    input: Payload<SnapshotCheck>,
+) -> Result<Json<SnapshotCheckResult>, AgentError> {
+    database.run(move |database| {
+        let input = input.into_inner();
+        check_schema_version(input.schema_version)?;
+        let context = format!("api_v3 /snapshot/{uuid}/check");
+        let client_id = client_id(database, uuid, &context)?;
+        let unchanged = unchanged_sections(database, client_id, &input.hashes)
+            .map_err(|e| AgentError::internal(&format!("{context} unchanged_sections"), e))?;
+        Ok(Json(SnapshotCheckResult {
+            changed: input.hashes.into_keys().filter(|s| !unchanged.contains(s)).collect(),
+        }))
+    })
+    .await
+}
+
+/// Uploads any subset of the inventory of a client in one document.
+///
+/// Every section is queued on its own and applied in the background in its own
+/// transaction. A failing section is reported in the result and does not affect
+/// the others. Sections whose hash matches the last accepted one are skipped.
+#[openapi(tag = "Agent")]
+#[post("/snapshot/<uuid>", data = "<input>")]
+async fn snapshot(
+    database: &State<Database>,
+    uuid: Uuid,
+    input: Payload<Snapshot>,
+) -> Result<status::Accepted<Json<SnapshotResult>>, AgentError> {
+    database.run(move |database| {
+        let mut input = input.into_inner();
+        check_schema_version(input.schema_version)?;
+        let context = format!("api_v3 /snapshot/{uuid}");
+        let client_id = client_id(database, uuid, &context)?;
+
+        let hashes = std::mem::take(&mut input.hashes);
+        let unchanged = unchanged_sections(database, client_id, &hashes).unwrap_or_else(|e| {
+            println!("[ERROR] In {context} unchanged_sections {e:?}");
+            vec![]
+        });
+        let mut queue = SectionQueue {
+            database,
+            client_id,
+            context: &context,
+            hashes,
+            unchanged,
+            results: vec![],
+        };
+        if let Some(os) = &input.os {
+            queue.push(Section::Os, os);
+        }
+        if let Some(hardware) = &input.hardware {
+            queue.push(Section::Hardware, hardware);
+        }
+        if let Some(software) = &input.software {
+            queue.push(Section::Software, software);
+        }
+        if let Some(profiles) = &input.profiles {
+            queue.push(Section::Profiles, profiles);
+        }
+        if let Some(volumes) = &input.volumes {
+            queue.push(Section::Volumes, volumes);
+        }
+        if let Some(licenses) = &input.licenses {
+            queue.push(Section::Licenses, licenses);
+        }
+        if let Some(battery) = &input.battery {
+            queue.push(Section::Battery, battery);
+        }
+        Ok(status::Accepted(Json(SnapshotResult {
+            schema_version: SCHEMA_VERSION,
+            sections: queue.results,
+        })))
+    })
+    .await
+}
+
+pub fn routes(settings: &OpenApiSettings) -> (Vec<Route>, OpenApi) {
+    openapi_get_routes_spec![
+        settings:
+        snapshot_check,
+        snapshot,
+    ]
+}
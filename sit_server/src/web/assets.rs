@@ -0,0 +1,129 @@
+use bigdecimal::RoundingMode;
+use chrono::{Months, NaiveDate, Utc};
+use rocket::fs::TempFile;
+use rocket::tokio::io::AsyncReadExt;
+use rocket::{form::Form, http::Status, response::Redirect, Route, State};
+use rocket_dyn_templates::{context, Template};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    auth::{Permission, User},
+    database::{AssetImport, ClientAsset, Database},
+};
+
+use super::group_filter::GroupFilter;
+
+#[derive(Clone, Debug, Serialize)]
+struct AssetRow {
+    pub uuid: Uuid,
+    pub computer_name: String,
+    pub domain: String,
+    pub model: String,
+    pub serial_number: String,
+    pub inventory_number: String,
+    pub purchase_date: String,
+    pub supplier: String,
+    pub price: String,
+    pub warranty_end: String,
+    pub responsible: String,
+    pub expired: bool,
+}
+
+/// The uploaded CSV, limited by Rocket's `file` limit (1 MiB unless raised with
+/// `limits.file` in `Rocket.toml`).
+#[derive(FromForm)]
+struct ImportFile<'r> {
+    file: TempFile<'r>,
+}
+
+fn asset_rows(assets: Vec<ClientAsset>, today: NaiveDate) -> Vec<AssetRow> {
+    assets
+        .into_iter()
+        .map(|(asset, (client, os_info, model))| AssetRow {
+            uuid: client.uuid,
+            computer_name: os_info.as_ref().map(|o| o.computer_name.clone()).unwrap_or_default(),
+            domain: os_info.and_then(|o| o.domain).unwrap_or_default(),
+            model: model
+                .as_ref()
+                .map(|m| format!("{} {}", m.manufacturer, m.model_family))
+                .unwrap_or_default(),
+            serial_number: model.map(|m| m.serial_number).unwrap_or_default(),
+            inventory_number: asset.inventory_number.unwrap_or_default(),
+            purchase_date: asset.purchase_date.map(|d| d.to_string()).unwrap_or_default(),
+            supplier: asset.supplier.unwrap_or_default(),
+            price: asset
+                .price
+                .map(|p| format!("{} €", p.with_scale_round(2, RoundingMode::HalfUp)).replacen('.', ",", 1))
+                .unwrap_or_default(),
+            warranty_end: asset.warranty_end.map(|d| d.to_string()).unwrap_or_default(),
+            responsible: asset.responsible.unwrap_or_default(),
+            expired: asset.warranty_end.is_some_and(|d| d < today),
+        })
+        .collect()
+}
+
+#[get("/?<group>")]
+async fn index(database: &State<Database>, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let assets = database.asset_manager().get_assets(group).unwrap_or_default();
+        let assets = asset_rows(assets, Utc::now().date_naive());
+        Template::render("assets/index", context! { assets, group_filter, user })
+    })
+    .await
+}
+
+#[get("/warranty?<months>&<group>")]
+async fn warranty(database: &State<Database>, months: Option<u32>, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let months = months.unwrap_or(3);
+        let today = Utc::now().date_naive();
+        let until = today.checked_add_months(Months::new(months)).unwrap_or(NaiveDate::MAX);
+        let assets = database
+            .asset_manager()
+            .get_expiring_warranties(until, group)
+            .unwrap_or_default();
+        let assets = asset_rows(assets, today);
+        Template::render(
+            "assets/warranty",
+            context! { assets, months, until: until.to_string(), group, group_filter, user },
+        )
+    })
+    .await
+}
+
+#[post("/import", data = "<import>")]
+async fn import(database: &State<Database>, import: Form<ImportFile<'_>>, user: User) -> Result<Template, Status> {
+    user.require(Permission::EditInventory)?;
+    let mut csv = String::new();
+    let read = match import.file.open().await {
+        Ok(mut file) => file.read_to_string(&mut csv).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    Ok(database.run(move |database| {
+        let result: Result<AssetImport, String> = read.and_then(|_| {
+            database
+                .asset_manager()
+                .import_csv(&csv, |client_id| user.groups.require_client(database, client_id).is_ok())
+                .map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(result) => Template::render("assets/import", context! { result, user }),
+            Err(error) => Template::render("assets/import", context! { error, user }),
+        }
+    })
+    .await)
+}
+
+#[get("/<_..>", rank = 10)]
+fn catch_all() -> Redirect {
+    Redirect::to(uri!("/auth/login"))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![index, warranty, import, catch_all]
+}
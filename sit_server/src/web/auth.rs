@@ -1,3 +1,7 @@
+use std::net::IpAddr;
+
+use anyhow::bail;
+use chrono::{Duration, Utc};
 use rocket::{
     form::Form,
     http::CookieJar,
@@ -6,8 +10,17 @@ use rocket::{
     Route, State,
 };
 use rocket_dyn_templates::{context, Template};
+use serde::Serialize;
+
+use crate::{
+    auth::{
+        token::{self, Scope},
+        LoginError, LoginPolicy, PasswordPolicy, Role, User,
+    },
+    database::Database,
+};
 
-use crate::{auth::User, database::Database};
+use super::{notice::Notice, settings::validate_new_password};
 
 #[derive(FromForm)]
 struct Login<'r> {
@@ -15,9 +28,156 @@ struct Login<'r> {
     password: &'r str,
 }
 
+#[derive(FromForm)]
+struct Setup<'r> {
+    username: &'r str,
+    password: &'r str,
+    password_confirmation: &'r str,
+}
+
+#[derive(FromForm)]
+struct PasswordChange<'r> {
+    current_password: &'r str,
+    password: &'r str,
+    password_confirmation: &'r str,
+}
+
+#[derive(FromForm)]
+struct NewToken<'r> {
+    name: &'r str,
+    scopes: Vec<&'r str>,
+    /// Empty for a token without expiry.
+    expires_in_days: Option<i64>,
+}
+
+/// Scopes with their labels in the order of the form.
+const SCOPES: &[(Scope, &str)] = &[
+    (Scope::Clients, "Clients und Betriebssysteme"),
+    (Scope::Hardware, "Hardware"),
+    (Scope::Software, "Software"),
+    (Scope::Profiles, "Profile"),
+    (Scope::Volumes, "Laufwerke"),
+    (Scope::Licenses, "Lizenzen"),
+    (Scope::Tasks, "Aufgaben"),
+];
+
+#[derive(Clone, Debug, Serialize)]
+struct ScopeOption {
+    pub key: &'static str,
+    pub label: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct TokenRow {
+    pub id: i32,
+    pub name: String,
+    pub scopes: Vec<&'static str>,
+    pub created: String,
+    pub expires: Option<String>,
+    pub expired: bool,
+    pub last_used: Option<String>,
+}
+
+/// Context of the token page. `new_token` is shown once right after creating it.
+fn token_page(db: &Database, user: User, new_token: Option<String>, error: Option<String>) -> Template {
+    let now = Utc::now().naive_utc();
+    let show = |t: chrono::NaiveDateTime| t.format("%d.%m.%Y %H:%M").to_string();
+    let tokens: Vec<TokenRow> = db
+        .access_token_manager()
+        .get_tokens(user.user_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|t| TokenRow {
+            id: t.id,
+            scopes: token::parse_scopes(&t.scopes)
+                .into_iter()
+                .filter_map(|s| SCOPES.iter().find(|(k, _)| *k == s).map(|(_, label)| *label))
+                .collect(),
+            created: show(t.created),
+            expires: t.expires.map(show),
+            expired: t.expires.is_some_and(|e| e <= now),
+            last_used: t.last_used.map(show),
+            name: t.name,
+        })
+        .collect();
+    let scopes: Vec<ScopeOption> = SCOPES
+        .iter()
+        .map(|(k, label)| ScopeOption { key: k.as_str(), label })
+        .collect();
+    Template::render(
+        "auth/index",
+        context! { tokens, scopes, new_token, error, user },
+    )
+}
+
 #[get("/")]
-fn index(user: User) -> Template {
-    Template::render("auth/index", context! { user_id: user.user_id })
+async fn index(db: &State<Database>, user: User) -> Template {
+    db.run(move |db| token_page(db, user, None, None)).await
+}
+
+#[post("/tokens", data = "<new_token>")]
+async fn post_new_token(db: &State<Database>, new_token: Form<NewToken<'_>>, user: User) -> Template {
+    let scopes: Result<Vec<Scope>, _> = new_token.scopes.iter().map(|s| s.parse()).collect();
+    let expires = new_token
+        .expires_in_days
+        .filter(|days| *days > 0)
+        .map(|days| Utc::now().naive_utc() + Duration::days(days));
+    let name = new_token.name.to_owned();
+    db.run(move |db| {
+        let result = scopes.and_then(|scopes| token::create_token(db, user.user_id, &name, &scopes, expires));
+        match result {
+            Ok(created) => token_page(db, user, Some(created), None),
+            Err(e) => token_page(db, user, None, Some(e.to_string())),
+        }
+    })
+    .await
+}
+
+#[get("/tokens/<id>/revoke")]
+async fn revoke_token(db: &State<Database>, id: i32, user: User) -> Redirect {
+    let _ = db
+        .run(move |db| db.access_token_manager().revoke_token(user.user_id, id))
+        .await;
+    Redirect::to(uri!("/auth", index))
+}
+
+#[get("/password")]
+fn password(flash: Option<FlashMessage<'_>>, user: User) -> Template {
+    let notice = Notice::from_flash(flash);
+    Template::render("auth/password", context! { notice, user })
+}
+
+#[get("/password", rank = 2)]
+fn no_auth_password() -> Redirect {
+    Redirect::to(uri!("/auth", login_page))
+}
+
+#[post("/password", data = "<change>")]
+async fn post_password(
+    db: &State<Database>,
+    policy: &State<PasswordPolicy>,
+    change: Form<PasswordChange<'_>>,
+    user: User,
+) -> Flash<Redirect> {
+    let current_password = change.current_password.to_owned();
+    let new_password = change.password.to_owned();
+    let policy = policy.inner().clone();
+    let result = match validate_new_password(change.password, change.password_confirmation) {
+        Ok(()) => {
+            db.run(move |db| {
+                crate::auth::change_password(db, &policy, user.user_id, &current_password, &new_password)
+            })
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => Flash::success(Redirect::to(uri!("/auth", password)), "Das Passwort wurde geändert."),
+        Err(e) => Flash::error(
+            Redirect::to(uri!("/auth", password)),
+            format!("Das Passwort konnte nicht geändert werden: {e}"),
+        ),
+    }
 }
 
 #[get("/", rank = 2)]
@@ -31,24 +191,108 @@ fn login(_user: User) -> Redirect {
 }
 
 #[get("/login", rank = 2)]
-fn login_page(flash: Option<FlashMessage<'_>>) -> Template {
-    Template::render("auth/login", flash)
+async fn login_page(db: &State<Database>, flash: Option<FlashMessage<'_>>) -> Result<Template, Redirect> {
+    if !has_users(db).await {
+        return Err(Redirect::to(uri!("/auth", setup)));
+    }
+    let notice = Notice::from_flash(flash);
+    Ok(Template::render("auth/login", context! { notice }))
 }
 
-#[post("/login", data = "<login>")]
-fn post_login(db: &State<Database>, jar: &CookieJar<'_>, login: Form<Login<'_>>) -> Redirect {
-    let result = crate::auth::login(db, login.username, login.password, jar);
-    if result.is_err() {
-        Redirect::to(uri!("/auth", login_page))
-        // TODO: Add error cause "Invalid username/password."
-    } else {
-        Redirect::to(uri!("/"))
+/// Whether any user exists. Errors count as yes, so the setup stays closed.
+async fn has_users(db: &Database) -> bool {
+    db.run(|db| db.count_auth_users().map(|count| count > 0))
+        .await
+        .unwrap_or(true)
+}
+
+/// Creates the first administrator, unless a user exists by now.
+fn create_first_administrator(
+    db: &Database,
+    policy: &PasswordPolicy,
+    username: &str,
+    password: &str,
+    confirmation: &str,
+) -> anyhow::Result<()> {
+    if username.is_empty() {
+        bail!("Der Benutzername darf nicht leer sein.");
+    }
+    validate_new_password(password, confirmation)?;
+    if !crate::auth::create_first_user(db, policy, username, password, Role::Administrator)? {
+        bail!("Es gibt bereits einen Benutzer.");
+    }
+    Ok(())
+}
+
+/// The first-run setup, only available while no user exists.
+#[get("/setup")]
+async fn setup(db: &State<Database>, flash: Option<FlashMessage<'_>>) -> Result<Template, Redirect> {
+    if has_users(db).await {
+        return Err(Redirect::to(uri!("/auth", login_page)));
+    }
+    let notice = Notice::from_flash(flash);
+    Ok(Template::render("auth/setup", context! { notice }))
+}
+
+#[post("/setup", data = "<form>")]
+async fn post_setup(
+    db: &State<Database>,
+    login_policy: &State<LoginPolicy>,
+    password_policy: &State<PasswordPolicy>,
+    ip: Option<IpAddr>,
+    jar: &CookieJar<'_>,
+    form: Form<Setup<'_>>,
+) -> Result<Redirect, Flash<Redirect>> {
+    if has_users(db).await {
+        return Ok(Redirect::to(uri!("/auth", login_page)));
+    }
+    let username = form.username.trim().to_owned();
+    let password = form.password.to_owned();
+    let confirmation = form.password_confirmation.to_owned();
+    let policy = password_policy.inner().clone();
+    let result = db
+        .run(move |db| {
+            create_first_administrator(db, &policy, &username, &password, &confirmation).map(|_| (username, password))
+        })
+        .await;
+    match result {
+        Ok((username, password)) => {
+            let _ = crate::auth::login(db, login_policy, &username, &password, ip, jar).await;
+            Ok(Redirect::to(uri!("/")))
+        }
+        Err(e) => Err(Flash::error(
+            Redirect::to(uri!("/auth", setup)),
+            format!("Der Administrator konnte nicht angelegt werden: {e}"),
+        )),
     }
 }
 
+#[post("/login", data = "<login>")]
+async fn post_login(
+    db: &State<Database>,
+    policy: &State<LoginPolicy>,
+    ip: Option<IpAddr>,
+    jar: &CookieJar<'_>,
+    login: Form<Login<'_>>,
+) -> Result<Redirect, Flash<Redirect>> {
+    let message = match crate::auth::login(db, policy, login.username, login.password, ip, jar).await {
+        Ok(()) => return Ok(Redirect::to(uri!("/"))),
+        Err(LoginError::Invalid) => "Ungültiger Benutzername oder ungültiges Passwort.".to_string(),
+        Err(LoginError::Locked) => format!(
+            "Zu viele fehlgeschlagene Anmeldungen, die Anmeldung ist für {} Minuten gesperrt.",
+            policy.lockout_minutes
+        ),
+        Err(LoginError::Internal(e)) => {
+            println!("[ERROR] In post_login {e:?}");
+            "Die Anmeldung ist fehlgeschlagen.".to_string()
+        }
+    };
+    Err(Flash::error(Redirect::to(uri!("/auth", login_page)), message))
+}
+
 #[get("/logout")]
-fn logout(db: &State<Database>, jar: &CookieJar<'_>) -> Flash<Redirect> {
-    let _result = crate::auth::logout(db, jar);
+async fn logout(db: &State<Database>, jar: &CookieJar<'_>) -> Flash<Redirect> {
+    let _result = crate::auth::logout(db, jar).await;
     Flash::success(
         Redirect::to(uri!("/auth", login_page)),
         "Successfully logged out.",
@@ -56,5 +300,19 @@ fn logout(db: &State<Database>, jar: &CookieJar<'_>) -> Flash<Redirect> {
 }
 
 pub fn routes() -> Vec<Route> {
-    routes![index, no_auth_index, login, login_page, post_login, logout,]
+    routes![
+        index,
+        no_auth_index,
+        post_new_token,
+        revoke_token,
+        password,
+        no_auth_password,
+        post_password,
+        login,
+        login_page,
+        post_login,
+        setup,
+        post_setup,
+        logout,
+    ]
 }
@@ -1,11 +1,23 @@
-use rocket::{response::Redirect, Route, State};
+use std::collections::HashMap;
+
+use bigdecimal::RoundingMode;
+use rocket::{form::Form, http::Status, response::Redirect, Route, State};
 use rocket_dyn_templates::{context, Template};
 use serde::Serialize;
 use uuid::Uuid;
 
-use crate::{auth::User, database::Database};
+use crate::{
+    auth::{Permission, User},
+    database::{AssetInput, AttributeKind, Database, Page},
+};
 
-use super::{display_util, ms_magic};
+use super::{
+    display_util,
+    export::ExportLinks,
+    group_filter::GroupFilter,
+    list::{self, ListParams, ListView},
+    ms_magic,
+};
 
 #[derive(Clone, Debug, Serialize)]
 struct Profile {
@@ -62,245 +74,613 @@ struct UserProfilePaths {
     pub size: String,
 }
 
-#[get("/")]
-fn index(database: &State<Database>, user: User) -> Template {
-    let client_info = database.get_clients_with_os_info().unwrap_or_default();
-    Template::render("clients/index", context! { clients: client_info, user })
+#[derive(FromForm)]
+struct GroupAssignment {
+    group_id: i32,
 }
 
-#[get("/<uuid>")]
-fn client(database: &State<Database>, uuid: Uuid, user: User) -> Template {
-    let client = database.get_client(&uuid);
-    let os_info = database.get_client_os_info(&uuid);
-    if let (Ok(client), Ok(os_info)) = (client, os_info) {
-        Template::render("clients/client", context! { client, os_info, user })
-    } else {
-        Template::render("clients/client", context! {})
+#[derive(FromForm)]
+struct LocationAssignment {
+    room_id: Option<i32>,
+}
+
+#[derive(FromForm)]
+struct AssetForm {
+    inventory_number: String,
+    purchase_date: String,
+    supplier: String,
+    price: String,
+    warranty_end: String,
+    responsible: String,
+}
+
+#[derive(FromForm)]
+struct AttributeValues {
+    values: HashMap<i32, String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct ClientRow {
+    pub uuid: Uuid,
+    pub computer_name: String,
+    pub domain: String,
+    pub os: String,
+    pub os_version: String,
+    /// Values of the custom attributes, in the order of the column headers.
+    pub attributes: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct AttributeColumn {
+    pub id: i32,
+    pub name: String,
+    /// Column key for sorting and filtering.
+    pub key: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct AttributeField {
+    pub id: i32,
+    pub name: String,
+    pub kind: AttributeKind,
+    pub options: Vec<OptionEntry>,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct OptionEntry {
+    pub value: String,
+    pub selected: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct RoomOption {
+    pub id: i32,
+    pub name: String,
+    pub selected: bool,
+}
+
+/// `Forbidden` if the client `uuid` is outside the groups of `user`. Unknown
+/// clients are left to the page.
+async fn check_client(database: &Database, user: &User, uuid: Uuid) -> Result<(), Status> {
+    if !user.groups.is_restricted() {
+        return Ok(());
     }
+    let groups = user.groups.clone();
+    database
+        .run(move |database| match database.get_client(&uuid) {
+            Ok(c) => groups.require_client(database, c.id),
+            Err(_) => Ok(()),
+        })
+        .await
 }
 
-#[get("/<uuid>/profiles")]
-fn profiles(database: &State<Database>, uuid: Uuid, user: User) -> Template {
-    let client = database.get_client(&uuid);
-    let os_info = database.get_client_os_info(&uuid);
-    let client_profiles = database.get_client_profiles(&uuid);
-    if let (Ok(client), Ok(os_info), Ok(client_profiles)) = (client, os_info, client_profiles) {
-        let profiles: Vec<Profile> = client_profiles
+/// `Conflict` for smart groups, their members follow the rule and are not
+/// assigned by hand. Unknown groups are `NotFound`.
+fn check_manual_group(database: &Database, group_id: i32) -> Result<(), Status> {
+    let group = database
+        .group_manager()
+        .get_group(group_id)
+        .map_err(|_| Status::NotFound)?;
+    if group.rule.is_some() {
+        return Err(Status::Conflict);
+    }
+    Ok(())
+}
+
+/// The client list. Besides the OS columns it can be filtered and sorted by the
+/// custom attributes, with `attribute-<id>` as column key.
+#[get("/?<group>&<list..>")]
+async fn index(database: &State<Database>, group: Option<i32>, list: ListParams, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let export = ExportLinks::new("/clients", &list.query_string(group));
+        let inventory_export = ExportLinks::for_group("/inventory", group);
+        let page = database
+            .get_client_page(group, &list.query())
+            .unwrap_or_else(|_| Page::empty());
+        let definitions = database.attribute_manager().get_definitions().unwrap_or_default();
+        let client_ids: Vec<i32> = page.items.iter().map(|(c, _)| c.id).collect();
+        let values: HashMap<(i32, i32), String> = database
+            .attribute_manager()
+            .get_attributes_of_clients(&client_ids)
+            .unwrap_or_default()
             .into_iter()
-            .map(|(up, u)| Profile {
-                user_sid: u.sid,
-                user_name: u.username.unwrap_or("<_user>".to_owned()),
-                user_domain: u.domain.unwrap_or("<_domain>".to_owned()),
-                health_status: ms_magic::resolve_profile_health_status(up.health_status),
-                roaming_configured: up.roaming_configured,
-                roaming_path: up.roaming_path,
-                roaming_preference: up.roaming_preference,
-                last_use_time: up
-                    .last_use_time
-                    .map(display_util::format_date_time)
-                    .unwrap_or_default(),
-                last_download_time: up
-                    .last_download_time
-                    .map(display_util::format_date_time)
-                    .unwrap_or_default(),
-                last_upload_time: up
-                    .last_upload_time
-                    .map(display_util::format_date_time)
-                    .unwrap_or_default(),
-                status: ms_magic::resolve_profile_status(up.status),
-                size: display_util::format_option_big_decimal(
-                    &up.size,
-                    display_util::format_filesize_byte,
-                ),
+            .map(|a| ((a.client_id, a.attribute_id), a.value))
+            .collect();
+
+        let columns: Vec<AttributeColumn> = definitions
+            .iter()
+            .map(|d| AttributeColumn {
+                id: d.id,
+                name: d.name.clone(),
+                key: format!("attribute-{}", d.id),
             })
             .collect();
-        Template::render(
-            "clients/profiles",
-            context! { profiles, client, os_info, user },
-        )
-    } else {
-        Template::render("clients/profiles", context! {})
-    }
-}
+        let mut keys = list::columns(&["name", "os", "os_version"]);
+        keys.extend(columns.iter().map(|c| c.key.clone()));
+        let list = ListView::new(&list, &page, group, &keys);
 
-#[get("/<uuid>/profiles/<sid>")]
-fn profile_paths(database: &State<Database>, uuid: Uuid, sid: String, user: User) -> Template {
-    let client = database.get_client(&uuid);
-    let os_info = database.get_client_os_info(&uuid);
-    let computer_user = database.user_manager().get_user(&sid);
-    let profile_paths = database.user_manager().get_profile_paths(&uuid, &sid);
-    if let (Ok(client), Ok(os_info), Ok(computer_user), Ok(profile_paths)) =
-        (client, os_info, computer_user, profile_paths)
-    {
-        let paths: Vec<UserProfilePaths> = profile_paths
+        let clients: Vec<ClientRow> = page
+            .items
             .into_iter()
-            .map(|p| UserProfilePaths {
-                path: p.path,
-                size: display_util::format_big_decimal(&p.size, display_util::format_filesize_byte),
+            .map(|(c, os_info)| ClientRow {
+                attributes: definitions
+                    .iter()
+                    .map(|d| values.get(&(c.id, d.id)).cloned().unwrap_or_default())
+                    .collect(),
+                uuid: c.uuid,
+                computer_name: os_info.as_ref().map(|o| o.computer_name.clone()).unwrap_or_default(),
+                domain: os_info.as_ref().and_then(|o| o.domain.clone()).unwrap_or_default(),
+                os: os_info.as_ref().and_then(|o| o.os.clone()).unwrap_or_default(),
+                os_version: os_info.and_then(|o| o.os_version).unwrap_or_default(),
             })
             .collect();
         Template::render(
-            "clients/profiles_path",
-            context! { paths, computer_user, client, os_info, user },
+            "clients/index",
+            context! { clients, columns, list, export, inventory_export, group_filter, user },
         )
-    } else {
-        Template::render("clients/profiles_path", context! {})
-    }
+    })
+    .await
+}
+
+#[get("/<uuid>")]
+async fn client(database: &State<Database>, uuid: Uuid, user: User) -> Result<Template, Status> {
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        let client = database.get_client(&uuid);
+        let os_info = database.get_client_os_info(&uuid);
+        if let (Ok(client), Ok(os_info)) = (client, os_info) {
+            let groups = database
+                .group_manager()
+                .get_client_groups(client.id)
+                .unwrap_or_default();
+            // Members of smart groups follow the rules, they are not assigned by hand.
+            let all_groups: Vec<_> = database
+                .group_manager()
+                .get_groups()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|g| g.rule.is_none() && user.groups.allows_group(g.id))
+                .collect();
+            let locations = database.location_manager();
+            let location = locations.get_client_location(client.id).unwrap_or_default();
+            let manual_room = location.as_ref().filter(|(l, _)| l.manual).map(|(l, _)| l.room_id);
+            let sites = locations.get_sites().unwrap_or_default();
+            let buildings = locations.get_buildings().unwrap_or_default();
+            let mut rooms: Vec<RoomOption> = locations
+                .get_rooms()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|r| {
+                    let building = buildings.iter().find(|b| b.id == r.building_id)?;
+                    let site = sites.iter().find(|s| s.id == building.site_id)?;
+                    Some(RoomOption {
+                        id: r.id,
+                        name: format!("{} / {} / {}", site.name, building.name, r.name),
+                        selected: manual_room == Some(r.id),
+                    })
+                })
+                .collect();
+            rooms.sort_by(|a, b| a.name.cmp(&b.name));
+            let asset = database.asset_manager().get_asset(client.id).unwrap_or_default();
+            let mut attribute_values: HashMap<i32, String> = database
+                .attribute_manager()
+                .get_client_attributes(client.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| (a.attribute_id, a.value))
+                .collect();
+            let attributes: Vec<AttributeField> = database
+                .attribute_manager()
+                .get_definitions()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|d| {
+                    let value = attribute_values.remove(&d.id).unwrap_or_default();
+                    AttributeField {
+                        id: d.id,
+                        kind: d.kind(),
+                        options: d
+                            .option_list()
+                            .into_iter()
+                            .map(|o| OptionEntry {
+                                selected: o == value,
+                                value: o.to_string(),
+                            })
+                            .collect(),
+                        name: d.name,
+                        value,
+                    }
+                })
+                .collect();
+            let price = asset
+                .as_ref()
+                .and_then(|a| a.price.as_ref())
+                .map(|p| p.with_scale_round(2, RoundingMode::HalfUp).to_string().replacen('.', ",", 1));
+            Template::render(
+                "clients/client",
+                context! { client, os_info, groups, all_groups, location, rooms, asset, price, attributes, user },
+            )
+        } else {
+            Template::render("clients/client", context! {})
+        }
+    })
+    .await)
+}
+
+#[post("/<uuid>/groups", data = "<assignment>")]
+async fn add_group(
+    database: &State<Database>,
+    uuid: Uuid,
+    assignment: Form<GroupAssignment>,
+    user: User,
+) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    check_client(database, &user, uuid).await?;
+    user.groups.require_group(assignment.group_id)?;
+    database
+        .run(move |database| {
+            check_manual_group(database, assignment.group_id)?;
+            if let Ok(c) = database.get_client(&uuid) {
+                let _ = database
+                    .group_manager()
+                    .add_client(assignment.group_id, c.id);
+            }
+            Ok(Redirect::to(uri!("/clients", client(uuid))))
+        })
+        .await
+}
+
+#[post("/<uuid>/location", data = "<assignment>")]
+async fn set_location(
+    database: &State<Database>,
+    uuid: Uuid,
+    assignment: Form<LocationAssignment>,
+    user: User,
+) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        if let Ok(c) = database.get_client(&uuid) {
+            if let Err(e) = database
+                .location_manager()
+                .set_client_room(c.id, assignment.room_id)
+            {
+                println!("[ERROR] Could not set location of {uuid}: {e}");
+            }
+        }
+        Redirect::to(uri!("/clients", client(uuid)))
+    })
+    .await)
+}
+
+#[post("/<uuid>/asset", data = "<form>")]
+async fn set_asset(
+    database: &State<Database>,
+    uuid: Uuid,
+    form: Form<AssetForm>,
+    user: User,
+) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        if let Ok(c) = database.get_client(&uuid) {
+            let input = AssetInput {
+                inventory_number: Some(form.inventory_number.as_str()),
+                purchase_date: Some(form.purchase_date.as_str()),
+                supplier: Some(form.supplier.as_str()),
+                price: Some(form.price.as_str()),
+                warranty_end: Some(form.warranty_end.as_str()),
+                responsible: Some(form.responsible.as_str()),
+            };
+            if let Err(e) = database.asset_manager().update_asset(c.id, &input) {
+                println!("[ERROR] Could not save asset record of {uuid}: {e}");
+            }
+        }
+        Redirect::to(uri!("/clients", client(uuid)))
+    })
+    .await)
+}
+
+#[post("/<uuid>/attributes", data = "<form>")]
+async fn set_attributes(
+    database: &State<Database>,
+    uuid: Uuid,
+    form: Form<AttributeValues>,
+    user: User,
+) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        if let Ok(c) = database.get_client(&uuid) {
+            for (attribute_id, value) in &form.values {
+                if let Err(e) = database
+                    .attribute_manager()
+                    .set_client_attribute(c.id, *attribute_id, value)
+                {
+                    println!("[ERROR] Could not set attribute {attribute_id} of {uuid}: {e}");
+                }
+            }
+            // Smart groups may select on attributes.
+            if let Err(e) = database.group_manager().refresh_client(c.id) {
+                println!("[ERROR] Could not refresh groups of {uuid}: {e}");
+            }
+        }
+        Redirect::to(uri!("/clients", client(uuid)))
+    })
+    .await)
+}
+
+#[post("/<uuid>/groups/<group_id>/remove")]
+async fn remove_group(database: &State<Database>, uuid: Uuid, group_id: i32, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    check_client(database, &user, uuid).await?;
+    user.groups.require_group(group_id)?;
+    database
+        .run(move |database| {
+            check_manual_group(database, group_id)?;
+            if let Ok(c) = database.get_client(&uuid) {
+                let _ = database.group_manager().remove_client(group_id, c.id);
+            }
+            Ok(Redirect::to(uri!("/clients", client(uuid))))
+        })
+        .await
+}
+
+#[get("/<uuid>/profiles")]
+async fn profiles(database: &State<Database>, uuid: Uuid, user: User) -> Result<Template, Status> {
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        let client = database.get_client(&uuid);
+        let os_info = database.get_client_os_info(&uuid);
+        let client_profiles = database.get_client_profiles(&uuid);
+        if let (Ok(client), Ok(os_info), Ok(client_profiles)) = (client, os_info, client_profiles) {
+            let profiles: Vec<Profile> = client_profiles
+                .into_iter()
+                .map(|(up, u)| Profile {
+                    user_sid: u.sid,
+                    user_name: u.username.unwrap_or("<_user>".to_owned()),
+                    user_domain: u.domain.unwrap_or("<_domain>".to_owned()),
+                    health_status: ms_magic::resolve_profile_health_status(up.health_status),
+                    roaming_configured: up.roaming_configured,
+                    roaming_path: up.roaming_path,
+                    roaming_preference: up.roaming_preference,
+                    last_use_time: up
+                        .last_use_time
+                        .map(display_util::format_date_time)
+                        .unwrap_or_default(),
+                    last_download_time: up
+                        .last_download_time
+                        .map(display_util::format_date_time)
+                        .unwrap_or_default(),
+                    last_upload_time: up
+                        .last_upload_time
+                        .map(display_util::format_date_time)
+                        .unwrap_or_default(),
+                    status: ms_magic::resolve_profile_status(up.status),
+                    size: display_util::format_option_big_decimal(
+                        &up.size,
+                        display_util::format_filesize_byte,
+                    ),
+                })
+                .collect();
+            Template::render(
+                "clients/profiles",
+                context! { profiles, client, os_info, user },
+            )
+        } else {
+            Template::render("clients/profiles", context! {})
+        }
+    })
+    .await)
+}
+
+#[get("/<uuid>/profiles/<sid>")]
+async fn profile_paths(database: &State<Database>, uuid: Uuid, sid: String, user: User) -> Result<Template, Status> {
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        let client = database.get_client(&uuid);
+        let os_info = database.get_client_os_info(&uuid);
+        let computer_user = database.user_manager().get_user(&sid);
+        let profile_paths = database.user_manager().get_profile_paths(&uuid, &sid);
+        if let (Ok(client), Ok(os_info), Ok(computer_user), Ok(profile_paths)) =
+            (client, os_info, computer_user, profile_paths)
+        {
+            let paths: Vec<UserProfilePaths> = profile_paths
+                .into_iter()
+                .map(|p| UserProfilePaths {
+                    path: p.path,
+                    size: display_util::format_big_decimal(&p.size, display_util::format_filesize_byte),
+                })
+                .collect();
+            Template::render(
+                "clients/profiles_path",
+                context! { paths, computer_user, client, os_info, user },
+            )
+        } else {
+            Template::render("clients/profiles_path", context! {})
+        }
+    })
+    .await)
 }
 
 #[get("/<uuid>/profiles/<sid>/delete")]
-fn profile_delete(database: &State<Database>, uuid: Uuid, sid: String, user: User) -> Template {
-    let client = database.get_client(&uuid);
-    if let Ok(client) = client {
-        let task = database.task_manager().delete_user_profile(client.id, sid.clone());
-        if task.is_ok() {
-            Template::render("task/task_created_successful", context! {
-                task_name: "delete-user-profile",
-                task_client: client.id,
-                task_info: format!("SID: {}", sid),
-                user,
-            })
+async fn profile_delete(database: &State<Database>, uuid: Uuid, sid: String, user: User) -> Result<Template, Status> {
+    user.require(Permission::RunTasks)?;
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        let client = database.get_client(&uuid);
+        if let Ok(client) = client {
+            let task = database.task_manager().delete_user_profile(client.id, sid.clone());
+            if task.is_ok() {
+                Template::render("task/task_created_successful", context! {
+                    task_name: "delete-user-profile",
+                    task_client: client.id,
+                    task_info: format!("SID: {}", sid),
+                    user,
+                })
+            } else {
+                Template::render("task/task_create_error", context! {
+                    error: "task_create_error",
+                    user,
+                })
+            }
         } else {
             Template::render("task/task_create_error", context! {
-                error: "task_create_error",
+                error: "task_create_pre_check_error",
                 user,
             })
         }
-    } else {
-        Template::render("task/task_create_error", context! {
-            error: "task_create_pre_check_error",
-            user,
-        })
-    }
+    })
+    .await)
 }
 
 #[get("/<uuid>/software")]
-fn software(database: &State<Database>, uuid: Uuid, user: User) -> Template {
-    let client = database.get_client(&uuid);
-    let os_info = database.get_client_os_info(&uuid);
-    let software = database.get_client_software(uuid);
-    if let (Ok(client), Ok(os_info), Ok(software)) = (client, os_info, software) {
-        Template::render(
-            "clients/software",
-            context! { software, client, os_info, user },
-        )
-    } else {
-        Template::render("clients/software", context! {})
-    }
+async fn software(database: &State<Database>, uuid: Uuid, user: User) -> Result<Template, Status> {
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        let client = database.get_client(&uuid);
+        let os_info = database.get_client_os_info(&uuid);
+        let software = database.get_client_software(uuid);
+        if let (Ok(client), Ok(os_info), Ok(software)) = (client, os_info, software) {
+            Template::render(
+                "clients/software",
+                context! { software, client, os_info, user },
+            )
+        } else {
+            Template::render("clients/software", context! {})
+        }
+    })
+    .await)
 }
 
 #[get("/<uuid>/hardware")]
-fn hardware(database: &State<Database>, uuid: Uuid, user: User) -> Template {
-    let client = database.get_client(&uuid);
-    let os_info = database.get_client_os_info(&uuid);
-    if let (Ok(client), Ok(os_info)) = (client, os_info) {
-        let processors = database.get_client_processors(uuid).unwrap_or_default();
-        let memory: Vec<Memory> = database
-            .get_client_memory(uuid)
-            .unwrap_or_default()
-            .into_iter()
-            .map(|m| Memory {
-                capacity: display_util::format_option_big_decimal(
-                    &m.capacity,
-                    display_util::format_filesize_byte_iec,
-                ),
-                stick_count: m.stick_count,
-            })
-            .collect();
-        let memory_sticks: Vec<MemoryStick> = database
-            .get_client_memory_sticks(uuid)
-            .unwrap_or_default()
-            .into_iter()
-            .map(|m| MemoryStick {
-                capacity: display_util::format_option_big_decimal(
-                    &m.capacity,
-                    display_util::format_filesize_byte_iec,
-                ),
-                bank_label: m.bank_label,
-            })
-            .collect();
-        let graphics_cards = database.get_client_graphics_cards(uuid).unwrap_or_default();
-        let disks: Vec<Disk> = database
-            .get_client_disks(uuid)
-            .unwrap_or_default()
-            .into_iter()
-            .map(|d| Disk {
-                model: d.model,
-                serial_number: d.serial_number,
-                size: display_util::format_option_big_decimal(
-                    &d.size,
-                    display_util::format_filesize_byte,
-                ),
-                device_id: d.device_id,
-                status: d.status,
-                media_type: d.media_type,
-            })
-            .collect();
-        let computer_models = database.get_client_computer_model(uuid).unwrap_or_default();
-        let bios_list = database.get_client_bios(uuid).unwrap_or_default();
-        let network_adapters = database.get_client_network_adapters(uuid).unwrap_or_default();
-        Template::render(
-            "clients/hardware",
-            context! { processors, memory, memory_sticks, graphics_cards, disks, computer_models, bios_list, network_adapters, client, os_info, user },
-        )
-    } else {
-        Template::render("clients/hardware", context! {})
-    }
+async fn hardware(database: &State<Database>, uuid: Uuid, user: User) -> Result<Template, Status> {
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        let client = database.get_client(&uuid);
+        let os_info = database.get_client_os_info(&uuid);
+        if let (Ok(client), Ok(os_info)) = (client, os_info) {
+            let processors = database.get_client_processors(uuid).unwrap_or_default();
+            let memory: Vec<Memory> = database
+                .get_client_memory(uuid)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|m| Memory {
+                    capacity: display_util::format_option_big_decimal(
+                        &m.capacity,
+                        display_util::format_filesize_byte_iec,
+                    ),
+                    stick_count: m.stick_count,
+                })
+                .collect();
+            let memory_sticks: Vec<MemoryStick> = database
+                .get_client_memory_sticks(uuid)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|m| MemoryStick {
+                    capacity: display_util::format_option_big_decimal(
+                        &m.capacity,
+                        display_util::format_filesize_byte_iec,
+                    ),
+                    bank_label: m.bank_label,
+                })
+                .collect();
+            let graphics_cards = database.get_client_graphics_cards(uuid).unwrap_or_default();
+            let disks: Vec<Disk> = database
+                .get_client_disks(uuid)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|d| Disk {
+                    model: d.model,
+                    serial_number: d.serial_number,
+                    size: display_util::format_option_big_decimal(
+                        &d.size,
+                        display_util::format_filesize_byte,
+                    ),
+                    device_id: d.device_id,
+                    status: d.status,
+                    media_type: d.media_type,
+                })
+                .collect();
+            let computer_models = database.get_client_computer_model(uuid).unwrap_or_default();
+            let bios_list = database.get_client_bios(uuid).unwrap_or_default();
+            let network_adapters = database.get_client_network_adapters(uuid).unwrap_or_default();
+            Template::render(
+                "clients/hardware",
+                context! { processors, memory, memory_sticks, graphics_cards, disks, computer_models, bios_list, network_adapters, client, os_info, user },
+            )
+        } else {
+            Template::render("clients/hardware", context! {})
+        }
+    })
+    .await)
 }
 
 #[get("/<uuid>/status")]
-fn status(database: &State<Database>, uuid: Uuid, user: User) -> Template {
-    let client = database.get_client(&uuid);
-    let os_info = database.get_client_os_info(&uuid);
-    if let (Ok(client), Ok(os_info)) = (client, os_info) {
-        let volumes: Vec<VolumeStatus> = database
-            .get_client_volume_status(uuid)
-            .unwrap_or_default()
-            .into_iter()
-            .map(|v| VolumeStatus {
-                drive_letter: v.drive_letter,
-                label: v.label,
-                file_system: v.file_system,
-                capacity: display_util::format_big_decimal(
-                    &v.capacity,
-                    display_util::format_filesize_byte,
-                ),
-                free_space: display_util::format_big_decimal(
-                    &v.free_space,
-                    display_util::format_filesize_byte,
-                ),
-                occupied_space: display_util::format_big_decimal(
-                    &(&v.capacity - &v.free_space),
-                    display_util::format_filesize_byte,
-                ),
-                occupied_percentage: display_util::format_bd_percentage(
-                    &(&v.capacity - &v.free_space),
-                    &v.capacity,
-                ),
-            })
-            .collect();
-        Template::render(
-            "clients/status",
-            context! { volumes, client, os_info, user },
-        )
-    } else {
-        Template::render("clients/status", context! {})
-    }
+async fn status(database: &State<Database>, uuid: Uuid, user: User) -> Result<Template, Status> {
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        let client = database.get_client(&uuid);
+        let os_info = database.get_client_os_info(&uuid);
+        if let (Ok(client), Ok(os_info)) = (client, os_info) {
+            let volumes: Vec<VolumeStatus> = database
+                .get_client_volume_status(uuid)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|v| VolumeStatus {
+                    drive_letter: v.drive_letter,
+                    label: v.label,
+                    file_system: v.file_system,
+                    capacity: display_util::format_big_decimal(
+                        &v.capacity,
+                        display_util::format_filesize_byte,
+                    ),
+                    free_space: display_util::format_big_decimal(
+                        &v.free_space,
+                        display_util::format_filesize_byte,
+                    ),
+                    occupied_space: display_util::format_big_decimal(
+                        &(&v.capacity - &v.free_space),
+                        display_util::format_filesize_byte,
+                    ),
+                    occupied_percentage: display_util::format_bd_percentage(
+                        &(&v.capacity - &v.free_space),
+                        &v.capacity,
+                    ),
+                })
+                .collect();
+            Template::render(
+                "clients/status",
+                context! { volumes, client, os_info, user },
+            )
+        } else {
+            Template::render("clients/status", context! {})
+        }
+    })
+    .await)
 }
 
 #[get("/<uuid>/licenses")]
-fn licenses(database: &State<Database>, uuid: Uuid, user: User) -> Template {
-    let client = database.get_client(&uuid);
-    let os_info = database.get_client_os_info(&uuid);
-    if let (Ok(client), Ok(os_info)) = (client, os_info) {
-        let licenses = database.get_client_licenses(uuid).unwrap_or_default();
-        Template::render(
-            "clients/licenses",
-            context! { licenses, client, os_info, user },
-        )
-    } else {
-        Template::render("clients/licenses", context! {})
-    }
+async fn licenses(database: &State<Database>, uuid: Uuid, user: User) -> Result<Template, Status> {
+    user.require(Permission::ViewLicenseKeys)?;
+    check_client(database, &user, uuid).await?;
+    Ok(database.run(move |database| {
+        let client = database.get_client(&uuid);
+        let os_info = database.get_client_os_info(&uuid);
+        if let (Ok(client), Ok(os_info)) = (client, os_info) {
+            let licenses = database.get_client_licenses(uuid).unwrap_or_default();
+            Template::render(
+                "clients/licenses",
+                context! { licenses, client, os_info, user },
+            )
+        } else {
+            Template::render("clients/licenses", context! {})
+        }
+    })
+    .await)
 }
 
 #[get("/<_..>", rank = 10)]
@@ -319,6 +699,11 @@ pub fn routes() -> Vec<Route> {
         licenses,
         profile_paths,
         profile_delete,
+        add_group,
+        set_asset,
+        set_attributes,
+        remove_group,
+        set_location,
         catch_all,
     ]
 }
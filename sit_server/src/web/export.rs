@@ -0,0 +1,874 @@
+//! CSV and XLSX downloads of the list pages.
+//!
+//! Every route mirrors a page under the same path below `/export` and takes the
+//! same query string, so an export contains exactly the rows the page shows,
+//! only without paging. `format` selects `csv` (the default) or `xlsx`.
+
+use std::io::Cursor;
+
+use anyhow::Result;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use rocket::http::{ContentType, Status};
+use rocket::response::{self, Redirect, Responder, Response};
+use rocket::{Request, Route, State};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    auth::{Permission, User},
+    database::Database,
+};
+
+use super::list::ListParams;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, FromFormField)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Xlsx,
+}
+
+/// Links to the CSV and XLSX export of a page, rendered by `_export_links`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportLinks {
+    csv: String,
+    xlsx: String,
+}
+
+impl ExportLinks {
+    /// `query` is the query string of the page, without `?`.
+    pub fn new(path: &str, query: &str) -> ExportLinks {
+        let link = |format: &str| {
+            if query.is_empty() {
+                format!("/export{path}?format={format}")
+            } else {
+                format!("/export{path}?{query}&format={format}")
+            }
+        };
+        ExportLinks {
+            csv: link("csv"),
+            xlsx: link("xlsx"),
+        }
+    }
+
+    pub fn for_group(path: &str, group: Option<i32>) -> ExportLinks {
+        ExportLinks::new(path, &group.map(|g| format!("group={g}")).unwrap_or_default())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Cell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_owned())
+    }
+}
+
+impl From<Option<String>> for Cell {
+    fn from(value: Option<String>) -> Self {
+        value.map_or(Cell::Empty, Cell::Text)
+    }
+}
+
+impl From<i64> for Cell {
+    fn from(value: i64) -> Self {
+        Cell::Number(value as f64)
+    }
+}
+
+impl From<Option<i64>> for Cell {
+    fn from(value: Option<i64>) -> Self {
+        value.map_or(Cell::Empty, Cell::from)
+    }
+}
+
+impl From<Option<i32>> for Cell {
+    fn from(value: Option<i32>) -> Self {
+        value.map_or(Cell::Empty, |v| Cell::Number(v.into()))
+    }
+}
+
+impl From<Option<&BigDecimal>> for Cell {
+    fn from(value: Option<&BigDecimal>) -> Self {
+        value.and_then(|v| v.to_f64()).map_or(Cell::Empty, Cell::Number)
+    }
+}
+
+impl From<Uuid> for Cell {
+    fn from(value: Uuid) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+/// The content of one export: a header row and the data rows.
+struct Table {
+    /// File name without extension, also used as sheet name.
+    name: String,
+    headers: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    fn new(name: &str, headers: &[&str]) -> Table {
+        Table {
+            name: if name.trim().is_empty() { "Export".to_owned() } else { name.to_owned() },
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: vec![],
+        }
+    }
+
+    fn push(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    /// Semicolon separated with BOM, which is what spreadsheet applications in
+    /// German locales open without an import dialog.
+    fn to_csv(&self) -> Vec<u8> {
+        fn field(value: &str) -> String {
+            if value.contains([';', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_owned()
+            }
+        }
+
+        let mut csv = String::from("\u{feff}");
+        let headers: Vec<String> = self.headers.iter().map(|h| field(h)).collect();
+        csv.push_str(&headers.join(";"));
+        csv.push_str("\r\n");
+        for row in &self.rows {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|cell| match cell {
+                    Cell::Text(text) => field(text),
+                    Cell::Number(number) => number.to_string(),
+                    Cell::Empty => String::new(),
+                })
+                .collect();
+            csv.push_str(&fields.join(";"));
+            csv.push_str("\r\n");
+        }
+        csv.into_bytes()
+    }
+
+    fn to_xlsx(&self) -> Result<Vec<u8>> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        // Sheet names are limited to 31 characters and some punctuation.
+        let sheet_name: String = self
+            .name
+            .chars()
+            .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+            .take(31)
+            .collect();
+        worksheet.set_name(sheet_name)?;
+        let bold = Format::new().set_bold();
+        for (col, header) in self.headers.iter().enumerate() {
+            worksheet.write_string_with_format(0, col as u16, header, &bold)?;
+        }
+        for (row, cells) in self.rows.iter().enumerate() {
+            let row = row as u32 + 1;
+            for (col, cell) in cells.iter().enumerate() {
+                match cell {
+                    Cell::Text(text) => {
+                        worksheet.write_string(row, col as u16, text)?;
+                    }
+                    Cell::Number(number) => {
+                        worksheet.write_number(row, col as u16, *number)?;
+                    }
+                    Cell::Empty => {}
+                }
+            }
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+        worksheet.autofit();
+        Ok(workbook.save_to_buffer()?)
+    }
+}
+
+/// A table sent as file download.
+pub struct ExportFile {
+    file_name: String,
+    content_type: ContentType,
+    body: Vec<u8>,
+}
+
+impl ExportFile {
+    fn new(table: Table, format: Option<ExportFormat>) -> Result<ExportFile, Status> {
+        let (extension, content_type, body) = match format.unwrap_or_default() {
+            ExportFormat::Csv => ("csv", ContentType::CSV, table.to_csv()),
+            ExportFormat::Xlsx => (
+                "xlsx",
+                ContentType::new("application", "vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+                table.to_xlsx().map_err(|e| {
+                    println!("[ERROR] Export of {} failed: {e}", table.name);
+                    Status::InternalServerError
+                })?,
+            ),
+        };
+        // Software and OS names end up in the file name, keep it to plain ASCII.
+        let name: String = table
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || "-_. ".contains(c) { c } else { '_' })
+            .collect();
+        Ok(ExportFile {
+            file_name: format!("{name}.{extension}"),
+            content_type,
+            body,
+        })
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ExportFile {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(self.content_type)
+            .raw_header(
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", self.file_name),
+            )
+            .sized_body(self.body.len(), Cursor::new(self.body))
+            .ok()
+    }
+}
+
+fn load<T>(result: Result<T>, name: &str) -> Result<T, Status> {
+    result.map_err(|e| {
+        println!("[ERROR] Export of {name} failed: {e}");
+        Status::InternalServerError
+    })
+}
+
+/// Computer name, domain and UUID of a client, the first columns of every
+/// export listing clients.
+fn client_cells(uuid: Uuid, computer_name: String, domain: Option<String>) -> Vec<Cell> {
+    vec![computer_name.into(), domain.into(), uuid.into()]
+}
+
+#[get("/clients?<group>&<format>&<list..>")]
+async fn clients(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    list: ListParams,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let page = load(database.get_client_page(group, &list.query().unpaged()), "clients")?;
+        let definitions = load(database.attribute_manager().get_definitions(), "clients")?;
+        let client_ids: Vec<i32> = page.items.iter().map(|(c, _)| c.id).collect();
+        let values = load(
+            database.attribute_manager().get_attributes_of_clients(&client_ids),
+            "clients",
+        )?;
+
+        let mut headers = vec!["Computername", "Domäne", "UUID", "OS", "OS-Version"];
+        headers.extend(definitions.iter().map(|d| d.name.as_str()));
+        let mut table = Table::new("Clients", &headers);
+        for (c, os_info) in page.items {
+            let mut row = client_cells(
+                c.uuid,
+                os_info.as_ref().map(|o| o.computer_name.clone()).unwrap_or_default(),
+                os_info.as_ref().and_then(|o| o.domain.clone()),
+            );
+            row.push(os_info.as_ref().and_then(|o| o.os.clone()).into());
+            row.push(os_info.and_then(|o| o.os_version).into());
+            for definition in &definitions {
+                row.push(
+                    values
+                        .iter()
+                        .find(|v| v.client_id == c.id && v.attribute_id == definition.id)
+                        .map(|v| v.value.clone())
+                        .into(),
+                );
+            }
+            table.push(row);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+/// All clients with their complete hardware, asset record and custom attributes,
+/// one row per client.
+#[get("/inventory?<group>&<format>")]
+async fn inventory(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let rows = load(database.get_inventory_rows(group), "inventory")?;
+        let definitions = load(database.attribute_manager().get_definitions(), "inventory")?;
+
+        let mut headers = vec![
+            "Computername",
+            "Domäne",
+            "UUID",
+            "OS",
+            "OS-Version",
+            "Hersteller",
+            "Modellfamilie",
+            "Seriennummer",
+            "BIOS",
+            "Prozessor",
+            "Kerne",
+            "Logische Kerne",
+            "Takt (MHz)",
+            "Arbeitsspeicher (Byte)",
+            "Anzahl Module",
+            "Festplatten",
+            "Festplattenkapazität (Byte)",
+            "Grafikkarten",
+            "Netzwerkadapter",
+            "MAC-Adressen",
+            "IP-Adressen",
+            "Inventarnummer",
+            "Kaufdatum",
+            "Lieferant",
+            "Preis (€)",
+            "Garantie bis",
+            "Verantwortlich",
+        ];
+        headers.extend(definitions.iter().map(|d| d.name.as_str()));
+        let mut table = Table::new("Inventar", &headers);
+        for row in rows {
+            let disk_capacity: BigDecimal = row.disks.iter().filter_map(|d| d.size.as_ref()).sum();
+            let mut cells = client_cells(row.uuid, row.computer_name, Some(row.domain));
+            cells.push(row.os.into());
+            cells.push(row.os_version.into());
+            cells.push(row.model.as_ref().map(|m| m.manufacturer.clone()).into());
+            cells.push(row.model.as_ref().map(|m| m.model_family.clone()).into());
+            cells.push(row.model.map(|m| m.serial_number).into());
+            cells.push(
+                row.bios
+                    .map(|b| format!("{} {} {}", b.manufacturer, b.name, b.version))
+                    .into(),
+            );
+            cells.push(row.processor.as_ref().map(|p| p.name.clone()).into());
+            cells.push(row.processor.as_ref().map(|p| p.cores).into());
+            cells.push(row.processor.as_ref().map(|p| p.logical_cores).into());
+            cells.push(row.processor.as_ref().map(|p| p.clock_speed).into());
+            cells.push(row.memory_capacity.as_ref().into());
+            cells.push(row.memory_sticks.into());
+            cells.push(
+                row.disks
+                    .iter()
+                    .map(|d| d.model.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .into(),
+            );
+            cells.push((!row.disks.is_empty()).then_some(&disk_capacity).into());
+            cells.push(row.graphics_cards.join(", ").into());
+            cells.push(
+                row.network_adapters
+                    .iter()
+                    .map(|a| a.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .into(),
+            );
+            cells.push(
+                row.network_adapters
+                    .iter()
+                    .filter_map(|a| a.mac_address.as_deref())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .into(),
+            );
+            cells.push(row.ip_addresses.join(", ").into());
+            let asset = row.asset.unwrap_or_default();
+            cells.push(asset.inventory_number.into());
+            cells.push(asset.purchase_date.map(|d| d.to_string()).into());
+            cells.push(asset.supplier.into());
+            cells.push(asset.price.as_ref().into());
+            cells.push(asset.warranty_end.map(|d| d.to_string()).into());
+            cells.push(asset.responsible.into());
+            for definition in &definitions {
+                cells.push(row.attributes.get(&definition.id).cloned().into());
+            }
+            table.push(cells);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/hardware/processors?<group>&<format>&<list..>")]
+async fn processors(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    list: ListParams,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let page = load(database.get_processors_page(group, &list.query().unpaged()), "processors")?;
+        let mut table = Table::new(
+            "Prozessoren",
+            &[
+                "Name",
+                "Hersteller",
+                "Kerne",
+                "Logische Kerne",
+                "Takt (MHz)",
+                "Adressbreite (Bit)",
+                "Anzahl Geräte",
+            ],
+        );
+        for p in page.items {
+            table.push(vec![
+                p.name.into(),
+                p.manufacturer.into(),
+                p.cores.into(),
+                p.logical_cores.into(),
+                p.clock_speed.into(),
+                p.address_width.into(),
+                p.count.into(),
+            ]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/hardware/memory?<group>&<format>&<list..>")]
+async fn memory(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    list: ListParams,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let page = load(database.get_memory_page(group, &list.query().unpaged()), "memory")?;
+        let mut table = Table::new(
+            "Arbeitsspeicher",
+            &["Gesamtkapazität (Byte)", "Anzahl Module", "Anzahl Geräte"],
+        );
+        for m in page.items {
+            table.push(vec![m.capacity.as_ref().into(), m.sticks.into(), m.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/hardware/graphics_cards?<group>&<format>&<list..>")]
+async fn graphics_cards(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    list: ListParams,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let page = load(
+            database.get_graphics_cards_page(group, &list.query().unpaged()),
+            "graphics cards",
+        )?;
+        let mut table = Table::new("Grafikkarten", &["Name", "Anzahl Geräte"]);
+        for gc in page.items {
+            table.push(vec![gc.name.into(), gc.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/hardware/disks?<group>&<format>&<list..>")]
+async fn disks(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    list: ListParams,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let page = load(database.get_disks_page(group, &list.query().unpaged()), "disks")?;
+        let mut table = Table::new("Festplatten", &["Model", "Größe (Byte)", "Anzahl Geräte"]);
+        for d in page.items {
+            table.push(vec![d.model.into(), d.size.as_ref().into(), d.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/hardware/models?<group>&<format>&<list..>")]
+async fn models(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    list: ListParams,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let page = load(
+            database.get_computer_models_page(group, &list.query().unpaged()),
+            "computer models",
+        )?;
+        let mut table = Table::new("Computer Modelle", &["Hersteller", "Modellfamilie", "Anzahl Geräte"]);
+        for m in page.items {
+            table.push(vec![m.manufacturer.into(), m.model_family.into(), m.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/hardware/network_adapters?<group>&<format>&<list..>")]
+async fn network_adapters(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    list: ListParams,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let page = load(
+            database.get_network_adapters_page(group, &list.query().unpaged()),
+            "network adapters",
+        )?;
+        let mut table = Table::new("Netzwerkadapter", &["Name", "Anzahl Geräte"]);
+        for na in page.items {
+            table.push(vec![na.name.into(), na.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/software?<group>&<format>&<list..>")]
+async fn software_list(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    list: ListParams,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let page = load(database.get_software_page(group, &list.query().unpaged()), "software")?;
+        let mut table = Table::new("Software", &["Name", "Hersteller"]);
+        for s in page.items {
+            table.push(vec![s.name.into(), s.publisher.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/software/<id>?<group>&<format>")]
+async fn software_versions(
+    database: &State<Database>,
+    id: i32,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let software = load(database.get_software_info(id), "software versions")?;
+        let versions = load(
+            database.statistics_manager().get_software_versions(id, group),
+            "software versions",
+        )?;
+        let mut table = Table::new(&software.name, &["Version", "Anzahl Geräte"]);
+        for v in versions {
+            table.push(vec![v.version.into(), v.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/software/<id>/computer?<group>&<format>")]
+async fn software_computers(
+    database: &State<Database>,
+    id: i32,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let software = load(database.get_software_info(id), "software computers")?;
+        let computers = load(database.get_software_computer_list(id, group), "software computers")?;
+        let mut table = Table::new(&software.name, &["Computername", "Domäne", "UUID", "Version"]);
+        for (_, version, (c, os_info)) in computers {
+            let mut row = client_cells(c.uuid, os_info.computer_name, os_info.domain);
+            row.push(version.version.into());
+            table.push(row);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/software/<_>/version/<id>?<group>&<format>")]
+async fn software_version_computers(
+    database: &State<Database>,
+    id: i32,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let version = load(database.get_software_version(id), "software version")?;
+        let software = load(database.get_software_info(version.software_id), "software version")?;
+        let clients = load(database.get_software_version_clients(id, group), "software version")?;
+        let mut table = Table::new(
+            &format!("{} {}", software.name, version.version),
+            &["Computername", "Domäne", "UUID"],
+        );
+        for (c, os_info) in clients {
+            table.push(client_cells(c.uuid, os_info.computer_name, os_info.domain));
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/os?<group>&<format>")]
+async fn os_list(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let os_list = load(database.statistics_manager().get_os_list(group), "os")?;
+        let mut table = Table::new("Betriebssysteme", &["Betriebssystem", "Anzahl Geräte"]);
+        for os in os_list {
+            table.push(vec![os.os.into(), os.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/os/<name>?<group>&<format>")]
+async fn os_versions(
+    database: &State<Database>,
+    name: String,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let versions = load(database.get_os_versions(name.clone(), group), "os versions")?;
+        let mut table = Table::new(&name, &["Version", "Anzahl Geräte"]);
+        for v in versions {
+            table.push(vec![v.os_version.into(), v.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/os/<name>/computer?<group>&<format>")]
+async fn os_computers(
+    database: &State<Database>,
+    name: String,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let computers = load(database.get_os_client_list(name.clone(), group), "os computers")?;
+        let mut table = Table::new(&name, &["Computername", "Domäne", "UUID", "Version"]);
+        for entry in computers {
+            let mut row = client_cells(entry.client.uuid, entry.os_info.computer_name, entry.os_info.domain);
+            row.push(entry.os_info.os_version.into());
+            table.push(row);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/os/<name>/version/<version>?<group>&<format>")]
+async fn os_version_computers(
+    database: &State<Database>,
+    name: String,
+    version: String,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let computers = load(
+            database.get_os_version_client_list(name, version, group),
+            "os version computers",
+        )?;
+        let mut table = Table::new(
+            &format!("{} {}", computers.os, computers.os_version),
+            &["Computername", "Domäne", "UUID"],
+        );
+        for (os_info, c) in computers.list {
+            table.push(client_cells(c.uuid, os_info.computer_name, os_info.domain));
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/license?<group>&<format>")]
+async fn license_list(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let licenses = load(database.get_license_list(group), "licenses")?;
+        let mut table = Table::new("Lizenzen", &["Name", "Anzahl Geräte"]);
+        for l in licenses {
+            table.push(vec![l.name.into(), l.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/software/license/<name>?<group>&<format>")]
+async fn license_computers(
+    database: &State<Database>,
+    name: String,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    user.require(Permission::ViewLicenseKeys)?;
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let licenses = load(database.get_license_with_computers(&name, group), "license computers")?;
+        let mut table = Table::new(&name, &["Computername", "Domäne", "UUID", "Lizenzschlüssel"]);
+        for (license, (c, os_info)) in licenses {
+            let mut row = client_cells(c.uuid, os_info.computer_name, os_info.domain);
+            row.push(license.key.into());
+            table.push(row);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/system-status/volumes?<group>&<format>")]
+async fn volumes(
+    database: &State<Database>,
+    group: Option<i32>,
+    format: Option<ExportFormat>,
+    user: User,
+) -> Result<ExportFile, Status> {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let volumes = load(database.get_system_status_volume_crit(group), "volumes")?;
+        let mut table = Table::new(
+            "Volumes",
+            &[
+                "Computername",
+                "Domäne",
+                "UUID",
+                "Laufwerk",
+                "Bezeichnung",
+                "Dateisystem",
+                "Kapazität (Byte)",
+                "Frei (Byte)",
+                "Belegt (Byte)",
+            ],
+        );
+        for (v, (c, os_info)) in volumes {
+            let mut row = client_cells(c.uuid, os_info.computer_name, os_info.domain);
+            row.push(v.drive_letter.into());
+            row.push(v.label.into());
+            row.push(v.file_system.into());
+            row.push(Some(&v.capacity).into());
+            row.push(Some(&v.free_space).into());
+            row.push(Some(&(&v.capacity - &v.free_space)).into());
+            table.push(row);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/profile?<format>&<list..>")]
+async fn profiles(
+    database: &State<Database>,
+    format: Option<ExportFormat>,
+    list: ListParams,
+    user: User,
+) -> Result<ExportFile, Status> {
+    user.groups.require_unrestricted()?;
+    database.run(move |database| {
+        let page = load(database.get_profile_page(&list.query().unpaged()), "profiles")?;
+        let mut table = Table::new("Profile", &["Domäne", "Username", "SID", "Anzahl Computer"]);
+        for p in page.items {
+            table.push(vec![p.domain.into(), p.username.into(), p.sid.into(), p.count.into()]);
+        }
+        ExportFile::new(table, format)
+    })
+    .await
+}
+
+#[get("/<_..>", rank = 10)]
+fn catch_all() -> Redirect {
+    Redirect::to(uri!("/auth/login"))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        clients,
+        inventory,
+        processors,
+        memory,
+        graphics_cards,
+        disks,
+        models,
+        network_adapters,
+        software_list,
+        software_versions,
+        software_computers,
+        software_version_computers,
+        os_list,
+        os_versions,
+        os_computers,
+        os_version_computers,
+        license_list,
+        license_computers,
+        volumes,
+        profiles,
+        catch_all,
+    ]
+}
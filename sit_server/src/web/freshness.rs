@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+use crate::database::Database;
+
+use super::display_util;
+
+/// When the statistics shown on a page were computed, rendered by `_freshness`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Freshness {
+    /// `None` while the first refresh is still outstanding.
+    refreshed: Option<String>,
+    /// The inventory changed since.
+    outdated: bool,
+}
+
+impl Freshness {
+    pub fn new(database: &Database) -> Freshness {
+        match database.statistics_manager().get_state() {
+            Ok(state) => Freshness {
+                refreshed: state.refreshed.map(display_util::format_date_time),
+                outdated: matches!((state.refreshed, state.changed), (Some(r), Some(c)) if c >= r),
+            },
+            Err(_) => Freshness {
+                refreshed: None,
+                outdated: true,
+            },
+        }
+    }
+}
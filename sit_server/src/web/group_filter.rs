@@ -0,0 +1,44 @@
+use serde::Serialize;
+
+use crate::{auth::GroupRestriction, database::Database};
+
+#[derive(Clone, Debug, Serialize)]
+struct GroupOption {
+    pub id: i32,
+    pub name: String,
+    pub count: i64,
+}
+
+/// Client group selection shown on the list pages, rendered by `_group_filter`.
+#[derive(Clone, Debug, Serialize)]
+pub struct GroupFilter {
+    selected: Option<i32>,
+    /// Appended to links so the selection is kept while drilling down.
+    query: String,
+    groups: Vec<GroupOption>,
+    /// Offers "all clients", unless the user is restricted to some groups.
+    all: bool,
+}
+
+impl GroupFilter {
+    pub fn new(database: &Database, restriction: &GroupRestriction, selected: Option<i32>) -> GroupFilter {
+        let groups = database
+            .group_manager()
+            .get_groups()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|g| restriction.allows_group(g.id))
+            .map(|g| GroupOption {
+                id: g.id,
+                name: g.name,
+                count: g.count,
+            })
+            .collect();
+        GroupFilter {
+            selected,
+            query: selected.map(|id| format!("?group={id}")).unwrap_or_default(),
+            groups,
+            all: !restriction.is_restricted(),
+        }
+    }
+}
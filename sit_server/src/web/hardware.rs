@@ -3,9 +3,18 @@ use rocket::{response::Redirect, Route, State};
 use rocket_dyn_templates::{context, Template};
 use serde::Serialize;
 
-use crate::{auth::User, database::Database};
+use crate::{
+    auth::User,
+    database::{Database, Page},
+};
 
-use super::display_util;
+use super::{
+    display_util,
+    export::ExportLinks,
+    freshness::Freshness,
+    group_filter::GroupFilter,
+    list::{self, ListParams, ListView},
+};
 
 #[derive(Clone, Debug, Serialize)]
 struct ProcessorCount {
@@ -64,11 +73,28 @@ fn index(user: User) -> Template {
     Template::render("hardware/index", context! { user })
 }
 
-#[get("/processors")]
-fn processors(database: &State<Database>, user: User) -> Template {
-    let processors = database.get_processors_count();
-    if let Ok(processors) = processors {
-        let processors: Vec<ProcessorCount> = processors
+#[get("/processors?<group>&<list..>")]
+async fn processors(database: &State<Database>, group: Option<i32>, list: ListParams, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let freshness = Freshness::new(database);
+        let export = ExportLinks::new("/hardware/processors", &list.query_string(group));
+        let page = database
+            .get_processors_page(group, &list.query())
+            .unwrap_or_else(|_| Page::empty());
+        let columns = list::columns(&[
+            "name",
+            "manufacturer",
+            "cores",
+            "logical_cores",
+            "clock_speed",
+            "address_width",
+            "count",
+        ]);
+        let list = ListView::new(&list, &page, group, &columns);
+        let processors: Vec<ProcessorCount> = page
+            .items
             .into_iter()
             .map(|p| ProcessorCount {
                 url_name: urlencoding::encode(&p.name).into_owned(),
@@ -81,26 +107,48 @@ fn processors(database: &State<Database>, user: User) -> Template {
                 count: p.count,
             })
             .collect();
-        Template::render("hardware/processors", context! { processors, user })
-    } else {
-        Template::render("hardware/processors", context! {})
-    }
+        Template::render(
+            "hardware/processors",
+            context! { processors, list, export, group_filter, freshness, user },
+        )
+    })
+    .await
 }
 
-#[get("/processors/<processor>")]
-fn processor_clients(database: &State<Database>, processor: String, user: User) -> Template {
-    let clients = database.get_processor_clients(&processor).unwrap_or_default();
-    Template::render(
-        "hardware/clients",
-        context! { clients, headline: processor, user },
-    )
+#[get("/processors/<processor>?<group>")]
+async fn processor_clients(
+    database: &State<Database>,
+    processor: String,
+    group: Option<i32>,
+    user: User,
+) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let clients = database
+            .get_processor_clients(&processor, group)
+            .unwrap_or_default();
+        Template::render(
+            "hardware/clients",
+            context! { clients, headline: processor, group_filter, user },
+        )
+    })
+    .await
 }
 
-#[get("/memory")]
-fn memory(database: &State<Database>, user: User) -> Template {
-    let memory = database.get_memory_count();
-    if let Ok(memory) = memory {
-        let memory: Vec<MemoryCount> = memory
+#[get("/memory?<group>&<list..>")]
+async fn memory(database: &State<Database>, group: Option<i32>, list: ListParams, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let freshness = Freshness::new(database);
+        let export = ExportLinks::new("/hardware/memory", &list.query_string(group));
+        let page = database
+            .get_memory_page(group, &list.query())
+            .unwrap_or_else(|_| Page::empty());
+        let list = ListView::new(&list, &page, group, &list::columns(&["capacity", "sticks", "count"]));
+        let memory: Vec<MemoryCount> = page
+            .items
             .into_iter()
             .map(|m| MemoryCount {
                 capacity: display_util::format_option_big_decimal(
@@ -116,26 +164,45 @@ fn memory(database: &State<Database>, user: User) -> Template {
                 count: m.count,
             })
             .collect();
-        Template::render("hardware/memory", context! { memory, user })
-    } else {
-        Template::render("hardware/memory", context! {})
-    }
+        Template::render("hardware/memory", context! { memory, list, export, group_filter, freshness, user })
+    })
+    .await
 }
 
-#[get("/memory/<size>/<count>")]
-fn memory_clients(database: &State<Database>, size: u64, count: i64, user: User) -> Template {
-    let clients = database.get_memory_clients(size, count).unwrap_or_default();
-    Template::render(
-        "hardware/clients",
-        context! { clients, headline: format!("{}, {} Stick(s)", display_util::format_filesize_byte_iec(size as f64, 0), count), user },
-    )
+#[get("/memory/<size>/<count>?<group>")]
+async fn memory_clients(
+    database: &State<Database>,
+    size: u64,
+    count: i64,
+    group: Option<i32>,
+    user: User,
+) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let clients = database
+            .get_memory_clients(size, count, group)
+            .unwrap_or_default();
+        Template::render(
+            "hardware/clients",
+            context! { clients, headline: format!("{}, {} Stick(s)", display_util::format_filesize_byte_iec(size as f64, 0), count), group_filter, user },
+        )
+    })
+    .await
 }
 
-#[get("/graphics_cards")]
-fn graphics_cards(database: &State<Database>, user: User) -> Template {
-    let graphics_cards = database.get_graphics_cards_count();
-    if let Ok(graphics_cards) = graphics_cards {
-        let graphics_cards: Vec<GraphicsCardCount> = graphics_cards
+#[get("/graphics_cards?<group>&<list..>")]
+async fn graphics_cards(database: &State<Database>, group: Option<i32>, list: ListParams, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let export = ExportLinks::new("/hardware/graphics_cards", &list.query_string(group));
+        let page = database
+            .get_graphics_cards_page(group, &list.query())
+            .unwrap_or_else(|_| Page::empty());
+        let list = ListView::new(&list, &page, group, &list::columns(&["name", "count"]));
+        let graphics_cards: Vec<GraphicsCardCount> = page
+            .items
             .into_iter()
             .map(|gc| GraphicsCardCount {
                 url_name: urlencoding::encode(&gc.name).into_owned(),
@@ -143,26 +210,48 @@ fn graphics_cards(database: &State<Database>, user: User) -> Template {
                 count: gc.count,
             })
             .collect();
-        Template::render("hardware/graphics_cards", context! { graphics_cards, user })
-    } else {
-        Template::render("hardware/graphics_cards", context! {})
-    }
+        Template::render(
+            "hardware/graphics_cards",
+            context! { graphics_cards, list, export, group_filter, user },
+        )
+    })
+    .await
 }
 
-#[get("/graphics_cards/<card>")]
-fn graphics_card_clients(database: &State<Database>, card: String, user: User) -> Template {
-    let clients = database.get_graphics_card_clients(&card).unwrap_or_default();
-    Template::render(
-        "hardware/clients",
-        context! { clients, headline: card, user },
-    )
+#[get("/graphics_cards/<card>?<group>")]
+async fn graphics_card_clients(
+    database: &State<Database>,
+    card: String,
+    group: Option<i32>,
+    user: User,
+) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let clients = database
+            .get_graphics_card_clients(&card, group)
+            .unwrap_or_default();
+        Template::render(
+            "hardware/clients",
+            context! { clients, headline: card, group_filter, user },
+        )
+    })
+    .await
 }
 
-#[get("/disks")]
-fn disks(database: &State<Database>, user: User) -> Template {
-    let disks = database.get_disks_count();
-    if let Ok(disks) = disks {
-        let disks: Vec<DiskCount> = disks
+#[get("/disks?<group>&<list..>")]
+async fn disks(database: &State<Database>, group: Option<i32>, list: ListParams, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let freshness = Freshness::new(database);
+        let export = ExportLinks::new("/hardware/disks", &list.query_string(group));
+        let page = database
+            .get_disks_page(group, &list.query())
+            .unwrap_or_else(|_| Page::empty());
+        let list = ListView::new(&list, &page, group, &list::columns(&["model", "size", "count"]));
+        let disks: Vec<DiskCount> = page
+            .items
             .into_iter()
             .map(|d| DiskCount {
                 url_model: urlencoding::encode(&d.model).into_owned(),
@@ -179,26 +268,46 @@ fn disks(database: &State<Database>, user: User) -> Template {
                 count: d.count,
             })
             .collect();
-        Template::render("hardware/disks", context! { disks, user })
-    } else {
-        Template::render("hardware/disks", context! {})
-    }
+        Template::render("hardware/disks", context! { disks, list, export, group_filter, freshness, user })
+    })
+    .await
 }
 
-#[get("/disks/<model>/<size>")]
-fn disk_clients(database: &State<Database>, model: String, size: u64, user: User) -> Template {
-    let clients = database.get_disk_clients(&model, size).unwrap_or_default();
-    Template::render(
-        "hardware/clients",
-        context! { clients, headline: format!("{}, {}", model, display_util::format_filesize_byte(size as f64, 0)), user },
-    )
+#[get("/disks/<model>/<size>?<group>")]
+async fn disk_clients(
+    database: &State<Database>,
+    model: String,
+    size: u64,
+    group: Option<i32>,
+    user: User,
+) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let clients = database
+            .get_disk_clients(&model, size, group)
+            .unwrap_or_default();
+        Template::render(
+            "hardware/clients",
+            context! { clients, headline: format!("{}, {}", model, display_util::format_filesize_byte(size as f64, 0)), group_filter, user },
+        )
+    })
+    .await
 }
 
-#[get("/models")]
-fn models(database: &State<Database>, user: User) -> Template {
-    let computer_models = database.get_computer_models_count();
-    if let Ok(computer_models) = computer_models {
-        let computer_models: Vec<ComputerModelCount> = computer_models
+#[get("/models?<group>&<list..>")]
+async fn models(database: &State<Database>, group: Option<i32>, list: ListParams, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let freshness = Freshness::new(database);
+        let export = ExportLinks::new("/hardware/models", &list.query_string(group));
+        let page = database
+            .get_computer_models_page(group, &list.query())
+            .unwrap_or_else(|_| Page::empty());
+        let list = ListView::new(&list, &page, group, &list::columns(&["manufacturer", "model_family", "count"]));
+        let computer_models: Vec<ComputerModelCount> = page
+            .items
             .into_iter()
             .map(|m| ComputerModelCount {
                 url_manufacturer: urlencoding::encode(&m.manufacturer).into_owned(),
@@ -208,33 +317,48 @@ fn models(database: &State<Database>, user: User) -> Template {
                 count: m.count,
             })
             .collect();
-        Template::render("hardware/models", context! { computer_models, user })
-    } else {
-        Template::render("hardware/models", context! {})
-    }
+        Template::render(
+            "hardware/models",
+            context! { computer_models, list, export, group_filter, freshness, user },
+        )
+    })
+    .await
 }
 
-#[get("/models/<manufacturer>/<model>")]
-fn model_clients(
+#[get("/models/<manufacturer>/<model>?<group>")]
+async fn model_clients(
     database: &State<Database>,
     manufacturer: String,
     model: String,
+    group: Option<i32>,
     user: User,
 ) -> Template {
-    let clients = database
-        .get_computer_model_clients(&model, &manufacturer)
-        .unwrap_or_default();
-    Template::render(
-        "hardware/clients",
-        context! { clients, headline: format!("{}, {}", manufacturer, model), user },
-    )
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let clients = database
+            .get_computer_model_clients(&model, &manufacturer, group)
+            .unwrap_or_default();
+        Template::render(
+            "hardware/clients",
+            context! { clients, headline: format!("{}, {}", manufacturer, model), group_filter, user },
+        )
+    })
+    .await
 }
 
-#[get("/network_adapters")]
-fn network_adapters(database: &State<Database>, user: User) -> Template {
-    let network_adapters = database.get_network_adapters_count();
-    if let Ok(network_adapters) = network_adapters {
-        let network_adapters: Vec<NetworkAdapterCount> = network_adapters
+#[get("/network_adapters?<group>&<list..>")]
+async fn network_adapters(database: &State<Database>, group: Option<i32>, list: ListParams, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let export = ExportLinks::new("/hardware/network_adapters", &list.query_string(group));
+        let page = database
+            .get_network_adapters_page(group, &list.query())
+            .unwrap_or_else(|_| Page::empty());
+        let list = ListView::new(&list, &page, group, &list::columns(&["name", "count"]));
+        let network_adapters: Vec<NetworkAdapterCount> = page
+            .items
             .into_iter()
             .map(|na| NetworkAdapterCount {
                 url_name: urlencoding::encode(&na.name).into_owned(),
@@ -244,22 +368,31 @@ fn network_adapters(database: &State<Database>, user: User) -> Template {
             .collect();
         Template::render(
             "hardware/network_adapters",
-            context! { network_adapters, user },
+            context! { network_adapters, list, export, group_filter, user },
         )
-    } else {
-        Template::render("hardware/network_adapters", context! {})
-    }
+    })
+    .await
 }
 
-#[get("/network_adapters/<name>")]
-fn network_adapter_clients(database: &State<Database>, name: String, user: User) -> Template {
-    let clients = database
-        .get_network_adapter_clients(&name)
-        .unwrap_or_default();
-    Template::render(
-        "hardware/clients",
-        context! { clients, headline: name, user },
-    )
+#[get("/network_adapters/<name>?<group>")]
+async fn network_adapter_clients(
+    database: &State<Database>,
+    name: String,
+    group: Option<i32>,
+    user: User,
+) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let clients = database
+            .get_network_adapter_clients(&name, group)
+            .unwrap_or_default();
+        Template::render(
+            "hardware/clients",
+            context! { clients, headline: name, group_filter, user },
+        )
+    })
+    .await
 }
 
 #[get("/<_..>", rank = 10)]
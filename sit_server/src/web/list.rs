@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::database::{ListQuery, Page, MAX_PER_PAGE};
+
+/// Paging, sorting and column filters of a list page from the query string:
+/// `page`, `per_page`, `sort`, `desc` and `filter.<column>`.
+#[derive(Clone, Debug, Default, FromForm, JsonSchema)]
+pub struct ListParams {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub sort: Option<String>,
+    pub desc: bool,
+    pub filter: HashMap<String, String>,
+}
+
+impl ListParams {
+    pub fn query(&self) -> ListQuery {
+        let defaults = ListQuery::default();
+        ListQuery {
+            page: self.page.unwrap_or(defaults.page),
+            per_page: self.per_page.unwrap_or(defaults.per_page).clamp(1, MAX_PER_PAGE),
+            sort: self.sort.clone().filter(|s| !s.is_empty()),
+            desc: self.desc,
+            filters: self.filter.clone(),
+        }
+    }
+
+    /// The query string of this state, without `page`.
+    pub fn query_string(&self, group: Option<i32>) -> String {
+        let mut parts: Vec<String> = vec![];
+        if let Some(group) = group {
+            parts.push(format!("group={group}"));
+        }
+        if let Some(per_page) = self.per_page {
+            parts.push(format!("per_page={per_page}"));
+        }
+        if let Some(sort) = self.sort.as_deref().filter(|s| !s.is_empty()) {
+            parts.push(format!("sort={}", urlencoding::encode(sort)));
+            if self.desc {
+                parts.push("desc=true".to_owned());
+            }
+        }
+        let mut filters: Vec<(&String, &String)> = self.filter.iter().filter(|(_, v)| !v.trim().is_empty()).collect();
+        filters.sort();
+        for (column, value) in filters {
+            parts.push(format!(
+                "filter.{}={}",
+                urlencoding::encode(column),
+                urlencoding::encode(value)
+            ));
+        }
+        parts.join("&")
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct HiddenField {
+    pub name: String,
+    pub value: String,
+}
+
+/// Links and state of a paged list, rendered by `_list_pager` and used in the
+/// column headers of the list templates.
+#[derive(Clone, Debug, Serialize)]
+pub struct ListView {
+    page: i64,
+    pages: i64,
+    total: i64,
+    previous: Option<String>,
+    next: Option<String>,
+    /// Link per sortable column, reversing the direction on the current one.
+    sort: HashMap<String, String>,
+    /// Direction marker of the current sort column.
+    sorted: HashMap<String, &'static str>,
+    /// Current filter values, for the filter inputs.
+    filter: HashMap<String, String>,
+    /// State the filter form has to send along.
+    hidden: Vec<HiddenField>,
+}
+
+impl ListView {
+    pub fn new<T>(params: &ListParams, page: &Page<T>, group: Option<i32>, columns: &[String]) -> ListView {
+        let base = params.query_string(group);
+        let link = |page: i64| {
+            if base.is_empty() {
+                format!("?page={page}")
+            } else {
+                format!("?{base}&page={page}")
+            }
+        };
+        let current_sort = params.sort.as_deref().filter(|s| !s.is_empty());
+        let sort = columns
+            .iter()
+            .map(|column| {
+                let desc = current_sort == Some(column.as_str()) && !params.desc;
+                let sorted = ListParams {
+                    page: None,
+                    sort: Some(column.clone()),
+                    desc,
+                    ..params.clone()
+                };
+                (column.clone(), format!("?{}", sorted.query_string(group)))
+            })
+            .collect();
+        let sorted = current_sort
+            .map(|s| (s.to_owned(), if params.desc { "▼" } else { "▲" }))
+            .into_iter()
+            .collect();
+
+        let mut hidden = vec![];
+        if let Some(group) = group {
+            hidden.push(HiddenField {
+                name: "group".to_owned(),
+                value: group.to_string(),
+            });
+        }
+        if let Some(per_page) = params.per_page {
+            hidden.push(HiddenField {
+                name: "per_page".to_owned(),
+                value: per_page.to_string(),
+            });
+        }
+        if let Some(sort) = current_sort {
+            hidden.push(HiddenField {
+                name: "sort".to_owned(),
+                value: sort.to_owned(),
+            });
+            if params.desc {
+                hidden.push(HiddenField {
+                    name: "desc".to_owned(),
+                    value: "true".to_owned(),
+                });
+            }
+        }
+
+        ListView {
+            page: page.page,
+            pages: page.pages,
+            total: page.total,
+            previous: (page.page > 1).then(|| link(page.page - 1)),
+            next: (page.page < page.pages).then(|| link(page.page + 1)),
+            sort,
+            sorted,
+            filter: params.filter.clone(),
+            hidden,
+        }
+    }
+}
+
+/// Owned column keys for [`ListView::new`].
+pub fn columns(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|k| k.to_string()).collect()
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use rocket::{form::Form, http::Status, response::Redirect, Route, State};
+use rocket_dyn_templates::{context, Template};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    auth::{Permission, User},
+    database::Database,
+};
+
+#[derive(Clone, Debug, Serialize)]
+struct SiteTree {
+    pub id: i32,
+    pub name: String,
+    pub buildings: Vec<BuildingTree>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct BuildingTree {
+    pub id: i32,
+    pub name: String,
+    pub rooms: Vec<RoomEntry>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct RoomEntry {
+    pub id: i32,
+    pub name: String,
+    pub count: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct RoomClient {
+    pub uuid: Uuid,
+    pub computer_name: String,
+    pub domain: String,
+    pub os: String,
+    pub os_version: String,
+    pub model: String,
+    pub ips: Vec<String>,
+    pub crit_volumes: usize,
+    pub manual: bool,
+}
+
+#[derive(FromForm)]
+struct NewLocation {
+    parent_id: Option<i32>,
+    name: String,
+}
+
+#[derive(FromForm)]
+struct NewSubnet {
+    subnet: String,
+}
+
+#[get("/")]
+async fn index(database: &State<Database>, user: User) -> Result<Template, Status> {
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let locations = database.location_manager();
+        let counts: HashMap<i32, i64> = locations
+            .get_room_client_counts()
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        let rooms = locations.get_rooms().unwrap_or_default();
+        let buildings = locations.get_buildings().unwrap_or_default();
+        let sites: Vec<SiteTree> = locations
+            .get_sites()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| SiteTree {
+                buildings: buildings
+                    .iter()
+                    .filter(|b| b.site_id == s.id)
+                    .map(|b| BuildingTree {
+                        rooms: rooms
+                            .iter()
+                            .filter(|r| r.building_id == b.id)
+                            .map(|r| RoomEntry {
+                                id: r.id,
+                                name: r.name.clone(),
+                                count: counts.get(&r.id).copied().unwrap_or_default(),
+                            })
+                            .collect(),
+                        id: b.id,
+                        name: b.name.clone(),
+                    })
+                    .collect(),
+                id: s.id,
+                name: s.name,
+            })
+            .collect();
+        Template::render("locations/index", context! { sites, user })
+    })
+    .await)
+}
+
+#[post("/sites", data = "<location>")]
+async fn post_site(database: &State<Database>, location: Form<NewLocation>, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let name = location.name.trim();
+        if !name.is_empty() {
+            if let Err(e) = database.location_manager().create_site(name) {
+                println!("[ERROR] Could not create site {name}: {e}");
+            }
+        }
+        Redirect::to(uri!("/locations", index))
+    })
+    .await)
+}
+
+#[post("/buildings", data = "<location>")]
+async fn post_building(
+    database: &State<Database>,
+    location: Form<NewLocation>,
+    user: User,
+) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let name = location.name.trim();
+        if let (Some(site_id), false) = (location.parent_id, name.is_empty()) {
+            if let Err(e) = database.location_manager().create_building(site_id, name) {
+                println!("[ERROR] Could not create building {name}: {e}");
+            }
+        }
+        Redirect::to(uri!("/locations", index))
+    })
+    .await)
+}
+
+#[post("/rooms", data = "<location>")]
+async fn post_room(database: &State<Database>, location: Form<NewLocation>, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let name = location.name.trim();
+        if let (Some(building_id), false) = (location.parent_id, name.is_empty()) {
+            if let Err(e) = database.location_manager().create_room(building_id, name) {
+                println!("[ERROR] Could not create room {name}: {e}");
+            }
+        }
+        Redirect::to(uri!("/locations", index))
+    })
+    .await)
+}
+
+#[get("/sites/<id>/delete")]
+async fn delete_site(database: &State<Database>, id: i32, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let _ = database.location_manager().delete_site(id);
+        Redirect::to(uri!("/locations", index))
+    })
+    .await)
+}
+
+#[get("/buildings/<id>/delete")]
+async fn delete_building(database: &State<Database>, id: i32, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let _ = database.location_manager().delete_building(id);
+        Redirect::to(uri!("/locations", index))
+    })
+    .await)
+}
+
+#[get("/rooms/<id>/delete")]
+async fn delete_room(database: &State<Database>, id: i32, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let _ = database.location_manager().delete_room(id);
+        Redirect::to(uri!("/locations", index))
+    })
+    .await)
+}
+
+#[get("/rooms/<id>")]
+async fn room(database: &State<Database>, id: i32, user: User) -> Result<Template, Status> {
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let locations = database.location_manager();
+        let room = locations.get_room(id);
+        let room_clients = locations.get_room_clients(id);
+        let subnets = locations.get_room_subnets(id);
+        if let (Ok((room, (building, site))), Ok(room_clients), Ok(subnets)) = (room, room_clients, subnets) {
+            let client_ids: Vec<i32> = room_clients.iter().map(|(l, _)| l.client_id).collect();
+            let mut ips: HashMap<i32, Vec<String>> = HashMap::new();
+            for (client_id, ip) in locations.get_client_ips(&client_ids).unwrap_or_default() {
+                ips.entry(client_id).or_default().push(ip);
+            }
+            let crit_volumes = database.get_system_status_volume_crit(None).unwrap_or_default();
+            let clients: Vec<RoomClient> = room_clients
+                .into_iter()
+                .map(|(location, (c, os_info, model))| RoomClient {
+                    crit_volumes: crit_volumes.iter().filter(|(v, _)| v.client_id == c.id).count(),
+                    ips: ips.remove(&c.id).unwrap_or_default(),
+                    uuid: c.uuid,
+                    computer_name: os_info.as_ref().map(|o| o.computer_name.clone()).unwrap_or_default(),
+                    domain: os_info.as_ref().and_then(|o| o.domain.clone()).unwrap_or_default(),
+                    os: os_info.as_ref().and_then(|o| o.os.clone()).unwrap_or_default(),
+                    os_version: os_info.and_then(|o| o.os_version).unwrap_or_default(),
+                    model: model
+                        .map(|m| format!("{} {}", m.manufacturer, m.model_family))
+                        .unwrap_or_default(),
+                    manual: location.manual,
+                })
+                .collect();
+            Template::render(
+                "locations/room",
+                context! { room, building, site, clients, subnets, user },
+            )
+        } else {
+            Template::render("locations/room", context! { user })
+        }
+    })
+    .await)
+}
+
+#[post("/rooms/<id>/subnets", data = "<subnet>")]
+async fn post_subnet(
+    database: &State<Database>,
+    id: i32,
+    subnet: Form<NewSubnet>,
+    user: User,
+) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        if let Err(e) = database.location_manager().add_room_subnet(id, &subnet.subnet) {
+            println!("[ERROR] Could not add subnet {} to room {id}: {e}", subnet.subnet);
+        }
+        Redirect::to(uri!("/locations", room(id)))
+    })
+    .await)
+}
+
+#[get("/rooms/<id>/subnets/<subnet_id>/delete")]
+async fn delete_subnet(database: &State<Database>, id: i32, subnet_id: i32, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::EditInventory)?;
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let _ = database.location_manager().delete_room_subnet(subnet_id);
+        Redirect::to(uri!("/locations", room(id)))
+    })
+    .await)
+}
+
+#[get("/<_..>", rank = 10)]
+fn catch_all() -> Redirect {
+    Redirect::to(uri!("/auth/login"))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        index,
+        post_site,
+        post_building,
+        post_room,
+        delete_site,
+        delete_building,
+        delete_room,
+        room,
+        post_subnet,
+        delete_subnet,
+        catch_all,
+    ]
+}
@@ -1,11 +1,22 @@
+pub mod agent_error;
+pub mod api_query;
 pub mod api_v1;
 pub mod api_v2;
+pub mod api_v3;
+pub mod assets;
 pub mod auth;
 pub mod clients;
 mod display_util;
+pub mod export;
+mod freshness;
+mod group_filter;
 pub mod hardware;
+mod list;
+pub mod locations;
 mod ms_magic;
+mod notice;
 pub mod profile;
+pub mod search;
 pub mod settings;
 pub mod software;
 pub mod system_status;
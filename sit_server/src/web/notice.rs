@@ -0,0 +1,18 @@
+use rocket::request::FlashMessage;
+use serde::Serialize;
+
+/// The outcome of a form shown after the redirect, rendered by `_notice`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Notice {
+    success: bool,
+    message: String,
+}
+
+impl Notice {
+    pub fn from_flash(flash: Option<FlashMessage<'_>>) -> Option<Notice> {
+        flash.map(|f| Notice {
+            success: f.kind() == "success",
+            message: f.message().to_string(),
+        })
+    }
+}
@@ -1,11 +1,19 @@
-use rocket::{response::Redirect, Route, State};
+use rocket::{http::Status, response::Redirect, Route, State};
 use rocket_dyn_templates::{context, Template};
 use serde::Serialize;
 use uuid::Uuid;
 
-use crate::{auth::User, database::Database};
+use crate::{
+    auth::User,
+    database::{Database, Page},
+};
 
-use super::{display_util, ms_magic};
+use super::{
+    display_util,
+    export::ExportLinks,
+    list::{self, ListParams, ListView},
+    ms_magic,
+};
 
 #[derive(Clone, Debug, Serialize)]
 struct Profile {
@@ -31,72 +39,83 @@ struct UserWithProfileCount {
     pub count: i64,
 }
 
-#[get("/")]
-fn index(database: &State<Database>, user: User) -> Template {
-    let profiles: Vec<UserWithProfileCount> = database
-        .user_manager()
-        .get_profiles()
-        .unwrap_or_default()
-        .into_iter()
-        .map(|p| UserWithProfileCount {
-            id: p.id,
-            sid: p.sid,
-            username: p.username.unwrap_or("<_user>".to_owned()),
-            domain: p.domain.unwrap_or("<_domain>".to_owned()),
-            count: p.count,
-        })
-        .collect();
-    Template::render("profile/index", context! { profiles, user })
+#[get("/?<list..>")]
+async fn index(database: &State<Database>, list: ListParams, user: User) -> Result<Template, Status> {
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let export = ExportLinks::new("/profile", &list.query_string(None));
+        let page = database
+            .get_profile_page(&list.query())
+            .unwrap_or_else(|_| Page::empty());
+        let view = ListView::new(&list, &page, None, &list::columns(&["username", "domain", "count"]));
+        let profiles: Vec<UserWithProfileCount> = page
+            .items
+            .into_iter()
+            .map(|p| UserWithProfileCount {
+                id: p.id,
+                sid: p.sid,
+                username: p.username.unwrap_or("<_user>".to_owned()),
+                domain: p.domain.unwrap_or("<_domain>".to_owned()),
+                count: p.count,
+            })
+            .collect();
+        Template::render("profile/index", context! { profiles, list: view, export, user })
+    })
+    .await)
 }
 
 #[get("/<sid>")]
-fn profile(database: &State<Database>, sid: String, user: User) -> Template {
-    let user_id = database.user_manager().get_user_id_for_sid(&sid);
-    if let Ok(Some(user_id)) = user_id {
-        let profiles_result = database.user_manager().get_profile_info(user_id);
-        if let Ok(profiles) = profiles_result {
-            let profile: Vec<Profile> = profiles
-                .into_iter()
-                .map(|(up, c, os)| Profile {
-                    client_uuid: c.uuid,
-                    os_computer_name: os
-                        .map_or("<_computer_name>".to_string(), |os| {
-                            if let Some(domain) = os.domain {
-                                format!("{}.{}", os.computer_name, domain)
-                            } else {
-                                os.computer_name
-                            }
-                        }),
-                    health_status: ms_magic::resolve_profile_health_status(up.health_status),
-                    roaming_configured: up.roaming_configured,
-                    roaming_path: up.roaming_path,
-                    roaming_preference: up.roaming_preference,
-                    last_use_time: up
-                        .last_use_time
-                        .map(display_util::format_date_time)
-                        .unwrap_or_default(),
-                    last_download_time: up
-                        .last_download_time
-                        .map(display_util::format_date_time)
-                        .unwrap_or_default(),
-                    last_upload_time: up
-                        .last_upload_time
-                        .map(display_util::format_date_time)
-                        .unwrap_or_default(),
-                    status: ms_magic::resolve_profile_status(up.status),
-                    size: display_util::format_option_big_decimal(
-                        &up.size,
-                        display_util::format_filesize_byte,
-                    ),
-                })
-                .collect();
-            Template::render("profile/profile", context! { profile, user })
+async fn profile(database: &State<Database>, sid: String, user: User) -> Result<Template, Status> {
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let user_id = database.user_manager().get_user_id_for_sid(&sid);
+        if let Ok(Some(user_id)) = user_id {
+            let profiles_result = database.user_manager().get_profile_info(user_id);
+            if let Ok(profiles) = profiles_result {
+                let profile: Vec<Profile> = profiles
+                    .into_iter()
+                    .map(|(up, c, os)| Profile {
+                        client_uuid: c.uuid,
+                        os_computer_name: os
+                            .map_or("<_computer_name>".to_string(), |os| {
+                                if let Some(domain) = os.domain {
+                                    format!("{}.{}", os.computer_name, domain)
+                                } else {
+                                    os.computer_name
+                                }
+                            }),
+                        health_status: ms_magic::resolve_profile_health_status(up.health_status),
+                        roaming_configured: up.roaming_configured,
+                        roaming_path: up.roaming_path,
+                        roaming_preference: up.roaming_preference,
+                        last_use_time: up
+                            .last_use_time
+                            .map(display_util::format_date_time)
+                            .unwrap_or_default(),
+                        last_download_time: up
+                            .last_download_time
+                            .map(display_util::format_date_time)
+                            .unwrap_or_default(),
+                        last_upload_time: up
+                            .last_upload_time
+                            .map(display_util::format_date_time)
+                            .unwrap_or_default(),
+                        status: ms_magic::resolve_profile_status(up.status),
+                        size: display_util::format_option_big_decimal(
+                            &up.size,
+                            display_util::format_filesize_byte,
+                        ),
+                    })
+                    .collect();
+                Template::render("profile/profile", context! { profile, user })
+            } else {
+                Template::render("profile/profile", context! {})
+            }
         } else {
             Template::render("profile/profile", context! {})
         }
-    } else {
-        Template::render("profile/profile", context! {})
-    }
+    })
+    .await)
 }
 
 #[get("/<_..>", rank = 10)]
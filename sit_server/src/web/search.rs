@@ -0,0 +1,25 @@
+use rocket::{http::Status, response::Redirect, Route, State};
+use rocket_dyn_templates::{context, Template};
+
+use crate::{auth::User, database::Database};
+
+#[get("/?<q>")]
+async fn index(database: &State<Database>, q: Option<String>, user: User) -> Result<Template, Status> {
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let query = q.as_deref().unwrap_or_default().trim();
+        let results = database.search_manager().search(query).unwrap_or_default();
+        let found = !results.is_empty();
+        Template::render("search/index", context! { query, results, found, user })
+    })
+    .await)
+}
+
+#[get("/<_..>", rank = 10)]
+fn catch_all() -> Redirect {
+    Redirect::to(uri!("/auth/login"))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![index, catch_all]
+}
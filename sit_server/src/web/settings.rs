@@ -1,8 +1,20 @@
-use rocket::{form::Form, response::Redirect, Route, State};
+use anyhow::{anyhow, bail};
+use rocket::{
+    form::Form,
+    http::{uri::Origin, Status},
+    request::FlashMessage,
+    response::{Flash, Redirect},
+    Route, State,
+};
 use rocket_dyn_templates::{context, Template};
 use serde::Serialize;
 
-use crate::{auth::User, database::Database};
+use crate::{
+    auth::{PasswordPolicy, Permission, Role, User},
+    database::{AttributeKind, Database, GroupRule, RuleCondition, RuleField},
+};
+
+use super::notice::Notice;
 
 #[derive(Clone, Debug, Serialize)]
 pub struct SoftwareInfo {
@@ -18,104 +30,702 @@ pub struct SoftwareVersion {
 }
 
 #[derive(FromForm)]
-struct Login<'r> {
-    username: &'r str,
-    password: &'r str,
+struct NewUser {
+    username: String,
+    password: String,
+    password_confirmation: String,
+    role: String,
+}
+
+#[derive(FromForm)]
+struct NewPassword {
+    password: String,
+    password_confirmation: String,
+}
+
+#[derive(FromForm)]
+struct UserAccess {
+    role: String,
+    groups: Vec<i32>,
+}
+
+#[derive(FromForm)]
+struct NewGroup {
+    name: String,
+    description: String,
+}
+
+#[derive(FromForm)]
+struct NewAttribute {
+    name: String,
+    kind: String,
+    options: String,
+}
+
+#[derive(FromForm)]
+struct RuleForm {
+    conditions: Vec<ConditionForm>,
+}
+
+#[derive(FromForm)]
+struct ConditionForm {
+    field: String,
+    value: String,
+    version: String,
+}
+
+/// Selectable rule fields in the order of the editor.
+const RULE_FIELDS: &[(&str, &str)] = &[
+    ("os_name", "Betriebssystem"),
+    ("os_version", "OS-Version"),
+    ("domain", "Domäne"),
+    ("computer_name", "Computername"),
+    ("model", "Modell"),
+    ("software", "Software"),
+    ("memory_min", "Arbeitsspeicher mind. (GiB)"),
+    ("memory_max", "Arbeitsspeicher max. (GiB)"),
+];
+
+/// Attribute kinds with their labels in the order of the form.
+const ATTRIBUTE_KINDS: &[(AttributeKind, &str)] = &[
+    (AttributeKind::Text, "Text"),
+    (AttributeKind::Number, "Zahl"),
+    (AttributeKind::Date, "Datum"),
+    (AttributeKind::Enum, "Auswahl"),
+    (AttributeKind::Boolean, "Ja/Nein"),
+];
+
+/// Empty rows offered below the existing conditions.
+const EMPTY_RULE_ROWS: usize = 3;
+/// Failed logins listed below the users.
+const FAILED_LOGINS_SHOWN: i64 = 20;
+
+#[derive(Clone, Debug, Serialize)]
+struct RuleFieldOption {
+    pub key: String,
+    pub label: String,
+    pub selected: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct AttributeRow {
+    pub id: i32,
+    pub name: String,
+    pub kind: &'static str,
+    pub options: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct AttributeKindOption {
+    pub key: &'static str,
+    pub label: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct UserRow {
+    pub id: i32,
+    pub username: String,
+    pub role: &'static str,
+    pub groups: Vec<String>,
+    pub disabled: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct FailedLoginRow {
+    pub username: String,
+    pub ip: Option<String>,
+    pub attempted: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct RoleOption {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub selected: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct GroupOption {
+    pub id: i32,
+    pub name: String,
+    pub checked: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct RuleRow {
+    pub fields: Vec<RuleFieldOption>,
+    pub value: String,
+    pub version: String,
+}
+
+impl RuleForm {
+    /// Rows without a value are left out, a rule without conditions is no rule.
+    fn to_rule(&self) -> Result<Option<GroupRule>, String> {
+        let mut conditions = vec![];
+        for c in self.conditions.iter().filter(|c| !c.value.trim().is_empty()) {
+            conditions.push(RuleCondition {
+                field: c.field.parse::<RuleField>().map_err(|e| e.to_string())?,
+                value: c.value.trim().to_string(),
+                version: Some(c.version.trim())
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string),
+            });
+        }
+        if conditions.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(GroupRule { conditions }))
+        }
+    }
+}
+
+/// Editor rows for `conditions`, custom attributes are offered after the built-in fields.
+fn rule_rows(db: &Database, conditions: &[RuleCondition]) -> Vec<RuleRow> {
+    let fields: Vec<(String, String)> = RULE_FIELDS
+        .iter()
+        .map(|(key, label)| (key.to_string(), label.to_string()))
+        .chain(
+            db.attribute_manager()
+                .get_definitions()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| (RuleField::Attribute(a.id).to_string(), format!("Attribut: {}", a.name))),
+        )
+        .collect();
+    let row = |key: &str, value: String, version: String| RuleRow {
+        fields: fields
+            .iter()
+            .map(|(k, label)| RuleFieldOption {
+                key: k.clone(),
+                label: label.clone(),
+                selected: k == key,
+            })
+            .collect(),
+        value,
+        version,
+    };
+    conditions
+        .iter()
+        .map(|c| {
+            row(
+                &c.field.to_string(),
+                c.value.clone(),
+                c.version.clone().unwrap_or_default(),
+            )
+        })
+        .chain((0..EMPTY_RULE_ROWS).map(|_| row("", String::new(), String::new())))
+        .collect()
 }
 
 #[get("/")]
-fn index(user: User) -> Template {
-    Template::render("settings/index", context! { user })
+fn index(user: User) -> Result<Template, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(Template::render("settings/index", context! { user }))
+}
+
+fn role_options(selected: Role) -> Vec<RoleOption> {
+    Role::ALL
+        .iter()
+        .map(|r| RoleOption {
+            key: r.as_str(),
+            label: r.label(),
+            selected: *r == selected,
+        })
+        .collect()
 }
 
 #[get("/users")]
-fn users(db: &State<Database>, user: User) -> Template {
-    let users_result = db.get_auth_users();
-    if let Ok(auth_users) = users_result {
-        Template::render("settings/users", context! { user, auth_users })
-    } else {
-        Template::render("settings/users", context! { user })
-    }
+async fn users(db: &State<Database>, flash: Option<FlashMessage<'_>>, user: User) -> Result<Template, Status> {
+    user.require(Permission::ManageUsers)?;
+    let notice = Notice::from_flash(flash);
+    Ok(db.run(move |db| {
+        let groups = db.group_manager().get_groups().unwrap_or_default();
+        let failed_logins: Vec<FailedLoginRow> = db
+            .login_attempt_manager()
+            .get_failures(FAILED_LOGINS_SHOWN)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| FailedLoginRow {
+                attempted: a.attempted.format("%d.%m.%Y %H:%M").to_string(),
+                username: a.username,
+                ip: a.ip,
+            })
+            .collect();
+        let users_result = db.get_auth_users();
+        if let Ok(auth_users) = users_result {
+            let auth_users: Vec<UserRow> = auth_users
+                .into_iter()
+                .map(|u| UserRow {
+                    role: u.role.parse::<Role>().map(|r| r.label()).unwrap_or_default(),
+                    groups: db
+                        .get_auth_user_groups(u.id)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|id| groups.iter().find(|g| g.id == id).map(|g| g.name.clone()))
+                        .collect(),
+                    disabled: u.disabled,
+                    id: u.id,
+                    username: u.username,
+                })
+                .collect();
+            Template::render("settings/users", context! { user, auth_users, failed_logins, notice })
+        } else {
+            Template::render("settings/users", context! { user, failed_logins, notice })
+        }
+    })
+    .await)
 }
 
 #[get("/users/new")]
-fn new_user(_user: User) -> Template {
-    Template::render("settings/users_new", context! {})
+fn new_user(flash: Option<FlashMessage<'_>>, user: User) -> Result<Template, Status> {
+    user.require(Permission::ManageUsers)?;
+    let roles = role_options(Role::Technician);
+    let notice = Notice::from_flash(flash);
+    Ok(Template::render("settings/users_new", context! { roles, notice }))
+}
+
+/// Checks a password entered twice in a form.
+pub(super) fn validate_new_password(password: &str, confirmation: &str) -> anyhow::Result<()> {
+    if password.is_empty() {
+        bail!("Das Passwort darf nicht leer sein.");
+    }
+    if password != confirmation {
+        bail!("Die Passwörter stimmen nicht überein.");
+    }
+    Ok(())
+}
+
+/// Redirects to `uri` with a notice about `result`. `failure` introduces the
+/// cause of an error.
+fn notify(result: anyhow::Result<()>, uri: Origin<'static>, success: &str, failure: &str) -> Flash<Redirect> {
+    match result {
+        Ok(()) => Flash::success(Redirect::to(uri), success),
+        Err(e) => Flash::error(Redirect::to(uri), format!("{failure}: {e}")),
+    }
 }
 
-#[post("/users/new", data = "<user>")]
-fn post_new_user(db: &State<Database>, user: Form<Login<'_>>, _guard: User) -> Redirect {
-    let result = crate::auth::create_new_user(db, user.username, user.password);
-    if result.is_err() {
-        Redirect::to(uri!("/settings", new_user))
-        // TODO: Add error cause
+/// Creates the user entered in the form after validating it.
+fn create_user(db: &Database, policy: &PasswordPolicy, form: &NewUser) -> anyhow::Result<()> {
+    let username = form.username.trim();
+    if username.is_empty() {
+        bail!("Der Benutzername darf nicht leer sein.");
+    }
+    if db.get_auth_user_by_username(username).is_ok() {
+        bail!("Der Benutzername ist bereits vergeben.");
+    }
+    validate_new_password(&form.password, &form.password_confirmation)?;
+    let role = form.role.parse::<Role>()?;
+    crate::auth::create_new_user(db, policy, username, &form.password, role)
+}
+
+#[post("/users/new", data = "<form>")]
+async fn post_new_user(
+    db: &State<Database>,
+    policy: &State<PasswordPolicy>,
+    form: Form<NewUser>,
+    user: User,
+) -> Result<Flash<Redirect>, Status> {
+    user.require(Permission::ManageUsers)?;
+    let policy = policy.inner().clone();
+    Ok(db.run(move |db| match create_user(db, &policy, &form) {
+        Ok(()) => Flash::success(
+            Redirect::to(uri!("/settings", users)),
+            format!("Der Benutzer {} wurde angelegt.", form.username.trim()),
+        ),
+        Err(e) => Flash::error(
+            Redirect::to(uri!("/settings", new_user)),
+            format!("Der Benutzer konnte nicht angelegt werden: {e}"),
+        ),
+    })
+    .await)
+}
+
+/// The page of the user `id` with role, groups, password and account status.
+fn user_page(db: &Database, id: i32, user: User, notice: Option<Notice>) -> Template {
+    let auth_user = db.get_auth_user_by_id(id);
+    let restriction = db.get_auth_user_groups(id);
+    let groups = db.group_manager().get_groups();
+    if let (Ok(auth_user), Ok(restriction), Ok(groups)) = (auth_user, restriction, groups) {
+        let roles = role_options(auth_user.role.parse().unwrap_or(Role::Auditor));
+        let groups: Vec<GroupOption> = groups
+            .into_iter()
+            .map(|g| GroupOption {
+                checked: restriction.contains(&g.id),
+                id: g.id,
+                name: g.name,
+            })
+            .collect();
+        let own = id == user.user_id;
+        Template::render(
+            "settings/user",
+            context! {
+                id,
+                username: auth_user.username,
+                disabled: auth_user.disabled,
+                own,
+                roles,
+                groups,
+                notice,
+                user,
+            },
+        )
     } else {
-        Redirect::to(uri!("/settings", users))
+        Template::render("settings/user", context! { user })
     }
 }
 
+#[get("/users/<id>")]
+async fn edit_user(
+    db: &State<Database>,
+    id: i32,
+    flash: Option<FlashMessage<'_>>,
+    user: User,
+) -> Result<Template, Status> {
+    user.require(Permission::ManageUsers)?;
+    let notice = Notice::from_flash(flash);
+    Ok(db.run(move |db| user_page(db, id, user, notice)).await)
+}
+
+#[post("/users/<id>", data = "<access>")]
+async fn post_edit_user(
+    db: &State<Database>,
+    id: i32,
+    access: Form<UserAccess>,
+    user: User,
+) -> Result<Flash<Redirect>, Status> {
+    user.require(Permission::ManageUsers)?;
+    Ok(db.run(move |db| {
+        let result = access
+            .role
+            .parse::<Role>()
+            .and_then(|role| crate::auth::set_role(db, id, role))
+            .and_then(|_| db.set_auth_user_groups(id, &access.groups));
+        notify(
+            result,
+            uri!("/settings", edit_user(id)),
+            "Rolle und Gruppen wurden gespeichert.",
+            "Rolle und Gruppen konnten nicht gespeichert werden",
+        )
+    })
+    .await)
+}
+
+/// Sets a new password for the user `id` and logs the user out everywhere.
+#[post("/users/<id>/password", data = "<password>")]
+async fn post_user_password(
+    db: &State<Database>,
+    policy: &State<PasswordPolicy>,
+    id: i32,
+    password: Form<NewPassword>,
+    user: User,
+) -> Result<Flash<Redirect>, Status> {
+    user.require(Permission::ManageUsers)?;
+    let policy = policy.inner().clone();
+    Ok(db.run(move |db| {
+        let result = validate_new_password(&password.password, &password.password_confirmation)
+            .and_then(|_| db.get_auth_user_by_id(id))
+            .and_then(|u| crate::auth::set_new_password(db, &policy, &u.username, &password.password))
+            .and_then(|_| db.delete_sessions_of_user(id));
+        notify(
+            result,
+            uri!("/settings", edit_user(id)),
+            "Das Passwort wurde zurückgesetzt.",
+            "Das Passwort konnte nicht zurückgesetzt werden",
+        )
+    })
+    .await)
+}
+
+#[post("/users/<id>/disable")]
+async fn disable_user(db: &State<Database>, id: i32, user: User) -> Result<Flash<Redirect>, Status> {
+    user.require(Permission::ManageUsers)?;
+    Ok(db.run(move |db| {
+        let result = if id == user.user_id {
+            Err(anyhow!("Das eigene Konto kann nicht deaktiviert werden."))
+        } else {
+            crate::auth::set_disabled(db, id, true)
+        };
+        notify(
+            result,
+            uri!("/settings", users),
+            "Der Benutzer wurde deaktiviert.",
+            "Der Benutzer konnte nicht deaktiviert werden",
+        )
+    })
+    .await)
+}
+
+#[post("/users/<id>/enable")]
+async fn enable_user(db: &State<Database>, id: i32, user: User) -> Result<Flash<Redirect>, Status> {
+    user.require(Permission::ManageUsers)?;
+    Ok(db.run(move |db| {
+        notify(
+            crate::auth::set_disabled(db, id, false),
+            uri!("/settings", users),
+            "Der Benutzer wurde aktiviert.",
+            "Der Benutzer konnte nicht aktiviert werden",
+        )
+    })
+    .await)
+}
+
+#[post("/users/<id>/delete")]
+async fn delete_user(db: &State<Database>, id: i32, user: User) -> Result<Flash<Redirect>, Status> {
+    user.require(Permission::ManageUsers)?;
+    Ok(db.run(move |db| {
+        let result = if id == user.user_id {
+            Err(anyhow!("Das eigene Konto kann nicht gelöscht werden."))
+        } else {
+            db.get_auth_user_by_id(id)
+                .and_then(|u| crate::auth::delete_user(db, &u.username))
+        };
+        notify(
+            result,
+            uri!("/settings", users),
+            "Der Benutzer wurde gelöscht.",
+            "Der Benutzer konnte nicht gelöscht werden",
+        )
+    })
+    .await)
+}
+
+#[get("/groups")]
+async fn groups(db: &State<Database>, user: User) -> Result<Template, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        let groups = db.group_manager().get_groups().unwrap_or_default();
+        Template::render("settings/groups", context! { groups, user })
+    })
+    .await)
+}
+
+#[post("/groups/new", data = "<group>")]
+async fn post_new_group(db: &State<Database>, group: Form<NewGroup>, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        let name = group.name.trim();
+        let description = Some(group.description.trim()).filter(|d| !d.is_empty());
+        if !name.is_empty() {
+            if let Err(e) = db.group_manager().create_group(name, description) {
+                println!("[ERROR] Could not create group {name}: {e}");
+            }
+        }
+        Redirect::to(uri!("/settings", groups))
+    })
+    .await)
+}
+
+#[get("/groups/<id>")]
+async fn group(db: &State<Database>, id: i32, user: User) -> Result<Template, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        let group = db.group_manager().get_group(id);
+        let clients = db.get_clients_with_os_info(Some(id));
+        if let (Ok(group), Ok(clients)) = (group, clients) {
+            let rule: GroupRule = group
+                .rule
+                .clone()
+                .and_then(|r| serde_json::from_value(r).ok())
+                .unwrap_or_default();
+            let rule_rows = rule_rows(db, &rule.conditions);
+            Template::render(
+                "settings/group",
+                context! { group, clients, rule_rows, user },
+            )
+        } else {
+            Template::render("settings/group", context! { user })
+        }
+    })
+    .await)
+}
+
+#[post("/groups/<id>/rule", data = "<rule>")]
+async fn post_group_rule(db: &State<Database>, id: i32, rule: Form<RuleForm>, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        match rule.to_rule() {
+            Ok(rule) => {
+                if let Err(e) = db.group_manager().set_rule(id, rule.as_ref()) {
+                    println!("[ERROR] Could not save rule of group {id}: {e}");
+                }
+            }
+            Err(e) => println!("[ERROR] Invalid rule for group {id}: {e}"),
+        }
+        Redirect::to(uri!("/settings", group(id)))
+    })
+    .await)
+}
+
+#[post("/groups/<id>/preview", data = "<rule>")]
+async fn post_group_preview(
+    db: &State<Database>,
+    id: i32,
+    rule: Form<RuleForm>,
+    user: User,
+) -> Result<Template, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        let group = db.group_manager().get_group(id);
+        let rule = rule.to_rule();
+        if let (Ok(group), Ok(rule)) = (group, rule) {
+            let rule = rule.unwrap_or_default();
+            let preview = db.group_manager().preview(&rule);
+            let rule_rows = rule_rows(db, &rule.conditions);
+            match preview {
+                Ok(preview) => Template::render(
+                    "settings/group",
+                    context! { group, preview, previewed: true, rule_rows, user },
+                ),
+                Err(e) => Template::render(
+                    "settings/group",
+                    context! { group, preview_error: e.to_string(), previewed: true, rule_rows, user },
+                ),
+            }
+        } else {
+            Template::render("settings/group", context! { user })
+        }
+    })
+    .await)
+}
+
+#[get("/groups/<id>/delete")]
+async fn delete_group(db: &State<Database>, id: i32, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        let _ = db.group_manager().delete_group(id);
+        Redirect::to(uri!("/settings", groups))
+    })
+    .await)
+}
+
+#[get("/attributes")]
+async fn attributes(db: &State<Database>, user: User) -> Result<Template, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        let attributes: Vec<AttributeRow> = db
+            .attribute_manager()
+            .get_definitions()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| AttributeRow {
+                id: a.id,
+                kind: ATTRIBUTE_KINDS
+                    .iter()
+                    .find(|(k, _)| *k == a.kind())
+                    .map(|(_, label)| *label)
+                    .unwrap_or_default(),
+                options: a.option_list().into_iter().map(str::to_string).collect(),
+                name: a.name,
+            })
+            .collect();
+        let kinds: Vec<AttributeKindOption> = ATTRIBUTE_KINDS
+            .iter()
+            .map(|(k, label)| AttributeKindOption {
+                key: k.as_str(),
+                label,
+            })
+            .collect();
+        Template::render("settings/attributes", context! { attributes, kinds, user })
+    })
+    .await)
+}
+
+#[post("/attributes/new", data = "<attribute>")]
+async fn post_new_attribute(
+    db: &State<Database>,
+    attribute: Form<NewAttribute>,
+    user: User,
+) -> Result<Redirect, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        let name = attribute.name.trim();
+        if !name.is_empty() {
+            let result = attribute
+                .kind
+                .parse::<AttributeKind>()
+                .and_then(|kind| db.attribute_manager().create_definition(name, kind, &attribute.options));
+            if let Err(e) = result {
+                println!("[ERROR] Could not create attribute {name}: {e}");
+            }
+        }
+        Redirect::to(uri!("/settings", attributes))
+    })
+    .await)
+}
+
+#[get("/attributes/<id>/delete")]
+async fn delete_attribute(db: &State<Database>, id: i32, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        let _ = db.attribute_manager().delete_definition(id);
+        Redirect::to(uri!("/settings", attributes))
+    })
+    .await)
+}
+
 #[get("/service")]
-fn service_index(user: User) -> Template {
-    Template::render("settings/service", context! { user })
+fn service_index(user: User) -> Result<Template, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(Template::render("settings/service", context! { user }))
 }
 
 #[get("/service/software")]
-fn service_software(db: &State<Database>, user: User) -> Template {
-    let mut delete_software_version: Vec<SoftwareVersion> = vec![];
-    let mut delete_software: Vec<SoftwareInfo> = vec![];
-    let software_list = db.get_software_list().unwrap_or_default();
-    for software in software_list {
-        let versions = db.get_software_versions(software.id).unwrap_or_default();
-        for version in &versions {
-            if version.count == 0 {
-                delete_software_version.push(SoftwareVersion {
-                    id: version.id,
-                    name: software.name.clone(),
-                    version: version.version.clone(),
+async fn service_software(db: &State<Database>, user: User) -> Result<Template, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        let mut delete_software_version: Vec<SoftwareVersion> = vec![];
+        let mut delete_software: Vec<SoftwareInfo> = vec![];
+        let software_list = db.get_software_list(None).unwrap_or_default();
+        for software in software_list {
+            let versions = db.get_software_versions(software.id, None).unwrap_or_default();
+            for version in &versions {
+                if version.count == 0 {
+                    delete_software_version.push(SoftwareVersion {
+                        id: version.id,
+                        name: software.name.clone(),
+                        version: version.version.clone(),
+                    });
+                }
+            }
+            if versions.is_empty() {
+                delete_software.push(SoftwareInfo {
+                    id: software.id,
+                    name: software.name,
                 });
             }
         }
-        if versions.is_empty() {
-            delete_software.push(SoftwareInfo {
-                id: software.id,
-                name: software.name,
-            });
-        }
-    }
-    delete_software_version.sort_by_key(|f| f.name.clone());
-    delete_software.sort_by_key(|f| f.name.clone());
-    Template::render(
-        "settings/service_software",
-        context! { delete_software_version, delete_software, user },
-    )
+        delete_software_version.sort_by_key(|f| f.name.clone());
+        delete_software.sort_by_key(|f| f.name.clone());
+        Template::render(
+            "settings/service_software",
+            context! { delete_software_version, delete_software, user },
+        )
+    })
+    .await)
 }
 
 #[get("/service/software/cleanup/version")]
-fn service_software_cleanup_version(db: &State<Database>, _user: User) -> Redirect {
-    let software_list = db.get_software_list().unwrap_or_default();
-    for software in software_list {
-        let versions = db.get_software_versions(software.id).unwrap_or_default();
-        for version in &versions {
-            if version.count == 0 {
-                let _ = db.delete_software_version(version.id);
-            }
+async fn service_software_cleanup_version(db: &State<Database>, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        if let Err(e) = db.delete_unused_software_versions() {
+            println!("[ERROR] Could not delete unused software versions: {e}");
         }
-    }
-    Redirect::to(uri!("/settings", service_software))
+        Redirect::to(uri!("/settings", service_software))
+    })
+    .await)
 }
 
 #[get("/service/software/cleanup/info")]
-fn service_software_cleanup_list(db: &State<Database>, _user: User) -> Redirect {
-    let software_list = db.get_software_list().unwrap_or_default();
-    for software in software_list {
-        let versions = db.get_software_versions(software.id);
-        if let Ok(versions) = versions {
-            if versions.is_empty() {
-                let _ = db.delete_software_info(software.id);
-            }
+async fn service_software_cleanup_list(db: &State<Database>, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(db.run(move |db| {
+        if let Err(e) = db.delete_software_without_versions() {
+            println!("[ERROR] Could not delete software without versions: {e}");
         }
-    }
-    Redirect::to(uri!("/settings", service_software))
+        Redirect::to(uri!("/settings", service_software))
+    })
+    .await)
 }
 
 pub fn routes() -> Vec<Route> {
@@ -124,6 +734,21 @@ pub fn routes() -> Vec<Route> {
         users,
         new_user,
         post_new_user,
+        edit_user,
+        post_edit_user,
+        post_user_password,
+        disable_user,
+        enable_user,
+        delete_user,
+        groups,
+        post_new_group,
+        group,
+        post_group_rule,
+        post_group_preview,
+        delete_group,
+        attributes,
+        post_new_attribute,
+        delete_attribute,
         service_index,
         service_software,
         service_software_cleanup_version,
@@ -1,8 +1,18 @@
-use rocket::{response::Redirect, Route, State};
+use rocket::{http::Status, response::Redirect, Route, State};
 use rocket_dyn_templates::{context, Template};
 use serde::Serialize;
 
-use crate::{auth::User, database::Database};
+use crate::{
+    auth::{Permission, User},
+    database::{Database, Page},
+};
+
+use super::{
+    export::ExportLinks,
+    freshness::Freshness,
+    group_filter::GroupFilter,
+    list::{self, ListParams, ListView},
+};
 
 #[derive(Clone, Debug, Serialize)]
 pub struct SoftwareVersionWithCount {
@@ -17,134 +27,215 @@ fn index(user: User) -> Template {
     Template::render("software/index", context! { user })
 }
 
-#[get("/software")]
-fn software_list(database: &State<Database>, user: User) -> Template {
-    let software_info = database.get_software_list().unwrap_or_default();
-    Template::render(
-        "software/software_list",
-        context! { software: software_info, user },
-    )
-}
-
-#[get("/software/<id>")]
-fn software(database: &State<Database>, id: i32, user: User) -> Template {
-    let software_info = database.get_software_info(id);
-    let software_versions = database.get_software_versions(id);
-    if let (Ok(software_info), Ok(software_versions)) = (software_info, software_versions) {
-        let software_versions: Vec<SoftwareVersionWithCount> = software_versions
-            .into_iter()
-            .map(|sv| SoftwareVersionWithCount {
-                id: sv.id,
-                software_id: sv.software_id,
-                version: if sv.version.is_empty() { "<_version>".to_string() } else { sv.version },
-                count: sv.count,
-            })
-            .collect();
+#[get("/software?<group>&<list..>")]
+async fn software_list(database: &State<Database>, group: Option<i32>, list: ListParams, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let export = ExportLinks::new("/software/software", &list.query_string(group));
+        let page = database
+            .get_software_page(group, &list.query())
+            .unwrap_or_else(|_| Page::empty());
+        let list = ListView::new(&list, &page, group, &list::columns(&["name", "publisher"]));
         Template::render(
-            "software/software",
-            context! { software_info, software_versions, user },
+            "software/software_list",
+            context! { software: page.items, list, export, group_filter, user },
         )
-    } else {
-        Template::render("software/software", context! {})
-    }
+    })
+    .await
 }
 
-#[get("/software/<id>/computer")]
-fn software_computer(database: &State<Database>, id: i32, user: User) -> Template {
-    let software_info = database.get_software_info(id);
-    let computer_list = database.get_software_computer_list(id);
-    if let (Ok(software_info), Ok(computer_list)) = (software_info, computer_list) {
-        Template::render(
-            "software/software_computer_list",
-            context! { software_info, computer_list, user },
-        )
-    } else {
-        Template::render("software/software_computer_list", context! {})
-    }
+#[get("/software/<id>?<group>")]
+async fn software(database: &State<Database>, id: i32, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let freshness = Freshness::new(database);
+        let software_info = database.get_software_info(id);
+        let software_versions = database.statistics_manager().get_software_versions(id, group);
+        let export = ExportLinks::for_group(&format!("/software/software/{id}"), group);
+        if let (Ok(software_info), Ok(software_versions)) = (software_info, software_versions) {
+            let software_versions: Vec<SoftwareVersionWithCount> = software_versions
+                .into_iter()
+                .map(|sv| SoftwareVersionWithCount {
+                    id: sv.id,
+                    software_id: sv.software_id,
+                    version: if sv.version.is_empty() { "<_version>".to_string() } else { sv.version },
+                    count: sv.count,
+                })
+                .collect();
+            Template::render(
+                "software/software",
+                context! { software_info, software_versions, export, group_filter, freshness, user },
+            )
+        } else {
+            Template::render("software/software", context! {})
+        }
+    })
+    .await
 }
 
-#[get("/software/<_>/version/<id>")]
-fn software_version(database: &State<Database>, id: i32, user: User) -> Template {
-    let software_version = database.get_software_version(id);
-    if let Ok(software_version) = software_version {
-        let software_info = database.get_software_info(software_version.software_id);
-        let software_versions_list = database.get_software_version_clients(id);
-        if let (Ok(software_versions_list), Ok(software_info)) =
-            (software_versions_list, software_info)
-        {
-            return Template::render(
-                "software/software_version",
-                context! { software_info, software_version, software_versions_list, user },
-            );
+#[get("/software/<id>/computer?<group>")]
+async fn software_computer(database: &State<Database>, id: i32, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let software_info = database.get_software_info(id);
+        let computer_list = database.get_software_computer_list(id, group);
+        let export = ExportLinks::for_group(&format!("/software/software/{id}/computer"), group);
+        if let (Ok(software_info), Ok(computer_list)) = (software_info, computer_list) {
+            Template::render(
+                "software/software_computer_list",
+                context! { software_info, computer_list, export, group_filter, user },
+            )
+        } else {
+            Template::render("software/software_computer_list", context! {})
         }
-    }
-    Template::render("software/software_version", context! {})
+    })
+    .await
 }
 
-#[get("/os")]
-fn os_list(database: &State<Database>, user: User) -> Template {
-    let os_list = database.get_os_list().unwrap_or_default();
-    Template::render(
-        "software/os_list",
-        context! { os: os_list, user },
-    )
+#[get("/software/<_>/version/<id>?<group>")]
+async fn software_version(database: &State<Database>, id: i32, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let software_version = database.get_software_version(id);
+        if let Ok(software_version) = software_version {
+            let software_info = database.get_software_info(software_version.software_id);
+            let software_versions_list = database.get_software_version_clients(id, group);
+            let export = ExportLinks::for_group(
+                &format!("/software/software/{}/version/{id}", software_version.software_id),
+                group,
+            );
+            if let (Ok(software_versions_list), Ok(software_info)) =
+                (software_versions_list, software_info)
+            {
+                return Template::render(
+                    "software/software_version",
+                    context! { software_info, software_version, software_versions_list, export, group_filter, user },
+                );
+            }
+        }
+        Template::render("software/software_version", context! {})
+    })
+    .await
 }
 
-#[get("/os/<name>")]
-fn os_versions(database: &State<Database>, name: String, user: User) -> Template {
-    let os_version = database.get_os_versions(name.clone());
-    if let Ok(os_version) = os_version {
+#[get("/os?<group>")]
+async fn os_list(database: &State<Database>, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let freshness = Freshness::new(database);
+        let os_list = database.statistics_manager().get_os_list(group).unwrap_or_default();
+        let export = ExportLinks::for_group("/software/os", group);
         Template::render(
-            "software/os_version",
-            context! { os_name: name, os_version, user },
+            "software/os_list",
+            context! { os: os_list, export, group_filter, freshness, user },
         )
-    } else {
-        Template::render("software/os_version", context! {})
-    }
+    })
+    .await
 }
 
-#[get("/os/<name>/computer")]
-fn os_computer(database: &State<Database>, name: String, user: User) -> Template {
-    let os_computer = database.get_os_client_list(name.clone());
-    if let Ok(os_computer) = os_computer {
-        Template::render(
-            "software/os_computer",
-            context! { os_name: name, os_computer: os_computer, user },
-        )
-    } else {
-        Template::render("software/os_computer", context! {})
-    }
+#[get("/os/<name>?<group>")]
+async fn os_versions(database: &State<Database>, name: String, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let os_version = database.get_os_versions(name.clone(), group);
+        let export = ExportLinks::for_group(&format!("/software/os/{}", urlencoding::encode(&name)), group);
+        if let Ok(os_version) = os_version {
+            Template::render(
+                "software/os_version",
+                context! { os_name: name, os_version, export, group_filter, user },
+            )
+        } else {
+            Template::render("software/os_version", context! {})
+        }
+    })
+    .await
 }
 
-#[get("/os/<name>/version/<version>")]
-fn os_version_computer(database: &State<Database>, name: String, version: String, user: User) -> Template {
-    let os_version_computer = database.get_os_version_client_list(name, version);
-    if let Ok(os_version_computer) = os_version_computer {
-        Template::render(
-            "software/os_version_computer",
-            context! { os_name: os_version_computer.os, os_version: os_version_computer.os_version, os_version_computer: os_version_computer.list, user },
-        )
-    } else {
-        Template::render("software/os_version_computer", context! {})
-    }
+#[get("/os/<name>/computer?<group>")]
+async fn os_computer(database: &State<Database>, name: String, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let os_computer = database.get_os_client_list(name.clone(), group);
+        let export = ExportLinks::for_group(&format!("/software/os/{}/computer", urlencoding::encode(&name)), group);
+        if let Ok(os_computer) = os_computer {
+            Template::render(
+                "software/os_computer",
+                context! { os_name: name, os_computer: os_computer, export, group_filter, user },
+            )
+        } else {
+            Template::render("software/os_computer", context! {})
+        }
+    })
+    .await
 }
 
-#[get("/license")]
-fn license_list(database: &State<Database>, user: User) -> Template {
-    let license_info = database.get_license_list().unwrap_or_default();
-    Template::render(
-        "software/license_list",
-        context! { license: license_info, user },
-    )
+#[get("/os/<name>/version/<version>?<group>")]
+async fn os_version_computer(database: &State<Database>, name: String, version: String, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let export = ExportLinks::for_group(
+            &format!(
+                "/software/os/{}/version/{}",
+                urlencoding::encode(&name),
+                urlencoding::encode(&version)
+            ),
+            group,
+        );
+        let os_version_computer = database.get_os_version_client_list(name, version, group);
+        if let Ok(os_version_computer) = os_version_computer {
+            Template::render(
+                "software/os_version_computer",
+                context! { os_name: os_version_computer.os, os_version: os_version_computer.os_version, os_version_computer: os_version_computer.list, export, group_filter, user },
+            )
+        } else {
+            Template::render("software/os_version_computer", context! {})
+        }
+    })
+    .await
 }
 
-#[get("/license/<name>")]
-fn license_computer(database: &State<Database>, name: String, user: User) -> Template {
-    let license_info = database.get_license_with_computers(&name).unwrap_or_default();
-    Template::render(
-        "software/license_computer",
-        context! { license: license_info, user },
-    )
+#[get("/license?<group>")]
+async fn license_list(database: &State<Database>, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let license_info = database.get_license_list(group).unwrap_or_default();
+        let export = ExportLinks::for_group("/software/license", group);
+        Template::render(
+            "software/license_list",
+            context! { license: license_info, export, group_filter, user },
+        )
+    })
+    .await
+}
+
+#[get("/license/<name>?<group>")]
+async fn license_computer(
+    database: &State<Database>,
+    name: String,
+    group: Option<i32>,
+    user: User,
+) -> Result<Template, Status> {
+    user.require(Permission::ViewLicenseKeys)?;
+    let group = user.groups.scope(group);
+    Ok(database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let license_info = database.get_license_with_computers(&name, group).unwrap_or_default();
+        let export = ExportLinks::for_group(&format!("/software/license/{}", urlencoding::encode(&name)), group);
+        Template::render(
+            "software/license_computer",
+            context! { license: license_info, export, group_filter, user },
+        )
+    })
+    .await)
 }
 
 #[get("/<_..>", rank = 10)]
@@ -1,11 +1,16 @@
-use rocket::{response::Redirect, Route, State};
+use chrono::Utc;
+use rocket::{http::Status, response::Redirect, Route, State};
 use rocket_dyn_templates::{context, Template};
 use serde::Serialize;
 use uuid::Uuid;
 
-use crate::{auth::User, database::Database};
+use crate::{
+    auth::{Permission, User},
+    database::{Database, STATUS_FAILED},
+    ingest::IngestKind,
+};
 
-use super::display_util;
+use super::{display_util, export::ExportLinks, group_filter::GroupFilter};
 
 #[derive(Clone, Debug, Serialize)]
 struct VolumeStatus {
@@ -21,47 +26,165 @@ struct VolumeStatus {
     pub occupied_percentage: String,
 }
 
-#[get("/")]
-fn index(database: &State<Database>, user: User) -> Template {
-    let crit_volume = database
-        .get_system_status_volume_crit()
-        .unwrap_or_default()
-        .len();
-    Template::render("system_status/index", context! { crit_volume, user })
+#[get("/?<group>")]
+async fn index(database: &State<Database>, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let crit_volume = database
+            .get_system_status_volume_crit(group)
+            .unwrap_or_default()
+            .len();
+        Template::render("system_status/index", context! { crit_volume, group_filter, user })
+    })
+    .await
 }
 
-#[get("/volumes")]
-fn volumes(database: &State<Database>, user: User) -> Template {
-    let volumes: Vec<VolumeStatus> = database
-        .get_system_status_volume_crit()
-        .unwrap_or_default()
-        .into_iter()
-        .map(|(v, (c, os))| VolumeStatus {
-            uuid: c.uuid,
-            computer_name: os.computer_name,
-            domain_name: os.domain.unwrap_or_default(),
-            drive_letter: v.drive_letter,
-            label: v.label,
-            file_system: v.file_system,
-            capacity: display_util::format_big_decimal(
-                &v.capacity,
-                display_util::format_filesize_byte,
-            ),
-            free_space: display_util::format_big_decimal(
-                &v.free_space,
-                display_util::format_filesize_byte,
-            ),
-            occupied_space: display_util::format_big_decimal(
-                &(&v.capacity - &v.free_space),
-                display_util::format_filesize_byte,
-            ),
-            occupied_percentage: display_util::format_bd_percentage(
-                &(&v.capacity - &v.free_space),
-                &v.capacity,
-            ),
-        })
-        .collect();
-    Template::render("system_status/volumes", context! { volumes, user })
+#[get("/volumes?<group>")]
+async fn volumes(database: &State<Database>, group: Option<i32>, user: User) -> Template {
+    let group = user.groups.scope(group);
+    database.run(move |database| {
+        let group_filter = GroupFilter::new(database, &user.groups, group);
+        let export = ExportLinks::for_group("/system-status/volumes", group);
+        let volumes: Vec<VolumeStatus> = database
+            .get_system_status_volume_crit(group)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(v, (c, os))| VolumeStatus {
+                uuid: c.uuid,
+                computer_name: os.computer_name,
+                domain_name: os.domain.unwrap_or_default(),
+                drive_letter: v.drive_letter,
+                label: v.label,
+                file_system: v.file_system,
+                capacity: display_util::format_big_decimal(
+                    &v.capacity,
+                    display_util::format_filesize_byte,
+                ),
+                free_space: display_util::format_big_decimal(
+                    &v.free_space,
+                    display_util::format_filesize_byte,
+                ),
+                occupied_space: display_util::format_big_decimal(
+                    &(&v.capacity - &v.free_space),
+                    display_util::format_filesize_byte,
+                ),
+                occupied_percentage: display_util::format_bd_percentage(
+                    &(&v.capacity - &v.free_space),
+                    &v.capacity,
+                ),
+            })
+            .collect();
+        Template::render("system_status/volumes", context! { volumes, export, group_filter, user })
+    })
+    .await
+}
+
+/// Labels of the ingest kinds, see [`IngestKind`].
+const INGEST_KINDS: [(IngestKind, &str); 8] = [
+    (IngestKind::Os, "Betriebssystem"),
+    (IngestKind::HardwareV1, "Hardware (v1)"),
+    (IngestKind::Hardware, "Hardware"),
+    (IngestKind::Software, "Software"),
+    (IngestKind::Profiles, "Profile"),
+    (IngestKind::Volumes, "Laufwerke"),
+    (IngestKind::Licenses, "Lizenzen"),
+    (IngestKind::Battery, "Akku"),
+];
+
+fn ingest_label(kind: &str) -> String {
+    INGEST_KINDS
+        .iter()
+        .find(|(k, _)| k.as_str() == kind)
+        .map(|(_, label)| label.to_string())
+        .unwrap_or_else(|| kind.to_owned())
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct IngestDepth {
+    pub label: String,
+    pub pending: i64,
+    pub failed: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct FailedJob {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub computer_name: String,
+    pub label: String,
+    pub received: String,
+    pub attempts: i32,
+    pub last_error: String,
+}
+
+#[get("/ingest")]
+async fn ingest(database: &State<Database>, user: User) -> Result<Template, Status> {
+    user.groups.require_unrestricted()?;
+    Ok(database.run(move |database| {
+        let mut depth: Vec<IngestDepth> = vec![];
+        for (status, kind, count) in database.ingest_manager().get_depth().unwrap_or_default() {
+            let label = ingest_label(&kind);
+            let index = match depth.iter().position(|d| d.label == label) {
+                Some(index) => index,
+                None => {
+                    depth.push(IngestDepth {
+                        label,
+                        pending: 0,
+                        failed: 0,
+                    });
+                    depth.len() - 1
+                }
+            };
+            if status == STATUS_FAILED {
+                depth[index].failed += count;
+            } else {
+                depth[index].pending += count;
+            }
+        }
+        let pending: i64 = depth.iter().map(|d| d.pending).sum();
+        let failed: Vec<FailedJob> = database
+            .ingest_manager()
+            .get_jobs(STATUS_FAILED)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(job, client, os)| FailedJob {
+                id: job.id,
+                uuid: client.uuid,
+                computer_name: os.map(|os| os.computer_name).unwrap_or_else(|| client.uuid.to_string()),
+                label: ingest_label(&job.kind),
+                received: display_util::format_date_time(job.received),
+                attempts: job.attempts,
+                last_error: job.last_error.unwrap_or_default(),
+            })
+            .collect();
+        Template::render("system_status/ingest", context! { depth, pending, failed, user })
+    })
+    .await)
+}
+
+#[post("/ingest/<id>/retry")]
+async fn ingest_retry(database: &State<Database>, id: i32, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(database.run(move |database| {
+        if let Err(e) = database.ingest_manager().retry_job(id, Utc::now().naive_utc()) {
+            println!("[ERROR] In /system-status/ingest/{id}/retry {e:?}");
+        }
+        Redirect::to(uri!("/system-status", ingest))
+    })
+    .await)
+}
+
+#[post("/ingest/<id>/discard")]
+async fn ingest_discard(database: &State<Database>, id: i32, user: User) -> Result<Redirect, Status> {
+    user.require(Permission::ManageSettings)?;
+    Ok(database.run(move |database| {
+        if let Err(e) = database.ingest_manager().delete_job(id) {
+            println!("[ERROR] In /system-status/ingest/{id}/discard {e:?}");
+        }
+        Redirect::to(uri!("/system-status", ingest))
+    })
+    .await)
 }
 
 #[get("/<_..>", rank = 10)]
@@ -70,5 +193,5 @@ fn catch_all() -> Redirect {
 }
 
 pub fn routes() -> Vec<Route> {
-    routes![index, volumes, catch_all]
+    routes![index, volumes, ingest, ingest_retry, ingest_discard, catch_all]
 }
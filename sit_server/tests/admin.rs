@@ -0,0 +1,106 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::TestApp;
+use rocket::http::{ContentType, Status};
+use sit_server::auth::{self, PasswordPolicy};
+
+#[test]
+fn setup_creates_the_first_administrator() {
+    let app = TestApp::new();
+
+    let response = app.client.get("/auth/login").dispatch();
+    assert_eq!(response.headers().get_one("Location"), Some("/auth/setup"));
+    let response = app
+        .client
+        .post("/auth/setup")
+        .header(ContentType::Form)
+        .body("username=admin&password=admin-passwort&password_confirmation=anders")
+        .dispatch();
+    assert_eq!(response.headers().get_one("Location"), Some("/auth/setup"));
+    assert!(app.get_page("/auth/setup").contains("stimmen nicht überein"));
+
+    let response = app
+        .client
+        .post("/auth/setup")
+        .header(ContentType::Form)
+        .body("username=admin&password=admin-passwort&password_confirmation=admin-passwort")
+        .dispatch();
+    assert_eq!(response.headers().get_one("Location"), Some("/"));
+    assert_eq!(app.db().get_auth_user_by_username("admin").unwrap().role, "administrator");
+    app.get_page("/settings/users");
+
+    // Once a user exists, the setup is gone.
+    let response = app.client.get("/auth/setup").dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    let response = app
+        .client
+        .post("/auth/setup")
+        .header(ContentType::Form)
+        .body("username=zweiter&password=admin-passwort&password_confirmation=admin-passwort")
+        .dispatch();
+    assert_eq!(response.headers().get_one("Location"), Some("/auth/login"));
+    assert!(app.db().get_auth_user_by_username("zweiter").is_err());
+}
+
+#[test]
+fn only_one_first_administrator_is_created() {
+    let app = TestApp::new();
+    let db = app.db();
+    let policy = PasswordPolicy::default();
+
+    let created = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|n| {
+                let policy = &policy;
+                scope.spawn(move || {
+                    auth::create_first_user(db, policy, &format!("admin{n}"), "admin-passwort", auth::Role::Administrator)
+                        .unwrap()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).filter(|created| *created).count()
+    });
+    assert_eq!(created, 1);
+    assert_eq!(db.count_auth_users().unwrap(), 1);
+}
+
+#[test]
+fn reset_administrator_restores_access() {
+    let app = TestApp::new();
+    let db = app.db();
+    let policy = PasswordPolicy::default();
+    auth::create_new_user(db, &policy, "admin", "vergessenes-passwort", auth::Role::Auditor).unwrap();
+    let user = db.get_auth_user_by_username("admin").unwrap();
+    db.set_auth_user_disabled(user.id, true).unwrap();
+
+    auth::reset_administrator(db, &policy, "admin", "neues-passwort").unwrap();
+    let user = auth::check_password(db, "admin", "neues-passwort").unwrap();
+    assert_eq!(user.role, auth::Role::Administrator);
+    assert!(auth::reset_administrator(db, &policy, "unbekannt", "neues-passwort").is_err());
+}
+
+#[test]
+fn stale_clients_and_unused_software_are_purged() {
+    let app = TestApp::new();
+    let stale = app.register_with_inventory();
+    let active = app.register_with_inventory();
+    let db = app.db();
+    let now = Utc::now().naive_utc();
+    let client = db.get_client(&stale).unwrap();
+    assert!(client.last_seen.is_some());
+    db.set_client_last_seen(client.id, now - Duration::days(100)).unwrap();
+
+    assert_eq!(db.delete_clients_not_seen_since(now - Duration::days(90)).unwrap(), 1);
+    assert!(db.get_client(&stale).is_err());
+    assert!(db.get_client(&active).is_ok());
+    // The remaining client has the same software installed.
+    assert_eq!(db.delete_unused_software_versions().unwrap(), 0);
+
+    let client = db.get_client(&active).unwrap();
+    db.set_client_last_seen(client.id, now - Duration::days(100)).unwrap();
+    db.delete_clients_not_seen_since(now - Duration::days(90)).unwrap();
+    assert!(db.delete_unused_software_versions().unwrap() > 0);
+    assert!(db.delete_software_without_versions().unwrap() > 0);
+    assert!(db.get_software_list(None).unwrap().is_empty());
+}
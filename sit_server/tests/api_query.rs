@@ -0,0 +1,84 @@
+mod common;
+
+use common::TestApp;
+use rocket::http::{Header, Status};
+use serde_json::Value;
+use sit_server::auth::token::{self, Scope};
+
+fn get_json(app: &TestApp, uri: &str, token: &str) -> (Status, Value) {
+    let response = app
+        .client
+        .get(uri.to_string())
+        .header(Header::new("Authorization", format!("Bearer {token}")))
+        .dispatch();
+    let status = response.status();
+    (status, response.into_json().unwrap_or(Value::Null))
+}
+
+#[test]
+fn query_api_requires_a_token_with_scope() {
+    let app = TestApp::new();
+    app.login();
+    let uuid = app.register_with_inventory();
+    let user = app.db().get_auth_user_by_username(common::TEST_USER).unwrap();
+    let token = token::create_token(app.db(), user.id, "Skript", &[Scope::Clients], None).unwrap();
+
+    let response = app.client.get("/api/query/v1/clients").dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    let body: Value = response.into_json().unwrap();
+    assert!(body["error"].is_string());
+
+    let (status, _) = get_json(&app, "/api/query/v1/clients", "sit_invalid");
+    assert_eq!(status, Status::Unauthorized);
+
+    let (status, body) = get_json(&app, "/api/query/v1/clients?filter.name=r204", &token);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["items"][0]["uuid"], uuid.to_string());
+    assert_eq!(body["items"][0]["computer_name"], "PC-R204-01");
+
+    let (status, _) = get_json(&app, &format!("/api/query/v1/clients/{uuid}/hardware"), &token);
+    assert_eq!(status, Status::Forbidden);
+
+    let page = app.get_page("/auth");
+    assert!(page.contains("Skript"));
+}
+
+#[test]
+fn query_api_returns_inventory_of_a_client() {
+    let app = TestApp::new();
+    app.login();
+    let uuid = app.register_with_inventory();
+    let user = app.db().get_auth_user_by_username(common::TEST_USER).unwrap();
+    let token = token::create_token(app.db(), user.id, "Helpdesk", &Scope::ALL, None).unwrap();
+
+    let (status, body) = get_json(&app, &format!("/api/query/v1/clients/{uuid}/hardware"), &token);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["model"]["serial_number"], "PC2X4K7B");
+
+    let (status, body) = get_json(&app, &format!("/api/query/v1/clients/{uuid}/software"), &token);
+    assert_eq!(status, Status::Ok);
+    assert!(body.as_array().unwrap().iter().any(|s| s["name"] == "7-Zip"));
+
+    let (status, body) = get_json(&app, "/api/query/v1/software?per_page=1", &token);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    assert!(body["pages"].as_i64().unwrap() > 1);
+
+    let (status, _) = get_json(
+        &app,
+        "/api/query/v1/clients/00000000-0000-0000-0000-000000000000",
+        &token,
+    );
+    assert_eq!(status, Status::NotFound);
+
+    let tokens = app.db().access_token_manager().get_tokens(user.id).unwrap();
+    assert!(tokens[0].last_used.is_some());
+    let response = app
+        .client
+        .get(format!("/auth/tokens/{}/revoke", tokens[0].id))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    let (status, _) = get_json(&app, "/api/query/v1/software", &token);
+    assert_eq!(status, Status::Unauthorized);
+}
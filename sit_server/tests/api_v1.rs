@@ -0,0 +1,206 @@
+mod common;
+
+use std::io::Write;
+
+use common::{fixtures, TestApp};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rocket::http::{ContentType, Header, Status};
+use sit_lib::error::{ApiError, ErrorKind};
+use sit_lib::task::TaskBundle;
+use uuid::Uuid;
+
+#[test]
+fn register_assigns_and_keeps_uuid() {
+    let app = TestApp::new();
+    let uuid = app.register();
+    assert_eq!(app.db().get_client(&uuid).unwrap().uuid, uuid);
+
+    let response = app.post_json(
+        "/api/v1/register",
+        &format!(r#"{{"name": "PC-R204-01", "uuid": "{uuid}"}}"#),
+    );
+    assert_eq!(response.status(), Status::Created);
+    assert!(response.into_string().unwrap().contains(&uuid.to_string()));
+    assert_eq!(app.db().get_clients_with_os_info(None).unwrap().len(), 1);
+}
+
+#[test]
+fn os_info_is_stored() {
+    let app = TestApp::new();
+    let uuid = app.register();
+    let response = app.post_json(&format!("/api/v1/os/{uuid}"), fixtures::OS_INFO);
+    assert_eq!(response.status(), Status::Accepted);
+    assert_eq!(app.process_ingest(), 1);
+
+    let os_info = app.db().get_client_os_info(&uuid).unwrap();
+    assert_eq!(os_info.computer_name, "PC-R204-01");
+    assert_eq!(os_info.domain.as_deref(), Some("schule.local"));
+    assert_eq!(os_info.os.as_deref(), Some("Microsoft Windows 11 Pro"));
+}
+
+#[test]
+fn hardware_v1_and_v2_are_stored() {
+    let app = TestApp::new();
+    let uuid = app.register();
+
+    let response = app.post_json(&format!("/api/v1/hardware/{uuid}"), fixtures::HARDWARE_V1);
+    assert_eq!(response.status(), Status::Accepted);
+    app.process_ingest();
+    assert_eq!(app.db().get_client_memory_sticks(uuid).unwrap().len(), 1);
+    assert_eq!(app.db().get_client_graphics_cards(uuid).unwrap().len(), 1);
+
+    let response = app.post_json(&format!("/api/v2/hardware/{uuid}"), fixtures::HARDWARE_V2);
+    assert_eq!(response.status(), Status::Accepted);
+    app.process_ingest();
+    let db = app.db();
+    assert_eq!(db.get_client_memory_sticks(uuid).unwrap().len(), 2);
+    assert_eq!(db.get_client_graphics_cards(uuid).unwrap().len(), 2);
+    assert_eq!(db.get_client_network_adapters(uuid).unwrap().len(), 2);
+    assert_eq!(db.get_client_disks(uuid).unwrap().len(), 1);
+    assert_eq!(db.get_client_processors(uuid).unwrap()[0].cores, 6);
+    assert_eq!(db.get_client_bios(uuid).unwrap()[0].name, "M2WKT3BA");
+    let model = &db.get_client_computer_model(uuid).unwrap()[0];
+    assert_eq!(model.serial_number, "PC2X4K7B");
+}
+
+#[test]
+fn software_profiles_volumes_licenses_battery_are_stored() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    let db = app.db();
+
+    let software = db.get_client_software(uuid).unwrap();
+    assert_eq!(software.len(), 5);
+    assert_eq!(db.get_software_list(None).unwrap().len(), 5);
+
+    let profiles = db.get_client_profiles(&uuid).unwrap();
+    assert_eq!(profiles.len(), 2);
+    let schmidt = profiles
+        .iter()
+        .find(|(_, u)| u.username.as_deref() == Some("schmidt"))
+        .expect("domain split from username");
+    assert_eq!(schmidt.1.domain.as_deref(), Some("SCHULE"));
+    let paths = db
+        .user_manager()
+        .get_profile_paths(&uuid, &"S-1-5-21-1004336348-1177238915-682003330-1105".to_string())
+        .unwrap();
+    assert_eq!(paths.len(), 2);
+
+    assert_eq!(db.get_client_volume_status(uuid).unwrap().len(), 2);
+    assert_eq!(db.get_client_licenses(uuid).unwrap().len(), 2);
+
+    // Uploading the same payloads again must not duplicate anything.
+    app.upload_inventory(uuid);
+    assert_eq!(db.get_client_software(uuid).unwrap().len(), 5);
+    assert_eq!(db.get_client_profiles(&uuid).unwrap().len(), 2);
+    assert_eq!(db.get_client_licenses(uuid).unwrap().len(), 2);
+}
+
+#[test]
+fn tasks_are_delivered_and_updated() {
+    let app = TestApp::new();
+    let uuid = app.register();
+    let client = app.db().get_client(&uuid).unwrap();
+    app.db()
+        .task_manager()
+        .delete_user_profile(client.id, "S-1-5-21-1".to_string())
+        .unwrap();
+
+    let response = app.client.get(format!("/api/v1/tasks/{uuid}")).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let bundle: TaskBundle = response.into_json().unwrap();
+    assert_eq!(bundle.tasks.len(), 1);
+    assert_eq!(bundle.tasks[0].task["name"], "delete-user-profile");
+
+    let response = app.post_json(&format!("/api/v1/tasks/{uuid}"), fixtures::TASK_UPDATE);
+    assert_eq!(response.status(), Status::Ok);
+    let response = app.client.get(format!("/api/v1/tasks/{uuid}")).dispatch();
+    let bundle: TaskBundle = response.into_json().unwrap();
+    assert!(bundle.tasks.is_empty());
+}
+
+#[test]
+fn unknown_client_is_rejected() {
+    let app = TestApp::new();
+    let uuid = Uuid::new_v4();
+    let response = app.post_json(&format!("/api/v1/os/{uuid}"), fixtures::OS_INFO);
+    assert_eq!(response.status(), Status::NotFound);
+    let error: ApiError = response.into_json().unwrap();
+    assert_eq!(error.kind, ErrorKind::UnknownClient);
+    let response = app.post_json(&format!("/api/v1/software/{uuid}"), fixtures::SOFTWARE);
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[test]
+fn invalid_payloads_are_reported() {
+    let app = TestApp::new();
+    let uuid = app.register();
+
+    let response = app.post_json(&format!("/api/v1/os/{uuid}"), r#"{"computer_name": "PC-R204-01""#);
+    assert_eq!(response.status(), Status::BadRequest);
+    let error: ApiError = response.into_json().unwrap();
+    assert_eq!(error.kind, ErrorKind::MalformedPayload);
+
+    let response = app.post_json(&format!("/api/v1/os/{uuid}"), r#"{"computer_name": 42}"#);
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let error: ApiError = response.into_json().unwrap();
+    assert_eq!(error.kind, ErrorKind::InvalidPayload);
+    assert_eq!(error.fields[0].field, "computer_name");
+
+    let response = app.client.get(format!("/api/v1/tasks/{uuid}/nothing")).dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    let error: ApiError = response.into_json().unwrap();
+    assert_eq!(error.kind, ErrorKind::NotFound);
+}
+
+fn post_encoded(app: &TestApp, uri: &str, encoding: &str, body: Vec<u8>) -> Status {
+    app.client
+        .post(uri.to_string())
+        .header(ContentType::JSON)
+        .header(Header::new("Content-Encoding", encoding.to_owned()))
+        .body(body)
+        .dispatch()
+        .status()
+}
+
+#[test]
+fn compressed_payloads_are_accepted() {
+    let app = TestApp::new();
+    let uuid = app.register();
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(fixtures::SOFTWARE.as_bytes()).unwrap();
+    let status = post_encoded(&app, &format!("/api/v1/software/{uuid}"), "gzip", encoder.finish().unwrap());
+    assert_eq!(status, Status::Accepted);
+    app.process_ingest();
+    assert_eq!(app.db().get_client_software(uuid).unwrap().len(), 5);
+
+    let body = zstd::encode_all(fixtures::PROFILES.as_bytes(), 3).unwrap();
+    let status = post_encoded(&app, &format!("/api/v1/profiles/{uuid}"), "zstd", body);
+    assert_eq!(status, Status::Accepted);
+    app.process_ingest();
+    assert_eq!(app.db().get_client_profiles(&uuid).unwrap().len(), 2);
+
+    let status = post_encoded(&app, &format!("/api/v1/os/{uuid}"), "br", fixtures::OS_INFO.into());
+    assert_eq!(status, Status::UnsupportedMediaType);
+}
+
+#[test]
+fn oversized_payloads_are_rejected() {
+    let app = TestApp::new();
+    let uuid = app.register();
+
+    let name = "x".repeat(8 * 1024);
+    let response = app.post_json("/api/v1/register", &format!(r#"{{"name": "{name}"}}"#));
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+    let error: ApiError = response.into_json().unwrap();
+    assert_eq!(error.kind, ErrorKind::PayloadTooLarge);
+
+    // Small when compressed, but far beyond the limit once decompressed.
+    let padded = format!("{}{}", " ".repeat(1024 * 1024), fixtures::OS_INFO);
+    let body = zstd::encode_all(padded.as_bytes(), 3).unwrap();
+    assert!(body.len() < 64 * 1024);
+    let status = post_encoded(&app, &format!("/api/v1/os/{uuid}"), "zstd", body);
+    assert_eq!(status, Status::PayloadTooLarge);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::{fixtures, TestApp};
+use rocket::http::Status;
+use serde_json::{json, Value};
+use sit_lib::error::{ApiError, ErrorKind};
+use sit_lib::snapshot::{Section, SectionStatus, SnapshotCheckResult, SnapshotResult};
+use uuid::Uuid;
+
+fn fixture(json: &str) -> Value {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn snapshot_stores_all_sections() {
+    let app = TestApp::new();
+    let uuid = app.register();
+    let snapshot = json!({
+        "schema_version": 1,
+        "os": fixture(fixtures::OS_INFO),
+        "hardware": fixture(fixtures::HARDWARE_V2),
+        "software": fixture(fixtures::SOFTWARE),
+        "profiles": fixture(fixtures::PROFILES),
+        "volumes": fixture(fixtures::VOLUMES),
+        "licenses": fixture(fixtures::LICENSES),
+        "battery": fixture(fixtures::BATTERY),
+    });
+    let response = app.post_json(&format!("/api/v3/snapshot/{uuid}"), &snapshot.to_string());
+    assert_eq!(response.status(), Status::Accepted);
+    let result: SnapshotResult = response.into_json().unwrap();
+    assert_eq!(result.sections.len(), 7);
+    assert!(result.sections.iter().all(|s| s.status == SectionStatus::Queued));
+    assert_eq!(app.process_ingest(), 7);
+
+    let db = app.db();
+    assert_eq!(db.get_client_os_info(&uuid).unwrap().computer_name, "PC-R204-01");
+    assert_eq!(db.get_client_software(uuid).unwrap().len(), 5);
+    assert_eq!(db.get_client_profiles(&uuid).unwrap().len(), 2);
+    assert_eq!(db.get_client_volume_status(uuid).unwrap().len(), 2);
+    assert_eq!(db.get_client_licenses(uuid).unwrap().len(), 2);
+    assert_eq!(db.get_client_computer_model(uuid).unwrap()[0].serial_number, "PC2X4K7B");
+}
+
+#[test]
+fn snapshot_accepts_partial_sections() {
+    let app = TestApp::new();
+    let uuid = app.register();
+    let snapshot = json!({
+        "schema_version": 1,
+        "volumes": fixture(fixtures::VOLUMES),
+    });
+    let response = app.post_json(&format!("/api/v3/snapshot/{uuid}"), &snapshot.to_string());
+    assert_eq!(response.status(), Status::Accepted);
+    let result: SnapshotResult = response.into_json().unwrap();
+    assert_eq!(result.sections.len(), 1);
+    assert_eq!(result.sections[0].section, Section::Volumes);
+    app.process_ingest();
+    assert_eq!(app.db().get_client_volume_status(uuid).unwrap().len(), 2);
+    assert!(app.db().get_client_software(uuid).unwrap().is_empty());
+}
+
+#[test]
+fn snapshot_rejects_unknown_clients_and_versions() {
+    let app = TestApp::new();
+    let response = app.post_json(
+        &format!("/api/v3/snapshot/{}", Uuid::new_v4()),
+        r#"{"schema_version": 1}"#,
+    );
+    assert_eq!(response.status(), Status::NotFound);
+    let error: ApiError = response.into_json().unwrap();
+    assert_eq!(error.kind, ErrorKind::UnknownClient);
+
+    let uuid = app.register();
+    let response = app.post_json(&format!("/api/v3/snapshot/{uuid}"), r#"{"schema_version": 99}"#);
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let error: ApiError = response.into_json().unwrap();
+    assert_eq!(error.fields[0].field, "schema_version");
+}
+
+#[test]
+fn unchanged_sections_are_skipped() {
+    let app = TestApp::new();
+    let uuid = app.register();
+    let snapshot = json!({
+        "schema_version": 1,
+        "volumes": fixture(fixtures::VOLUMES),
+        "hashes": {"volumes": "a1"},
+    });
+    let response = app.post_json(&format!("/api/v3/snapshot/{uuid}"), &snapshot.to_string());
+    let result: SnapshotResult = response.into_json().unwrap();
+    assert_eq!(result.sections[0].status, SectionStatus::Queued);
+    app.process_ingest();
+
+    let check = json!({
+        "schema_version": 1,
+        "hashes": {"volumes": "a1", "software": "b2"},
+    });
+    let response = app.post_json(&format!("/api/v3/snapshot/{uuid}/check"), &check.to_string());
+    assert_eq!(response.status(), Status::Ok);
+    let result: SnapshotCheckResult = response.into_json().unwrap();
+    assert_eq!(result.changed, vec![Section::Software]);
+
+    let response = app.post_json(&format!("/api/v3/snapshot/{uuid}"), &snapshot.to_string());
+    let result: SnapshotResult = response.into_json().unwrap();
+    assert_eq!(result.sections[0].status, SectionStatus::Unchanged);
+
+    let client_id = app.db().get_client(&uuid).unwrap().id;
+    let hashes = app.db().section_hash_manager().get_hashes(client_id).unwrap();
+    assert_eq!(hashes.len(), 1);
+    assert!(hashes[0].last_seen >= hashes[0].accepted);
+}
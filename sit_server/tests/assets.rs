@@ -0,0 +1,146 @@
+mod common;
+
+use bigdecimal::{BigDecimal, RoundingMode};
+use chrono::{Months, Utc};
+use common::TestApp;
+use rocket::http::{ContentType, Status};
+use sit_server::auth::Role;
+use sit_server::database::AssetInput;
+
+/// Uploads `csv` on the asset page and returns the result page.
+fn post_import(app: &TestApp, csv: &str) -> String {
+    let body = format!(
+        "--BOUNDARY\r\nContent-Disposition: form-data; name=\"file\"; filename=\"assets.csv\"\r\n\
+         Content-Type: text/csv\r\n\r\n{csv}\r\n--BOUNDARY--\r\n"
+    );
+    let response = app
+        .client
+        .post("/assets/import")
+        .header(ContentType::new("multipart", "form-data").with_params(("boundary", "BOUNDARY")))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    response.into_string().unwrap()
+}
+
+#[test]
+fn assets_are_imported_by_serial_number() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    let db = app.db();
+    let assets = db.asset_manager();
+    let client = db.get_client(&uuid).unwrap();
+
+    let csv = "\u{feff}Serial_Number;Inventory_Number;Purchase_Date;Price;Warranty_End;Comment\n\
+               pc2x4k7b;INV-0042;01.09.2023;\"849,90\";2026-08-31;ignored\n\
+               UNKNOWN;INV-0043;;;;\n\
+               PC2X4K7B;INV-0042;not a date;;;\n";
+    let import = assets.import_csv(csv, |_| true).unwrap();
+    assert_eq!(import.imported, 1);
+    assert_eq!(import.skipped.len(), 2);
+    assert!(import.skipped[0].starts_with("Line 3:"));
+
+    let asset = assets.get_asset(client.id).unwrap().unwrap();
+    assert_eq!(asset.inventory_number.as_deref(), Some("INV-0042"));
+    assert_eq!(asset.purchase_date.unwrap().to_string(), "2023-09-01");
+    assert_eq!(asset.price.unwrap().with_scale_round(2, RoundingMode::HalfUp).to_string(), "849.90");
+    assert!(asset.supplier.is_none());
+
+    // Fields left out keep their value, empty ones are cleared.
+    assets
+        .update_asset(
+            client.id,
+            &AssetInput {
+                supplier: Some("Bechtle"),
+                price: Some(""),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let asset = assets.get_asset(client.id).unwrap().unwrap();
+    assert_eq!(asset.supplier.as_deref(), Some("Bechtle"));
+    assert_eq!(asset.inventory_number.as_deref(), Some("INV-0042"));
+    assert!(asset.price.is_none());
+
+    // Prices are stored rounded to cents.
+    assets
+        .update_asset(
+            client.id,
+            &AssetInput {
+                price: Some("19,999"),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let price = assets.get_asset(client.id).unwrap().unwrap().price.unwrap();
+    assert_eq!(price, BigDecimal::from(20));
+
+    assert!(assets.import_csv("inventory_number\nINV-1\n", |_| true).is_err());
+}
+
+#[test]
+fn warranty_report_lists_machines_running_out() {
+    let app = TestApp::new();
+    app.login();
+    let uuid = app.register_with_inventory();
+
+    let warranty_end = Utc::now().date_naive() + Months::new(2);
+    let response = app
+        .client
+        .post(format!("/clients/{uuid}/asset"))
+        .header(ContentType::Form)
+        .body(format!(
+            "inventory_number=INV-0042&purchase_date=&supplier=Bechtle&price=849,90&warranty_end={warranty_end}&responsible=Hausmeister"
+        ))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert!(app.get_page(&format!("/clients/{uuid}")).contains("INV-0042"));
+    assert!(app.get_page("/assets").contains("849,90 €"));
+
+    let page = app.get_page("/assets/warranty?months=3");
+    assert!(page.contains("PC-R204-01"));
+    assert!(page.contains(&warranty_end.to_string()));
+    assert!(!app.get_page("/assets/warranty?months=1").contains("PC-R204-01"));
+}
+
+#[test]
+fn restricted_users_import_assets_of_their_groups_only() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    let db = app.db();
+    let client = db.get_client(&uuid).unwrap();
+    let group = db.group_manager().create_group("Raum 204", None).unwrap();
+    let other = db.group_manager().create_group("Verwaltung", None).unwrap();
+    let user_id = app.login_as("techniker", Role::Technician);
+    db.set_auth_user_groups(user_id, &[other.id]).unwrap();
+
+    let csv = "serial_number;inventory_number\nPC2X4K7B;INV-0042\n";
+    let page = post_import(&app, csv);
+    assert!(page.contains("client outside your groups"));
+    assert!(db.asset_manager().get_asset(client.id).unwrap().is_none());
+
+    db.group_manager().add_client(group.id, client.id).unwrap();
+    db.set_auth_user_groups(user_id, &[group.id]).unwrap();
+    post_import(&app, csv);
+    let asset = db.asset_manager().get_asset(client.id).unwrap().unwrap();
+    assert_eq!(asset.inventory_number.as_deref(), Some("INV-0042"));
+}
+
+#[test]
+fn large_asset_files_are_imported() {
+    let app = TestApp::new();
+    app.login();
+    let uuid = app.register_with_inventory();
+
+    let mut csv = String::from("serial_number;inventory_number;supplier\n");
+    for n in 0..300 {
+        csv.push_str(&format!("UNBEKANNT-{n:05};INV-{n:05};Lieferant mit langem Namen\n"));
+    }
+    csv.push_str("PC2X4K7B;INV-0042;Bechtle\n");
+    assert!(csv.len() > 8 * 1024);
+    let page = post_import(&app, &csv);
+    assert!(page.contains("Line 301: unknown serial number"));
+    let client = app.db().get_client(&uuid).unwrap();
+    let asset = app.db().asset_manager().get_asset(client.id).unwrap().unwrap();
+    assert_eq!(asset.inventory_number.as_deref(), Some("INV-0042"));
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::TestApp;
+use rocket::http::{ContentType, Status};
+use sit_server::database::{AttributeKind, GroupRule, RuleCondition, RuleField};
+
+#[test]
+fn attribute_values_are_normalized() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    let db = app.db();
+    let attributes = db.attribute_manager();
+    let client = db.get_client(&uuid).unwrap();
+
+    let beamer = attributes.create_definition("Beamer", AttributeKind::Boolean, "").unwrap();
+    let funding = attributes
+        .create_definition("Förderprogramm", AttributeKind::Enum, "DigitalPakt\n\n Landesmittel \n")
+        .unwrap();
+    let seats = attributes.create_definition("Plätze", AttributeKind::Number, "").unwrap();
+    assert_eq!(funding.option_list(), vec!["DigitalPakt", "Landesmittel"]);
+    assert!(attributes.create_definition("Leer", AttributeKind::Enum, " \n").is_err());
+
+    attributes.set_client_attribute(client.id, beamer.id, "Ja").unwrap();
+    attributes.set_client_attribute(client.id, funding.id, "landesmittel").unwrap();
+    attributes.set_client_attribute(client.id, seats.id, "24,50").unwrap();
+    assert!(attributes.set_client_attribute(client.id, funding.id, "Spende").is_err());
+    assert!(attributes.set_client_attribute(client.id, seats.id, "viele").is_err());
+
+    let mut values: Vec<(i32, String)> = attributes
+        .get_client_attributes(client.id)
+        .unwrap()
+        .into_iter()
+        .map(|a| (a.attribute_id, a.value))
+        .collect();
+    values.sort();
+    assert_eq!(
+        values,
+        vec![
+            (beamer.id, "true".to_string()),
+            (funding.id, "Landesmittel".to_string()),
+            (seats.id, "24.5".to_string()),
+        ]
+    );
+
+    // Smart groups select on the canonical value.
+    let group = db.group_manager().create_group("Mit Beamer", None).unwrap();
+    let rule = GroupRule {
+        conditions: vec![RuleCondition {
+            field: RuleField::Attribute(beamer.id),
+            value: "true".to_string(),
+            version: None,
+        }],
+    };
+    db.group_manager().set_rule(group.id, Some(&rule)).unwrap();
+    assert_eq!(db.get_clients_with_os_info(Some(group.id)).unwrap().len(), 1);
+    assert_eq!("attribute:7".parse::<RuleField>().unwrap(), RuleField::Attribute(7));
+
+    attributes.set_client_attribute(client.id, beamer.id, "").unwrap();
+    db.group_manager().refresh_client(client.id).unwrap();
+    assert!(db.get_clients_with_os_info(Some(group.id)).unwrap().is_empty());
+
+    attributes.delete_definition(seats.id).unwrap();
+    assert_eq!(attributes.get_client_attributes(client.id).unwrap().len(), 1);
+}
+
+#[test]
+fn clients_are_filtered_and_sorted_by_attribute() {
+    let app = TestApp::new();
+    app.login();
+    let first = app.register_with_inventory();
+    let response = app.post_json("/api/v1/register", r#"{"name": "PC-R105-07"}"#);
+    assert_eq!(response.status(), Status::Created);
+
+    let response = app
+        .client
+        .post("/settings/attributes/new")
+        .header(ContentType::Form)
+        .body("name=Pr%C3%BCfungsfreigabe&kind=boolean&options=")
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    let attribute = app.db().attribute_manager().get_definitions().unwrap().remove(0);
+    assert!(app.get_page("/settings/attributes").contains("Prüfungsfreigabe"));
+
+    let response = app
+        .client
+        .post(format!("/clients/{first}/attributes"))
+        .header(ContentType::Form)
+        .body(format!("values[{}]=true", attribute.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert!(app.get_page(&format!("/clients/{first}")).contains("Prüfungsfreigabe"));
+
+    let page = app.get_page(&format!("/clients?filter.attribute-{}=ja", attribute.id));
+    assert!(page.contains("PC-R204-01"));
+    assert!(!page.contains("PC-R105-07"));
+    let page = app.get_page(&format!("/clients?filter.attribute-{}=nein", attribute.id));
+    assert!(!page.contains("PC-R204-01"));
+
+    // By name PC-R105-07 comes first, clients without a value sort last.
+    let page = app.get_page(&format!("/clients?sort=attribute-{}", attribute.id));
+    assert!(page.find("PC-R204-01").unwrap() < page.find("PC-R105-07").unwrap());
+}
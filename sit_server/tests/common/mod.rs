@@ -0,0 +1,232 @@
+//! Shared harness for the end-to-end tests.
+//!
+//! Every test gets its own PostgreSQL schema inside the database given by
+//! `TEST_DATABASE_URL`, runs the embedded migrations against it and drives the
+//! Rocket app through a local client. The schema is dropped again when the
+//! [`TestApp`] goes out of scope. With `TEST_DATABASE_URL=sqlite://<dir>` every
+//! test gets a fresh SQLite file in `<dir>` (the system temp dir if empty)
+//! instead. Without `TEST_DATABASE_URL` the tests fail, so a missing database
+//! doesn't pass as a green run.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::{env, fs};
+
+use chrono::Utc;
+use diesel::{Connection, PgConnection, RunQueryDsl};
+use rocket::http::{ContentType, Status};
+use rocket::local::blocking::{Client, LocalResponse};
+use sit_lib::server::Register;
+use sit_server::auth::{PasswordPolicy, Role};
+use sit_server::database::Database;
+use sit_server::ingest::IngestQueue;
+use uuid::Uuid;
+
+pub const TEST_USER: &str = "test-admin";
+pub const TEST_PASSWORD: &str = "test-password";
+
+pub mod fixtures {
+    pub const REGISTER: &str = include_str!("../fixtures/register.json");
+    pub const OS_INFO: &str = include_str!("../fixtures/os_info.json");
+    pub const HARDWARE_V1: &str = include_str!("../fixtures/hardware_v1.json");
+    pub const HARDWARE_V2: &str = include_str!("../fixtures/hardware_v2.json");
+    pub const SOFTWARE: &str = include_str!("../fixtures/software.json");
+    pub const PROFILES: &str = include_str!("../fixtures/profiles.json");
+    pub const VOLUMES: &str = include_str!("../fixtures/volumes.json");
+    pub const LICENSES: &str = include_str!("../fixtures/licenses.json");
+    pub const BATTERY: &str = include_str!("../fixtures/battery.json");
+    pub const TASK_BUNDLE: &str = include_str!("../fixtures/task_bundle.json");
+    pub const TASK_UPDATE: &str = include_str!("../fixtures/task_update.json");
+}
+
+pub enum TestDatabase {
+    Postgres { base_url: String, schema: String },
+    Sqlite { path: PathBuf },
+}
+
+impl TestDatabase {
+    /// The test database selected by `TEST_DATABASE_URL`.
+    pub fn from_env() -> TestDatabase {
+        let base_url = env::var("TEST_DATABASE_URL").expect(
+            "TEST_DATABASE_URL must be set to a Postgres url or to sqlite:// for temporary SQLite files",
+        );
+        if let Some(dir) = base_url.strip_prefix("sqlite://") {
+            let dir = if dir.is_empty() { env::temp_dir() } else { PathBuf::from(dir) };
+            return Self::sqlite(dir);
+        }
+        let name = format!("sit_test_{}", Uuid::new_v4().simple());
+        let mut conn = PgConnection::establish(&base_url)
+            .unwrap_or_else(|e| panic!("Could not connect to {base_url}: {e}"));
+        diesel::sql_query(format!("CREATE SCHEMA \"{name}\""))
+            .execute(&mut conn)
+            .expect("Could not create test schema");
+        TestDatabase::Postgres {
+            base_url,
+            schema: name,
+        }
+    }
+
+    /// A fresh SQLite database file in `dir`.
+    pub fn sqlite(dir: PathBuf) -> TestDatabase {
+        TestDatabase::Sqlite {
+            path: dir.join(format!("sit_test_{}.db", Uuid::new_v4().simple())),
+        }
+    }
+
+    pub fn is_postgres(&self) -> bool {
+        matches!(self, TestDatabase::Postgres { .. })
+    }
+
+    /// Connection string for the test database. For Postgres it puts the test
+    /// schema first on the search path.
+    fn database_url(&self) -> String {
+        match self {
+            TestDatabase::Postgres { base_url, schema } => {
+                let separator = if base_url.contains('?') { '&' } else { '?' };
+                format!("{base_url}{separator}options=-c%20search_path%3D{schema}")
+            }
+            TestDatabase::Sqlite { path } => format!("sqlite://{}", path.display()),
+        }
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        match self {
+            TestDatabase::Postgres { base_url, schema } => {
+                if let Ok(mut conn) = PgConnection::establish(base_url) {
+                    let _ = diesel::sql_query(format!("DROP SCHEMA \"{schema}\" CASCADE"))
+                        .execute(&mut conn);
+                }
+            }
+            TestDatabase::Sqlite { path } => {
+                for suffix in ["", "-wal", "-shm"] {
+                    let mut file = path.clone().into_os_string();
+                    file.push(suffix);
+                    let _ = fs::remove_file(file);
+                }
+            }
+        }
+    }
+}
+
+pub struct TestApp {
+    // Field order matters: the client (and with it the pool) has to be
+    // dropped before the test database is removed.
+    pub client: Client,
+    _database: TestDatabase,
+}
+
+impl TestApp {
+    pub fn new() -> TestApp {
+        Self::with_database(TestDatabase::from_env())
+    }
+
+    pub fn with_database(test_database: TestDatabase) -> TestApp {
+        // Some ingest paths check out up to three connections at once.
+        let database = Database::connect(&test_database.database_url(), 4)
+            .expect("Could not set up test database");
+        // Without workers and statistics refresh, so tests apply queued payloads
+        // and refresh the statistics with `process_ingest`.
+        let rocket = sit_server::rocket(database);
+        let figment = rocket
+            .figment()
+            .clone()
+            .merge(("ingest_workers", 0))
+            .merge(("statistics_interval", 0));
+        let client = Client::tracked(rocket.configure(figment)).expect("valid rocket instance");
+        TestApp {
+            client,
+            _database: test_database,
+        }
+    }
+
+    pub fn db(&self) -> &Database {
+        self.client
+            .rocket()
+            .state::<Database>()
+            .expect("database is managed")
+    }
+
+    pub fn login(&self) {
+        self.login_as(TEST_USER, Role::Administrator);
+    }
+
+    /// Creates the user `username` with `role` and logs in as it. Returns the
+    /// id of the user.
+    pub fn login_as(&self, username: &str, role: Role) -> i32 {
+        sit_server::auth::create_new_user(self.db(), &PasswordPolicy::default(), username, TEST_PASSWORD, role)
+            .expect("Could not create test user");
+        let response = self
+            .client
+            .post("/auth/login")
+            .header(ContentType::Form)
+            .body(format!("username={username}&password={TEST_PASSWORD}"))
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        assert_eq!(response.headers().get_one("Location"), Some("/"));
+        self.db().get_auth_user_by_username(username).unwrap().id
+    }
+
+    pub fn register(&self) -> Uuid {
+        let response = self.post_json("/api/v1/register", fixtures::REGISTER);
+        assert_eq!(response.status(), Status::Created);
+        let register: Register = response.into_json().expect("register response");
+        register.uuid.expect("uuid assigned")
+    }
+
+    pub fn post_json(&self, uri: &str, body: &str) -> LocalResponse<'_> {
+        self.client
+            .post(uri.to_string())
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch()
+    }
+
+    /// Registers a client and uploads every fixture for it.
+    pub fn register_with_inventory(&self) -> Uuid {
+        let uuid = self.register();
+        self.upload_inventory(uuid);
+        uuid
+    }
+
+    pub fn upload_inventory(&self, uuid: Uuid) {
+        for (uri, body) in [
+            (format!("/api/v1/os/{uuid}"), fixtures::OS_INFO),
+            (format!("/api/v2/hardware/{uuid}"), fixtures::HARDWARE_V2),
+            (format!("/api/v1/software/{uuid}"), fixtures::SOFTWARE),
+            (format!("/api/v1/profiles/{uuid}"), fixtures::PROFILES),
+            (format!("/api/v1/status/{uuid}/volumes"), fixtures::VOLUMES),
+            (format!("/api/v1/licenses/{uuid}"), fixtures::LICENSES),
+            (format!("/api/v1/status/{uuid}/battery"), fixtures::BATTERY),
+        ] {
+            let response = self.post_json(&uri, body);
+            assert_eq!(response.status(), Status::Accepted, "POST {uri}");
+        }
+        self.process_ingest();
+    }
+
+    /// Applies all queued agent payloads and refreshes the statistics.
+    pub fn process_ingest(&self) -> usize {
+        let queue = self
+            .client
+            .rocket()
+            .state::<IngestQueue>()
+            .expect("ingest queue is managed");
+        let processed = queue.process_pending(self.db()).expect("ingest failed");
+        self.refresh_statistics();
+        processed
+    }
+
+    pub fn refresh_statistics(&self) {
+        self.db()
+            .refresh_statistics(Utc::now().naive_utc())
+            .expect("statistics refresh failed");
+    }
+
+    pub fn get_page(&self, uri: &str) -> String {
+        let response = self.client.get(uri.to_string()).dispatch();
+        assert_eq!(response.status(), Status::Ok, "GET {uri}");
+        response.into_string().unwrap_or_default()
+    }
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use bigdecimal::BigDecimal;
+use common::{fixtures, TestApp};
+use rocket::http::Status;
+use serde_json::{json, Value};
+
+const HARDWARE_SMALL: &str = r#"{
+  "model": { "manufacturer": "Dell Inc.", "model_family": "OptiPlex 3070", "model": "OptiPlex 3070", "serial_number": "7XK2M33" },
+  "memory": { "sticks": [ { "bank_label": "BANK 0", "capacity": 8589934592 } ] },
+  "processor": { "name": "Intel(R) Core(TM) i3-9100 CPU @ 3.60GHz", "manufacturer": "GenuineIntel", "cores": 4, "logical_cores": 4, "clock_speed": 3600, "address_width": 64 },
+  "disks": { "drives": [] },
+  "network": { "adapter": [] },
+  "graphics": [ { "name": "Intel(R) UHD Graphics 630" } ],
+  "bios": { "manufacturer": "Dell Inc.", "name": "1.20.0", "version": "DELL - 1072009" }
+}"#;
+
+const VOLUMES_HEALTHY: &str = r#"{ "volumes": [
+  { "drive_letter": "C:", "label": null, "file_system": "NTFS", "capacity": 254721126400, "free_space": 120000000000 }
+] }"#;
+
+fn register_second_client(app: &TestApp) -> uuid::Uuid {
+    let response = app.post_json("/api/v1/register", r#"{"name": "PC-R105-07"}"#);
+    assert_eq!(response.status(), Status::Created);
+    let register: sit_lib::server::Register = response.into_json().unwrap();
+    let uuid = register.uuid.unwrap();
+    let response = app.post_json(&format!("/api/v2/hardware/{uuid}"), HARDWARE_SMALL);
+    assert_eq!(response.status(), Status::Accepted);
+    let response = app.post_json(&format!("/api/v1/status/{uuid}/volumes"), VOLUMES_HEALTHY);
+    assert_eq!(response.status(), Status::Accepted);
+    app.process_ingest();
+    uuid
+}
+
+#[test]
+fn memory_count_groups_by_capacity_and_sticks() {
+    let app = TestApp::new();
+    app.register_with_inventory();
+    register_second_client(&app);
+
+    let memory = app.db().get_memory_count(None).unwrap();
+    assert_eq!(memory.len(), 2);
+    assert_eq!(memory[0].capacity, Some(BigDecimal::from(8_589_934_592_u64)));
+    assert_eq!(memory[0].sticks, Some(1));
+    assert_eq!(memory[0].count, 1);
+    assert_eq!(memory[1].capacity, Some(BigDecimal::from(17_179_869_184_u64)));
+    assert_eq!(memory[1].sticks, Some(2));
+
+    let clients = app.db().get_memory_clients(17_179_869_184, 2, None).unwrap();
+    assert_eq!(clients.len(), 1);
+    assert_eq!(clients[0].1.computer_name, "PC-R204-01");
+}
+
+#[test]
+fn volume_crit_only_lists_full_volumes() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    register_second_client(&app);
+
+    let crit = app.db().get_system_status_volume_crit(None).unwrap();
+    assert_eq!(crit.len(), 1);
+    let (volume, (client, os_info)) = &crit[0];
+    assert_eq!(volume.drive_letter, "C:");
+    assert_eq!(client.uuid, uuid);
+    assert_eq!(os_info.computer_name, "PC-R204-01");
+}
+
+#[test]
+fn hardware_aggregates_count_clients() {
+    let app = TestApp::new();
+    app.register_with_inventory();
+    register_second_client(&app);
+    let db = app.db();
+
+    let processors = db.get_processors_count(None).unwrap();
+    assert_eq!(processors.len(), 2);
+    assert!(processors.iter().all(|p| p.count == 1));
+
+    let graphics = db.get_graphics_cards_count(None).unwrap();
+    let uhd = graphics
+        .iter()
+        .find(|g| g.name == "Intel(R) UHD Graphics 630")
+        .unwrap();
+    assert_eq!(uhd.count, 2);
+
+    let models = db.get_computer_models_count(None).unwrap();
+    assert_eq!(models.len(), 2);
+    assert_eq!(db.get_disks_count(None).unwrap().len(), 1);
+    assert_eq!(db.get_network_adapters_count(None).unwrap().len(), 2);
+    assert_eq!(
+        db.get_computer_model_clients(&"ThinkCentre M70q".to_string(), &"LENOVO".to_string(), None)
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn software_and_os_aggregates() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    let db = app.db();
+
+    let firefox = db
+        .get_software_list(None)
+        .unwrap()
+        .into_iter()
+        .find(|s| s.name == "Mozilla Firefox (x64 de)")
+        .unwrap();
+    let versions = db.get_software_versions(firefox.id, None).unwrap();
+    assert_eq!(versions.len(), 1);
+    assert_eq!(versions[0].count, 1);
+    assert_eq!(db.get_software_computer_list(firefox.id, None).unwrap().len(), 1);
+
+    let os_list = db.get_os_list(None).unwrap();
+    assert_eq!(os_list.len(), 1);
+    assert_eq!(os_list[0].count, 1);
+    let versions = db
+        .get_os_version_client_list(
+            "Microsoft Windows 11 Pro".to_string(),
+            "10.0.22631".to_string(),
+            None,
+        )
+        .unwrap();
+    assert_eq!(versions.list[0].1.uuid, uuid);
+
+    let licenses = db.get_license_list(None).unwrap();
+    assert_eq!(licenses.len(), 2);
+}
+
+#[test]
+fn software_update_reuses_entries() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    let second = register_second_client(&app);
+    let db = app.db();
+    let programs = db.get_software_list(None).unwrap().len();
+
+    let software = r#"{ "software": [
+  { "name": "Mozilla Firefox (x64 de)", "version": "131.0.3", "publisher": "Mozilla" },
+  { "name": "Mozilla Firefox (x64 de)", "version": "131.0.3", "publisher": "Mozilla" },
+  { "name": "7-Zip 24.08 (x64)", "version": "24.08", "publisher": "Igor Pavlov" }
+] }"#;
+    for client in [uuid, second] {
+        let response = app.post_json(&format!("/api/v1/software/{client}"), software);
+        assert_eq!(response.status(), Status::Accepted);
+    }
+    app.process_ingest();
+
+    assert_eq!(db.get_software_list(None).unwrap().len(), programs + 1);
+    for client in [uuid, second] {
+        let list = db.get_client_software(client).unwrap();
+        assert_eq!(list.len(), 2);
+    }
+    let firefox = db
+        .get_software_list(None)
+        .unwrap()
+        .into_iter()
+        .find(|s| s.name == "Mozilla Firefox (x64 de)")
+        .unwrap();
+    let versions = db.get_software_versions(firefox.id, None).unwrap();
+    let current = versions.iter().find(|v| v.version == "131.0.3").unwrap();
+    assert_eq!(current.count, 2);
+    assert!(versions.iter().filter(|v| v.version != "131.0.3").all(|v| v.count == 0));
+}
+
+#[test]
+fn hardware_update_keeps_unchanged_rows() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    let db = app.db();
+    let disk = db.get_client_disks(uuid).unwrap()[0].id;
+    let adapters: Vec<i32> = db.get_client_network_adapters(uuid).unwrap().iter().map(|a| a.id).collect();
+
+    let mut hardware: Value = serde_json::from_str(fixtures::HARDWARE_V2).unwrap();
+    hardware["disks"]["drives"][0]["status"] = json!("Pred Fail");
+    hardware["memory"]["sticks"] = json!([{ "bank_label": "BANK 0", "capacity": 17179869184_u64 }]);
+    hardware["network"]["adapter"][0]["ip_addresses"] = json!(["10.20.4.102"]);
+    let response = app.post_json(&format!("/api/v2/hardware/{uuid}"), &hardware.to_string());
+    assert_eq!(response.status(), Status::Accepted);
+    app.process_ingest();
+
+    let disks = db.get_client_disks(uuid).unwrap();
+    assert_eq!(disks.len(), 1);
+    assert_eq!(disks[0].id, disk);
+    assert_eq!(disks[0].status, "Pred Fail");
+    let sticks = db.get_client_memory_sticks(uuid).unwrap();
+    assert_eq!(sticks.len(), 1);
+    assert_eq!(sticks[0].capacity, Some(BigDecimal::from(17_179_869_184_u64)));
+    let mut stored: Vec<i32> = db.get_client_network_adapters(uuid).unwrap().iter().map(|a| a.id).collect();
+    stored.sort();
+    let mut expected = adapters;
+    expected.sort();
+    assert_eq!(stored, expected);
+}
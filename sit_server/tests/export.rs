@@ -0,0 +1,65 @@
+mod common;
+
+use common::TestApp;
+use rocket::http::{ContentType, Status};
+
+#[test]
+fn exports_apply_the_filters_of_the_page() {
+    let app = TestApp::new();
+    app.login();
+    app.register_with_inventory();
+
+    let page = app.get_page("/software/software?filter.name=zip");
+    assert!(page.contains("/export/software/software?filter.name=zip&format=xlsx"));
+
+    let response = app
+        .client
+        .get("/export/software/software?filter.name=zip&per_page=1")
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::CSV));
+    assert_eq!(
+        response.headers().get_one("Content-Disposition"),
+        Some("attachment; filename=\"Software.csv\"")
+    );
+    let csv = response.into_string().unwrap();
+    assert!(csv.starts_with("\u{feff}Name;Hersteller\r\n"));
+    assert!(csv.contains("7-Zip;Igor Pavlov\r\n"));
+    assert!(!csv.contains("LibreOffice"));
+
+    let response = app.client.get("/export/hardware/processors?format=xlsx").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let xlsx = response.into_bytes().unwrap();
+    // XLSX files are zip archives.
+    assert!(xlsx.starts_with(b"PK"));
+}
+
+#[test]
+fn inventory_export_has_one_row_per_client() {
+    let app = TestApp::new();
+    app.login();
+    let uuid = app.register_with_inventory();
+    app.register();
+    let attribute = app
+        .db()
+        .attribute_manager()
+        .create_definition("Raum", sit_server::database::AttributeKind::Text, "")
+        .unwrap();
+    let client = app.db().get_client(&uuid).unwrap();
+    app.db()
+        .attribute_manager()
+        .set_client_attribute(client.id, attribute.id, "R204")
+        .unwrap();
+
+    let csv = app.get_page("/export/inventory");
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].ends_with(";Verantwortlich;Raum"));
+    let row = lines.iter().find(|l| l.contains(&uuid.to_string())).unwrap();
+    assert!(row.starts_with("PC-R204-01;"));
+    assert!(row.contains("PC2X4K7B"));
+    assert!(row.contains("8C:16:45:12:34:56"));
+    assert!(row.ends_with(";R204"));
+
+    assert!(app.get_page("/clients").contains("/export/inventory?format=csv"));
+}
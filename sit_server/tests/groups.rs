@@ -0,0 +1,218 @@
+mod common;
+
+use common::TestApp;
+use rocket::http::{ContentType, Status};
+use sit_server::database::{GroupRule, RuleCondition, RuleField};
+
+#[test]
+fn group_scopes_aggregates() {
+    let app = TestApp::new();
+    let member = app.register_with_inventory();
+    app.register_with_inventory();
+    let db = app.db();
+
+    let group = db
+        .group_manager()
+        .create_group("Raum 204", Some("Schülerrechner"))
+        .unwrap();
+    let empty = db.group_manager().create_group("Prüfungs-PCs", None).unwrap();
+    let client = db.get_client(&member).unwrap();
+    db.group_manager().add_client(group.id, client.id).unwrap();
+    // Adding twice is a no-op.
+    db.group_manager().add_client(group.id, client.id).unwrap();
+
+    assert_eq!(db.get_clients_with_os_info(None).unwrap().len(), 2);
+    let clients = db.get_clients_with_os_info(Some(group.id)).unwrap();
+    assert_eq!(clients.len(), 1);
+    assert_eq!(clients[0].0.uuid, member);
+    assert!(db.get_clients_with_os_info(Some(empty.id)).unwrap().is_empty());
+
+    assert_eq!(db.get_processors_count(None).unwrap()[0].count, 2);
+    assert_eq!(db.get_processors_count(Some(group.id)).unwrap()[0].count, 1);
+    assert_eq!(db.get_memory_count(Some(group.id)).unwrap()[0].count, 1);
+    assert!(db.get_memory_count(Some(empty.id)).unwrap().is_empty());
+    assert_eq!(db.get_os_list(Some(group.id)).unwrap()[0].count, 1);
+
+    let software = db.get_software_list(Some(group.id)).unwrap();
+    assert_eq!(software.len(), 5);
+    assert!(db.get_software_list(Some(empty.id)).unwrap().is_empty());
+    let versions = db.get_software_versions(software[0].id, Some(group.id)).unwrap();
+    assert_eq!(versions[0].count, 1);
+
+    let groups = db.group_manager().get_groups().unwrap();
+    assert_eq!(groups.len(), 2);
+    assert_eq!((groups[0].name.as_str(), groups[0].count), ("Prüfungs-PCs", 0));
+    assert_eq!((groups[1].name.as_str(), groups[1].count), ("Raum 204", 1));
+
+    db.group_manager().delete_group(group.id).unwrap();
+    assert!(db.get_clients_with_os_info(Some(group.id)).unwrap().is_empty());
+}
+
+#[test]
+fn groups_are_managed_from_the_web_ui() {
+    let app = TestApp::new();
+    app.login();
+    let uuid = app.register_with_inventory();
+
+    let response = app
+        .client
+        .post("/settings/groups/new")
+        .header(ContentType::Form)
+        .body("name=Lehrer-Laptops&description=")
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    let group = app.db().group_manager().get_groups().unwrap().remove(0);
+    assert_eq!(group.description, None);
+
+    let response = app
+        .client
+        .post(format!("/clients/{uuid}/groups"))
+        .header(ContentType::Form)
+        .body(format!("group_id={}", group.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert!(app.get_page(&format!("/clients/{uuid}")).contains("Lehrer-Laptops"));
+    assert!(app
+        .get_page(&format!("/settings/groups/{}", group.id))
+        .contains(&uuid.to_string()));
+
+    let page = app.get_page(&format!("/hardware/processors?group={}", group.id));
+    assert!(page.contains(&format!("?group={}", group.id)));
+    app.get_page(&format!("/software/software?group={}", group.id));
+    app.get_page(&format!("/system-status/volumes?group={}", group.id));
+
+    let response = app
+        .client
+        .post(format!("/clients/{uuid}/groups/{}/remove", group.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert!(app
+        .db()
+        .get_clients_with_os_info(Some(group.id))
+        .unwrap()
+        .is_empty());
+
+    // Members of smart groups are not assigned by hand.
+    let rule = GroupRule {
+        conditions: vec![RuleCondition {
+            field: RuleField::OsName,
+            value: "*".to_string(),
+            version: None,
+        }],
+    };
+    app.db().group_manager().set_rule(group.id, Some(&rule)).unwrap();
+    let response = app
+        .client
+        .post(format!("/clients/{uuid}/groups/{}/remove", group.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(app.db().get_clients_with_os_info(Some(group.id)).unwrap().len(), 1);
+    app.db().group_manager().set_rule(group.id, None).unwrap();
+    let response = app
+        .client
+        .post(format!("/clients/{uuid}/groups"))
+        .header(ContentType::Form)
+        .body(format!("group_id={}", group.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    let smart = app.db().group_manager().create_group("Alle", None).unwrap();
+    app.db().group_manager().set_rule(smart.id, Some(&rule)).unwrap();
+    let response = app
+        .client
+        .post(format!("/clients/{uuid}/groups"))
+        .header(ContentType::Form)
+        .body(format!("group_id={}", smart.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+}
+
+#[test]
+fn smart_group_follows_inventory() {
+    let app = TestApp::new();
+    let inventoried = app.register_with_inventory();
+    let response = app.post_json("/api/v1/register", r#"{"name": "PC-R105-07"}"#);
+    assert_eq!(response.status(), Status::Created);
+    let db = app.db();
+    let group = db.group_manager().create_group("Win11 mit Firefox 128", None).unwrap();
+    let rule = GroupRule {
+        conditions: vec![
+            RuleCondition {
+                field: RuleField::OsName,
+                value: "*windows 11*".to_string(),
+                version: None,
+            },
+            RuleCondition {
+                field: RuleField::Software,
+                value: "Mozilla Firefox*".to_string(),
+                version: Some("128.*".to_string()),
+            },
+            RuleCondition {
+                field: RuleField::Model,
+                value: "lenovo thinkcentre*".to_string(),
+                version: None,
+            },
+            RuleCondition {
+                field: RuleField::MemoryMin,
+                value: "16".to_string(),
+                version: None,
+            },
+        ],
+    };
+
+    assert_eq!(db.group_manager().preview(&rule).unwrap().len(), 1);
+    db.group_manager().set_rule(group.id, Some(&rule)).unwrap();
+    let members = db.get_clients_with_os_info(Some(group.id)).unwrap();
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].0.uuid, inventoried);
+
+    // `_` is no wildcard, only `?` is.
+    let mut strict = rule.clone();
+    strict.conditions[1].version = Some("128_3_1".to_string());
+    assert!(db.group_manager().preview(&strict).unwrap().is_empty());
+
+    // The second client joins once its inventory arrives.
+    let second = db.get_clients_with_os_info(None).unwrap();
+    let second = second.iter().find(|(c, _)| c.uuid != inventoried).unwrap().0.uuid;
+    app.upload_inventory(second);
+    assert_eq!(db.get_clients_with_os_info(Some(group.id)).unwrap().len(), 2);
+
+    let mut bigger = rule.clone();
+    bigger.conditions[3].value = "32".to_string();
+    db.group_manager().set_rule(group.id, Some(&bigger)).unwrap();
+    assert!(db.get_clients_with_os_info(Some(group.id)).unwrap().is_empty());
+
+    bigger.conditions[3].value = "viel".to_string();
+    assert!(db.group_manager().set_rule(group.id, Some(&bigger)).is_err());
+}
+
+#[test]
+fn smart_group_rule_is_edited_with_preview() {
+    let app = TestApp::new();
+    app.login();
+    app.register_with_inventory();
+    let group = app.db().group_manager().create_group("Schule", None).unwrap();
+
+    let rule = "conditions[0].field=domain&conditions[0].value=schule.local&conditions[0].version=\
+                &conditions[1].field=os_name&conditions[1].value=&conditions[1].version=";
+    let response = app
+        .client
+        .post(format!("/settings/groups/{}/preview", group.id))
+        .header(ContentType::Form)
+        .body(rule)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.into_string().unwrap().contains("PC-R204-01"));
+    assert!(app.db().get_clients_with_os_info(Some(group.id)).unwrap().is_empty());
+
+    let response = app
+        .client
+        .post(format!("/settings/groups/{}/rule", group.id))
+        .header(ContentType::Form)
+        .body(rule)
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert_eq!(app.db().get_clients_with_os_info(Some(group.id)).unwrap().len(), 1);
+    assert!(app
+        .get_page(&format!("/settings/groups/{}", group.id))
+        .contains("schule.local"));
+}
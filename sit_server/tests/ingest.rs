@@ -0,0 +1,146 @@
+mod common;
+
+use chrono::{TimeDelta, Utc};
+use common::{fixtures, TestApp};
+use rocket::http::Status;
+use sit_server::database::{STATUS_FAILED, STATUS_PENDING, STATUS_RUNNING};
+use sit_server::ingest::MAX_ATTEMPTS;
+
+const SOFTWARE_SMALL: &str = r#"{ "software": [
+  { "name": "7-Zip", "version": "24.08", "publisher": "Igor Pavlov" }
+] }"#;
+
+#[test]
+fn payloads_of_a_client_are_applied_in_order() {
+    let app = TestApp::new();
+    let uuid = app.register();
+    for body in [fixtures::SOFTWARE, SOFTWARE_SMALL] {
+        let response = app.post_json(&format!("/api/v1/software/{uuid}"), body);
+        assert_eq!(response.status(), Status::Accepted);
+    }
+    assert!(app.db().get_client_software(uuid).unwrap().is_empty());
+
+    assert_eq!(app.process_ingest(), 2);
+    assert_eq!(app.db().get_client_software(uuid).unwrap().len(), 1);
+    assert!(app.db().ingest_manager().get_jobs(STATUS_PENDING).unwrap().is_empty());
+}
+
+#[test]
+fn jobs_are_claimed_one_per_client() {
+    let app = TestApp::new();
+    let first = app.db().get_client(&app.register()).unwrap().id;
+    let second = app.db().get_client(&app.register()).unwrap().id;
+    let ingest = app.db().ingest_manager();
+    let now = Utc::now().naive_utc();
+    for client_id in [first, first, second] {
+        ingest.enqueue(client_id, "software", SOFTWARE_SMALL, None, now).unwrap();
+    }
+    let until = now + TimeDelta::minutes(1);
+
+    let claimed = ingest.claim(now, until).unwrap().unwrap();
+    let job = ingest.get_job(claimed).unwrap();
+    assert_eq!((job.client_id, job.status.as_str()), (first, STATUS_RUNNING));
+    // The next job of the first client waits for the running one.
+    let next = ingest.claim(now, until).unwrap().unwrap();
+    assert_eq!(ingest.get_job(next).unwrap().client_id, second);
+    assert_eq!(ingest.claim(now, until).unwrap(), None);
+
+    // An abandoned job is claimed again once its claim ran out.
+    assert_eq!(ingest.claim(until, until + TimeDelta::minutes(1)).unwrap(), Some(claimed));
+}
+
+#[test]
+fn jobs_that_take_down_the_server_are_set_aside() {
+    let app = TestApp::new();
+    let uuid = app.register();
+    let client_id = app.db().get_client(&uuid).unwrap().id;
+    let ingest = app.db().ingest_manager();
+    let start = Utc::now().naive_utc() - TimeDelta::hours(1);
+    ingest.enqueue(client_id, "software", SOFTWARE_SMALL, None, start).unwrap();
+
+    // Release builds abort on panic: the job is claimed, the server goes down
+    // and the claim runs out, again and again.
+    for n in 0..MAX_ATTEMPTS {
+        let claimed = start + TimeDelta::minutes(n.into());
+        assert!(ingest.claim(claimed, claimed + TimeDelta::minutes(1)).unwrap().is_some());
+    }
+    assert_eq!(app.process_ingest(), 1);
+    let job = ingest.get_jobs(STATUS_FAILED).unwrap().remove(0).0;
+    assert_eq!(job.attempts, MAX_ATTEMPTS);
+    assert!(job.last_error.unwrap().contains("stopped"));
+    assert!(app.db().get_client_software(uuid).unwrap().is_empty());
+}
+
+#[test]
+fn failing_payloads_are_set_aside_and_can_be_retried() {
+    let app = TestApp::new();
+    app.login();
+    let uuid = app.register();
+    let client_id = app.db().get_client(&uuid).unwrap().id;
+    let now = Utc::now().naive_utc();
+    app.db()
+        .ingest_manager()
+        .enqueue(client_id, "software", r#"{"software": 1}"#, Some("c3"), now)
+        .unwrap();
+    let job_id = app.db().ingest_manager().get_jobs(STATUS_PENDING).unwrap()[0].0.id;
+
+    // The first attempt fails, the job is retried after a delay.
+    assert_eq!(app.process_ingest(), 1);
+    let job = app.db().ingest_manager().get_job(job_id).unwrap();
+    assert_eq!(job.status, STATUS_PENDING);
+    assert_eq!(job.attempts, 1);
+    assert!(job.last_error.is_some());
+    assert!(app.db().section_hash_manager().get_hashes(client_id).unwrap().is_empty());
+
+    app.db()
+        .ingest_manager()
+        .record_failure(job_id, MAX_ATTEMPTS - 1, "earlier", now, false)
+        .unwrap();
+    assert_eq!(app.process_ingest(), 1);
+    assert_eq!(app.db().ingest_manager().get_job(job_id).unwrap().status, STATUS_FAILED);
+
+    let page = app.get_page("/system-status/ingest");
+    assert!(page.contains("Fehlgeschlagene Meldungen"));
+    assert!(page.contains(&format!("/system-status/ingest/{job_id}/retry")));
+
+    let response = app.client.post(format!("/system-status/ingest/{job_id}/retry")).dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    let job = app.db().ingest_manager().get_job(job_id).unwrap();
+    assert_eq!((job.status.as_str(), job.attempts), (STATUS_PENDING, 0));
+
+    // Only failed jobs are discarded.
+    let response = app.client.post(format!("/system-status/ingest/{job_id}/discard")).dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert!(app.db().ingest_manager().get_job(job_id).is_ok());
+    app.db()
+        .ingest_manager()
+        .record_failure(job_id, MAX_ATTEMPTS, "earlier", now, true)
+        .unwrap();
+    let response = app.client.post(format!("/system-status/ingest/{job_id}/discard")).dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert!(app.db().ingest_manager().get_job(job_id).is_err());
+}
+
+#[test]
+fn superseded_jobs_are_not_retried() {
+    let app = TestApp::new();
+    let uuid = app.register();
+    let client_id = app.db().get_client(&uuid).unwrap().id;
+    let ingest = app.db().ingest_manager();
+    let now = Utc::now().naive_utc();
+    ingest.enqueue(client_id, "software", SOFTWARE_SMALL, None, now).unwrap();
+    let job_id = ingest.get_jobs(STATUS_PENDING).unwrap()[0].0.id;
+    ingest.record_failure(job_id, MAX_ATTEMPTS, "earlier", now, true).unwrap();
+
+    // A pending job can't be discarded.
+    ingest.enqueue(client_id, "software", fixtures::SOFTWARE, None, now).unwrap();
+    let newer = ingest.get_jobs(STATUS_PENDING).unwrap()[0].0.id;
+    assert_eq!(ingest.delete_job(newer).unwrap(), 0);
+    // The old payload would overwrite the newer one.
+    assert_eq!(ingest.retry_job(job_id, now).unwrap(), 0);
+
+    // Once the newer one is applied, the failed job is gone.
+    assert_eq!(app.process_ingest(), 1);
+    assert!(ingest.get_jobs(STATUS_FAILED).unwrap().is_empty());
+    assert_eq!(app.db().get_client_software(uuid).unwrap().len(), 5);
+}
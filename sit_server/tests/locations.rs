@@ -0,0 +1,85 @@
+mod common;
+
+use common::TestApp;
+use rocket::http::{ContentType, Status};
+
+#[test]
+fn clients_are_placed_by_most_specific_subnet() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    let db = app.db();
+    let locations = db.location_manager();
+    let client = db.get_client(&uuid).unwrap();
+
+    let site = locations.create_site("Hauptschule").unwrap();
+    let building = locations.create_building(site.id, "Haus A").unwrap();
+    let floor = locations.create_room(building.id, "Flur 2. OG").unwrap();
+    let room = locations.create_room(building.id, "R204").unwrap();
+
+    locations.add_room_subnet(floor.id, "10.20.0.0/16").unwrap();
+    let (location, _) = locations.get_client_location(client.id).unwrap().unwrap();
+    assert_eq!((location.room_id, location.manual), (floor.id, false));
+
+    // Host bits are dropped, the more specific subnet wins.
+    locations.add_room_subnet(room.id, "10.20.4.1/24").unwrap();
+    assert_eq!(locations.get_room_subnets(room.id).unwrap()[0].subnet, "10.20.4.0/24");
+    let (location, (r, (b, s))) = locations.get_client_location(client.id).unwrap().unwrap();
+    assert_eq!(location.room_id, room.id);
+    assert_eq!((r.name.as_str(), b.name.as_str(), s.name.as_str()), ("R204", "Haus A", "Hauptschule"));
+    assert!(locations.add_room_subnet(room.id, "10.20.4.0/33").is_err());
+
+    // Manual placement sticks through re-ingest until it is reset.
+    locations.set_client_room(client.id, Some(floor.id)).unwrap();
+    app.upload_inventory(uuid);
+    let (location, _) = locations.get_client_location(client.id).unwrap().unwrap();
+    assert_eq!((location.room_id, location.manual), (floor.id, true));
+    locations.set_client_room(client.id, None).unwrap();
+    let (location, _) = locations.get_client_location(client.id).unwrap().unwrap();
+    assert_eq!((location.room_id, location.manual), (room.id, false));
+
+    let subnets = locations.get_room_subnets(room.id).unwrap();
+    locations.delete_room_subnet(subnets[0].id).unwrap();
+    let (location, _) = locations.get_client_location(client.id).unwrap().unwrap();
+    assert_eq!(location.room_id, floor.id);
+
+    locations.delete_site(site.id).unwrap();
+    assert!(locations.get_client_location(client.id).unwrap().is_none());
+    assert!(locations.get_rooms().unwrap().is_empty());
+}
+
+#[test]
+fn room_view_lists_its_machines() {
+    let app = TestApp::new();
+    app.login();
+    let uuid = app.register_with_inventory();
+
+    for (uri, body) in [
+        ("/locations/sites", "name=Hauptschule"),
+        ("/locations/buildings", "parent_id=1&name=Haus+A"),
+        ("/locations/rooms", "parent_id=1&name=R204"),
+    ] {
+        let response = app
+            .client
+            .post(uri)
+            .header(ContentType::Form)
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::SeeOther, "POST {uri}");
+    }
+    let room = app.db().location_manager().get_rooms().unwrap().remove(0);
+    assert!(app.get_page("/locations").contains("R204"));
+
+    let response = app
+        .client
+        .post(format!("/clients/{uuid}/location"))
+        .header(ContentType::Form)
+        .body(format!("room_id={}", room.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert!(app.get_page(&format!("/clients/{uuid}")).contains("Hauptschule / Haus A / R204"));
+
+    let page = app.get_page(&format!("/locations/rooms/{}", room.id));
+    assert!(page.contains("PC-R204-01"));
+    assert!(page.contains("10.20.4.101"));
+    assert!(page.contains("LENOVO ThinkCentre M70q"));
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use std::fs;
+use std::net::SocketAddr;
+
+use chrono::Utc;
+use common::{TestApp, TEST_PASSWORD, TEST_USER};
+use rocket::http::{ContentType, Status};
+use sit_server::auth::{self, LoginPolicy, PasswordPolicy, Role};
+
+/// Posts the login form and returns where it redirects to.
+fn post_login(app: &TestApp, username: &str, password: &str, remote: Option<SocketAddr>) -> Option<String> {
+    let mut request = app
+        .client
+        .post("/auth/login")
+        .header(ContentType::Form)
+        .body(format!("username={username}&password={password}"));
+    if let Some(remote) = remote {
+        request = request.remote(remote);
+    }
+    let response = request.dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    response.headers().get_one("Location").map(str::to_string)
+}
+
+#[test]
+fn failed_logins_lock_the_account() {
+    let app = TestApp::new();
+    let policy = PasswordPolicy::default();
+    auth::create_new_user(app.db(), &policy, "admin", "richtiges-passwort", Role::Administrator).unwrap();
+
+    for _ in 0..5 {
+        let location = post_login(&app, "admin", "falsch", None);
+        assert_eq!(location.as_deref(), Some("/auth/login"));
+        assert!(app.get_page("/auth/login").contains("Ungültiger Benutzername"));
+    }
+    // Locked, even with the right password.
+    let location = post_login(&app, "admin", "richtiges-passwort", None);
+    assert_eq!(location.as_deref(), Some("/auth/login"));
+    assert!(app.get_page("/auth/login").contains("Zu viele fehlgeschlagene Anmeldungen"));
+
+    let failures = app.db().login_attempt_manager().get_failures(10).unwrap();
+    assert_eq!(failures.len(), 6);
+    assert!(failures.iter().all(|a| a.username == "admin"));
+
+    // Other accounts are not affected, and the failures are listed.
+    app.login();
+    let users = app.get_page("/settings/users");
+    assert!(users.contains("Fehlgeschlagene Anmeldungen"));
+    assert!(!users.contains("Keine fehlgeschlagenen Anmeldungen"));
+}
+
+#[test]
+fn failed_logins_lock_the_address() {
+    let app = TestApp::new();
+    let policy = PasswordPolicy::default();
+    auth::create_new_user(app.db(), &policy, TEST_USER, TEST_PASSWORD, Role::Administrator).unwrap();
+    let attacker: SocketAddr = "192.0.2.10:40000".parse().unwrap();
+    let other: SocketAddr = "192.0.2.20:40000".parse().unwrap();
+
+    for n in 0..20 {
+        post_login(&app, &format!("benutzer{n}"), "falsch", Some(attacker));
+    }
+    let location = post_login(&app, TEST_USER, TEST_PASSWORD, Some(attacker));
+    assert_eq!(location.as_deref(), Some("/auth/login"));
+
+    // A successful login from the address doesn't lift the lock.
+    let now = Utc::now().naive_utc();
+    let db = app.db();
+    db.login_attempt_manager()
+        .add_attempt("eigenes-konto", Some("192.0.2.10"), now, true)
+        .unwrap();
+    assert!(LoginPolicy::default().is_locked(db, "benutzer0", Some("192.0.2.10"), now).unwrap());
+
+    let location = post_login(&app, TEST_USER, TEST_PASSWORD, Some(other));
+    assert_eq!(location.as_deref(), Some("/"));
+    let failures = app.db().login_attempt_manager().get_failures(1).unwrap();
+    assert_eq!(failures[0].ip.as_deref(), Some("192.0.2.10"));
+}
+
+#[test]
+fn password_policy_is_enforced() {
+    let app = TestApp::new();
+    let db = app.db();
+    let policy = PasswordPolicy::default();
+
+    assert!(auth::create_new_user(db, &policy, "kurz", "", Role::Auditor).is_err());
+    assert!(auth::create_new_user(db, &policy, "kurz", "zu-kurz", Role::Auditor).is_err());
+    assert!(auth::create_new_user(db, &policy, "bekannt", "Passwort123", Role::Auditor).is_err());
+    assert!(db.get_auth_user_by_username("kurz").is_err());
+
+    let list = std::env::temp_dir().join(format!("sit-breached-{}.txt", std::process::id()));
+    fs::write(&list, "erstes-leck\nzweites-leck\n").unwrap();
+    let custom = PasswordPolicy {
+        min_length: 8,
+        breached_passwords: Some(list.clone()),
+    };
+    assert!(auth::create_new_user(db, &custom, "eigen", "Zweites-Leck", Role::Auditor).is_err());
+    auth::create_new_user(db, &custom, "eigen", "acht-zei", Role::Auditor).unwrap();
+    fs::remove_file(list).unwrap();
+
+    app.login();
+    let response = app
+        .client
+        .post("/auth/password")
+        .header(ContentType::Form)
+        .body(format!("current_password={TEST_PASSWORD}&password=kurz&password_confirmation=kurz"))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert!(app.get_page("/auth/password").contains("at least 10 characters"));
+    assert!(auth::check_password(db, TEST_USER, TEST_PASSWORD).is_ok());
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use std::env;
+
+use common::{TestApp, TestDatabase, TEST_PASSWORD, TEST_USER};
+use rocket::http::{ContentType, Status};
+
+#[test]
+#[ignore = "needs a Postgres TEST_DATABASE_URL, run with --ignored"]
+fn sqlite_installation_moves_to_postgres() {
+    let target = TestDatabase::from_env();
+    assert!(target.is_postgres(), "TEST_DATABASE_URL must be a Postgres url for the migration test");
+    let target = TestApp::with_database(target);
+    let source = TestApp::with_database(TestDatabase::sqlite(env::temp_dir()));
+    source.login();
+    let uuid = source.register_with_inventory();
+
+    let copied = source.db().copy_into(target.db()).unwrap();
+    let rows = |table: &str| copied.iter().find(|(t, _)| *t == table).unwrap().1;
+    assert_eq!(rows("client"), 1);
+    assert_eq!(rows("software_list"), 5);
+    assert_eq!(rows("network_adapter_ip"), 2);
+    assert_eq!(rows("auth_user"), 1);
+
+    assert_eq!(target.db().get_client(&uuid).unwrap().uuid, uuid);
+    assert_eq!(target.db().get_memory_count(None).unwrap().len(), 1);
+
+    // Copied credentials keep working and sequences continue after the copied ids.
+    let response = target
+        .client
+        .post("/auth/login")
+        .header(ContentType::Form)
+        .body(format!("username={TEST_USER}&password={TEST_PASSWORD}"))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert!(target.get_page(&format!("/clients/{uuid}")).contains("PC-R204-01"));
+    let response = target.post_json("/api/v1/register", r#"{"name": "PC-R105-07"}"#);
+    assert_eq!(response.status(), Status::Created);
+
+    assert!(source.db().copy_into(target.db()).is_err());
+}
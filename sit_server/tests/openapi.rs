@@ -0,0 +1,38 @@
+mod common;
+
+use common::TestApp;
+use rocket::http::Status;
+use serde_json::Value;
+
+#[test]
+fn openapi_document_covers_agent_and_query_api() {
+    let app = TestApp::new();
+
+    let response = app.client.get("/api/openapi.json").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let spec: Value = response.into_json().unwrap();
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    // Paths are relative to the server URL.
+    assert_eq!(spec["servers"][0]["url"], "/api");
+    let paths = spec["paths"].as_object().unwrap();
+    for path in [
+        "/v1/register",
+        "/v1/os/{uuid}",
+        "/v1/tasks/{uuid}",
+        "/v2/hardware/{uuid}",
+        "/v3/snapshot/{uuid}",
+        "/query/v1/clients",
+    ] {
+        assert!(paths.contains_key(path), "{path} missing");
+    }
+    let schemas = spec["components"]["schemas"].as_object().unwrap();
+    assert!(schemas.contains_key("HardwareInfoV2"));
+    assert!(schemas["WinOsInfo"]["properties"]
+        .as_object()
+        .unwrap()
+        .contains_key("computer_name"));
+    assert!(spec["components"]["securitySchemes"]["AccessToken"].is_object());
+
+    let response = app.client.get("/api/docs/index.html").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+}
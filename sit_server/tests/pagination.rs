@@ -0,0 +1,96 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::TestApp;
+use sit_server::database::ListQuery;
+
+fn list(page: i64, per_page: i64, sort: &str, desc: bool, filters: &[(&str, &str)]) -> ListQuery {
+    ListQuery {
+        page,
+        per_page,
+        sort: Some(sort.to_owned()),
+        desc,
+        filters: filters
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+#[test]
+fn software_list_is_paged_sorted_and_filtered() {
+    let app = TestApp::new();
+    app.register_with_inventory();
+    let db = app.db();
+
+    let first = db.get_software_page(None, &list(1, 2, "name", false, &[])).unwrap();
+    assert_eq!(first.total, 5);
+    assert_eq!(first.pages, 3);
+    assert_eq!(first.items[0].name, "7-Zip");
+    assert_eq!(first.items[1].name, "GeoGebra Classic");
+    let last = db.get_software_page(None, &list(3, 2, "name", false, &[])).unwrap();
+    assert_eq!(last.items.len(), 1);
+    assert_eq!(last.items[0].name, "Mozilla Firefox (x64 de)");
+
+    let desc = db.get_software_page(None, &list(1, 2, "name", true, &[])).unwrap();
+    assert_eq!(desc.items[0].name, "Mozilla Firefox (x64 de)");
+
+    let filtered = db
+        .get_software_page(None, &list(1, 50, "name", false, &[("name", "OFFICE")]))
+        .unwrap();
+    assert_eq!(filtered.total, 1);
+    assert_eq!(filtered.items[0].name, "LibreOffice");
+    // `%` is taken literally, not as wildcard.
+    assert_eq!(
+        db.get_software_page(None, &list(1, 50, "name", false, &[("name", "%")]))
+            .unwrap()
+            .total,
+        0
+    );
+}
+
+#[test]
+fn aggregates_and_clients_are_paged() {
+    let app = TestApp::new();
+    app.register_with_inventory();
+    app.register_with_inventory();
+    let db = app.db();
+
+    let processors = db.get_processors_page(None, &ListQuery::default()).unwrap();
+    assert_eq!(processors.total, 1);
+    assert_eq!(processors.items[0].count, 2);
+    let none = db
+        .get_processors_page(None, &list(1, 50, "count", true, &[("name", "gibt es nicht")]))
+        .unwrap();
+    assert_eq!(none.total, 0);
+    assert_eq!(none.pages, 1);
+
+    let clients = db.get_client_page(None, &list(2, 1, "name", false, &[])).unwrap();
+    assert_eq!(clients.total, 2);
+    assert_eq!(clients.items.len(), 1);
+    let clients = db
+        .get_client_page(None, &list(1, 50, "os", false, &[("name", "pc-r204")]))
+        .unwrap();
+    assert_eq!(clients.total, 2);
+
+    let profiles = db.get_profile_page(&list(1, 50, "count", true, &[])).unwrap();
+    assert_eq!(profiles.items[0].count, 2);
+}
+
+#[test]
+fn list_pages_keep_their_state_in_links() {
+    let app = TestApp::new();
+    app.login();
+    app.register_with_inventory();
+
+    let page = app.get_page("/software/software?per_page=2&sort=name&desc=true&filter.publisher=a");
+    assert!(page.contains("Seite 1 von"));
+    assert!(page.contains("?per_page=2&sort=name&filter.publisher=a"));
+    assert!(page.contains("per_page=2&sort=name&desc=true&filter.publisher=a&page=2"));
+
+    let page = app.get_page("/hardware/processors?filter.name=xeon");
+    assert!(page.contains("0 Einträge"));
+    assert!(app.get_page("/profile?sort=count&desc=true").contains("Seite 1 von 1"));
+    assert!(app.get_page("/clients?filter.name=PC-R204").contains("PC-R204-01"));
+}
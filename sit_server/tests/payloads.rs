@@ -0,0 +1,38 @@
+mod common;
+
+use common::fixtures;
+use sit_lib::hardware::{BatteryStatus, HardwareInfo, HardwareInfoV2};
+use sit_lib::licenses::LicenseBundle;
+use sit_lib::os::{UserProfiles, WinOsInfo};
+use sit_lib::server::Register;
+use sit_lib::software::SoftwareLibrary;
+use sit_lib::system_status::VolumeList;
+use sit_lib::task::{TaskBundle, TaskStatus, TaskUpdate};
+
+#[test]
+fn fixtures_match_wire_types() {
+    let register: Register = serde_json::from_str(fixtures::REGISTER).unwrap();
+    assert!(register.uuid.is_none());
+    let _: WinOsInfo = serde_json::from_str(fixtures::OS_INFO).unwrap();
+    let _: HardwareInfo = serde_json::from_str(fixtures::HARDWARE_V1).unwrap();
+    let hardware: HardwareInfoV2 = serde_json::from_str(fixtures::HARDWARE_V2).unwrap();
+    assert_eq!(hardware.graphics.len(), 2);
+    let software: SoftwareLibrary = serde_json::from_str(fixtures::SOFTWARE).unwrap();
+    assert_eq!(software.software.len(), 5);
+    let profiles: UserProfiles = serde_json::from_str(fixtures::PROFILES).unwrap();
+    assert!(profiles.profiles[1].domain.is_none());
+    let _: VolumeList = serde_json::from_str(fixtures::VOLUMES).unwrap();
+    let _: LicenseBundle = serde_json::from_str(fixtures::LICENSES).unwrap();
+    let _: BatteryStatus = serde_json::from_str(fixtures::BATTERY).unwrap();
+    let _: TaskBundle = serde_json::from_str(fixtures::TASK_BUNDLE).unwrap();
+    let update: TaskUpdate = serde_json::from_str(fixtures::TASK_UPDATE).unwrap();
+    assert_eq!(update.task_status, TaskStatus::Downloaded);
+}
+
+#[test]
+fn fixtures_round_trip() {
+    let hardware: HardwareInfoV2 = serde_json::from_str(fixtures::HARDWARE_V2).unwrap();
+    let value = serde_json::to_value(&hardware).unwrap();
+    let original: serde_json::Value = serde_json::from_str(fixtures::HARDWARE_V2).unwrap();
+    assert_eq!(value, original);
+}
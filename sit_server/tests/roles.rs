@@ -0,0 +1,165 @@
+mod common;
+
+use common::TestApp;
+use rocket::http::{ContentType, Status};
+use sit_server::auth::{self, PasswordPolicy, Role};
+
+#[test]
+fn auditors_can_only_read() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    app.login_as("pruefer", Role::Auditor);
+
+    let client_page = app.get_page(&format!("/clients/{uuid}"));
+    assert!(client_page.contains("PC-R204-01"));
+    assert!(!client_page.contains("Einstellungen"));
+    for uri in [
+        "/settings".to_string(),
+        "/settings/users".to_string(),
+        format!("/clients/{uuid}/licenses"),
+    ] {
+        let response = app.client.get(uri.as_str()).dispatch();
+        assert_eq!(response.status(), Status::Forbidden, "GET {uri}");
+    }
+    let response = app
+        .client
+        .post(format!("/clients/{uuid}/asset"))
+        .header(ContentType::Form)
+        .body("inventory_number=INV-1&purchase_date=&supplier=&price=&warranty_end=&responsible=")
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+    let client = app.db().get_client(&uuid).unwrap();
+    assert!(app.db().asset_manager().get_asset(client.id).unwrap().is_none());
+}
+
+#[test]
+fn restricted_users_see_their_groups_only() {
+    let app = TestApp::new();
+    let member = app.register_with_inventory();
+    let other = app.register_with_inventory();
+    let db = app.db();
+    let group = db.group_manager().create_group("Raum 204", None).unwrap();
+    db.group_manager().add_client(group.id, db.get_client(&member).unwrap().id).unwrap();
+
+    let user_id = app.login_as("techniker", Role::Technician);
+    db.set_auth_user_groups(user_id, &[group.id]).unwrap();
+
+    let clients = app.get_page("/clients");
+    assert!(clients.contains(&member.to_string()));
+    assert!(!clients.contains(&other.to_string()));
+    assert!(!clients.contains(r#"<option value="">Alle Clients</option>"#));
+    app.get_page(&format!("/clients/{member}"));
+    for uri in [format!("/clients/{other}"), "/locations".to_string(), "/search?q=PC".to_string()] {
+        let response = app.client.get(uri.as_str()).dispatch();
+        assert_eq!(response.status(), Status::Forbidden, "GET {uri}");
+    }
+
+    // Clients can't be moved into or out of other groups.
+    let foreign = db.group_manager().create_group("Verwaltung", None).unwrap();
+    let member_id = db.get_client(&member).unwrap().id;
+    db.group_manager().add_client(foreign.id, member_id).unwrap();
+    let third = db.group_manager().create_group("Bibliothek", None).unwrap();
+    let page = app.get_page(&format!("/clients/{member}"));
+    assert!(!page.contains(&format!(r#"<option value="{}">"#, third.id)));
+    let response = app
+        .client
+        .post(format!("/clients/{member}/groups/{}/remove", foreign.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+    let response = app
+        .client
+        .post(format!("/clients/{member}/groups"))
+        .header(ContentType::Form)
+        .body(format!("group_id={}", third.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+    assert_eq!(db.group_manager().get_client_groups(member_id).unwrap().len(), 2);
+}
+
+#[test]
+fn restricted_users_do_not_manage_locations() {
+    let app = TestApp::new();
+    let db = app.db();
+    let locations = db.location_manager();
+    let site = locations.create_site("Hauptgebäude").unwrap();
+    let building = locations.create_building(site.id, "Haus A").unwrap();
+    let room = locations.create_room(building.id, "R204").unwrap();
+    locations.add_room_subnet(room.id, "10.0.204.0/24").unwrap();
+    let subnet = locations.get_room_subnets(room.id).unwrap().remove(0);
+    let group = db.group_manager().create_group("Raum 204", None).unwrap();
+    let user_id = app.login_as("techniker", Role::Technician);
+    db.set_auth_user_groups(user_id, &[group.id]).unwrap();
+
+    for (uri, body) in [
+        ("/locations/sites".to_string(), "name=Neu".to_string()),
+        ("/locations/buildings".to_string(), format!("parent_id={}&name=Neu", site.id)),
+        ("/locations/rooms".to_string(), format!("parent_id={}&name=Neu", building.id)),
+        (format!("/locations/rooms/{}/subnets", room.id), "subnet=10.0.0.0/8".to_string()),
+    ] {
+        let response = app
+            .client
+            .post(uri.as_str())
+            .header(ContentType::Form)
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden, "POST {uri}");
+    }
+    for uri in [
+        format!("/locations/sites/{}/delete", site.id),
+        format!("/locations/buildings/{}/delete", building.id),
+        format!("/locations/rooms/{}/delete", room.id),
+        format!("/locations/rooms/{}/subnets/{}/delete", room.id, subnet.id),
+    ] {
+        let response = app.client.get(uri.as_str()).dispatch();
+        assert_eq!(response.status(), Status::Forbidden, "GET {uri}");
+    }
+    assert_eq!(locations.get_sites().unwrap().len(), 1);
+    assert_eq!(locations.get_rooms().unwrap().len(), 1);
+    assert_eq!(locations.get_room_subnets(room.id).unwrap().len(), 1);
+}
+
+#[test]
+fn last_administrator_keeps_the_role() {
+    let app = TestApp::new();
+    let admin_id = app.login_as("admin", Role::Administrator);
+
+    assert!(auth::set_role(app.db(), admin_id, Role::Auditor).is_err());
+    let policy = PasswordPolicy::default();
+    auth::create_new_user(app.db(), &policy, "zweiter", "zweites-passwort", Role::Technician).unwrap();
+    let second = app.db().get_auth_user_by_username("zweiter").unwrap();
+    let response = app
+        .client
+        .post(format!("/settings/users/{}", second.id))
+        .header(ContentType::Form)
+        .body("role=administrator")
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    auth::set_role(app.db(), admin_id, Role::Auditor).unwrap();
+    assert_eq!(app.db().get_auth_user_by_id(admin_id).unwrap().role, "auditor");
+}
+
+#[test]
+fn two_administrators_do_not_disable_each_other() {
+    let app = TestApp::new();
+    let admin_id = app.login_as("admin", Role::Administrator);
+    let policy = PasswordPolicy::default();
+    auth::create_new_user(app.db(), &policy, "zweiter", "zweites-passwort", Role::Administrator).unwrap();
+    let second = app.db().get_auth_user_by_username("zweiter").unwrap();
+
+    let db = app.db();
+    let results: Vec<bool> = std::thread::scope(|s| {
+        let handles: Vec<_> = [admin_id, second.id]
+            .into_iter()
+            .map(|id| s.spawn(move || auth::set_disabled(db, id, true).is_ok()))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert_eq!(results.iter().filter(|ok| **ok).count(), 1);
+    let active = db
+        .get_auth_users()
+        .unwrap()
+        .into_iter()
+        .filter(|u| u.role == "administrator" && !u.disabled)
+        .count();
+    assert_eq!(active, 1);
+}
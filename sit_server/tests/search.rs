@@ -0,0 +1,46 @@
+mod common;
+
+use common::TestApp;
+
+#[test]
+fn search_finds_clients_by_their_properties() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    let search = app.db().search_manager();
+
+    let results = search.search("pc-r204").unwrap();
+    assert_eq!(results.clients.len(), 1);
+    assert_eq!(results.clients[0].client.uuid, uuid);
+    assert_eq!(search.search(&uuid.to_string()).unwrap().clients.len(), 1);
+
+    assert_eq!(search.search("pc2x4k").unwrap().serial_numbers[0].matched, "PC2X4K7B");
+    // `_` is taken literally, not as wildcard.
+    assert_eq!(search.search("388b_91b2").unwrap().disk_serial_numbers.len(), 1);
+    assert!(search.search("388b-91b2").unwrap().disk_serial_numbers.is_empty());
+    assert_eq!(search.search("8c-16-45").unwrap().mac_addresses[0].matched, "8C:16:45:12:34:56");
+    assert_eq!(search.search("10.20.4.10").unwrap().ip_addresses[0].matched, "10.20.4.101");
+
+    let results = search.search("Mueller").unwrap();
+    assert_eq!(results.users.len(), 1);
+    assert_eq!(results.users[0].clients[0].client.uuid, uuid);
+    assert_eq!(search.search("-1106").unwrap().users.len(), 1);
+
+    assert_eq!(search.search("7-zip").unwrap().software[0].name, "7-Zip");
+    assert_eq!(search.search("nphtm").unwrap().licenses.len(), 1);
+
+    assert!(search.search("x").unwrap().is_empty());
+    assert!(search.search("gibt es nicht").unwrap().is_empty());
+}
+
+#[test]
+fn search_page_links_to_results() {
+    let app = TestApp::new();
+    app.login();
+    let uuid = app.register_with_inventory();
+
+    let page = app.get_page("/search?q=8C:16:45");
+    assert!(page.contains("MAC-Adressen"));
+    assert!(page.contains(&format!("/clients/{uuid}/hardware")));
+    assert!(app.get_page("/search?q=mueller").contains("/profile/S-1-5-21-1004336348-1177238915-682003330-1105"));
+    assert!(app.get_page("/search").contains("Keine Treffer"));
+}
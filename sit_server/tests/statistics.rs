@@ -0,0 +1,61 @@
+mod common;
+
+use chrono::{TimeDelta, Utc};
+use common::TestApp;
+use sit_server::database::ListQuery;
+use sit_server::ingest::IngestQueue;
+use sit_server::statistics;
+
+#[test]
+fn statistics_follow_refreshes() {
+    let app = TestApp::new();
+    app.register_with_inventory();
+    let db = app.db();
+
+    let state = db.statistics_manager().get_state().unwrap();
+    assert!(state.refreshed.is_some());
+    let processors = db.get_processors_page(None, &ListQuery::default()).unwrap();
+    assert_eq!(processors.items[0].count, 1);
+    assert_eq!(db.statistics_manager().get_os_list(None).unwrap()[0].count, 1);
+
+    // Applied without a refresh, the pages keep the old counts until one is due.
+    let uuid = app.register();
+    for (uri, body) in [
+        (format!("/api/v1/os/{uuid}"), common::fixtures::OS_INFO),
+        (format!("/api/v2/hardware/{uuid}"), common::fixtures::HARDWARE_V2),
+    ] {
+        app.post_json(&uri, body);
+    }
+    let queue = app.client.rocket().state::<IngestQueue>().unwrap();
+    queue.process_pending(db).unwrap();
+    let state = db.statistics_manager().get_state().unwrap();
+    assert!(state.changed >= state.refreshed);
+    assert_eq!(db.get_processors_count(None).unwrap()[0].count, 2);
+    let processors = db.get_processors_page(None, &ListQuery::default()).unwrap();
+    assert_eq!(processors.items[0].count, 1);
+    // Changes within a minute of the last refresh wait for the next check.
+    assert!(!statistics::refresh_if_due(db, TimeDelta::hours(1)).unwrap());
+    assert!(statistics::refresh_if_due(db, TimeDelta::zero()).unwrap());
+    let processors = db.get_processors_page(None, &ListQuery::default()).unwrap();
+    assert_eq!(processors.items[0].count, 2);
+
+    let group = db.group_manager().create_group("Raum 204", None).unwrap();
+    let client = db.get_client(&uuid).unwrap();
+    db.group_manager().add_client(group.id, client.id).unwrap();
+    app.refresh_statistics();
+    let processors = db.get_processors_page(Some(group.id), &ListQuery::default()).unwrap();
+    assert_eq!(processors.items[0].count, 1);
+    assert!(db.get_memory_page(Some(group.id), &ListQuery::default()).unwrap().total > 0);
+    let state = db.statistics_manager().get_state().unwrap();
+    assert!(state.refreshed.unwrap() <= Utc::now().naive_utc());
+}
+
+#[test]
+fn pages_show_statistics_freshness() {
+    let app = TestApp::new();
+    app.register_with_inventory();
+    app.login();
+
+    assert!(app.get_page("/hardware/processors").contains("Stand der Statistik"));
+    assert!(app.get_page("/software/os").contains("Stand der Statistik"));
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{TestApp, TEST_PASSWORD, TEST_USER};
+use rocket::http::{ContentType, Status};
+use sit_server::auth::{self, Role};
+
+fn post_form(app: &TestApp, uri: &str, body: &str) -> Option<String> {
+    let response = app
+        .client
+        .post(uri.to_string())
+        .header(ContentType::Form)
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther, "POST {uri}");
+    response.headers().get_one("Location").map(str::to_string)
+}
+
+#[test]
+fn users_change_their_own_password() {
+    let app = TestApp::new();
+    app.login();
+
+    let location = post_form(
+        &app,
+        "/auth/password",
+        "current_password=wrong&password=neu-geheim&password_confirmation=neu-geheim",
+    );
+    assert_eq!(location.as_deref(), Some("/auth/password"));
+    assert!(app.get_page("/auth/password").contains("konnte nicht geändert werden"));
+    assert!(auth::check_password(app.db(), TEST_USER, TEST_PASSWORD).is_ok());
+
+    post_form(
+        &app,
+        "/auth/password",
+        &format!("current_password={TEST_PASSWORD}&password=neu-geheim&password_confirmation=anders"),
+    );
+    assert!(app.get_page("/auth/password").contains("stimmen nicht überein"));
+
+    post_form(
+        &app,
+        "/auth/password",
+        &format!("current_password={TEST_PASSWORD}&password=neu-geheim&password_confirmation=neu-geheim"),
+    );
+    assert!(app.get_page("/auth/password").contains("wurde geändert"));
+    assert!(auth::check_password(app.db(), TEST_USER, "neu-geheim").is_ok());
+    assert!(auth::check_password(app.db(), TEST_USER, TEST_PASSWORD).is_err());
+}
+
+#[test]
+fn administrators_manage_users() {
+    let app = TestApp::new();
+    let admin_id = app.login_as("admin", Role::Administrator);
+
+    let location = post_form(
+        &app,
+        "/settings/users/new",
+        "username=techniker&password=techniker-passwort&password_confirmation=anders&role=technician",
+    );
+    assert_eq!(location.as_deref(), Some("/settings/users/new"));
+    assert!(app.get_page("/settings/users/new").contains("stimmen nicht überein"));
+    assert!(app.db().get_auth_user_by_username("techniker").is_err());
+
+    let location = post_form(
+        &app,
+        "/settings/users/new",
+        "username=techniker&password=techniker-passwort&password_confirmation=techniker-passwort&role=technician",
+    );
+    assert_eq!(location.as_deref(), Some("/settings/users"));
+    let technician = app.db().get_auth_user_by_username("techniker").unwrap();
+    assert_eq!(technician.role, "technician");
+    post_form(
+        &app,
+        "/settings/users/new",
+        "username=techniker&password=techniker-passwort&password_confirmation=techniker-passwort&role=technician",
+    );
+    assert!(app.get_page("/settings/users/new").contains("bereits vergeben"));
+
+    post_form(
+        &app,
+        &format!("/settings/users/{}/password", technician.id),
+        "password=zurueckgesetzt&password_confirmation=zurueckgesetzt",
+    );
+    assert!(auth::check_password(app.db(), "techniker", "zurueckgesetzt").is_ok());
+
+    app.get_page(&format!("/settings/users/{}", technician.id));
+    let response = app.client.get(format!("/settings/users/{}/disable", technician.id)).dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    assert!(!app.db().get_auth_user_by_id(technician.id).unwrap().disabled);
+    post_form(&app, &format!("/settings/users/{}/disable", technician.id), "");
+    assert!(app.get_page("/settings/users").contains("deaktiviert"));
+    assert!(auth::check_password(app.db(), "techniker", "zurueckgesetzt").is_err());
+    post_form(&app, &format!("/settings/users/{}/enable", technician.id), "");
+    assert!(app.get_page("/settings/users").contains("wurde aktiviert"));
+    assert!(auth::check_password(app.db(), "techniker", "zurueckgesetzt").is_ok());
+
+    // The own account and the last administrator stay.
+    post_form(&app, &format!("/settings/users/{admin_id}/delete"), "");
+    assert!(app.get_page("/settings/users").contains("nicht gelöscht werden"));
+    assert!(auth::delete_user(app.db(), "admin").is_err());
+    assert!(auth::set_disabled(app.db(), admin_id, true).is_err());
+
+    post_form(&app, &format!("/settings/users/{}/delete", technician.id), "");
+    assert!(app.get_page("/settings/users").contains("wurde gelöscht"));
+    assert!(app.db().get_auth_user_by_username("techniker").is_err());
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::TestApp;
+use rocket::http::Status;
+use sit_server::auth::{PasswordPolicy, Role};
+
+#[test]
+fn pages_require_login() {
+    let app = TestApp::new();
+    for uri in ["/", "/clients", "/hardware/memory", "/software/software"] {
+        let response = app.client.get(uri).dispatch();
+        assert_eq!(response.status(), Status::SeeOther, "GET {uri}");
+        assert_eq!(response.headers().get_one("Location"), Some("/auth/login"));
+    }
+}
+
+#[test]
+fn invalid_login_is_rejected() {
+    let app = TestApp::new();
+    let policy = PasswordPolicy::default();
+    sit_server::auth::create_new_user(app.db(), &policy, "admin", "richtiges-passwort", Role::Administrator).unwrap();
+    let response = app
+        .client
+        .post("/auth/login")
+        .header(rocket::http::ContentType::Form)
+        .body("username=admin&password=wrong")
+        .dispatch();
+    assert_eq!(response.headers().get_one("Location"), Some("/auth/login"));
+    assert!(app.get_page("/auth/login").contains("Ungültiger Benutzername oder ungültiges Passwort"));
+    let response = app.client.get("/clients").dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+}
+
+#[test]
+fn inventory_pages_render() {
+    let app = TestApp::new();
+    let uuid = app.register_with_inventory();
+    app.login();
+
+    assert!(app.get_page("/").contains("SCHKOLA IT-Manager"));
+    assert!(app.get_page("/clients").contains("PC-R204-01"));
+    let client_page = app.get_page(&format!("/clients/{uuid}"));
+    assert!(client_page.contains("Microsoft Windows 11 Pro"));
+    assert!(app
+        .get_page(&format!("/clients/{uuid}/hardware"))
+        .contains("PC2X4K7B"));
+    assert!(app
+        .get_page(&format!("/clients/{uuid}/software"))
+        .contains("LibreOffice"));
+    assert!(app
+        .get_page(&format!("/clients/{uuid}/profiles"))
+        .contains("schmidt"));
+    assert!(app
+        .get_page(&format!("/clients/{uuid}/status"))
+        .contains("System"));
+    assert!(app
+        .get_page(&format!("/clients/{uuid}/licenses"))
+        .contains("Office 2021"));
+
+    assert!(app.get_page("/hardware/processors").contains("i5-10400T"));
+    assert!(app.get_page("/hardware/memory").contains("16,0 GiB"));
+    assert!(app.get_page("/hardware/graphics_cards").contains("UHD Graphics"));
+    assert!(app.get_page("/hardware/disks").contains("SAMSUNG"));
+    assert!(app.get_page("/hardware/models").contains("ThinkCentre"));
+    assert!(app.get_page("/hardware/network_adapters").contains("I219-LM"));
+    assert!(app.get_page("/software/software").contains("7-Zip"));
+    assert!(app.get_page("/software/os").contains("Windows 11"));
+    assert!(app.get_page("/software/license").contains("Windows"));
+    assert!(app.get_page("/profile").contains("mueller"));
+    app.get_page("/system-status");
+    assert!(app.get_page("/system-status/volumes").contains("C:"));
+    assert!(app.get_page("/settings/users").contains(common::TEST_USER));
+}